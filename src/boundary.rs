@@ -0,0 +1,128 @@
+use crate::materials::floor::SkBoundaryMaterial;
+use bevy::pbr::{NotShadowCaster, NotShadowReceiver};
+use bevy::prelude::*;
+use bevy::render::mesh::{Indices, PrimitiveTopology};
+use bevy::render::primitives::Aabb;
+use bevy::render::render_asset::RenderAssetUsages;
+
+/// Draws the [`PlayBoundary`] as a grid wall that fades in around the head as it comes close,
+/// like StereoKit's bounds rendering. Needs `PbrPlugin` for the [`SkBoundaryMaterial`].
+pub struct PlayBoundaryPlugin;
+
+impl Plugin for PlayBoundaryPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<PlayBoundary>();
+        app.register_type::<(PlayBoundary, PlayBoundaryWall)>();
+        app.add_systems(
+            PostUpdate,
+            update_boundary_wall.run_if(resource_changed::<PlayBoundary>),
+        );
+    }
+}
+
+/// The edge of the area the user can safely move around in, e.g. the guardian or chaperone
+/// polygon the XR runtime reports, filled by the app or XR backend
+#[derive(Resource, Reflect, Clone, Debug, PartialEq)]
+#[reflect(Resource)]
+pub struct PlayBoundary {
+    /// Corners of the floor polygon on world XZ, in order. Fewer than 3 hide the wall.
+    pub points: Vec<Vec2>,
+    /// Floor height the wall starts at, usually the floor of the XR stage space
+    pub floor: f32,
+    /// How far up the wall reaches from the floor
+    pub height: f32,
+}
+
+impl Default for PlayBoundary {
+    fn default() -> Self {
+        Self {
+            points: Vec::new(),
+            floor: 0.0,
+            height: 2.5,
+        }
+    }
+}
+
+/// The wall [`PlayBoundaryPlugin`] draws, swap its [`SkBoundaryMaterial`] to restyle it
+#[derive(Component, Reflect, Clone, Copy, Debug, Default)]
+#[reflect(Component)]
+pub struct PlayBoundaryWall;
+
+/// A quad up from every edge of the closed polygon, with UVs in meters along the boundary and
+/// up from the floor
+fn boundary_mesh(boundary: &PlayBoundary) -> Mesh {
+    let mut positions = Vec::new();
+    let mut normals = Vec::new();
+    let mut uvs = Vec::new();
+    let mut indices = Vec::new();
+
+    let points = &boundary.points;
+    let mut along = 0.0;
+    for (i, start) in points.iter().enumerate() {
+        let end = points[(i + 1) % points.len()];
+        let length = start.distance(end);
+        let normal = Vec3::new(start.y - end.y, 0.0, end.x - start.x).normalize_or_zero();
+        let first = positions.len() as u32;
+        for (point, u) in [(*start, along), (end, along + length)] {
+            for v in [0.0, boundary.height] {
+                positions.push([point.x, boundary.floor + v, point.y]);
+                normals.push(normal.to_array());
+                uvs.push([u, v]);
+            }
+        }
+        indices.extend([first, first + 2, first + 1, first + 1, first + 2, first + 3]);
+        along += length;
+    }
+
+    Mesh::new(PrimitiveTopology::TriangleList, RenderAssetUsages::RENDER_WORLD)
+        .with_inserted_attribute(Mesh::ATTRIBUTE_POSITION, positions)
+        .with_inserted_attribute(Mesh::ATTRIBUTE_NORMAL, normals)
+        .with_inserted_attribute(Mesh::ATTRIBUTE_UV_0, uvs)
+        .with_inserted_indices(Indices::U32(indices))
+}
+
+fn update_boundary_wall(
+    mut commands: Commands,
+    boundary: Res<PlayBoundary>,
+    walls: Query<Entity, With<PlayBoundaryWall>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    materials: Option<ResMut<Assets<SkBoundaryMaterial>>>,
+) {
+    let Some(mut materials) = materials else {
+        return;
+    };
+    if boundary.points.len() < 3 {
+        for wall in walls.iter() {
+            commands.entity(wall).insert(Visibility::Hidden);
+        }
+        return;
+    }
+
+    let mesh = meshes.add(boundary_mesh(&boundary));
+    // Bevy computes an entity's bounds only once
+    let (min, max) = boundary.points.iter().fold((Vec2::MAX, Vec2::MIN), |(min, max), point| {
+        (min.min(*point), max.max(*point))
+    });
+    let aabb = Aabb::from_min_max(
+        Vec3::new(min.x, boundary.floor, min.y),
+        Vec3::new(max.x, boundary.floor + boundary.height, max.y),
+    );
+    match walls.iter().next() {
+        Some(wall) => {
+            commands.entity(wall).insert((mesh, aabb, Visibility::Inherited));
+        }
+        None => {
+            commands.spawn((
+                MaterialMeshBundle {
+                    mesh,
+                    material: materials.add(SkBoundaryMaterial::default()),
+                    ..default()
+                },
+                aabb,
+                PlayBoundaryWall,
+                NotShadowCaster,
+                NotShadowReceiver,
+            ));
+        }
+    }
+}
@@ -0,0 +1,272 @@
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+use bevy::render::mesh::{Indices, PrimitiveTopology, VertexAttributeValues};
+use bevy::render::primitives::Aabb;
+use bevy::utils::HashSet;
+
+/// Keeps a [`Bounds`] on every entity with a mesh, for hand interaction and picking with
+/// [`Raycast`]. Entities without a mesh can be given one by hand.
+pub struct BoundsPlugin;
+
+impl Plugin for BoundsPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<Bounds>();
+        app.add_systems(PostUpdate, update_bounds);
+    }
+}
+
+/// Local space box around an entity, like StereoKit's `Bounds`. Unlike Bevy's `Aabb`, which
+/// is only computed once, it follows changes to the mesh.
+#[derive(Component, Reflect, Clone, Copy, Debug, Default, PartialEq)]
+#[reflect(Component)]
+pub struct Bounds {
+    pub center: Vec3,
+    /// Size along each axis
+    pub dimensions: Vec3,
+}
+
+impl Bounds {
+    pub fn new(center: Vec3, dimensions: Vec3) -> Self {
+        Self { center, dimensions }
+    }
+
+    pub fn from_min_max(min: Vec3, max: Vec3) -> Self {
+        Self::new((min + max) * 0.5, max - min)
+    }
+
+    pub fn min(&self) -> Vec3 {
+        self.center - self.dimensions * 0.5
+    }
+
+    pub fn max(&self) -> Vec3 {
+        self.center + self.dimensions * 0.5
+    }
+
+    pub fn contains(&self, point: Vec3) -> bool {
+        (point - self.center).abs().cmple(self.dimensions * 0.5).all()
+    }
+}
+
+impl From<Aabb> for Bounds {
+    fn from(aabb: Aabb) -> Self {
+        Self::new(aabb.center.into(), Vec3::from(aabb.half_extents) * 2.0)
+    }
+}
+
+/// Where a ray hit something, in world space
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RayHit {
+    /// Along the ray, in units of its direction
+    pub distance: f32,
+    pub point: Vec3,
+    /// Surface normal facing back along the ray, or the bounds face for bounds hits
+    pub normal: Vec3,
+}
+
+/// Where `ray` enters `bounds`, a hit at the ray origin when it starts inside
+pub fn ray_intersect_bounds(
+    ray: Ray3d,
+    bounds: &Bounds,
+    transform: &GlobalTransform,
+) -> Option<RayHit> {
+    // An affine map keeps the ray parameter, so the local hit distance is the world one
+    let inverse = transform.affine().inverse();
+    let origin = inverse.transform_point3(ray.origin);
+    let direction = inverse.transform_vector3(*ray.direction);
+    let t1 = (bounds.min() - origin) / direction;
+    let t2 = (bounds.max() - origin) / direction;
+    let near = t1.min(t2);
+    let distance = near.max_element().max(0.0);
+    if distance > t1.max(t2).min_element() {
+        return None;
+    }
+    // The face entered through is on the axis whose slab was entered last
+    let axis = Vec3::select(near.cmpeq(Vec3::splat(near.max_element())), Vec3::ONE, Vec3::ZERO);
+    let local_normal = -axis * direction.signum();
+    Some(RayHit {
+        distance,
+        point: ray.get_point(distance),
+        normal: world_normal(transform, local_normal),
+    })
+}
+
+/// The closest triangle of `mesh` that `ray` hits, from either side. Skinning and morph
+/// targets are ignored, only triangle lists with `f32` positions are tested.
+pub fn ray_intersect_mesh(
+    ray: Ray3d,
+    mesh: &Mesh,
+    transform: &GlobalTransform,
+) -> Option<RayHit> {
+    if mesh.primitive_topology() != PrimitiveTopology::TriangleList {
+        return None;
+    }
+    let Some(VertexAttributeValues::Float32x3(positions)) =
+        mesh.attribute(Mesh::ATTRIBUTE_POSITION)
+    else {
+        return None;
+    };
+    let inverse = transform.affine().inverse();
+    let origin = inverse.transform_point3(ray.origin);
+    let direction = inverse.transform_vector3(*ray.direction);
+
+    let triangle = |i: usize| -> [usize; 3] {
+        match mesh.indices() {
+            Some(Indices::U16(indices)) => [0, 1, 2].map(|k| indices[i * 3 + k] as usize),
+            Some(Indices::U32(indices)) => [0, 1, 2].map(|k| indices[i * 3 + k] as usize),
+            None => [0, 1, 2].map(|k| i * 3 + k),
+        }
+    };
+    let count = mesh.indices().map_or(positions.len(), Indices::len) / 3;
+    let (distance, normal) = (0..count)
+        .filter_map(|i| {
+            let [a, b, c] = triangle(i).map(|index| positions.get(index).map(Vec3::from));
+            ray_intersect_triangle(origin, direction, [a?, b?, c?])
+        })
+        .min_by(|(a, _), (b, _)| a.total_cmp(b))?;
+    Some(RayHit {
+        distance,
+        point: ray.get_point(distance),
+        normal: world_normal(transform, normal),
+    })
+}
+
+/// Möller–Trumbore, with the normal of the triangle's side the ray comes from
+fn ray_intersect_triangle(
+    origin: Vec3,
+    direction: Vec3,
+    [a, b, c]: [Vec3; 3],
+) -> Option<(f32, Vec3)> {
+    let (ab, ac) = (b - a, c - a);
+    let p = direction.cross(ac);
+    let determinant = ab.dot(p);
+    if determinant.abs() < f32::EPSILON {
+        return None;
+    }
+    let to_origin = origin - a;
+    let u = to_origin.dot(p) / determinant;
+    let q = to_origin.cross(ab);
+    let v = direction.dot(q) / determinant;
+    if u < 0.0 || v < 0.0 || u + v > 1.0 {
+        return None;
+    }
+    let distance = ac.dot(q) / determinant;
+    let normal = ab.cross(ac);
+    (distance >= 0.0).then(|| (distance, normal * -normal.dot(direction).signum()))
+}
+
+fn world_normal(transform: &GlobalTransform, local: Vec3) -> Vec3 {
+    // Normals follow the inverse transpose, so they stay perpendicular under non uniform scale
+    let matrix = transform.affine().matrix3.inverse().transpose();
+    (matrix * Vec3A::from(local)).normalize_or_zero().into()
+}
+
+/// `point` in the local space of `transform`, when it lies inside `bounds`
+pub(crate) fn local_point_in_bounds(
+    point: Vec3,
+    bounds: &Bounds,
+    transform: &GlobalTransform,
+) -> Option<Vec3> {
+    let local = transform.affine().inverse().transform_point3(point);
+    bounds.contains(local).then_some(local)
+}
+
+/// Casts rays against the [`Bounds`] of visible entities, and their triangles for entities
+/// whose mesh is kept in the main world, see `RenderAssetUsages::MAIN_WORLD`
+#[derive(SystemParam)]
+pub struct Raycast<'w, 's> {
+    meshes: Res<'w, Assets<Mesh>>,
+    entities: Query<
+        'w,
+        's,
+        (
+            Entity,
+            &'static Bounds,
+            &'static GlobalTransform,
+            Option<&'static Handle<Mesh>>,
+            Option<&'static InheritedVisibility>,
+        ),
+    >,
+}
+
+impl Raycast<'_, '_> {
+    /// The closest entity passing `filter` whose bounds `ray` hits
+    pub fn cast_bounds(
+        &self,
+        ray: Ray3d,
+        filter: impl Fn(Entity) -> bool,
+    ) -> Option<(Entity, RayHit)> {
+        self.entities
+            .iter()
+            .filter(|(entity, .., visibility)| is_visible(*visibility) && filter(*entity))
+            .filter_map(|(entity, bounds, transform, ..)| {
+                Some((entity, ray_intersect_bounds(ray, bounds, transform)?))
+            })
+            .min_by(|(_, a), (_, b)| a.distance.total_cmp(&b.distance))
+    }
+
+    /// The closest entity passing `filter` that `ray` hits, on its mesh's triangles or on its
+    /// bounds for entities without mesh data
+    pub fn cast(&self, ray: Ray3d, filter: impl Fn(Entity) -> bool) -> Option<(Entity, RayHit)> {
+        let mut candidates: Vec<_> = self
+            .entities
+            .iter()
+            .filter(|(entity, .., visibility)| is_visible(*visibility) && filter(*entity))
+            .filter_map(|(entity, bounds, transform, mesh, _)| {
+                let hit = ray_intersect_bounds(ray, bounds, transform)?;
+                Some((entity, transform, mesh, hit))
+            })
+            .collect();
+        candidates.sort_by(|a, b| a.3.distance.total_cmp(&b.3.distance));
+
+        let mut closest: Option<(Entity, RayHit)> = None;
+        for (entity, transform, mesh, bounds_hit) in candidates {
+            // Triangles can't be closer than the bounds around them
+            if closest.is_some_and(|(_, hit)| hit.distance <= bounds_hit.distance) {
+                break;
+            }
+            let hit = match mesh.and_then(|mesh| self.meshes.get(mesh)) {
+                Some(mesh) => ray_intersect_mesh(ray, mesh, transform),
+                None => Some(bounds_hit),
+            };
+            let closer = |hit: &RayHit| closest.map_or(true, |(_, c)| hit.distance < c.distance);
+            if let Some(hit) = hit.filter(closer) {
+                closest = Some((entity, hit));
+            }
+        }
+        closest
+    }
+}
+
+fn is_visible(visibility: Option<&InheritedVisibility>) -> bool {
+    visibility.map_or(true, InheritedVisibility::get)
+}
+
+fn update_bounds(
+    mut commands: Commands,
+    meshes: Res<Assets<Mesh>>,
+    mut events: EventReader<AssetEvent<Mesh>>,
+    entities: Query<(Entity, Ref<Handle<Mesh>>, Option<&Bounds>)>,
+) {
+    let changed: HashSet<AssetId<Mesh>> = events
+        .read()
+        .filter_map(|e| match e {
+            AssetEvent::Added { id }
+            | AssetEvent::Modified { id }
+            | AssetEvent::LoadedWithDependencies { id } => Some(*id),
+            _ => None,
+        })
+        .collect();
+    for (entity, mesh, bounds) in entities.iter() {
+        if bounds.is_some() && !mesh.is_changed() && !changed.contains(&mesh.id()) {
+            continue;
+        }
+        // Meshes still loading are picked up by their asset event
+        let Some(aabb) = meshes.get(&*mesh).and_then(Mesh::compute_aabb) else {
+            continue;
+        };
+        let new = Bounds::from(aabb);
+        if bounds != Some(&new) {
+            commands.entity(entity).insert(new);
+        }
+    }
+}
@@ -0,0 +1,791 @@
+use crate::skytex::export::{write_faces, write_texels};
+use crate::skytex::lifecycle::NoSkyTex;
+use crate::skytex::{cubemap_texel_at, read_texels};
+use bevy::core_pipeline::tonemapping::Tonemapping;
+use bevy::core_pipeline::Skybox;
+use bevy::pbr::environment_map::EnvironmentMapLight;
+use bevy::prelude::*;
+use bevy::render::camera::RenderTarget;
+use bevy::render::extract_component::{ExtractComponent, ExtractComponentPlugin};
+use bevy::render::render_asset::{RenderAssetUsages, RenderAssets};
+use bevy::render::render_resource::{
+    Buffer, BufferAsyncError, BufferDescriptor, BufferUsages, CommandEncoder,
+    CommandEncoderDescriptor, Extent3d, ImageCopyBuffer, ImageCopyTexture, ImageDataLayout,
+    Maintain, MapMode, Origin3d, Texture, TextureAspect, TextureDimension, TextureFormat,
+    TextureUsages, TextureViewDescriptor, TextureViewDimension,
+};
+use bevy::render::renderer::{render_system, RenderDevice, RenderQueue};
+use bevy::render::texture::GpuImage;
+use bevy::render::{Render, RenderApp, RenderSet};
+use bevy::tasks::IoTaskPool;
+use std::f32::consts::{FRAC_PI_2, PI, TAU};
+use std::io;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+/// Frames the face cameras render before their output is copied, so their pipelines are ready
+const CAPTURE_FRAMES: u32 = 3;
+const TEXEL_SIZE: u32 = 8;
+
+/// Renders the scene into cubemaps on [`CaptureCubemap`] and reads them back to the CPU
+pub struct CubemapCapturePlugin;
+
+impl Plugin for CubemapCapturePlugin {
+    fn build(&self, app: &mut App) {
+        let readbacks = CaptureReadbacks::default();
+        app.insert_resource(readbacks.clone());
+        app.add_event::<CubemapCaptured>();
+        app.register_type::<CaptureCubemap>();
+        app.add_plugins(ExtractComponentPlugin::<CubemapCopyJob>::default());
+        app.add_systems(
+            PostUpdate,
+            (
+                start_cubemap_captures,
+                advance_cubemap_captures,
+                receive_cubemap_captures,
+            )
+                .chain()
+                .in_set(CubemapCaptureSet)
+                .after(TransformSystem::TransformPropagate),
+        );
+
+        if let Some(render_app) = app.get_sub_app_mut(RenderApp) {
+            render_app.insert_resource(readbacks);
+            render_app.init_resource::<PendingReadbacks>();
+            render_app.add_systems(
+                Render,
+                (
+                    copy_captured_cubemaps
+                        .in_set(RenderSet::Render)
+                        .after(render_system),
+                    poll_readbacks.in_set(RenderSet::Cleanup),
+                ),
+            );
+        }
+    }
+}
+
+/// Systems starting cubemap captures and sending [`CubemapCaptured`]
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct CubemapCaptureSet;
+
+/// Captures the scene around this entity's position into a cubemap, removed once started.
+///
+/// The faces are rendered by temporary HDR cameras showing the current skybox, the result
+/// arrives a few frames later as a [`CubemapCaptured`] event.
+#[derive(Component, Reflect, Clone, Copy, Debug, PartialEq)]
+#[reflect(Component)]
+pub struct CaptureCubemap {
+    pub face_size: u32,
+}
+
+impl Default for CaptureCubemap {
+    fn default() -> Self {
+        Self { face_size: 64 }
+    }
+}
+
+/// A finished [`CaptureCubemap`]
+#[derive(Event, Clone, Debug)]
+pub struct CubemapCaptured {
+    pub entity: Entity,
+    /// `Rgba16Float` cube texture on the GPU, laid out like the generated sky
+    pub cubemap: Handle<Image>,
+    /// CPU copy of the cubemap, e.g. for `SphericalHarmonics::from_cubemap`
+    pub image: Image,
+}
+
+/// Marks the temporary cameras rendering the faces of a capture, or a flat
+/// [`RenderCaptureRequest`]
+#[derive(Component, Clone, Copy, Debug)]
+pub struct CaptureCamera;
+
+/// Renders screenshots and 360° captures of the scene on [`RenderCaptureRequest`], e.g. for
+/// marketing shots or `SphericalHarmonics::from_equirect`. Not part of `SkPlugins`.
+pub struct RenderCapturePlugin;
+
+impl Plugin for RenderCapturePlugin {
+    fn build(&self, app: &mut App) {
+        if !app.is_plugin_added::<CubemapCapturePlugin>() {
+            app.add_plugins(CubemapCapturePlugin);
+        }
+        let readbacks = FlatCaptureReadbacks::default();
+        app.insert_resource(readbacks.clone());
+        app.add_event::<RenderCaptureRequest>();
+        app.add_event::<RenderCaptured>();
+        app.add_plugins(ExtractComponentPlugin::<FlatCopyJob>::default());
+        app.add_systems(
+            PostUpdate,
+            (
+                (
+                    start_render_captures,
+                    advance_flat_captures,
+                    receive_flat_captures,
+                )
+                    .chain()
+                    .before(CubemapCaptureSet),
+                finish_render_captures.after(CubemapCaptureSet),
+            )
+                .after(TransformSystem::TransformPropagate),
+        );
+
+        if let Some(render_app) = app.get_sub_app_mut(RenderApp) {
+            render_app.insert_resource(readbacks);
+            render_app.add_systems(
+                Render,
+                copy_flat_captures
+                    .in_set(RenderSet::Render)
+                    .after(render_system),
+            );
+        }
+    }
+}
+
+/// Renders the scene from `transform`, the result arrives a few frames later as a
+/// [`RenderCaptured`] event and is written to `path` on the IO task pool
+#[derive(Event, Clone, Debug, PartialEq)]
+pub struct RenderCaptureRequest {
+    /// Flat captures look down its -Z, round captures only use its translation
+    pub transform: Transform,
+    pub kind: RenderCaptureKind,
+    /// An EXR if it ends in `.exr`, otherwise an 8 bit sRGB image of the format its extension
+    /// names, with HDR values clamped. Cubemaps are written as six `<path>_<face>` files.
+    pub path: Option<PathBuf>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum RenderCaptureKind {
+    /// A screenshot with a vertical field of view of `fov` radians, tonemapped like the first
+    /// 3D camera into an `Rgba8UnormSrgb` image
+    Flat { size: UVec2, fov: f32 },
+    /// The linear HDR scene all around in an `Rgba16Float` cubemap, laid out like the
+    /// generated sky
+    Cubemap { face_size: u32 },
+    /// The linear HDR scene all around in a `width` by `width / 2` `Rgba16Float` panorama
+    /// whose center looks down -Z, resampled from a cubemap
+    Equirect { width: u32 },
+}
+
+/// A finished [`RenderCaptureRequest`]
+#[derive(Event, Clone, Debug)]
+pub struct RenderCaptured {
+    pub request: RenderCaptureRequest,
+    pub image: Image,
+}
+
+#[derive(Component)]
+struct CubemapCaptureState {
+    cameras: Vec<Entity>,
+    faces: [Handle<Image>; 6],
+    cubemap: Handle<Image>,
+    face_size: u32,
+    frames_left: u32,
+}
+
+/// Copies the rendered faces into the cubemap and reads it back this frame
+#[derive(Component, ExtractComponent, Clone)]
+struct CubemapCopyJob {
+    entity: Entity,
+    faces: [Handle<Image>; 6],
+    cubemap: Handle<Image>,
+    face_size: u32,
+}
+
+/// Read back bytes per capture entity, empty if the capture was dropped
+type Readbacks = Arc<Mutex<Vec<(Entity, Vec<u8>)>>>;
+
+/// Read back cubemap bytes, sent from the render world
+#[derive(Resource, Clone, Default)]
+struct CaptureReadbacks(Readbacks);
+
+/// A [`RenderCaptureRequest`] in flight, on its own entity which is despawned once it's done
+#[derive(Component)]
+struct RenderCaptureJob(RenderCaptureRequest);
+
+#[derive(Component)]
+struct FlatCaptureState {
+    camera: Option<Entity>,
+    target: Handle<Image>,
+    size: UVec2,
+    frames_left: u32,
+}
+
+/// Reads the target of a flat capture back this frame
+#[derive(Component, ExtractComponent, Clone)]
+struct FlatCopyJob {
+    entity: Entity,
+    target: Handle<Image>,
+    size: UVec2,
+}
+
+/// Read back bytes of flat captures, sent from the render world
+#[derive(Resource, Clone, Default)]
+struct FlatCaptureReadbacks(Readbacks);
+
+/// A copy into a buffer whose mapping hasn't finished yet
+struct PendingReadback {
+    entity: Entity,
+    buffer: Buffer,
+    row: usize,
+    padded_row: usize,
+    mapped: Arc<Mutex<Option<Result<(), BufferAsyncError>>>>,
+    readbacks: Readbacks,
+}
+
+/// Readbacks in flight in the render world, polled every frame instead of waiting on the GPU
+#[derive(Resource, Default)]
+struct PendingReadbacks(Vec<PendingReadback>);
+
+/// Camera direction and up vector of every cube layer, rendered right handed the faces come
+/// out with z negated like Bevy's skybox expects
+const FACES: [(Vec3, Vec3); 6] = [
+    (Vec3::X, Vec3::Y),
+    (Vec3::NEG_X, Vec3::Y),
+    (Vec3::Y, Vec3::Z),
+    (Vec3::NEG_Y, Vec3::NEG_Z),
+    (Vec3::NEG_Z, Vec3::Y),
+    (Vec3::Z, Vec3::Y),
+];
+
+fn capture_image(face_size: u32, layers: u32, usage: TextureUsages) -> Image {
+    let mut image = Image::new_fill(
+        Extent3d {
+            width: face_size,
+            height: face_size,
+            depth_or_array_layers: layers,
+        },
+        TextureDimension::D2,
+        &[0; TEXEL_SIZE as usize],
+        TextureFormat::Rgba16Float,
+        RenderAssetUsages::RENDER_WORLD,
+    );
+    image.texture_descriptor.usage = usage | TextureUsages::TEXTURE_BINDING;
+    if layers == 6 {
+        image.texture_view_descriptor = Some(TextureViewDescriptor {
+            dimension: Some(TextureViewDimension::Cube),
+            ..default()
+        });
+    }
+    image
+}
+
+fn start_cubemap_captures(
+    mut commands: Commands,
+    requests: Query<(Entity, &CaptureCubemap, &GlobalTransform), Without<CubemapCaptureState>>,
+    skyboxes: Query<&Skybox, (With<Camera3d>, Without<CaptureCamera>)>,
+    mut images: ResMut<Assets<Image>>,
+) {
+    let skybox = skyboxes.iter().next();
+    for (entity, request, transform) in requests.iter() {
+        let face_size = request.face_size.max(1);
+        let faces = std::array::from_fn(|_| {
+            let usage = TextureUsages::RENDER_ATTACHMENT | TextureUsages::COPY_SRC;
+            images.add(capture_image(face_size, 1, usage))
+        });
+        let usage = TextureUsages::COPY_DST | TextureUsages::COPY_SRC;
+        let cubemap = images.add(capture_image(face_size, 6, usage));
+
+        let cameras = FACES
+            .iter()
+            .zip(&faces)
+            .enumerate()
+            .map(|(layer, ((forward, up), face))| {
+                let transform = Transform::from_translation(transform.translation())
+                    .looking_to(*forward, *up);
+                let mut camera = commands.spawn((
+                    Camera3dBundle {
+                        camera: Camera {
+                            target: RenderTarget::Image(face.clone()),
+                            order: -100 - layer as isize,
+                            hdr: true,
+                            ..default()
+                        },
+                        projection: Projection::Perspective(PerspectiveProjection {
+                            fov: FRAC_PI_2,
+                            aspect_ratio: 1.0,
+                            near: 0.05,
+                            ..default()
+                        }),
+                        tonemapping: Tonemapping::None,
+                        transform,
+                        global_transform: transform.into(),
+                        ..default()
+                    },
+                    CaptureCamera,
+                    NoSkyTex,
+                ));
+                if let Some(skybox) = skybox {
+                    camera.insert(skybox.clone());
+                }
+                camera.id()
+            })
+            .collect();
+
+        commands
+            .entity(entity)
+            .remove::<CaptureCubemap>()
+            .insert(CubemapCaptureState {
+                cameras,
+                faces,
+                cubemap,
+                face_size,
+                frames_left: CAPTURE_FRAMES,
+            });
+    }
+}
+
+fn advance_cubemap_captures(
+    mut commands: Commands,
+    mut captures: Query<(Entity, &mut CubemapCaptureState, Has<CubemapCopyJob>)>,
+) {
+    for (entity, mut state, copying) in captures.iter_mut() {
+        if copying {
+            // The job was extracted last frame, until the readback arrives only the state stays
+            for camera in state.cameras.drain(..) {
+                commands.entity(camera).despawn();
+            }
+            commands.entity(entity).remove::<CubemapCopyJob>();
+        } else if state.frames_left > 0 {
+            state.frames_left -= 1;
+            if state.frames_left == 0 {
+                commands.entity(entity).insert(CubemapCopyJob {
+                    entity,
+                    faces: state.faces.clone(),
+                    cubemap: state.cubemap.clone(),
+                    face_size: state.face_size,
+                });
+            }
+        }
+    }
+}
+
+fn receive_cubemap_captures(
+    mut commands: Commands,
+    readbacks: Res<CaptureReadbacks>,
+    captures: Query<&CubemapCaptureState>,
+    mut captured: EventWriter<CubemapCaptured>,
+) {
+    let finished: Vec<_> = readbacks.0.lock().unwrap().drain(..).collect();
+    for (entity, data) in finished {
+        let Ok(state) = captures.get(entity) else {
+            continue;
+        };
+        commands.entity(entity).remove::<CubemapCaptureState>();
+        if data.is_empty() {
+            continue;
+        }
+        let mut image = capture_image(state.face_size, 6, TextureUsages::empty());
+        image.data = data;
+        captured.send(CubemapCaptured {
+            entity,
+            cubemap: state.cubemap.clone(),
+            image,
+        });
+    }
+}
+
+fn copy_captured_cubemaps(
+    jobs: Query<&CubemapCopyJob>,
+    images: Res<RenderAssets<GpuImage>>,
+    device: Res<RenderDevice>,
+    queue: Res<RenderQueue>,
+    readbacks: Res<CaptureReadbacks>,
+    mut pending: ResMut<PendingReadbacks>,
+) {
+    for job in jobs.iter() {
+        let size = job.face_size;
+        let faces: Option<Vec<&GpuImage>> = job.faces.iter().map(|face| images.get(face)).collect();
+        let (Some(faces), Some(cubemap)) = (faces, images.get(&job.cubemap)) else {
+            warn!("Cubemap capture of {:?} wasn't ready and was dropped", job.entity);
+            readbacks.0.lock().unwrap().push((job.entity, Vec::new()));
+            continue;
+        };
+
+        let mut encoder = device.create_command_encoder(&CommandEncoderDescriptor {
+            label: Some("cubemap_capture"),
+        });
+        let face_extent = Extent3d {
+            width: size,
+            height: size,
+            depth_or_array_layers: 1,
+        };
+        for (layer, face) in faces.iter().enumerate() {
+            encoder.copy_texture_to_texture(
+                face.texture.as_image_copy(),
+                ImageCopyTexture {
+                    texture: &cubemap.texture,
+                    mip_level: 0,
+                    origin: Origin3d {
+                        x: 0,
+                        y: 0,
+                        z: layer as u32,
+                    },
+                    aspect: TextureAspect::All,
+                },
+                face_extent,
+            );
+        }
+
+        let extent = Extent3d {
+            depth_or_array_layers: 6,
+            ..face_extent
+        };
+        pending.0.push(read_back_texture(
+            &device,
+            &queue,
+            encoder,
+            &cubemap.texture,
+            extent,
+            job.entity,
+            &readbacks.0,
+        ));
+    }
+}
+
+fn start_render_captures(
+    mut commands: Commands,
+    mut requests: EventReader<RenderCaptureRequest>,
+    cameras: Query<
+        (Option<&Skybox>, Option<&EnvironmentMapLight>, &Tonemapping),
+        (With<Camera3d>, Without<CaptureCamera>),
+    >,
+    mut images: ResMut<Assets<Image>>,
+) {
+    for request in requests.read() {
+        let transform = request.transform;
+        let entity = commands
+            .spawn((
+                TransformBundle::from_transform(transform),
+                RenderCaptureJob(request.clone()),
+            ))
+            .id();
+        let (size, fov) = match request.kind {
+            RenderCaptureKind::Flat { size, fov } => (size.max(UVec2::ONE), fov),
+            RenderCaptureKind::Cubemap { face_size } => {
+                commands.entity(entity).insert(CaptureCubemap { face_size });
+                continue;
+            }
+            RenderCaptureKind::Equirect { width } => {
+                // A face covers a quarter of the panorama's width
+                let face_size = (width / 4).max(1);
+                commands.entity(entity).insert(CaptureCubemap { face_size });
+                continue;
+            }
+        };
+
+        let mut target = Image::new_fill(
+            Extent3d {
+                width: size.x,
+                height: size.y,
+                depth_or_array_layers: 1,
+            },
+            TextureDimension::D2,
+            &[0; 4],
+            TextureFormat::Rgba8UnormSrgb,
+            RenderAssetUsages::RENDER_WORLD,
+        );
+        target.texture_descriptor.usage = TextureUsages::RENDER_ATTACHMENT
+            | TextureUsages::COPY_SRC
+            | TextureUsages::TEXTURE_BINDING;
+        let target = images.add(target);
+
+        let main = cameras.iter().next();
+        let mut camera = commands.spawn((
+            Camera3dBundle {
+                camera: Camera {
+                    target: RenderTarget::Image(target.clone()),
+                    order: -110,
+                    hdr: true,
+                    ..default()
+                },
+                projection: Projection::Perspective(PerspectiveProjection {
+                    fov,
+                    aspect_ratio: size.x as f32 / size.y as f32,
+                    ..default()
+                }),
+                tonemapping: main.map_or(Tonemapping::default(), |(.., tonemapping)| *tonemapping),
+                transform,
+                global_transform: transform.into(),
+                ..default()
+            },
+            CaptureCamera,
+            NoSkyTex,
+        ));
+        if let Some((skybox, environment, _)) = main {
+            if let Some(skybox) = skybox {
+                camera.insert(skybox.clone());
+            }
+            if let Some(environment) = environment {
+                camera.insert(environment.clone());
+            }
+        }
+        let camera = camera.id();
+
+        commands.entity(entity).insert(FlatCaptureState {
+            camera: Some(camera),
+            target,
+            size,
+            frames_left: CAPTURE_FRAMES,
+        });
+    }
+}
+
+fn advance_flat_captures(
+    mut commands: Commands,
+    mut captures: Query<(Entity, &mut FlatCaptureState, Has<FlatCopyJob>)>,
+) {
+    for (entity, mut state, copying) in captures.iter_mut() {
+        if copying {
+            if let Some(camera) = state.camera.take() {
+                commands.entity(camera).despawn();
+            }
+            commands.entity(entity).remove::<FlatCopyJob>();
+        } else if state.frames_left > 0 {
+            state.frames_left -= 1;
+            if state.frames_left == 0 {
+                commands.entity(entity).insert(FlatCopyJob {
+                    entity,
+                    target: state.target.clone(),
+                    size: state.size,
+                });
+            }
+        }
+    }
+}
+
+fn receive_flat_captures(
+    mut commands: Commands,
+    readbacks: Res<FlatCaptureReadbacks>,
+    captures: Query<(&FlatCaptureState, &RenderCaptureJob)>,
+    mut captured: EventWriter<RenderCaptured>,
+) {
+    let finished: Vec<_> = readbacks.0.lock().unwrap().drain(..).collect();
+    for (entity, data) in finished {
+        let Ok((state, job)) = captures.get(entity) else {
+            continue;
+        };
+        commands.entity(entity).remove::<FlatCaptureState>();
+        if data.is_empty() {
+            continue;
+        }
+        let image = Image::new(
+            Extent3d {
+                width: state.size.x,
+                height: state.size.y,
+                depth_or_array_layers: 1,
+            },
+            TextureDimension::D2,
+            data,
+            TextureFormat::Rgba8UnormSrgb,
+            RenderAssetUsages::default(),
+        );
+        send_render_capture(job.0.clone(), image, &mut captured);
+    }
+}
+
+fn finish_render_captures(
+    mut commands: Commands,
+    mut cubemaps: EventReader<CubemapCaptured>,
+    jobs: Query<&RenderCaptureJob>,
+    done: Query<
+        Entity,
+        (
+            With<RenderCaptureJob>,
+            Without<CaptureCubemap>,
+            Without<CubemapCaptureState>,
+            Without<FlatCaptureState>,
+        ),
+    >,
+    mut captured: EventWriter<RenderCaptured>,
+) {
+    for capture in cubemaps.read() {
+        let Ok(job) = jobs.get(capture.entity) else {
+            continue;
+        };
+        let mut image = match job.0.kind {
+            RenderCaptureKind::Equirect { width } => equirect_image(&capture.image, width),
+            _ => capture.image.clone(),
+        };
+        image.asset_usage = RenderAssetUsages::default();
+        send_render_capture(job.0.clone(), image, &mut captured);
+    }
+    // Finished and dropped captures alike
+    for entity in done.iter() {
+        commands.entity(entity).despawn();
+    }
+}
+
+fn send_render_capture(
+    request: RenderCaptureRequest,
+    image: Image,
+    captured: &mut EventWriter<RenderCaptured>,
+) {
+    if let Some(path) = request.path.clone() {
+        let image = image.clone();
+        IoTaskPool::get()
+            .spawn(async move {
+                let result = if image.texture_descriptor.size.depth_or_array_layers == 6 {
+                    let extension = path.extension().and_then(|e| e.to_str()).unwrap_or("png");
+                    write_faces(&image, &path, extension)
+                } else {
+                    let invalid = || io::Error::new(io::ErrorKind::InvalidInput, "unreadable");
+                    read_texels(&image)
+                        .ok_or_else(invalid)
+                        .and_then(|texels| {
+                            write_texels(&texels, image.width(), image.height(), &path)
+                        })
+                };
+                match result {
+                    Ok(()) => info!("Saved capture to {}", path.display()),
+                    Err(err) => error!("Failed to save capture to {}: {err}", path.display()),
+                }
+            })
+            .detach();
+    }
+    captured.send(RenderCaptured { request, image });
+}
+
+/// Resamples a captured cubemap into a `width` by `width / 2` panorama, nearest texel
+fn equirect_image(cubemap: &Image, width: u32) -> Image {
+    let size = cubemap.width();
+    let (width, height) = (width.max(2), (width / 2).max(1));
+    let texel_size = TEXEL_SIZE as usize;
+    let mut data = Vec::with_capacity(width as usize * height as usize * texel_size);
+    for y in 0..height {
+        let lat = (0.5 - (y as f32 + 0.5) / height as f32) * PI;
+        for x in 0..width {
+            let lon = ((x as f32 + 0.5) / width as f32 - 0.5) * TAU;
+            let dir = Vec3::new(lat.cos() * lon.sin(), lat.sin(), -lat.cos() * lon.cos());
+            let texel = cubemap_texel_at(size, dir) * texel_size;
+            data.extend_from_slice(&cubemap.data[texel..texel + texel_size]);
+        }
+    }
+    Image::new(
+        Extent3d {
+            width,
+            height,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        data,
+        TextureFormat::Rgba16Float,
+        RenderAssetUsages::default(),
+    )
+}
+
+fn copy_flat_captures(
+    jobs: Query<&FlatCopyJob>,
+    images: Res<RenderAssets<GpuImage>>,
+    device: Res<RenderDevice>,
+    queue: Res<RenderQueue>,
+    readbacks: Res<FlatCaptureReadbacks>,
+    mut pending: ResMut<PendingReadbacks>,
+) {
+    for job in jobs.iter() {
+        let Some(target) = images.get(&job.target) else {
+            warn!("Render capture of {:?} wasn't ready and was dropped", job.entity);
+            readbacks.0.lock().unwrap().push((job.entity, Vec::new()));
+            continue;
+        };
+        let encoder = device.create_command_encoder(&CommandEncoderDescriptor {
+            label: Some("render_capture"),
+        });
+        let extent = Extent3d {
+            width: job.size.x,
+            height: job.size.y,
+            depth_or_array_layers: 1,
+        };
+        pending.0.push(read_back_texture(
+            &device,
+            &queue,
+            encoder,
+            &target.texture,
+            extent,
+            job.entity,
+            &readbacks.0,
+        ));
+    }
+}
+
+/// Copies `texture` into a buffer with `encoder` and starts mapping it, [`poll_readbacks`]
+/// sends its tightly packed bytes to `readbacks` once the GPU is done
+fn read_back_texture(
+    device: &RenderDevice,
+    queue: &RenderQueue,
+    mut encoder: CommandEncoder,
+    texture: &Texture,
+    extent: Extent3d,
+    entity: Entity,
+    readbacks: &Readbacks,
+) -> PendingReadback {
+    let texel_size = texture.format().block_copy_size(None).unwrap_or(4);
+    let row = (extent.width * texel_size) as usize;
+    let padded_row = RenderDevice::align_copy_bytes_per_row(row);
+    let rows = extent.height as usize * extent.depth_or_array_layers as usize;
+    let buffer = device.create_buffer(&BufferDescriptor {
+        label: Some("capture_readback"),
+        size: (padded_row * rows) as u64,
+        usage: BufferUsages::MAP_READ | BufferUsages::COPY_DST,
+        mapped_at_creation: false,
+    });
+    encoder.copy_texture_to_buffer(
+        texture.as_image_copy(),
+        ImageCopyBuffer {
+            buffer: &buffer,
+            layout: ImageDataLayout {
+                offset: 0,
+                bytes_per_row: Some(padded_row as u32),
+                rows_per_image: Some(extent.height),
+            },
+        },
+        extent,
+    );
+    queue.submit([encoder.finish()]);
+
+    let mapped = Arc::new(Mutex::new(None));
+    let result = mapped.clone();
+    buffer.slice(..).map_async(MapMode::Read, move |status| {
+        *result.lock().unwrap() = Some(status);
+    });
+    PendingReadback {
+        entity,
+        buffer,
+        row,
+        padded_row,
+        mapped,
+        readbacks: readbacks.clone(),
+    }
+}
+
+/// Sends the bytes of every mapped readback, keeping the buffers of the others alive
+fn poll_readbacks(device: Res<RenderDevice>, mut pending: ResMut<PendingReadbacks>) {
+    if pending.0.is_empty() {
+        return;
+    }
+    device.poll(Maintain::Poll);
+    pending.0.retain(|readback| {
+        let Some(mapped) = readback.mapped.lock().unwrap().take() else {
+            return true;
+        };
+        let data = match mapped {
+            Ok(()) => {
+                let data = readback
+                    .buffer
+                    .slice(..)
+                    .get_mapped_range()
+                    .chunks_exact(readback.padded_row)
+                    .flat_map(|padded| &padded[..readback.row])
+                    .copied()
+                    .collect();
+                readback.buffer.unmap();
+                data
+            }
+            Err(err) => {
+                warn!("Capture of {:?} couldn't be read back: {err}", readback.entity);
+                Vec::new()
+            }
+        };
+        readback.readbacks.lock().unwrap().push((readback.entity, data));
+        false
+    });
+}
@@ -0,0 +1,102 @@
+use crate::lighting::buffer::ShLightingBuffer;
+use crate::skytex::REFERENCE_EV100;
+use bevy::core_pipeline::tonemapping::Tonemapping;
+use bevy::prelude::*;
+use bevy::render::camera::Exposure;
+
+/// How the sky and the SH lit materials respond to the camera and reach the screen, read by
+/// the sky and material plugins.
+///
+/// Every bevy_sk material writes linear HDR color, so how it ends up on screen is up to the
+/// camera's `Exposure` and `Tonemapping`, like with `StandardMaterial`. Sky textures are linear
+/// too whatever their [`SkyTexFormat`](crate::skytex::SkyTexFormat), `Srgb` only spends the
+/// 8 bits more evenly.
+#[derive(Resource, Reflect, Clone, Copy, Debug, Default, PartialEq)]
+#[reflect(Resource)]
+pub struct SkColorSettings {
+    /// Scales the sky and the SH ambient with the camera `Exposure` like Bevy's lights, so one
+    /// exposure value brightens or darkens everything. Off keeps both at their
+    /// [`REFERENCE_EV100`] brightness, StereoKit's look, which drifts apart from Bevy's lights
+    /// once the exposure changes.
+    pub follow_camera_exposure: bool,
+    /// Tonemapping given to every 3d camera, `None` leaves them alone. StereoKit doesn't
+    /// tonemap, which `Tonemapping::None` comes closest to, while Bevy's default
+    /// `TonyMcMapface` compresses and desaturates bright SH lit surfaces.
+    pub tonemapping: Option<Tonemapping>,
+}
+
+impl SkColorSettings {
+    /// Factor keeping the sky brightness constant under `exposure`, 1 when following it
+    pub fn sky_exposure_factor(&self, exposure: &Exposure) -> f32 {
+        if self.follow_camera_exposure {
+            1.0
+        } else {
+            crate::skytex::exposure_compensation(exposure)
+        }
+    }
+}
+
+/// Brightness of everything bevy_sk lights on top of the camera exposure: the skybox and the
+/// light spots painted into it, its environment map, the SH ambient of every material and
+/// `PbrMaterial` emission. Brightness sliders and simple auto-exposure can drive it without
+/// the sky and the surfaces drifting apart.
+#[derive(Resource, Reflect, Clone, Copy, Debug, PartialEq)]
+#[reflect(Resource)]
+pub struct SkExposure {
+    /// Linear factor, 1 keeps the tuned brightness
+    pub scale: f32,
+}
+
+impl Default for SkExposure {
+    fn default() -> Self {
+        Self { scale: 1.0 }
+    }
+}
+
+impl SkExposure {
+    /// Brighter by `stops` doublings, darker when negative
+    pub fn from_stops(stops: f32) -> Self {
+        Self { scale: stops.exp2() }
+    }
+
+    pub fn stops(&self) -> f32 {
+        self.scale.log2()
+    }
+}
+
+pub(crate) fn apply_sk_exposure(exposure: Res<SkExposure>, mut buffer: ResMut<ShLightingBuffer>) {
+    if exposure.is_changed() {
+        buffer.set_brightness(exposure.scale);
+    }
+}
+
+pub(crate) fn apply_ambient_exposure(
+    settings: Res<SkColorSettings>,
+    mut buffer: ResMut<ShLightingBuffer>,
+) {
+    if !settings.is_changed() {
+        return;
+    }
+    let reference = Exposure {
+        ev100: REFERENCE_EV100,
+    };
+    buffer.set_reference_exposure(
+        settings
+            .follow_camera_exposure
+            .then(|| reference.exposure()),
+    );
+}
+
+pub(crate) fn apply_tonemapping(
+    settings: Res<SkColorSettings>,
+    mut cameras: Query<(Ref<Camera3d>, &mut Tonemapping)>,
+) {
+    let Some(tonemapping) = settings.tonemapping else {
+        return;
+    };
+    for (camera, mut current) in cameras.iter_mut() {
+        if (settings.is_changed() || camera.is_added()) && *current != tonemapping {
+            *current = tonemapping;
+        }
+    }
+}
@@ -0,0 +1,81 @@
+use crate::skytex::gpu::GpuSkyTex;
+use crate::skytex::SkyTexFormat;
+use bevy::prelude::*;
+use bevy::render::renderer::RenderDevice;
+
+/// Keeps bevy_sk scenes within what WebGL2 and GLES devices support, so they can be previewed
+/// in the browser.
+///
+/// `PbrMaterial` shaders adapt on their own, this falls back to CPU sky generation and
+/// filterable sky formats. The `webgl2` feature also unbinds the `PbrMaterial` textures past
+/// WebGL2's texture limit: the diffuse, depth, clear coat, anisotropy and detail textures and
+/// the second reflection probe. Without a render device at all, in a headless `App`, GPU skies
+/// move to the CPU as well.
+pub struct SkCompatibilityPlugin;
+
+impl Plugin for SkCompatibilityPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<SkCompatibility>();
+        app.register_type::<SkCompatibility>();
+        app.add_systems(PreUpdate, apply_compatibility);
+    }
+
+    fn finish(&self, app: &mut App) {
+        let device = app.world().get_resource::<RenderDevice>();
+        let headless = device.is_none();
+        let downlevel = device.is_some_and(is_downlevel);
+        if downlevel && !cfg!(feature = "webgl2") {
+            warn!(
+                "Render device lacks compute shaders, enable bevy_sk's webgl2 feature if \
+                 PbrMaterial pipelines fail on its texture limits"
+            );
+        }
+        app.insert_resource(SkCompatibility {
+            downlevel,
+            headless,
+        });
+    }
+}
+
+/// Whether `device` is a WebGL2 or similarly limited device, always true with the `webgl2`
+/// feature
+pub fn is_downlevel(device: &RenderDevice) -> bool {
+    cfg!(feature = "webgl2") || device.limits().max_compute_workgroups_per_dimension == 0
+}
+
+/// The capabilities [`SkCompatibilityPlugin`] detected
+#[derive(Resource, Reflect, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[reflect(Resource)]
+pub struct SkCompatibility {
+    /// No compute shaders nor filterable 32 bit float textures
+    pub downlevel: bool,
+    /// No render device, e.g. in tests, nothing is drawn and only the CPU paths run
+    pub headless: bool,
+}
+
+fn apply_compatibility(
+    mut commands: Commands,
+    compatibility: Res<SkCompatibility>,
+    gpu_skies: Query<Entity, With<GpuSkyTex>>,
+    format: Option<ResMut<SkyTexFormat>>,
+) {
+    if compatibility.headless {
+        for entity in gpu_skies.iter() {
+            debug!("No render device, generating the sky of {entity} on the CPU");
+            commands.entity(entity).remove::<GpuSkyTex>();
+        }
+    }
+    if !compatibility.downlevel {
+        return;
+    }
+    for entity in gpu_skies.iter() {
+        warn!("GpuSkyTex needs compute shaders, generating the sky of {entity} on the CPU");
+        commands.entity(entity).remove::<GpuSkyTex>();
+    }
+    if let Some(mut format) = format {
+        if *format == SkyTexFormat::Rgba32Float {
+            warn!("Rgba32Float skies can't be filtered here, using Rgba16Float");
+            *format = SkyTexFormat::Rgba16Float;
+        }
+    }
+}
@@ -0,0 +1,170 @@
+use crate::hands::Hand;
+use crate::materials::pbr::{PbrMaterial, ReplaceStandardMaterial};
+use bevy::ecs::system::EntityCommands;
+use bevy::prelude::*;
+use bevy::transform::TransformSystem;
+
+/// Draws a controller at each grip pose in [`ControllerPoses`], the glTF scenes of
+/// [`ControllerModels`] or simple procedural ones. Untracked controllers are hidden.
+///
+/// The models are lit by the SH like the rest of the scene, glTF materials are converted to
+/// `PbrMaterial` even in `ReplaceMaterialsMode::OnlyMarked`.
+pub struct ControllersPlugin;
+
+impl Plugin for ControllersPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ControllerPoses>();
+        app.init_resource::<ControllerModels>();
+        app.register_type::<(ControllerPoses, ControllerModels, ControllerModel)>();
+        app.add_systems(
+            Update,
+            spawn_controllers.run_if(resource_changed::<ControllerModels>),
+        );
+        app.add_systems(
+            PostUpdate,
+            update_controllers.before(TransformSystem::TransformPropagate),
+        );
+    }
+}
+
+/// The latest world space grip poses, `None` for a controller that isn't tracked.
+///
+/// Like OpenXR's grip pose, -Z points forward through the closed hand and +X away from the
+/// palm of the right hand.
+#[derive(Resource, Reflect, Clone, Debug, Default, PartialEq)]
+#[reflect(Resource)]
+pub struct ControllerPoses {
+    pub left: Option<Transform>,
+    pub right: Option<Transform>,
+}
+
+impl ControllerPoses {
+    pub fn get(&self, hand: Hand) -> Option<Transform> {
+        match hand {
+            Hand::Left => self.left,
+            Hand::Right => self.right,
+        }
+    }
+
+    pub fn set(&mut self, hand: Hand, pose: Option<Transform>) {
+        match hand {
+            Hand::Left => self.left = pose,
+            Hand::Right => self.right = pose,
+        }
+    }
+}
+
+/// Scenes drawn at the grip poses, e.g. `asset_server.load("controller.glb#Scene0")`, with
+/// their origin at the grip. `None` draws a procedural controller.
+#[derive(Resource, Reflect, Clone, Debug, Default, PartialEq)]
+#[reflect(Resource)]
+pub struct ControllerModels {
+    pub left: Option<Handle<Scene>>,
+    pub right: Option<Handle<Scene>>,
+}
+
+impl ControllerModels {
+    pub fn get(&self, hand: Hand) -> Option<&Handle<Scene>> {
+        match hand {
+            Hand::Left => self.left.as_ref(),
+            Hand::Right => self.right.as_ref(),
+        }
+    }
+}
+
+/// Root of the model [`ControllersPlugin`] drew for a controller, posed at its grip
+#[derive(Component, Reflect, Clone, Copy, Debug, PartialEq, Eq)]
+#[reflect(Component)]
+pub struct ControllerModel {
+    pub hand: Hand,
+}
+
+fn spawn_controllers(
+    mut commands: Commands,
+    models: Res<ControllerModels>,
+    existing: Query<Entity, With<ControllerModel>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<PbrMaterial>>,
+) {
+    for entity in existing.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+    for hand in [Hand::Left, Hand::Right] {
+        let mut root = commands.spawn((
+            SpatialBundle {
+                visibility: Visibility::Hidden,
+                ..default()
+            },
+            ControllerModel { hand },
+            ReplaceStandardMaterial,
+        ));
+        match models.get(hand) {
+            Some(scene) => {
+                root.with_children(|parent| {
+                    parent.spawn(SceneBundle {
+                        scene: scene.clone(),
+                        ..default()
+                    });
+                });
+            }
+            None => {
+                spawn_procedural_controller(&mut root, &mut meshes, &mut materials);
+            }
+        }
+    }
+}
+
+/// A handle along the grip's Z axis with a tracking ring around its front
+fn spawn_procedural_controller(
+    root: &mut EntityCommands,
+    meshes: &mut Assets<Mesh>,
+    materials: &mut Assets<PbrMaterial>,
+) {
+    let body = materials.add(PbrMaterial {
+        color: Color::srgb(0.1, 0.1, 0.12),
+        roughness: 0.5,
+        ..default()
+    });
+    let ring = materials.add(PbrMaterial {
+        color: Color::srgb(0.6, 0.6, 0.65),
+        roughness: 0.3,
+        ..default()
+    });
+    root.with_children(|parent| {
+        parent.spawn(MaterialMeshBundle {
+            mesh: meshes.add(Capsule3d::new(0.018, 0.08)),
+            material: body,
+            transform: Transform::from_xyz(0.0, 0.0, 0.01)
+                .with_rotation(Quat::from_rotation_x(std::f32::consts::FRAC_PI_2)),
+            ..default()
+        });
+        parent.spawn(MaterialMeshBundle {
+            mesh: meshes.add(Torus::new(0.03, 0.04)),
+            material: ring,
+            // Tilted back over the thumb like on most headset controllers
+            transform: Transform::from_xyz(0.0, 0.02, -0.06)
+                .with_rotation(Quat::from_rotation_x(0.6)),
+            ..default()
+        });
+    });
+}
+
+fn update_controllers(
+    poses: Res<ControllerPoses>,
+    mut controllers: Query<(Ref<ControllerModel>, &mut Transform, &mut Visibility)>,
+) {
+    for (controller, mut transform, mut visibility) in controllers.iter_mut() {
+        if !poses.is_changed() && !controller.is_added() {
+            continue;
+        }
+        match poses.get(controller.hand) {
+            Some(pose) => {
+                *transform = pose;
+                visibility.set_if_neq(Visibility::Inherited);
+            }
+            None => {
+                visibility.set_if_neq(Visibility::Hidden);
+            }
+        }
+    }
+}
@@ -0,0 +1,39 @@
+use bevy::asset::AssetPath;
+use bevy::prelude::*;
+use std::path::PathBuf;
+
+/// Shaders loaded by `load_shader!`, each with the fixed handle the materials refer to
+#[derive(Resource, Default)]
+struct DevShaders(Vec<(Handle<Shader>, Handle<Shader>)>);
+
+/// Loads the embedded shader at `path` and keeps `handle` a copy of it across reloads
+pub(crate) fn watch_shader(app: &mut App, handle: Handle<Shader>, path: PathBuf) {
+    if !app.world().contains_resource::<DevShaders>() {
+        app.init_resource::<DevShaders>();
+        app.add_systems(Last, sync_dev_shaders);
+    }
+    let path = AssetPath::from_path(&path).with_source("embedded").into_owned();
+    let loaded = app.world().resource::<AssetServer>().load(path);
+    app.world_mut()
+        .resource_mut::<DevShaders>()
+        .0
+        .push((loaded, handle));
+}
+
+fn sync_dev_shaders(
+    mut events: EventReader<AssetEvent<Shader>>,
+    dev_shaders: Res<DevShaders>,
+    mut shaders: ResMut<Assets<Shader>>,
+) {
+    for event in events.read() {
+        let (AssetEvent::LoadedWithDependencies { id } | AssetEvent::Modified { id }) = event else {
+            continue;
+        };
+        for (loaded, handle) in dev_shaders.0.iter().filter(|(loaded, _)| loaded.id() == *id) {
+            if let Some(shader) = shaders.get(loaded).cloned() {
+                debug!("Reloaded {:?}", shader.path);
+                shaders.insert(handle, shader);
+            }
+        }
+    }
+}
@@ -0,0 +1,59 @@
+use crate::lighting::buffer::ShLightingBuffer;
+use crate::materials::pbr::{MaterialReplaced, PbrMaterial};
+use crate::skytex::SkyGenerationTime;
+use bevy::diagnostic::{Diagnostic, DiagnosticPath, Diagnostics, RegisterDiagnostic};
+use bevy::prelude::*;
+
+/// Reports the material replacement and sky work of bevy_sk as Bevy diagnostics, so they show
+/// up next to the frame time in `LogDiagnosticsPlugin` and overlays. Needs `PbrPlugin`, not
+/// part of `SkPlugins`.
+pub struct SkDiagnosticsPlugin;
+
+impl SkDiagnosticsPlugin {
+    /// `StandardMaterial`s replaced by a `PbrMaterial` this frame
+    pub const MATERIALS_CONVERTED: DiagnosticPath =
+        DiagnosticPath::const_new("bevy_sk/materials_converted");
+    /// `PbrMaterial` assets alive
+    pub const PBR_MATERIALS: DiagnosticPath = DiagnosticPath::const_new("bevy_sk/pbr_materials");
+    /// Time a CPU sky cubemap generation task ran, in milliseconds
+    pub const SKY_GENERATION_TIME: DiagnosticPath =
+        DiagnosticPath::const_new("bevy_sk/sky_generation_time");
+    /// Uploads of the shared SH texture per second
+    pub const SH_UPDATES: DiagnosticPath = DiagnosticPath::const_new("bevy_sk/sh_updates");
+}
+
+impl Plugin for SkDiagnosticsPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_diagnostic(Diagnostic::new(Self::MATERIALS_CONVERTED))
+            .register_diagnostic(Diagnostic::new(Self::PBR_MATERIALS))
+            .register_diagnostic(Diagnostic::new(Self::SKY_GENERATION_TIME).with_suffix("ms"))
+            .register_diagnostic(Diagnostic::new(Self::SH_UPDATES).with_suffix("/s"));
+        // Without `SkyTexPlugin` the sky generation time is never measured
+        app.add_event::<SkyGenerationTime>();
+        app.add_systems(Last, measure);
+    }
+}
+
+fn measure(
+    mut diagnostics: Diagnostics,
+    time: Res<Time<Real>>,
+    mut replaced: EventReader<MaterialReplaced>,
+    mut generation_times: EventReader<SkyGenerationTime>,
+    materials: Res<Assets<PbrMaterial>>,
+    buffer: Option<Res<ShLightingBuffer>>,
+) {
+    let converted = replaced.read().count();
+    diagnostics.add_measurement(&SkDiagnosticsPlugin::MATERIALS_CONVERTED, || converted as f64);
+    diagnostics.add_measurement(&SkDiagnosticsPlugin::PBR_MATERIALS, || materials.len() as f64);
+    for SkyGenerationTime(duration) in generation_times.read() {
+        diagnostics.add_measurement(&SkDiagnosticsPlugin::SKY_GENERATION_TIME, || {
+            duration.as_secs_f64() * 1000.0
+        });
+    }
+    // Averaged over the history, a change every frame reads as the frame rate
+    let delta = time.delta_seconds_f64();
+    if let Some(buffer) = buffer.filter(|_| delta > 0.0) {
+        let updates = if buffer.is_changed() { 1.0 / delta } else { 0.0 };
+        diagnostics.add_measurement(&SkDiagnosticsPlugin::SH_UPDATES, || updates);
+    }
+}
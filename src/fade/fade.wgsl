@@ -0,0 +1,29 @@
+#import bevy_pbr::{
+    forward_io::{Vertex, VertexOutput},
+    mesh_view_bindings::view,
+}
+
+struct SkScreenFadeMaterial {
+    color: vec4<f32>,
+};
+
+@group(2) @binding(0)
+var<uniform> material: SkScreenFadeMaterial;
+
+@vertex
+fn vertex(vertex: Vertex) -> VertexOutput {
+    var out: VertexOutput;
+    // The mesh is already in clip space, covering every view it is drawn into
+    out.position = vec4(vertex.position.xy, 1.0, 1.0);
+    out.world_position = vec4(view.world_position, 1.0);
+    out.world_normal = vec3(0.0, 0.0, 1.0);
+#ifdef VERTEX_OUTPUT_INSTANCE_INDEX
+    out.instance_index = vertex.instance_index;
+#endif
+    return out;
+}
+
+@fragment
+fn fragment(in: VertexOutput) -> @location(0) vec4<f32> {
+    return material.color;
+}
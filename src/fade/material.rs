@@ -0,0 +1,90 @@
+use bevy::prelude::*;
+use bevy::render::mesh::MeshVertexBufferLayoutRef;
+use bevy::render::render_asset::RenderAssets;
+use bevy::render::render_resource::{
+    AsBindGroup, AsBindGroupShaderType, CompareFunction, RenderPipelineDescriptor, ShaderRef,
+    ShaderType, SpecializedMeshPipelineError,
+};
+use bevy::render::texture::GpuImage;
+
+const SHADER_HANDLE: Handle<Shader> = Handle::weak_from_u128(0x9c4d2e7b61f0);
+
+/// Registers [`SkScreenFadeMaterial`], added by `ScreenFadePlugin`
+pub struct SkScreenFadeMaterialPlugin;
+
+impl Plugin for SkScreenFadeMaterialPlugin {
+    fn build(&self, app: &mut App) {
+        load_shader!(app, SHADER_HANDLE, "fade.wgsl");
+        app.add_plugins(MaterialPlugin::<SkScreenFadeMaterial> {
+            prepass_enabled: false,
+            shadows_enabled: false,
+            ..default()
+        });
+        app.register_asset_reflect::<SkScreenFadeMaterial>();
+    }
+}
+
+/// Covers every view it is drawn into with `color`, sorted after all other transparent
+/// meshes. Expects a triangle covering clip space, whatever the entity's transform.
+#[derive(Asset, AsBindGroup, Reflect, PartialEq, Debug, Clone)]
+#[uniform(0, SkScreenFadeMaterialUniform)]
+pub struct SkScreenFadeMaterial {
+    pub color: Color,
+}
+
+impl Default for SkScreenFadeMaterial {
+    fn default() -> Self {
+        Self {
+            color: Color::NONE,
+        }
+    }
+}
+
+#[derive(Clone, Default, ShaderType)]
+pub struct SkScreenFadeMaterialUniform {
+    pub color: Vec4,
+}
+
+impl AsBindGroupShaderType<SkScreenFadeMaterialUniform> for SkScreenFadeMaterial {
+    fn as_bind_group_shader_type(
+        &self,
+        _: &RenderAssets<GpuImage>,
+    ) -> SkScreenFadeMaterialUniform {
+        SkScreenFadeMaterialUniform {
+            color: self.color.to_linear().to_vec4(),
+        }
+    }
+}
+
+impl Material for SkScreenFadeMaterial {
+    fn vertex_shader() -> ShaderRef {
+        SHADER_HANDLE.into()
+    }
+
+    fn fragment_shader() -> ShaderRef {
+        SHADER_HANDLE.into()
+    }
+
+    fn alpha_mode(&self) -> AlphaMode {
+        AlphaMode::Blend
+    }
+
+    fn depth_bias(&self) -> f32 {
+        // Transparent meshes are drawn from the far end, this sorts the fade after all of them
+        1.0e9
+    }
+
+    fn specialize(
+        _pipeline: &bevy::pbr::MaterialPipeline<Self>,
+        descriptor: &mut RenderPipelineDescriptor,
+        _layout: &MeshVertexBufferLayoutRef,
+        _key: bevy::pbr::MaterialPipelineKey<Self>,
+    ) -> Result<(), SpecializedMeshPipelineError> {
+        descriptor.primitive.cull_mode = None;
+        if let Some(depth_stencil) = descriptor.depth_stencil.as_mut() {
+            depth_stencil.depth_compare = CompareFunction::Always;
+            depth_stencil.depth_write_enabled = false;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,193 @@
+pub mod material;
+
+use crate::fade::material::{SkScreenFadeMaterial, SkScreenFadeMaterialPlugin};
+use bevy::pbr::{NotShadowCaster, NotShadowReceiver};
+use bevy::prelude::*;
+use bevy::render::mesh::{Indices, PrimitiveTopology};
+use bevy::render::render_asset::RenderAssetUsages;
+use bevy::render::view::{NoFrustumCulling, VisibilitySystems};
+
+/// Fades every view to a color and back through [`ScreenFade`], e.g. to hide scene loads and
+/// teleports. Drawn into each view of the headset, so both eyes fade together.
+pub struct ScreenFadePlugin;
+
+impl Plugin for ScreenFadePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins(SkScreenFadeMaterialPlugin);
+        app.init_resource::<ScreenFade>();
+        app.add_event::<ScreenFadeFinished>();
+        app.register_type::<(ScreenFade, FadeEasing)>();
+        app.add_systems(
+            PostUpdate,
+            update_screen_fade.before(VisibilitySystems::VisibilityPropagate),
+        );
+    }
+}
+
+/// How much the views are covered by `color`, animated by [`ScreenFade::fade_out`] and
+/// [`ScreenFade::fade_in`]. Only cameras on the default `RenderLayers` see the fade.
+#[derive(Resource, Reflect, Clone, Debug, PartialEq)]
+#[reflect(Resource)]
+pub struct ScreenFade {
+    pub color: Color,
+    pub easing: FadeEasing,
+    amount: f32,
+    from: f32,
+    target: f32,
+    duration: f32,
+    elapsed: f32,
+}
+
+impl Default for ScreenFade {
+    fn default() -> Self {
+        Self {
+            color: Color::BLACK,
+            easing: FadeEasing::default(),
+            amount: 0.0,
+            from: 0.0,
+            target: 0.0,
+            duration: 0.0,
+            elapsed: 0.0,
+        }
+    }
+}
+
+impl ScreenFade {
+    /// Covers the views with `color` over `seconds`
+    pub fn fade_out(&mut self, seconds: f32) {
+        self.fade_to(1.0, seconds);
+    }
+
+    /// Uncovers the views again over `seconds`
+    pub fn fade_in(&mut self, seconds: f32) {
+        self.fade_to(0.0, seconds);
+    }
+
+    /// Fades from the current amount to `target`, 0 clear to 1 covered, over `seconds`
+    pub fn fade_to(&mut self, target: f32, seconds: f32) {
+        self.from = self.amount;
+        self.target = target.clamp(0.0, 1.0);
+        self.duration = seconds.max(0.0);
+        self.elapsed = 0.0;
+    }
+
+    /// Jumps to `amount` without fading
+    pub fn set(&mut self, amount: f32) {
+        self.fade_to(amount, 0.0);
+        self.amount = self.target;
+    }
+
+    /// How much the views are covered right now, 0 to 1
+    pub fn amount(&self) -> f32 {
+        self.amount
+    }
+
+    pub fn is_fading(&self) -> bool {
+        self.amount != self.target
+    }
+
+    /// Whether the views are fully covered, e.g. to start a scene load once it is
+    pub fn is_covered(&self) -> bool {
+        self.amount >= 1.0
+    }
+}
+
+/// Shape of a [`ScreenFade`] over its duration
+#[derive(Reflect, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum FadeEasing {
+    Linear,
+    /// Starts and ends slowly
+    #[default]
+    SmoothStep,
+    /// Starts slowly
+    EaseIn,
+    /// Ends slowly
+    EaseOut,
+}
+
+impl FadeEasing {
+    /// Eased progress at `t` from 0 to 1
+    pub fn ease(self, t: f32) -> f32 {
+        let t = t.clamp(0.0, 1.0);
+        match self {
+            Self::Linear => t,
+            Self::SmoothStep => t * t * (3.0 - 2.0 * t),
+            Self::EaseIn => t * t,
+            Self::EaseOut => t * (2.0 - t),
+        }
+    }
+}
+
+/// Sent when a [`ScreenFade`] reaches its target
+#[derive(Event, Clone, Copy, Debug, PartialEq)]
+pub struct ScreenFadeFinished {
+    /// The amount faded to, 1 when the views are covered
+    pub amount: f32,
+}
+
+/// The fullscreen triangle drawing the [`ScreenFade`]
+#[derive(Component)]
+struct ScreenFadeOverlay;
+
+fn update_screen_fade(
+    mut commands: Commands,
+    time: Res<Time<Real>>,
+    mut fade: ResMut<ScreenFade>,
+    mut finished: EventWriter<ScreenFadeFinished>,
+    mut overlays: Query<(&Handle<SkScreenFadeMaterial>, &mut Visibility), With<ScreenFadeOverlay>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<SkScreenFadeMaterial>>,
+) {
+    if fade.is_fading() {
+        // Real time keeps fading while the app is paused, capped so a long loading frame
+        // doesn't skip the fade
+        fade.elapsed += time.delta_seconds().min(0.1);
+        let t = if fade.duration > 0.0 {
+            fade.elapsed / fade.duration
+        } else {
+            1.0
+        };
+        fade.amount = fade.from + (fade.target - fade.from) * fade.easing.ease(t);
+        if t >= 1.0 {
+            fade.amount = fade.target;
+            finished.send(ScreenFadeFinished {
+                amount: fade.amount,
+            });
+        }
+    }
+
+    let color = fade.color.with_alpha(fade.color.alpha() * fade.amount);
+    let Ok((material, mut visibility)) = overlays.get_single_mut() else {
+        commands.spawn((
+            MaterialMeshBundle {
+                mesh: meshes.add(fullscreen_triangle()),
+                material: materials.add(SkScreenFadeMaterial { color }),
+                visibility: Visibility::Hidden,
+                ..default()
+            },
+            ScreenFadeOverlay,
+            NoFrustumCulling,
+            NotShadowCaster,
+            NotShadowReceiver,
+        ));
+        return;
+    };
+    visibility.set_if_neq(if fade.amount > 0.0 {
+        Visibility::Inherited
+    } else {
+        Visibility::Hidden
+    });
+    if let Some(material) = materials.get_mut(material).filter(|m| m.color != color) {
+        material.color = color;
+    }
+}
+
+/// A triangle covering clip space
+fn fullscreen_triangle() -> Mesh {
+    Mesh::new(PrimitiveTopology::TriangleList, RenderAssetUsages::RENDER_WORLD)
+        .with_inserted_attribute(
+            Mesh::ATTRIBUTE_POSITION,
+            vec![[-1.0, -1.0, 0.0], [3.0, -1.0, 0.0], [-1.0, 3.0, 0.0]],
+        )
+        .with_inserted_indices(Indices::U32(vec![0, 1, 2]))
+}
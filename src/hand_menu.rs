@@ -0,0 +1,374 @@
+use crate::hands::{Hand, HandJointId, HandJoints, HandPointers};
+use crate::interaction::HandInput;
+use crate::materials::text::{SkText, TextAlign};
+use crate::materials::ui::SkUiMaterial;
+use crate::ui::{UiAssets, UiTheme};
+use bevy::ecs::system::SystemParam;
+use bevy::pbr::{NotShadowCaster, NotShadowReceiver};
+use bevy::prelude::*;
+use std::f32::consts::{FRAC_PI_2, TAU};
+
+/// The palm normal has to be within about 60 degrees of straight up to open the menu
+const PALM_UP: f32 = 0.5;
+
+/// StereoKit-style radial menu, like `HandMenuRadial`, for quick actions without a floating
+/// panel.
+///
+/// Pinching with the palm facing up opens the root layer of [`HandMenu`] around the pinch,
+/// facing the head. Moving the pinch into an item and releasing it selects the item, releasing
+/// in the center goes back a layer, or closes the root layer. Selected items send
+/// [`HandMenuSelected`]. Uses the colors and font of the `UiTheme`.
+pub struct HandMenuPlugin;
+
+impl Plugin for HandMenuPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<HandJoints>();
+        app.init_resource::<HandPointers>();
+        app.init_resource::<UiTheme>();
+        app.init_resource::<HandMenu>();
+        app.init_resource::<HandMenuState>();
+        app.add_event::<HandMenuSelected>();
+        app.register_type::<HandMenu>();
+        app.add_systems(Update, update_hand_menu);
+    }
+}
+
+/// Layers of the [`HandMenuPlugin`] menu, the first one opens with the menu. Without any
+/// the menu doesn't open.
+#[derive(Resource, Reflect, Clone, Debug, PartialEq)]
+#[reflect(Resource)]
+pub struct HandMenu {
+    pub layers: Vec<HandMenuLayer>,
+    /// Size of the ring the items sit on, in meters
+    pub radius: f32,
+}
+
+impl Default for HandMenu {
+    fn default() -> Self {
+        Self {
+            layers: Vec::new(),
+            radius: 0.08,
+        }
+    }
+}
+
+impl HandMenu {
+    pub fn new(layers: impl IntoIterator<Item = HandMenuLayer>) -> Self {
+        Self {
+            layers: layers.into_iter().collect(),
+            ..default()
+        }
+    }
+}
+
+/// A ring of items, named to be opened by [`HandMenuAction::Layer`]
+#[derive(Reflect, Clone, Debug, Default, PartialEq)]
+pub struct HandMenuLayer {
+    pub name: String,
+    /// Laid out clockwise from the top
+    pub items: Vec<HandMenuItem>,
+}
+
+impl HandMenuLayer {
+    pub fn new(name: impl Into<String>, items: impl IntoIterator<Item = HandMenuItem>) -> Self {
+        Self {
+            name: name.into(),
+            items: items.into_iter().collect(),
+        }
+    }
+}
+
+#[derive(Reflect, Clone, Debug, PartialEq)]
+pub struct HandMenuItem {
+    pub text: String,
+    pub action: HandMenuAction,
+}
+
+impl HandMenuItem {
+    /// Sends [`HandMenuSelected`] and closes the menu
+    pub fn select(text: impl Into<String>) -> Self {
+        Self {
+            text: text.into(),
+            action: HandMenuAction::Select,
+        }
+    }
+
+    /// Opens the layer named `layer`
+    pub fn layer(text: impl Into<String>, layer: impl Into<String>) -> Self {
+        Self {
+            text: text.into(),
+            action: HandMenuAction::Layer(layer.into()),
+        }
+    }
+
+    pub fn back(text: impl Into<String>) -> Self {
+        Self {
+            text: text.into(),
+            action: HandMenuAction::Back,
+        }
+    }
+}
+
+/// What selecting a [`HandMenuItem`] does
+#[derive(Reflect, Clone, Debug, PartialEq, Eq)]
+pub enum HandMenuAction {
+    /// Sends [`HandMenuSelected`] and closes the menu
+    Select,
+    /// Opens a submenu, by [`HandMenuLayer::name`]
+    Layer(String),
+    /// Returns to the layer the current one was opened from
+    Back,
+    Close,
+}
+
+/// A [`HandMenuAction::Select`] item was selected
+#[derive(Event, Clone, Debug, PartialEq, Eq)]
+pub struct HandMenuSelected {
+    /// Name of the item's layer
+    pub layer: String,
+    /// Text of the item
+    pub item: String,
+    pub hand: Hand,
+}
+
+/// The open menu, kept by `HandMenuPlugin`
+#[derive(Resource, Default)]
+struct HandMenuState {
+    open: Option<OpenMenu>,
+}
+
+struct OpenMenu {
+    hand: Hand,
+    /// Center of the ring, +Z towards the head
+    pose: Transform,
+    /// Indices into [`HandMenu::layers`], the current layer last
+    layers: Vec<usize>,
+    hovered: Option<usize>,
+    root: Option<Entity>,
+    /// One per item of the current layer
+    items: Vec<Entity>,
+}
+
+impl OpenMenu {
+    fn layer(&self) -> usize {
+        self.layers.last().copied().unwrap_or_default()
+    }
+
+    /// The item whose slice `point` is in, none in the center
+    fn item_at(&self, point: Vec3, count: usize, radius: f32) -> Option<usize> {
+        let local = self.pose.compute_affine().inverse().transform_point3(point);
+        if count == 0 || local.truncate().length() < radius * 0.4 {
+            return None;
+        }
+        // Clockwise from the top, like the layout
+        let angle = (FRAC_PI_2 - local.y.atan2(local.x)).rem_euclid(TAU);
+        let slice = TAU / count as f32;
+        Some(((angle / slice).round() as usize) % count)
+    }
+}
+
+/// Whether the palm of `hand` faces up
+fn palm_up(joints: &HandJoints, hand: Hand) -> bool {
+    joints.get(hand).is_some_and(|pose| {
+        // +Y of the palm joint points out of the back of the hand
+        let normal = pose.joint(HandJointId::Palm).rotation * Vec3::NEG_Y;
+        normal.dot(Vec3::Y) > PALM_UP
+    })
+}
+
+#[derive(SystemParam)]
+struct HandMenuInput<'w, 's> {
+    joints: Res<'w, HandJoints>,
+    pointers: Res<'w, HandPointers>,
+    cameras: Query<'w, 's, &'static GlobalTransform, With<Camera3d>>,
+}
+
+#[derive(SystemParam)]
+struct HandMenuVisuals<'w, 's> {
+    theme: Res<'w, UiTheme>,
+    assets: UiAssets<'w, 's>,
+    materials: Query<'w, 's, &'static mut Handle<SkUiMaterial>>,
+}
+
+fn update_hand_menu(
+    mut commands: Commands,
+    menu: Res<HandMenu>,
+    mut state: ResMut<HandMenuState>,
+    input: HandMenuInput,
+    visuals: HandMenuVisuals,
+    mut selected: EventWriter<HandMenuSelected>,
+    mut was_pinching: Local<[bool; 2]>,
+) {
+    let HandMenuInput {
+        joints,
+        pointers,
+        cameras,
+    } = input;
+    let inputs = [Hand::Left, Hand::Right].map(|hand| HandInput::new(hand, &joints, &pointers));
+    let pinching = inputs.map(|input| input.is_some_and(|input| input.pinching));
+    let previous = std::mem::replace(&mut *was_pinching, pinching);
+
+    if state.open.is_none() {
+        if menu.layers.is_empty() {
+            return;
+        }
+        let opening = inputs.into_iter().flatten().find(|input| {
+            input.pinching && !previous[input.hand as usize] && palm_up(&joints, input.hand)
+        });
+        let Some((input, center)) = opening.and_then(|input| Some((input, input.pinch_point?)))
+        else {
+            return;
+        };
+        let head = cameras.iter().next().map_or(Vec3::ZERO, |camera| camera.translation());
+        let pose = Transform::from_translation(center).looking_to(center - head, Vec3::Y);
+        state.open = Some(OpenMenu {
+            hand: input.hand,
+            pose,
+            layers: vec![0],
+            hovered: None,
+            root: None,
+            items: Vec::new(),
+        });
+    }
+    let Some(open) = state.open.as_mut() else {
+        return;
+    };
+    let Some(input) = inputs[open.hand as usize] else {
+        // The menu closes with the hand dropping out of tracking
+        if let Some(root) = open.root {
+            commands.entity(root).despawn_recursive();
+        }
+        state.open = None;
+        return;
+    };
+
+    let layer = &menu.layers[open.layer().min(menu.layers.len() - 1)];
+    let radius = menu.radius;
+    let hovered = input
+        .pinch_point
+        .and_then(|point| open.item_at(point, layer.items.len(), radius));
+
+    let mut changed = open.root.is_none();
+    if !input.pinching && previous[open.hand as usize] {
+        let action = match hovered.map(|item| &layer.items[item]) {
+            Some(item) => item.action.clone(),
+            None => HandMenuAction::Back,
+        };
+        match action {
+            HandMenuAction::Select => {
+                let item = &layer.items[hovered.unwrap_or_default()];
+                selected.send(HandMenuSelected {
+                    layer: layer.name.clone(),
+                    item: item.text.clone(),
+                    hand: open.hand,
+                });
+                open.layers.clear();
+            }
+            HandMenuAction::Layer(name) => {
+                match menu.layers.iter().position(|layer| layer.name == name) {
+                    Some(index) => open.layers.push(index),
+                    None => warn!("HandMenu has no layer named {name}"),
+                }
+            }
+            HandMenuAction::Back => {
+                open.layers.pop();
+            }
+            HandMenuAction::Close => open.layers.clear(),
+        }
+        if open.layers.is_empty() {
+            if let Some(root) = open.root {
+                commands.entity(root).despawn_recursive();
+            }
+            state.open = None;
+            return;
+        }
+        changed = true;
+    }
+
+    let HandMenuVisuals {
+        theme,
+        mut assets,
+        mut materials,
+    } = visuals;
+    assets.update(&theme);
+    if changed {
+        if let Some(root) = open.root.take() {
+            commands.entity(root).despawn_recursive();
+        }
+        open.hovered = None;
+        open.items.clear();
+        spawn_layer(&mut commands, open, &menu, &theme, &assets);
+    }
+
+    // Highlights the item the pinch would select
+    let hovered = if changed { None } else { hovered };
+    if open.hovered != hovered {
+        open.hovered = hovered;
+        for (i, entity) in open.items.iter().enumerate() {
+            if let Ok(mut material) = materials.get_mut(*entity) {
+                *material = if Some(i) == hovered {
+                    assets.cache.active.clone()
+                } else {
+                    assets.cache.element.clone()
+                };
+            }
+        }
+    }
+}
+
+/// The items of the current layer around the menu center, with their labels
+fn spawn_layer(
+    commands: &mut Commands,
+    open: &mut OpenMenu,
+    menu: &HandMenu,
+    theme: &UiTheme,
+    assets: &UiAssets,
+) {
+    let layer = &menu.layers[open.layer().min(menu.layers.len() - 1)];
+    let root = commands.spawn(SpatialBundle::from_transform(open.pose)).id();
+    let count = layer.items.len().max(1) as f32;
+    for (i, item) in layer.items.iter().enumerate() {
+        let angle = FRAC_PI_2 - i as f32 * TAU / count;
+        let center = Vec2::from_angle(angle).extend(0.0) * menu.radius;
+        let width = (menu.radius * TAU / count * 0.85).min(menu.radius);
+        let entity = commands
+            .spawn((
+                MaterialMeshBundle {
+                    mesh: assets.cache.cube.clone(),
+                    material: assets.cache.element.clone(),
+                    transform: Transform::from_translation(center - Vec3::Z * theme.depth * 0.5)
+                        .with_scale(Vec3::new(width, theme.row_height, theme.depth)),
+                    ..default()
+                },
+                NotShadowCaster,
+                NotShadowReceiver,
+            ))
+            .set_parent(root)
+            .id();
+        open.items.push(entity);
+
+        let (Some(material), Some(font)) = (assets.cache.text.clone(), theme.font.clone()) else {
+            continue;
+        };
+        commands
+            .spawn((
+                MaterialMeshBundle {
+                    material,
+                    transform: Transform::from_translation(
+                        center + Vec3::new(0.0, -theme.text_size * 0.35, 0.0005),
+                    ),
+                    ..default()
+                },
+                SkText {
+                    text: item.text.clone(),
+                    font,
+                    size: theme.text_size,
+                    align: TextAlign::Center,
+                },
+                NotShadowCaster,
+                NotShadowReceiver,
+            ))
+            .set_parent(root);
+    }
+    open.root = Some(root);
+}
@@ -0,0 +1,50 @@
+#import bevy_pbr::{
+    forward_io::VertexOutput,
+    mesh_view_bindings::view,
+}
+#import bevy_sk::{
+    lighting::{sk_lighting, sk_sh_exposure},
+    pbr_types::sk_alpha_output,
+}
+
+struct SkHandMaterial {
+    color: vec4<f32>,
+    rim_color: vec4<f32>,
+    palm_fade: f32,
+    fingertip_fade: f32,
+    sh_slot: u32,
+    flags: u32,
+};
+
+@group(2) @binding(0)
+var<uniform> material: SkHandMaterial;
+@group(2) @binding(1)
+var sh_buffer: texture_2d<f32>;
+
+// Reads the SH of a ShLightingBuffer slot, one row of 9 texels per slot
+fn sk_hand_sh(slot: u32) -> array<vec3<f32>, 9> {
+    let first = textureLoad(sh_buffer, vec2<i32>(0, i32(slot)), 0);
+    let exposure = sk_sh_exposure(first.a, view.exposure);
+    var sh: array<vec3<f32>, 9>;
+    for (var i = 0u; i < 9u; i += 1u) {
+        sh[i] = textureLoad(sh_buffer, vec2<i32>(i32(i), i32(slot)), 0).rgb * exposure;
+    }
+    return sh;
+}
+
+@fragment
+fn fragment(in: VertexOutput) -> @location(0) vec4<f32> {
+    let N = normalize(in.world_normal);
+    let V = normalize(view.world_position - in.world_position.xyz);
+    let rim = pow(1.0 - saturate(dot(N, V)), 2.0);
+
+    // uv.y runs along each finger, from its palm end to the tip
+    let along = in.uv.y;
+    let palm = smoothstep(0.0, max(material.palm_fade, 0.0001), along);
+    let tip = 1.0 - smoothstep(1.0 - max(material.fingertip_fade, 0.0001), 1.0, along);
+
+    let color = material.color.rgb * sk_lighting(N, sk_hand_sh(material.sh_slot))
+        + material.rim_color.rgb * rim;
+    let alpha = saturate(material.color.a + rim * material.rim_color.a) * palm * tip;
+    return sk_alpha_output(material.flags, color, alpha);
+}
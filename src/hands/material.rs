@@ -0,0 +1,91 @@
+use crate::lighting::buffer::{ShSlot, SH_BUFFER_IMAGE_HANDLE};
+use crate::materials::pbr::alpha_mode_flags;
+use bevy::prelude::*;
+use bevy::render::render_asset::RenderAssets;
+use bevy::render::render_resource::{AsBindGroup, AsBindGroupShaderType, ShaderRef, ShaderType};
+use bevy::render::texture::GpuImage;
+
+const SHADER_HANDLE: Handle<Shader> = Handle::weak_from_u128(0x7a41c9e25d83);
+
+/// Registers [`SkHandMaterial`], added by `HandsPlugin`
+pub struct SkHandMaterialPlugin;
+
+impl Plugin for SkHandMaterialPlugin {
+    fn build(&self, app: &mut App) {
+        load_shader!(app, SHADER_HANDLE, "hand.wgsl");
+        app.add_plugins(MaterialPlugin::<SkHandMaterial> {
+            prepass_enabled: false,
+            shadows_enabled: false,
+            ..default()
+        });
+        app.register_asset_reflect::<SkHandMaterial>();
+    }
+}
+
+/// StereoKit's default hand look, a translucent SH lit surface with a fresnel rim that fades
+/// in from the palm and out at the fingertips. Expects the UVs of the `HandsPlugin` mesh,
+/// which run from 0 at the palm end of each finger to 1 at its tip.
+#[derive(Asset, AsBindGroup, Reflect, PartialEq, Debug, Clone)]
+#[uniform(0, SkHandMaterialUniform)]
+pub struct SkHandMaterial {
+    pub color: Color,
+    /// Color added towards the silhouette
+    pub rim_color: Color,
+    /// Part of each finger's length fading in from the palm, 0 disables it
+    pub palm_fade: f32,
+    /// Part of each finger's length fading out towards the tip, 0 disables it
+    pub fingertip_fade: f32,
+    /// Slot of the shared `ShLightingBuffer` the hands are lit by
+    pub lighting: ShSlot,
+    /// The shared SH texture, leave this at its default
+    #[texture(1, sample_type = "float", filterable = false)]
+    pub sh_buffer: Handle<Image>,
+    pub alpha_mode: AlphaMode,
+}
+
+impl Default for SkHandMaterial {
+    fn default() -> Self {
+        Self {
+            color: Color::srgba(1.0, 1.0, 1.0, 0.9),
+            rim_color: Color::srgb(0.4, 0.4, 0.4),
+            palm_fade: 0.35,
+            fingertip_fade: 0.1,
+            lighting: ShSlot::GLOBAL,
+            sh_buffer: SH_BUFFER_IMAGE_HANDLE,
+            alpha_mode: AlphaMode::Blend,
+        }
+    }
+}
+
+#[derive(Clone, Default, ShaderType)]
+pub struct SkHandMaterialUniform {
+    pub color: Vec4,
+    pub rim_color: Vec4,
+    pub palm_fade: f32,
+    pub fingertip_fade: f32,
+    pub sh_slot: u32,
+    pub flags: u32,
+}
+
+impl AsBindGroupShaderType<SkHandMaterialUniform> for SkHandMaterial {
+    fn as_bind_group_shader_type(&self, _: &RenderAssets<GpuImage>) -> SkHandMaterialUniform {
+        SkHandMaterialUniform {
+            color: self.color.to_linear().to_vec4(),
+            rim_color: self.rim_color.to_linear().to_vec4(),
+            palm_fade: self.palm_fade,
+            fingertip_fade: self.fingertip_fade,
+            sh_slot: self.lighting.0,
+            flags: alpha_mode_flags(self.alpha_mode).0.bits(),
+        }
+    }
+}
+
+impl Material for SkHandMaterial {
+    fn fragment_shader() -> ShaderRef {
+        SHADER_HANDLE.into()
+    }
+
+    fn alpha_mode(&self) -> AlphaMode {
+        self.alpha_mode
+    }
+}
@@ -0,0 +1,329 @@
+pub mod material;
+
+use crate::hands::material::{SkHandMaterial, SkHandMaterialPlugin};
+use bevy::pbr::{NotShadowCaster, NotShadowReceiver};
+use bevy::prelude::*;
+use bevy::render::mesh::skinning::{SkinnedMesh, SkinnedMeshInverseBindposes};
+use bevy::render::mesh::{Indices, PrimitiveTopology, VertexAttributeValues};
+use bevy::render::render_asset::RenderAssetUsages;
+use bevy::render::view::NoFrustumCulling;
+use bevy::transform::TransformSystem;
+use std::f32::consts::TAU;
+
+/// Joints per hand, in the order of `XrHandJointEXT`
+pub const HAND_JOINT_COUNT: usize = 26;
+
+/// Vertices around each finger ring
+const RING_SLICES: u32 = 8;
+
+/// The palm to tip joints the finger tubes are built along
+const FINGERS: [&[HandJointId]; 5] = {
+    use HandJointId::*;
+    [
+        &[ThumbMetacarpal, ThumbProximal, ThumbDistal, ThumbTip],
+        &[IndexMetacarpal, IndexProximal, IndexIntermediate, IndexDistal, IndexTip],
+        &[MiddleMetacarpal, MiddleProximal, MiddleIntermediate, MiddleDistal, MiddleTip],
+        &[RingMetacarpal, RingProximal, RingIntermediate, RingDistal, RingTip],
+        &[LittleMetacarpal, LittleProximal, LittleIntermediate, LittleDistal, LittleTip],
+    ]
+};
+
+/// Draws StereoKit-style hands from the poses in [`HandJoints`], with a [`SkHandMaterial`]
+/// that can be swapped on the [`HandMesh`] entities. Hands without a pose are hidden.
+pub struct HandsPlugin;
+
+impl Plugin for HandsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins(SkHandMaterialPlugin);
+        app.init_resource::<HandJoints>();
+        app.init_resource::<HandPointers>();
+        app.register_type::<(HandJoints, HandPointers, HandMesh)>();
+        app.add_systems(Startup, spawn_hands);
+        app.add_systems(
+            PostUpdate,
+            update_hands.before(TransformSystem::TransformPropagate),
+        );
+    }
+}
+
+/// Left or right hand
+#[derive(Reflect, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Hand {
+    Left,
+    Right,
+}
+
+/// A hand joint, in the order of `XrHandJointEXT`
+#[derive(Reflect, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum HandJointId {
+    Palm,
+    Wrist,
+    ThumbMetacarpal,
+    ThumbProximal,
+    ThumbDistal,
+    ThumbTip,
+    IndexMetacarpal,
+    IndexProximal,
+    IndexIntermediate,
+    IndexDistal,
+    IndexTip,
+    MiddleMetacarpal,
+    MiddleProximal,
+    MiddleIntermediate,
+    MiddleDistal,
+    MiddleTip,
+    RingMetacarpal,
+    RingProximal,
+    RingIntermediate,
+    RingDistal,
+    RingTip,
+    LittleMetacarpal,
+    LittleProximal,
+    LittleIntermediate,
+    LittleDistal,
+    LittleTip,
+}
+
+/// World space pose of a hand joint. Like in OpenXR its -Z points along the bone towards the
+/// fingertip and +Y out of the back of the hand.
+#[derive(Reflect, Clone, Copy, Debug, PartialEq)]
+pub struct HandJoint {
+    pub position: Vec3,
+    pub rotation: Quat,
+    /// Distance from the joint to the skin
+    pub radius: f32,
+}
+
+impl Default for HandJoint {
+    fn default() -> Self {
+        Self {
+            position: Vec3::ZERO,
+            rotation: Quat::IDENTITY,
+            radius: 0.01,
+        }
+    }
+}
+
+/// Every joint of a tracked hand
+#[derive(Reflect, Clone, Copy, Debug, Default, PartialEq)]
+pub struct HandPose {
+    /// Indexed by [`HandJointId`]
+    pub joints: [HandJoint; HAND_JOINT_COUNT],
+}
+
+impl HandPose {
+    pub fn joint(&self, id: HandJointId) -> &HandJoint {
+        &self.joints[id as usize]
+    }
+
+    pub fn joint_mut(&mut self, id: HandJointId) -> &mut HandJoint {
+        &mut self.joints[id as usize]
+    }
+}
+
+/// The latest hand poses, `None` for a hand that isn't tracked.
+///
+/// Filled by whatever tracks the hands, e.g. a system copying the joints of the
+/// `bevy_mod_openxr` hand tracker, or [`FlatscreenSimPlugin`](crate::sim::FlatscreenSimPlugin)
+/// on desktop.
+#[derive(Resource, Reflect, Clone, Debug, Default, PartialEq)]
+#[reflect(Resource)]
+pub struct HandJoints {
+    pub left: Option<HandPose>,
+    pub right: Option<HandPose>,
+}
+
+impl HandJoints {
+    pub fn get(&self, hand: Hand) -> Option<&HandPose> {
+        match hand {
+            Hand::Left => self.left.as_ref(),
+            Hand::Right => self.right.as_ref(),
+        }
+    }
+
+    pub fn set(&mut self, hand: Hand, pose: Option<HandPose>) {
+        match hand {
+            Hand::Left => self.left = pose,
+            Hand::Right => self.right = pose,
+        }
+    }
+}
+
+/// Where each hand points and whether it pinches, `None` for a hand that isn't tracked.
+/// Filled alongside [`HandJoints`].
+#[derive(Resource, Reflect, Clone, Debug, Default, PartialEq)]
+#[reflect(Resource)]
+pub struct HandPointers {
+    pub left: Option<HandPointer>,
+    pub right: Option<HandPointer>,
+}
+
+impl HandPointers {
+    pub fn get(&self, hand: Hand) -> Option<&HandPointer> {
+        match hand {
+            Hand::Left => self.left.as_ref(),
+            Hand::Right => self.right.as_ref(),
+        }
+    }
+
+    pub fn set(&mut self, hand: Hand, pointer: Option<HandPointer>) {
+        match hand {
+            Hand::Left => self.left = pointer,
+            Hand::Right => self.right = pointer,
+        }
+    }
+}
+
+/// Pointer of a hand, like OpenXR's aim pose
+#[derive(Reflect, Clone, Copy, Debug, PartialEq)]
+pub struct HandPointer {
+    /// World space aim, pointing along its -Z
+    pub aim: Transform,
+    /// 0 for an open hand, 1 with thumb and index touching
+    pub pinch: f32,
+}
+
+impl HandPointer {
+    pub fn ray(&self) -> Ray3d {
+        Ray3d {
+            origin: self.aim.translation,
+            direction: self.aim.forward(),
+        }
+    }
+
+    /// Whether the pinch is far enough along to count as a click
+    pub fn pinching(&self) -> bool {
+        self.pinch > 0.8
+    }
+}
+
+/// The skinned mesh drawn for a hand, its joints are the entities of [`HandMesh::joints`]
+#[derive(Component, Reflect, Clone, Debug)]
+#[reflect(Component)]
+pub struct HandMesh {
+    pub hand: Hand,
+    /// Indexed by [`HandJointId`]
+    pub joints: Vec<Entity>,
+}
+
+/// Tubes around the finger joints, each ring skinned to its joint alone and sized by the
+/// joint's scale, so the unit rings in joint space become `HandJoint::radius` wide
+fn hand_mesh() -> Mesh {
+    let mut positions = Vec::new();
+    let mut normals = Vec::new();
+    let mut uvs = Vec::new();
+    let mut joint_indices = Vec::new();
+    let mut indices = Vec::new();
+
+    for finger in FINGERS {
+        let first = positions.len() as u32;
+        let rings = finger.len() as u32;
+        for (i, joint) in finger.iter().enumerate() {
+            // The tip ring sits half a radius back so the cap rounds off at the tip joint
+            let z = if i + 1 == finger.len() { 0.5 } else { 0.0 };
+            let along = (i + 1) as f32 / (rings + 1) as f32;
+            for slice in 0..RING_SLICES {
+                let angle = slice as f32 / RING_SLICES as f32 * TAU;
+                let (sin, cos) = angle.sin_cos();
+                positions.push([cos, sin, z]);
+                normals.push([cos, sin, 0.0]);
+                uvs.push([slice as f32 / RING_SLICES as f32, along]);
+                joint_indices.push([*joint as u16, 0, 0, 0]);
+            }
+        }
+        // Caps towards the wrist and at the fingertip
+        let base = positions.len() as u32;
+        positions.push([0.0, 0.0, 1.0]);
+        normals.push([0.0, 0.0, 1.0]);
+        uvs.push([0.5, 0.0]);
+        joint_indices.push([finger[0] as u16, 0, 0, 0]);
+        let tip = base + 1;
+        positions.push([0.0, 0.0, 0.0]);
+        normals.push([0.0, 0.0, -1.0]);
+        uvs.push([0.5, 1.0]);
+        joint_indices.push([finger[finger.len() - 1] as u16, 0, 0, 0]);
+
+        let vertex = |ring: u32, slice: u32| first + ring * RING_SLICES + slice % RING_SLICES;
+        for slice in 0..RING_SLICES {
+            for ring in 0..rings - 1 {
+                let (a, b) = (vertex(ring, slice), vertex(ring, slice + 1));
+                let (c, d) = (vertex(ring + 1, slice), vertex(ring + 1, slice + 1));
+                indices.extend([a, c, b, b, c, d]);
+            }
+            indices.extend([base, vertex(0, slice), vertex(0, slice + 1)]);
+            indices.extend([tip, vertex(rings - 1, slice + 1), vertex(rings - 1, slice)]);
+        }
+    }
+
+    let weights = vec![[1.0, 0.0, 0.0, 0.0]; positions.len()];
+    Mesh::new(PrimitiveTopology::TriangleList, RenderAssetUsages::RENDER_WORLD)
+        .with_inserted_attribute(Mesh::ATTRIBUTE_POSITION, positions)
+        .with_inserted_attribute(Mesh::ATTRIBUTE_NORMAL, normals)
+        .with_inserted_attribute(Mesh::ATTRIBUTE_UV_0, uvs)
+        .with_inserted_attribute(
+            Mesh::ATTRIBUTE_JOINT_INDEX,
+            VertexAttributeValues::Uint16x4(joint_indices),
+        )
+        .with_inserted_attribute(Mesh::ATTRIBUTE_JOINT_WEIGHT, weights)
+        .with_inserted_indices(Indices::U32(indices))
+}
+
+fn spawn_hands(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<SkHandMaterial>>,
+    mut bindposes: ResMut<Assets<SkinnedMeshInverseBindposes>>,
+) {
+    let mesh = meshes.add(hand_mesh());
+    let material = materials.add(SkHandMaterial::default());
+    // The mesh is built in joint space already
+    let inverse_bindposes = bindposes.add(vec![Mat4::IDENTITY; HAND_JOINT_COUNT]);
+    for hand in [Hand::Left, Hand::Right] {
+        let joints: Vec<Entity> = (0..HAND_JOINT_COUNT)
+            .map(|_| commands.spawn(TransformBundle::default()).id())
+            .collect();
+        commands.spawn((
+            MaterialMeshBundle {
+                mesh: mesh.clone(),
+                material: material.clone(),
+                visibility: Visibility::Hidden,
+                ..default()
+            },
+            SkinnedMesh {
+                inverse_bindposes: inverse_bindposes.clone(),
+                joints: joints.clone(),
+            },
+            HandMesh { hand, joints },
+            // The bounds of the joint space mesh say nothing about the posed hand
+            NoFrustumCulling,
+            NotShadowCaster,
+            NotShadowReceiver,
+        ));
+    }
+}
+
+fn update_hands(
+    poses: Res<HandJoints>,
+    mut hands: Query<(&HandMesh, &mut Visibility)>,
+    mut joints: Query<&mut Transform>,
+) {
+    if !poses.is_changed() {
+        return;
+    }
+    for (hand, mut visibility) in hands.iter_mut() {
+        let Some(pose) = poses.get(hand.hand) else {
+            visibility.set_if_neq(Visibility::Hidden);
+            continue;
+        };
+        visibility.set_if_neq(Visibility::Inherited);
+        for (entity, joint) in hand.joints.iter().zip(pose.joints.iter()) {
+            if let Ok(mut transform) = joints.get_mut(*entity) {
+                *transform = Transform {
+                    translation: joint.position,
+                    rotation: joint.rotation,
+                    scale: Vec3::splat(joint.radius),
+                };
+            }
+        }
+    }
+}
@@ -0,0 +1,122 @@
+use crate::lighting::buffer::{ShLightingBuffer, ShSlot};
+use crate::materials::pbr::PbrMaterial;
+use crate::skytex::SphericalHarmonics;
+use bevy::prelude::*;
+use bevy::window::PrimaryWindow;
+use bevy_inspector_egui::bevy_egui::{egui, EguiContext, EguiPlugin};
+use bevy_inspector_egui::bevy_inspector::ui_for_assets;
+use bevy_inspector_egui::DefaultInspectorConfigPlugin;
+
+/// Band and order of each SH coefficient, in the order `SphericalHarmonics` stores them
+const COEFFICIENT_NAMES: [&str; 9] = [
+    "L0", "L1,-1", "L1,0", "L1,1", "L2,-2", "L2,-1", "L2,0", "L2,1", "L2,2",
+];
+
+/// Egui windows to tune the SH lighting and every `PbrMaterial` while the app runs, needs
+/// `PbrPlugin`
+pub struct SkInspectorPlugin;
+
+impl Plugin for SkInspectorPlugin {
+    fn build(&self, app: &mut App) {
+        if !app.is_plugin_added::<EguiPlugin>() {
+            app.add_plugins(EguiPlugin);
+        }
+        if !app.is_plugin_added::<DefaultInspectorConfigPlugin>() {
+            app.add_plugins(DefaultInspectorConfigPlugin);
+        }
+        app.init_resource::<ShInspector>();
+        app.register_type::<ShInspector>();
+        app.add_systems(Update, inspector_windows);
+    }
+}
+
+/// State of the SH lighting window
+#[derive(Resource, Reflect, Clone, Debug)]
+#[reflect(Resource)]
+pub struct ShInspector {
+    /// `ShLightingBuffer` slot being edited
+    pub slot: ShSlot,
+    /// Writes every edit into the slot right away, otherwise only "Apply" does
+    pub live_preview: bool,
+    /// Largest magnitude the coefficient sliders reach
+    pub range: f32,
+    /// The edited SH, loaded from the slot whenever another one is picked
+    pub lighting: SphericalHarmonics,
+    loaded: Option<ShSlot>,
+}
+
+impl Default for ShInspector {
+    fn default() -> Self {
+        Self {
+            slot: ShSlot::GLOBAL,
+            live_preview: true,
+            range: 2.0,
+            lighting: SphericalHarmonics::default(),
+            loaded: None,
+        }
+    }
+}
+
+fn inspector_windows(world: &mut World) {
+    let Ok(context) = world
+        .query_filtered::<&mut EguiContext, With<PrimaryWindow>>()
+        .get_single(world)
+    else {
+        return;
+    };
+    let mut context = context.clone();
+
+    egui::Window::new("SH lighting").show(context.get_mut(), |ui| {
+        world.resource_scope(|world, mut inspector: Mut<ShInspector>| {
+            let Some(mut buffer) = world.get_resource_mut::<ShLightingBuffer>() else {
+                ui.label("No ShLightingBuffer, add PbrPlugin");
+                return;
+            };
+            sh_lighting_ui(ui, &mut inspector, &mut buffer);
+        });
+    });
+    egui::Window::new("PbrMaterials").show(context.get_mut(), |ui| {
+        egui::ScrollArea::vertical().show(ui, |ui| {
+            ui_for_assets::<PbrMaterial>(world, ui);
+        });
+    });
+}
+
+fn sh_lighting_ui(ui: &mut egui::Ui, inspector: &mut ShInspector, buffer: &mut ShLightingBuffer) {
+    let mut slot = inspector.slot.0;
+    ui.horizontal(|ui| {
+        ui.label("Slot");
+        ui.add(egui::DragValue::new(&mut slot));
+    });
+    inspector.slot = ShSlot(slot);
+    if inspector.loaded != Some(inspector.slot) {
+        inspector.lighting = buffer.get(inspector.slot).copied().unwrap_or_default();
+        inspector.loaded = Some(inspector.slot);
+    }
+    ui.checkbox(&mut inspector.live_preview, "Live preview");
+
+    let range = inspector.range;
+    let mut changed = false;
+    egui::Grid::new("sh_coefficients").show(ui, |ui| {
+        for (name, coefficient) in COEFFICIENT_NAMES
+            .iter()
+            .zip(inspector.lighting.coefficients.iter_mut())
+        {
+            ui.label(*name);
+            for value in [&mut coefficient.x, &mut coefficient.y, &mut coefficient.z] {
+                changed |= ui.add(egui::Slider::new(value, -range..=range)).changed();
+            }
+            ui.end_row();
+        }
+    });
+
+    ui.horizontal(|ui| {
+        if ui.button("Apply").clicked() || (changed && inspector.live_preview) {
+            buffer.set(inspector.slot, inspector.lighting);
+        }
+        // Reloads the slot, which also brings back lighting the sky wrote meanwhile
+        if ui.button("Revert").clicked() {
+            inspector.loaded = None;
+        }
+    });
+}
@@ -0,0 +1,314 @@
+use crate::bounds::{local_point_in_bounds, ray_intersect_bounds, Bounds, BoundsPlugin};
+use crate::hands::{Hand, HandJointId, HandJoints, HandPointers};
+use crate::materials::ui::{FingerTips, MAX_FINGER_TIPS};
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+
+/// Thumb and index tips closer than this pinch, for hands without a `HandPointer`
+const PINCH_DISTANCE: f32 = 0.02;
+
+/// StereoKit-style hand interaction with [`Interactable`] entities, poking [`Pressable`]s with
+/// the index fingertip and pinching [`Grabbable`]s, up close or along the hand's pointer ray.
+///
+/// Reads [`HandJoints`] and [`HandPointers`], and fills the `FingerTips` the UI materials
+/// glow around. Entities are tested against their [`Bounds`], which `BoundsPlugin` keeps for
+/// meshes, add one by hand to entities without a mesh.
+pub struct InteractionPlugin;
+
+impl Plugin for InteractionPlugin {
+    fn build(&self, app: &mut App) {
+        if !app.is_plugin_added::<BoundsPlugin>() {
+            app.add_plugins(BoundsPlugin);
+        }
+        app.init_resource::<HandJoints>();
+        app.init_resource::<HandPointers>();
+        app.add_event::<Poked>();
+        app.add_event::<Grabbed>();
+        app.add_event::<Released>();
+        app.register_type::<(Interactable, Pressable, Grabbable)>();
+        app.add_systems(Update, (update_finger_tips, interact));
+    }
+}
+
+/// An entity hands interact with, what they do is up to its [`Pressable`] and [`Grabbable`]
+#[derive(Component, Reflect, Clone, Copy, Debug, PartialEq)]
+#[reflect(Component)]
+pub struct Interactable {
+    /// Cleared to ignore the hands
+    pub enabled: bool,
+    /// The hand touching or pointing at the entity, updated every frame
+    pub hovered: Option<Hand>,
+}
+
+impl Default for Interactable {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            hovered: None,
+        }
+    }
+}
+
+/// A button pushed in through the front, +Z, face of its bounds, or clicked by pinching while
+/// pointing at it. Sends [`Poked`] when pressed.
+#[derive(Component, Reflect, Clone, Copy, Debug, PartialEq)]
+#[reflect(Component)]
+pub struct Pressable {
+    /// How far in meters the fingertip pushes in before the press activates
+    pub depth: f32,
+    /// How far along the press is, from 0 to 1, updated every frame
+    pub press: f32,
+    /// Whether the press activated and hasn't been released yet
+    pub pressed: bool,
+}
+
+impl Default for Pressable {
+    fn default() -> Self {
+        Self {
+            depth: 0.01,
+            press: 0.0,
+            pressed: false,
+        }
+    }
+}
+
+/// Follows the hand that pinches inside its bounds or while pointing at it until the pinch
+/// ends, sending [`Grabbed`] and [`Released`]
+#[derive(Component, Reflect, Clone, Copy, Debug, Default, PartialEq)]
+#[reflect(Component)]
+pub struct Grabbable {
+    pub grabbed_by: Option<Hand>,
+    /// The entity's pose relative to the grabbing hand
+    offset: Transform,
+}
+
+/// A [`Pressable`] was pressed
+#[derive(Event, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Poked {
+    pub entity: Entity,
+    pub hand: Hand,
+}
+
+/// A [`Grabbable`] started following `hand`
+#[derive(Event, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Grabbed {
+    pub entity: Entity,
+    pub hand: Hand,
+}
+
+/// A [`Grabbable`] was let go of
+#[derive(Event, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Released {
+    pub entity: Entity,
+    pub hand: Hand,
+}
+
+#[derive(SystemParam)]
+struct InteractionEvents<'w> {
+    poked: EventWriter<'w, Poked>,
+    grabbed: EventWriter<'w, Grabbed>,
+    released: EventWriter<'w, Released>,
+}
+
+/// What a hand can interact with this frame
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct HandInput {
+    pub hand: Hand,
+    /// Index fingertip
+    pub tip: Option<Vec3>,
+    /// Between thumb and index tip
+    pub pinch_point: Option<Vec3>,
+    pub ray: Option<Ray3d>,
+    pub pinching: bool,
+    /// The pose grabbed entities follow
+    pub frame: Transform,
+}
+
+impl HandInput {
+    pub fn new(hand: Hand, joints: &HandJoints, pointers: &HandPointers) -> Option<Self> {
+        let pose = joints.get(hand);
+        let pointer = pointers.get(hand);
+        let tip = pose.map(|pose| pose.joint(HandJointId::IndexTip).position);
+        let thumb = pose.map(|pose| pose.joint(HandJointId::ThumbTip).position);
+        let pinch_point = tip.zip(thumb).map(|(tip, thumb)| (tip + thumb) * 0.5);
+        let pinching = match (pointer, tip.zip(thumb)) {
+            (Some(pointer), _) => pointer.pinching(),
+            (None, Some((tip, thumb))) => tip.distance(thumb) < PINCH_DISTANCE,
+            (None, None) => return None,
+        };
+        let rotation = match (pointer, pose) {
+            (Some(pointer), _) => pointer.aim.rotation,
+            (None, Some(pose)) => pose.joint(HandJointId::Palm).rotation,
+            (None, None) => return None,
+        };
+        let translation = pinch_point.or(pointer.map(|p| p.aim.translation))?;
+        Some(Self {
+            hand,
+            tip,
+            pinch_point,
+            ray: pointer.map(|pointer| pointer.ray()),
+            pinching,
+            frame: Transform::from_translation(translation).with_rotation(rotation),
+        })
+    }
+}
+
+/// Index and thumb tips of both hands, for the glow of the UI materials
+fn update_finger_tips(joints: Res<HandJoints>, tips: Option<ResMut<FingerTips>>) {
+    let Some(mut tips) = tips.filter(|_| joints.is_changed()) else {
+        return;
+    };
+    tips.positions = [Hand::Left, Hand::Right]
+        .into_iter()
+        .filter_map(|hand| joints.get(hand))
+        .flat_map(|pose| {
+            [HandJointId::IndexTip, HandJointId::ThumbTip].map(|id| pose.joint(id).position)
+        })
+        .take(MAX_FINGER_TIPS)
+        .collect();
+}
+
+fn interact(
+    joints: Res<HandJoints>,
+    pointers: Res<HandPointers>,
+    mut interactables: Query<(
+        Entity,
+        &mut Interactable,
+        &Bounds,
+        &GlobalTransform,
+        Option<&mut Pressable>,
+        Option<(&mut Grabbable, &mut Transform)>,
+        Option<&Parent>,
+    )>,
+    parents: Query<&GlobalTransform>,
+    mut events: InteractionEvents,
+    mut was_pinching: Local<[bool; 2]>,
+) {
+    let hands: Vec<HandInput> = [Hand::Left, Hand::Right]
+        .into_iter()
+        .filter_map(|hand| HandInput::new(hand, &joints, &pointers))
+        .collect();
+    let pinch_started = |input: &HandInput| input.pinching && !was_pinching[input.hand as usize];
+
+    // Each ray only points at the closest entity it hits
+    let pointed: Vec<(Hand, Entity)> = hands
+        .iter()
+        .filter_map(|input| {
+            let ray = input.ray?;
+            interactables
+                .iter()
+                .filter(|(_, interactable, ..)| interactable.enabled)
+                .filter_map(|(entity, _, bounds, transform, ..)| {
+                    let hit = ray_intersect_bounds(ray, bounds, transform)?;
+                    Some((entity, hit.distance))
+                })
+                .min_by(|(_, a), (_, b)| a.total_cmp(b))
+                .map(|(entity, _)| (input.hand, entity))
+        })
+        .collect();
+
+    for (entity, mut interactable, bounds, transform, pressable, grabbable, parent) in
+        interactables.iter_mut()
+    {
+        if !interactable.enabled {
+            interactable.hovered = None;
+            continue;
+        }
+        let touching = |input: &HandInput| {
+            [input.tip, input.pinch_point]
+                .into_iter()
+                .flatten()
+                .any(|point| local_point_in_bounds(point, bounds, transform).is_some())
+        };
+        let pointed_by = |input: &HandInput| pointed.contains(&(input.hand, entity));
+        let hovered = hands
+            .iter()
+            .find(|input| touching(input))
+            .or_else(|| hands.iter().find(|input| pointed_by(input)))
+            .map(|input| input.hand);
+        if interactable.hovered != hovered {
+            interactable.hovered = hovered;
+        }
+
+        if let Some(mut pressable) = pressable {
+            // Depth of the fingertip behind the front face, in meters
+            let (scale, ..) = transform.to_scale_rotation_translation();
+            let poke = hands
+                .iter()
+                .filter_map(|input| {
+                    let local = local_point_in_bounds(input.tip?, bounds, transform)?;
+                    let depth = (bounds.max().z - local.z) * scale.z;
+                    Some((input.hand, depth / pressable.depth.max(0.0001)))
+                })
+                .max_by(|(_, a), (_, b)| a.total_cmp(b));
+            let clicked = hands
+                .iter()
+                .find(|input| pointed_by(input) && pinch_started(input));
+            let press = poke.map_or(0.0, |(_, press)| press.min(1.0));
+            if pressable.press != press {
+                pressable.press = press;
+            }
+            let activated = match (poke, clicked) {
+                (Some((hand, press)), _) if press >= 1.0 => Some(hand),
+                (_, Some(input)) => Some(input.hand),
+                _ => None,
+            };
+            match activated {
+                Some(hand) if !pressable.pressed => {
+                    pressable.pressed = true;
+                    events.poked.send(Poked { entity, hand });
+                }
+                // Held until the fingertip backs out halfway, or the pinch ends
+                None if pressable.pressed
+                    && press < 0.5
+                    && !hands.iter().any(|input| pointed_by(input) && input.pinching) =>
+                {
+                    pressable.pressed = false;
+                }
+                _ => {}
+            }
+        }
+
+        let Some((mut grabbable, mut local_transform)) = grabbable else {
+            continue;
+        };
+        match grabbable.grabbed_by {
+            None => {
+                let grab = hands.iter().find(|input| {
+                    let near = input.pinch_point.is_some_and(|point| {
+                        local_point_in_bounds(point, bounds, transform).is_some()
+                    });
+                    pinch_started(input) && (near || pointed_by(input))
+                });
+                if let Some(input) = grab {
+                    grabbable.grabbed_by = Some(input.hand);
+                    grabbable.offset = transform.reparented_to(&GlobalTransform::from(input.frame));
+                    events.grabbed.send(Grabbed {
+                        entity,
+                        hand: input.hand,
+                    });
+                }
+            }
+            Some(hand) => match hands.iter().find(|input| input.hand == hand) {
+                Some(input) if input.pinching => {
+                    let target = GlobalTransform::from(input.frame) * grabbable.offset;
+                    let parent = parent.and_then(|parent| parents.get(parent.get()).ok());
+                    *local_transform = match parent {
+                        Some(parent) => target.reparented_to(parent),
+                        None => target.compute_transform(),
+                    };
+                }
+                _ => {
+                    grabbable.grabbed_by = None;
+                    events.released.send(Released { entity, hand });
+                }
+            },
+        }
+    }
+
+    *was_pinching = [Hand::Left, Hand::Right].map(|hand| {
+        hands
+            .iter()
+            .any(|input| input.hand == hand && input.pinching)
+    });
+}
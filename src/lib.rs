@@ -1,30 +1,539 @@
 use bevy::app::PluginGroupBuilder;
 use bevy::prelude::*;
-use crate::materials::pbr::PbrPlugin;
+use bevy::render::render_asset::RenderAssetBytesPerFrame;
+use bevy::render::render_resource::TextureUsages;
+use bevy::utils::Instant;
+use bevy_mod_xr::camera::XrCamera;
+use crate::boundary::PlayBoundaryPlugin;
+use crate::bounds::BoundsPlugin;
+use crate::compat::SkCompatibilityPlugin;
+use crate::controllers::ControllersPlugin;
+use crate::fade::ScreenFadePlugin;
+use crate::hand_menu::HandMenuPlugin;
+use crate::hands::HandsPlugin;
+use crate::interaction::InteractionPlugin;
+use crate::lighting::grid::LightProbeGridPlugin;
+use crate::lighting::overrides::OverrideLightingPlugin;
+use crate::lighting::probes::ReflectionProbePlugin;
+use crate::lighting::volume::ShVolumePlugin;
+use crate::lighting::zones::LightingZonePlugin;
+use crate::lines::LinesPlugin;
+use crate::materials::pbr::{PbrMaterial, PbrPlugin, ReplaceMaterialsMode};
+use crate::materials::warmup::PipelineWarmupPlugin;
+use crate::scene::SkScenePlugin;
 use crate::skytex::SkyTexPlugin;
+use crate::ui::SkUiPlugin;
+use crate::upload::{UploadQueue, UploadSchedulingPlugin};
+use crate::vignette::ComfortVignettePlugin;
 
+/// `load_internal_asset!` for the crate's WGSL, with the `dev-shaders` feature the shader is
+/// loaded as a watched embedded asset instead, so editing it hot reloads the pipelines using it
+macro_rules! load_shader {
+    ($app:ident, $handle:expr, $path:expr) => {{
+        #[cfg(not(feature = "dev-shaders"))]
+        bevy::asset::load_internal_asset!($app, $handle, $path, Shader::from_wgsl);
+        #[cfg(feature = "dev-shaders")]
+        {
+            bevy::asset::embedded_asset!($app, $path);
+            $crate::dev_shaders::watch_shader($app, $handle, bevy::asset::embedded_path!($path));
+        }
+    }};
+}
+
+pub mod boundary;
+pub mod bounds;
+pub mod capture;
+pub mod color;
+pub mod compat;
+pub mod controllers;
+#[cfg(feature = "dev-shaders")]
+mod dev_shaders;
+pub mod diagnostics;
+pub mod fade;
+pub mod hand_menu;
+pub mod hands;
+pub mod interaction;
+#[cfg(feature = "inspector")]
+pub mod inspector;
+pub mod lighting;
+pub mod lines;
 pub mod materials;
+pub mod mesh_gen;
+pub mod perf_hud;
+pub mod quality;
+pub mod scene;
+pub mod sim;
 pub mod skytex;
+#[cfg(feature = "test-utils")]
+pub mod test_utils;
+pub mod ui;
+pub mod upload;
+pub mod vignette;
 
+/// Render settings suited to standalone headsets, configured through [`XrUsefulSetup`]
 pub struct XrUsefulSetupPlugin;
 
 impl Plugin for XrUsefulSetupPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(Startup, |mut commands: Commands| {
-            commands.insert_resource(bevy::render::render_asset::RenderAssetBytesPerFrame::new(
-                4096,
-            ))
-        });
+        app.init_resource::<XrUsefulSetup>();
+        app.init_resource::<XrDepthSubmission>();
+        app.init_resource::<FrameStart>();
+        app.register_type::<(XrUsefulSetup, XrDepthSubmission)>();
+        app.add_systems(PreUpdate, start_frame_timer);
+        app.add_systems(
+            PostUpdate,
+            (
+                apply_xr_useful_setup.run_if(resource_changed::<XrUsefulSetup>),
+                adapt_bytes_per_frame.after(apply_xr_useful_setup),
+                apply_xr_foveation.run_if(resource_exists::<Assets<PbrMaterial>>),
+                configure_xr_depth_submission,
+            ),
+        );
+    }
+}
+
+/// Settings [`XrUsefulSetupPlugin`] keeps applied, `None` leaves the app's own value alone
+#[derive(Resource, Reflect, Clone, Debug, PartialEq)]
+#[reflect(Resource)]
+pub struct XrUsefulSetup {
+    /// `RenderAssetBytesPerFrame` budget, spreading uploads over frames so they don't drop
+    /// any. The default 4096 suits small scenes, apps streaming large textures need far more.
+    /// The starting point of `adaptive_bytes_per_frame`.
+    pub bytes_per_frame: Option<usize>,
+    /// Raises and lowers `bytes_per_frame` with the frame time, see [`AdaptiveBytesPerFrame`].
+    /// Off by default, once set it owns the budget and `bytes_per_frame` is only its start.
+    pub adaptive_bytes_per_frame: Option<AdaptiveBytesPerFrame>,
+    pub msaa: Option<Msaa>,
+    pub clear_color: Option<Color>,
+    /// Opt-in foveated rendering of every `PbrMaterial`, see [`XrFoveation`]
+    pub foveation: Option<XrFoveation>,
+}
+
+/// How much of each XR view is rendered at reduced quality.
+///
+/// Fixed foveated rendering through `XR_FB_foveation` needs the XR backend to create
+/// foveation-aware swapchains, which the pinned `bevy_mod_openxr` doesn't. Until it does this
+/// is a radial density fallback: outside a circle around the view center `PbrMaterial`s only
+/// evaluate SH diffuse, like past their `lod_distance`.
+#[derive(Reflect, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum XrFoveation {
+    /// Only the corners are simplified
+    Low,
+    Medium,
+    /// Everything but the central half of the view is simplified
+    High,
+}
+
+impl XrFoveation {
+    /// Radius of the fully shaded circle, relative to half the view's width and height
+    pub fn radius(self) -> f32 {
+        match self {
+            XrFoveation::Low => 0.9,
+            XrFoveation::Medium => 0.7,
+            XrFoveation::High => 0.5,
+        }
+    }
+}
+
+/// Upload budget following the frame time: it grows while frames finish well within
+/// `target_frame_time` and shrinks quickly once they come close to missing it. Also drives the
+/// budget of the `UploadQueue`.
+///
+/// The frame time is the CPU time from `PreUpdate` to `PostUpdate`, which leaves out the wait
+/// for the display. `Time<Real>` would count it and never drop below the display rate.
+#[derive(Reflect, Clone, Debug, PartialEq)]
+pub struct AdaptiveBytesPerFrame {
+    pub min_bytes: usize,
+    pub max_bytes: usize,
+    /// Seconds per frame of the headset's display, 1/72 for a Quest at its default rate
+    pub target_frame_time: f32,
+    /// Part of `target_frame_time` below which the budget grows
+    pub headroom: f32,
+    /// Jumps straight to `max_bytes` whatever the frame time, e.g. behind a loading screen
+    pub loading: bool,
+}
+
+impl Default for AdaptiveBytesPerFrame {
+    fn default() -> Self {
+        Self {
+            min_bytes: 4096,
+            max_bytes: 4 * 1024 * 1024,
+            target_frame_time: 1.0 / 72.0,
+            headroom: 0.75,
+            loading: false,
+        }
+    }
+}
+
+impl AdaptiveBytesPerFrame {
+    /// The budget after a frame that took `frame_time` seconds, averaged over the last frames
+    pub fn next_budget(&self, budget: usize, frame_time: f32) -> usize {
+        let budget = if self.loading {
+            self.max_bytes
+        } else if frame_time > self.target_frame_time * 0.95 {
+            // Missing the deadline costs more than slow uploads, back off fast
+            budget / 2
+        } else if frame_time < self.target_frame_time * self.headroom {
+            budget + budget / 8 + 1024
+        } else {
+            budget
+        };
+        budget.clamp(self.min_bytes, self.max_bytes.max(self.min_bytes))
+    }
+}
+
+impl Default for XrUsefulSetup {
+    fn default() -> Self {
+        Self {
+            bytes_per_frame: Some(4096),
+            adaptive_bytes_per_frame: None,
+            msaa: None,
+            clear_color: None,
+            foveation: None,
+        }
+    }
+}
+
+fn apply_xr_useful_setup(
+    setup: Res<XrUsefulSetup>,
+    bytes_per_frame: Option<ResMut<RenderAssetBytesPerFrame>>,
+    msaa: Option<ResMut<Msaa>>,
+    clear_color: Option<ResMut<ClearColor>>,
+) {
+    if let (Some(mut bytes_per_frame), Some(max_bytes)) = (bytes_per_frame, setup.bytes_per_frame) {
+        if bytes_per_frame.max_bytes != Some(max_bytes) {
+            *bytes_per_frame = RenderAssetBytesPerFrame::new(max_bytes);
+        }
+    }
+    if let (Some(mut msaa), Some(samples)) = (msaa, setup.msaa) {
+        if *msaa != samples {
+            *msaa = samples;
+        }
+    }
+    if let (Some(mut clear_color), Some(color)) = (clear_color, setup.clear_color) {
+        if clear_color.0 != color {
+            clear_color.0 = color;
+        }
+    }
+}
+
+/// When the CPU work of the current frame started
+#[derive(Resource, Default)]
+struct FrameStart(Option<Instant>);
+
+fn start_frame_timer(mut start: ResMut<FrameStart>) {
+    start.0 = Some(Instant::now());
+}
+
+fn adapt_bytes_per_frame(
+    setup: Res<XrUsefulSetup>,
+    start: Res<FrameStart>,
+    bytes_per_frame: Option<ResMut<RenderAssetBytesPerFrame>>,
+    upload_queue: Option<ResMut<UploadQueue>>,
+    mut average_frame_time: Local<Option<f32>>,
+) {
+    let (Some(adaptive), Some(mut bytes_per_frame)) =
+        (&setup.adaptive_bytes_per_frame, bytes_per_frame)
+    else {
+        return;
+    };
+    let Some(frame_start) = start.0 else {
+        return;
+    };
+    // A single hitch shouldn't throw the budget around
+    let frame_time = frame_start.elapsed().as_secs_f32();
+    let average = match *average_frame_time {
+        Some(average) => average + (frame_time - average) * 0.1,
+        None => frame_time,
+    };
+    *average_frame_time = Some(average);
+
+    let current = bytes_per_frame.max_bytes.or(setup.bytes_per_frame).unwrap_or(adaptive.min_bytes);
+    let budget = adaptive.next_budget(current, average);
+    if bytes_per_frame.max_bytes != Some(budget) {
+        *bytes_per_frame = RenderAssetBytesPerFrame::new(budget);
+    }
+    if let Some(mut queue) = upload_queue.filter(|queue| queue.bytes_per_frame != budget) {
+        queue.bytes_per_frame = budget;
+    }
+}
+
+/// Pushes [`XrUsefulSetup::foveation`] into every `PbrMaterial`
+fn apply_xr_foveation(
+    setup: Res<XrUsefulSetup>,
+    mut events: EventReader<AssetEvent<PbrMaterial>>,
+    mut materials: ResMut<Assets<PbrMaterial>>,
+) {
+    let added = events
+        .read()
+        .any(|e| matches!(e, AssetEvent::Added { .. }));
+    if !setup.is_changed() && !added {
+        return;
+    }
+
+    let outdated: Vec<_> = materials
+        .iter()
+        .filter(|(_, m)| m.foveation != setup.foveation)
+        .map(|(id, _)| id)
+        .collect();
+    for id in outdated {
+        materials.get_mut(id).unwrap().foveation = setup.foveation;
+    }
+}
+
+/// Groundwork for submitting the depth of the XR view cameras for positional reprojection.
+///
+/// When enabled, `COPY_SRC` is added to the usages of their depth textures, so they can be
+/// copied into an `XR_KHR_composition_layer_depth` swapchain. Nothing submits that layer yet:
+/// the pinned `bevy_mod_openxr` has no depth layer path. Off by default, since the extra usage
+/// can cost the depth target its compression with nothing to show for it.
+#[derive(Resource, Reflect, Clone, Debug, Default, PartialEq)]
+#[reflect(Resource)]
+pub struct XrDepthSubmission {
+    pub enabled: bool,
+}
+
+fn configure_xr_depth_submission(
+    settings: Res<XrDepthSubmission>,
+    mut cameras: Query<&mut Camera3d, With<XrCamera>>,
+) {
+    if !settings.enabled {
+        return;
+    }
+    // Keeps the usages Bevy or the app set, e.g. `TEXTURE_BINDING`
+    let copy_src = TextureUsages::COPY_SRC.bits();
+    for mut camera in cameras.iter_mut() {
+        if camera.depth_texture_usages.0 & copy_src == 0 {
+            camera.depth_texture_usages.0 |= copy_src;
+        }
     }
 }
 
 pub struct SkPlugins;
 
+impl SkPlugins {
+    /// Turns the plugins of the group on and off and sets their settings in one place, instead
+    /// of `PluginGroupBuilder::disable` and separate resources
+    pub fn builder() -> SkPluginsBuilder {
+        SkPluginsBuilder::default()
+    }
+}
+
 impl PluginGroup for SkPlugins {
     fn build(self) -> PluginGroupBuilder {
         PluginGroupBuilder::start::<SkPlugins>()
             .add(XrUsefulSetupPlugin)
+            .add(UploadSchedulingPlugin)
             .add(PbrPlugin)
+            .add(PipelineWarmupPlugin)
             .add(SkyTexPlugin)
+            .add(ShVolumePlugin)
+            .add(LightingZonePlugin)
+            .add(OverrideLightingPlugin)
+            .add(LightProbeGridPlugin)
+            .add(ReflectionProbePlugin)
+            .add(PlayBoundaryPlugin)
+            .add(HandsPlugin)
+            .add(ControllersPlugin)
+            .add(LinesPlugin)
+            .add(ComfortVignettePlugin)
+            .add(ScreenFadePlugin)
+            .add(BoundsPlugin)
+            .add(InteractionPlugin)
+            .add(SkUiPlugin)
+            .add(HandMenuPlugin)
+            .add(SkScenePlugin)
+            .add(SkCompatibilityPlugin)
     }
-}
\ No newline at end of file
+}
+
+/// [`SkPlugins`] with some plugins left out and their settings chosen up front, added like
+/// the group itself: `app.add_plugins(SkPlugins::builder().sky(false))`
+#[derive(Clone, Debug)]
+pub struct SkPluginsBuilder {
+    replace_materials: ReplaceMaterialsMode,
+    xr_setup: XrUsefulSetup,
+    sky: bool,
+    light_probes: bool,
+    play_boundary: bool,
+    hands: bool,
+    controllers: bool,
+    lines: bool,
+    ui: bool,
+    comfort_vignette: bool,
+}
+
+impl Default for SkPluginsBuilder {
+    fn default() -> Self {
+        Self {
+            replace_materials: ReplaceMaterialsMode::default(),
+            xr_setup: XrUsefulSetup::default(),
+            sky: true,
+            light_probes: true,
+            play_boundary: true,
+            hands: true,
+            controllers: true,
+            lines: true,
+            ui: true,
+            comfort_vignette: true,
+        }
+    }
+}
+
+impl SkPluginsBuilder {
+    /// Which `StandardMaterial`s get replaced, `ReplaceMaterialsMode::Off` keeps them all
+    pub fn replace_materials(mut self, mode: ReplaceMaterialsMode) -> Self {
+        self.replace_materials = mode;
+        self
+    }
+
+    pub fn xr_setup(mut self, setup: XrUsefulSetup) -> Self {
+        self.xr_setup = setup;
+        self
+    }
+
+    /// `SkyTexPlugin`, without it the lighting stays at its default
+    pub fn sky(mut self, enabled: bool) -> Self {
+        self.sky = enabled;
+        self
+    }
+
+    /// `ShVolumePlugin`, `LightingZonePlugin`, `LightProbeGridPlugin` and
+    /// `ReflectionProbePlugin`
+    pub fn light_probes(mut self, enabled: bool) -> Self {
+        self.light_probes = enabled;
+        self
+    }
+
+    /// `PlayBoundaryPlugin`
+    pub fn play_boundary(mut self, enabled: bool) -> Self {
+        self.play_boundary = enabled;
+        self
+    }
+
+    /// `HandsPlugin`, the hand poses are still kept for interaction without it
+    pub fn hands(mut self, enabled: bool) -> Self {
+        self.hands = enabled;
+        self
+    }
+
+    /// `ControllersPlugin`
+    pub fn controllers(mut self, enabled: bool) -> Self {
+        self.controllers = enabled;
+        self
+    }
+
+    /// `LinesPlugin`
+    pub fn lines(mut self, enabled: bool) -> Self {
+        self.lines = enabled;
+        self
+    }
+
+    /// `InteractionPlugin`, `SkUiPlugin` and `HandMenuPlugin`
+    pub fn ui(mut self, enabled: bool) -> Self {
+        self.ui = enabled;
+        self
+    }
+
+    /// `ComfortVignettePlugin`
+    pub fn comfort_vignette(mut self, enabled: bool) -> Self {
+        self.comfort_vignette = enabled;
+        self
+    }
+}
+
+impl PluginGroup for SkPluginsBuilder {
+    fn build(self) -> PluginGroupBuilder {
+        let mut group = SkPlugins.build().add_before::<XrUsefulSetupPlugin, _>(SkSettingsPlugin {
+            replace_materials: self.replace_materials,
+            xr_setup: self.xr_setup,
+        });
+        if !self.sky {
+            group = group.disable::<SkyTexPlugin>();
+        }
+        if !self.light_probes {
+            group = group
+                .disable::<ShVolumePlugin>()
+                .disable::<LightingZonePlugin>()
+                .disable::<LightProbeGridPlugin>()
+                .disable::<ReflectionProbePlugin>();
+        }
+        if !self.play_boundary {
+            group = group.disable::<PlayBoundaryPlugin>();
+        }
+        if !self.hands {
+            group = group.disable::<HandsPlugin>();
+        }
+        if !self.controllers {
+            group = group.disable::<ControllersPlugin>();
+        }
+        if !self.lines {
+            group = group.disable::<LinesPlugin>();
+        }
+        if !self.ui {
+            group = group
+                .disable::<InteractionPlugin>()
+                .disable::<SkUiPlugin>()
+                .disable::<HandMenuPlugin>();
+        }
+        if !self.comfort_vignette {
+            group = group.disable::<ComfortVignettePlugin>();
+        }
+        group
+    }
+}
+
+/// Inserts the settings of a [`SkPluginsBuilder`] before the plugins initialize their defaults
+struct SkSettingsPlugin {
+    replace_materials: ReplaceMaterialsMode,
+    xr_setup: XrUsefulSetup,
+}
+
+impl Plugin for SkSettingsPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(self.replace_materials);
+        app.insert_resource(self.xr_setup.clone());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn budget_backs_off_at_the_display_rate() {
+        let adaptive = AdaptiveBytesPerFrame::default();
+        let budget = adaptive.next_budget(64 * 1024, adaptive.target_frame_time);
+        assert_eq!(budget, 32 * 1024);
+        assert_eq!(adaptive.next_budget(adaptive.min_bytes, 1.0), adaptive.min_bytes);
+    }
+
+    #[test]
+    fn budget_grows_below_the_display_rate() {
+        let adaptive = AdaptiveBytesPerFrame::default();
+        let frame_time = adaptive.target_frame_time * 0.5;
+        let mut budget = adaptive.min_bytes;
+        for _ in 0..200 {
+            let next = adaptive.next_budget(budget, frame_time);
+            assert!(next > budget || next == adaptive.max_bytes);
+            budget = next;
+        }
+        assert_eq!(budget, adaptive.max_bytes);
+    }
+
+    #[test]
+    fn budget_holds_between_headroom_and_the_deadline() {
+        let adaptive = AdaptiveBytesPerFrame::default();
+        let frame_time = adaptive.target_frame_time * 0.85;
+        assert_eq!(adaptive.next_budget(64 * 1024, frame_time), 64 * 1024);
+    }
+
+    #[test]
+    fn loading_jumps_to_the_maximum() {
+        let adaptive = AdaptiveBytesPerFrame {
+            loading: true,
+            ..default()
+        };
+        assert_eq!(adaptive.next_budget(4096, 1.0), adaptive.max_bytes);
+    }
+}
@@ -0,0 +1,164 @@
+use crate::capture::{
+    CaptureCamera, CaptureCubemap, CubemapCaptureSet, CubemapCapturePlugin, CubemapCaptured,
+};
+use crate::lighting::buffer::{ShLightingBuffer, ShSlot};
+use crate::skytex::{global_lighting, SkyLighting, SkyTexSettings, SphericalHarmonics};
+use bevy::prelude::*;
+
+/// Periodically captures a low resolution cubemap around the active camera, or
+/// [`SceneAmbient::source`], and blends its SH into the global `PbrMaterial` lighting, so
+/// lights turning on or walking into a cave change the ambient shading.
+///
+/// The blend goes into [`ShSlot::GLOBAL`] on top of what [`SkyLighting`] puts there rather
+/// than into `SkyLighting` itself, which would darken the sky along with the cave.
+pub struct SceneAmbientPlugin;
+
+impl Plugin for SceneAmbientPlugin {
+    fn build(&self, app: &mut App) {
+        if !app.is_plugin_added::<CubemapCapturePlugin>() {
+            app.add_plugins(CubemapCapturePlugin);
+        }
+        app.init_resource::<SceneAmbient>();
+        app.init_resource::<SceneAmbientLighting>();
+        app.register_type::<(SceneAmbient, SceneAmbientLighting)>();
+        app.add_systems(
+            PostUpdate,
+            (
+                schedule_ambient_captures.before(CubemapCaptureSet),
+                (receive_ambient_captures, blend_scene_ambient)
+                    .chain()
+                    .after(CubemapCaptureSet),
+            )
+                .after(TransformSystem::TransformPropagate),
+        );
+    }
+}
+
+#[derive(Resource, Reflect, Clone, Copy, Debug, PartialEq)]
+#[reflect(Resource)]
+pub struct SceneAmbient {
+    pub enabled: bool,
+    /// Entity to capture from, `None` uses the first active 3d camera
+    pub source: Option<Entity>,
+    /// Seconds between captures
+    pub interval: f32,
+    pub face_size: u32,
+    /// How much of the captured SH replaces the sky lighting, 0 to 1
+    pub blend: f32,
+    /// Seconds for the ambient to move halfway to a new capture, 0 snaps
+    pub half_life: f32,
+}
+
+impl Default for SceneAmbient {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            source: None,
+            interval: 1.0,
+            face_size: 16,
+            blend: 0.7,
+            half_life: 0.5,
+        }
+    }
+}
+
+/// The last captured and the smoothed scene SH, `None` until the first capture arrived
+#[derive(Resource, Reflect, Clone, Copy, Debug, Default, PartialEq)]
+#[reflect(Resource)]
+pub struct SceneAmbientLighting {
+    pub captured: Option<SphericalHarmonics>,
+    pub smoothed: Option<SphericalHarmonics>,
+}
+
+/// Follows the capture source around, captures are taken from its position
+#[derive(Component)]
+struct SceneAmbientProbe;
+
+fn schedule_ambient_captures(
+    mut commands: Commands,
+    time: Res<Time>,
+    ambient: Res<SceneAmbient>,
+    sources: Query<&GlobalTransform>,
+    cameras: Query<(&Camera, &GlobalTransform), (With<Camera3d>, Without<CaptureCamera>)>,
+    probes: Query<Entity, With<SceneAmbientProbe>>,
+    mut since_capture: Local<Option<f32>>,
+) {
+    if !ambient.enabled {
+        return;
+    }
+    let elapsed = since_capture.map_or(f32::INFINITY, |t| t + time.delta_seconds());
+    if elapsed < ambient.interval {
+        *since_capture = Some(elapsed);
+        return;
+    }
+    let source = match ambient.source {
+        Some(entity) => sources.get(entity).ok(),
+        None => cameras
+            .iter()
+            .find(|(camera, _)| camera.is_active)
+            .map(|(_, transform)| transform),
+    };
+    let Some(source) = source else {
+        return;
+    };
+    *since_capture = Some(0.0);
+
+    let capture = (
+        CaptureCubemap {
+            face_size: ambient.face_size,
+        },
+        GlobalTransform::from_translation(source.translation()),
+    );
+    match probes.iter().next() {
+        Some(probe) => {
+            commands.entity(probe).insert(capture);
+        }
+        None => {
+            commands.spawn((SceneAmbientProbe, capture));
+        }
+    }
+}
+
+fn receive_ambient_captures(
+    mut captured: EventReader<CubemapCaptured>,
+    probes: Query<(), With<SceneAmbientProbe>>,
+    mut lighting: ResMut<SceneAmbientLighting>,
+) {
+    for capture in captured.read() {
+        if !probes.contains(capture.entity) {
+            continue;
+        }
+        if let Some(sh) = SphericalHarmonics::from_cubemap(&capture.image) {
+            lighting.captured = Some(sh);
+        }
+    }
+}
+
+fn blend_scene_ambient(
+    time: Res<Time>,
+    ambient: Res<SceneAmbient>,
+    sky: Res<SkyLighting>,
+    settings: Res<SkyTexSettings>,
+    mut lighting: ResMut<SceneAmbientLighting>,
+    mut buffer: ResMut<ShLightingBuffer>,
+) {
+    let base = global_lighting(&sky.0, &settings);
+    let Some(captured) = lighting.captured.filter(|_| ambient.enabled) else {
+        if lighting.smoothed.take().is_some() && buffer.get(ShSlot::GLOBAL) != Some(&base) {
+            buffer.set(ShSlot::GLOBAL, base);
+        }
+        return;
+    };
+    let t = if ambient.half_life <= 0.0 {
+        1.0
+    } else {
+        1.0 - 0.5f32.powf(time.delta_seconds() / ambient.half_life)
+    };
+    let smoothed = lighting.smoothed.map_or(captured, |s| s.lerp(&captured, t));
+    lighting.smoothed = Some(smoothed);
+
+    let global = base.lerp(&smoothed, ambient.blend.clamp(0.0, 1.0));
+    if buffer.get(ShSlot::GLOBAL) != Some(&global) {
+        buffer.set(ShSlot::GLOBAL, global);
+    }
+}
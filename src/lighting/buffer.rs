@@ -0,0 +1,337 @@
+use crate::materials::pbr::PbrMaterial;
+use crate::skytex::sh3::Sh3;
+use crate::skytex::{SphericalHarmonics, DEFAULT_LIGHTING};
+use bevy::prelude::*;
+use bevy::render::extract_resource::{ExtractResource, ExtractResourcePlugin};
+use bevy::render::render_asset::{RenderAssetUsages, RenderAssets};
+use bevy::render::render_resource::{
+    Extent3d, ImageCopyTexture, ImageDataLayout, Origin3d, TextureDimension, TextureFormat,
+    TextureId,
+};
+use bevy::render::renderer::RenderQueue;
+use bevy::render::texture::GpuImage;
+use bevy::render::{Render, RenderApp, RenderSet};
+use bevy::utils::HashMap;
+
+/// Shared texture holding the SH of every [`ShSlot`], one row of 9 texels per slot, 16 with
+/// the `sh3` feature
+pub const SH_BUFFER_IMAGE_HANDLE: Handle<Image> = Handle::weak_from_u128(0x5c0e81d27a93);
+
+/// Maximum number of lighting slots in the shared SH texture
+pub const SH_BUFFER_SLOTS: usize = 256;
+#[cfg(not(feature = "sh3"))]
+const SH_COEFFICIENTS: u32 = 9;
+#[cfg(feature = "sh3")]
+const SH_COEFFICIENTS: u32 = 16;
+#[cfg(not(feature = "packed-uniforms"))]
+const TEXEL_SIZE: u32 = 16;
+#[cfg(not(feature = "packed-uniforms"))]
+const TEXEL_FORMAT: TextureFormat = TextureFormat::Rgba32Float;
+#[cfg(feature = "packed-uniforms")]
+const TEXEL_SIZE: u32 = 8;
+#[cfg(feature = "packed-uniforms")]
+const TEXEL_FORMAT: TextureFormat = TextureFormat::Rgba16Float;
+
+/// Index of a set of SH coefficients in the shared [`ShLightingBuffer`]
+#[derive(Reflect, Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct ShSlot(pub u32);
+
+impl ShSlot {
+    /// Scene wide lighting, used by every `PbrMaterial` that doesn't pick its own slot
+    pub const GLOBAL: ShSlot = ShSlot(0);
+}
+
+/// Uploads [`ShLightingBuffer`] into the shared SH texture read by `PbrMaterial`
+pub struct ShLightingBufferPlugin;
+
+impl Plugin for ShLightingBufferPlugin {
+    fn build(&self, app: &mut App) {
+        let buffer = ShLightingBuffer::default();
+        app.world_mut()
+            .resource_mut::<Assets<Image>>()
+            .insert(SH_BUFFER_IMAGE_HANDLE.id(), buffer.to_image());
+        app.insert_resource(buffer);
+        app.add_plugins(ExtractResourcePlugin::<ShLightingBuffer>::default());
+
+        if let Some(render_app) = app.get_sub_app_mut(RenderApp) {
+            render_app.add_systems(Render, write_sh_buffer.in_set(RenderSet::PrepareResources));
+        }
+    }
+}
+
+/// SH coefficients shared by all `PbrMaterial`s.
+///
+/// Materials only store an [`ShSlot`], so changing the lighting of a slot rewrites a single
+/// texture in the render world instead of re-uploading every material that uses it.
+#[derive(Resource, ExtractResource, Clone, Debug)]
+pub struct ShLightingBuffer {
+    slots: Vec<SphericalHarmonics>,
+    /// Band 3 of every slot as read by the shader, only uploaded with the `sh3` feature
+    band3: Vec<[Vec3; 7]>,
+    free: Vec<u32>,
+    /// Reciprocal of the camera exposure the SH is scaled relative to, 0 ignores the exposure
+    inverse_exposure: f32,
+    /// Scale of every slot's SH, also carried to emission, see
+    /// [`SkExposure`](crate::color::SkExposure)
+    brightness: f32,
+}
+
+impl Default for ShLightingBuffer {
+    fn default() -> Self {
+        Self {
+            slots: vec![DEFAULT_LIGHTING],
+            band3: vec![[Vec3::ZERO; 7]],
+            free: Vec::new(),
+            inverse_exposure: 0.0,
+            brightness: 1.0,
+        }
+    }
+}
+
+impl ShLightingBuffer {
+    pub fn get(&self, slot: ShSlot) -> Option<&SphericalHarmonics> {
+        self.slots.get(slot.0 as usize)
+    }
+
+    /// Sets the SH of `slot`, use [`ShSlot::GLOBAL`] to change the scene lighting
+    pub fn set(&mut self, slot: ShSlot, lighting: SphericalHarmonics) {
+        self.set_with_band3(slot, lighting, [Vec3::ZERO; 7]);
+    }
+
+    /// Sets `slot` from third order SH, band 3 only reaches the shader with the `sh3` feature
+    pub fn set_sh3(&mut self, slot: ShSlot, lighting: &Sh3) {
+        self.set_with_band3(slot, lighting.l2(), lighting.shader_band3());
+    }
+
+    fn set_with_band3(&mut self, slot: ShSlot, lighting: SphericalHarmonics, band3: [Vec3; 7]) {
+        if let Some(current) = self.slots.get_mut(slot.0 as usize) {
+            *current = lighting;
+            self.band3[slot.0 as usize] = band3;
+        }
+    }
+
+    /// Reserves a new slot, returns `None` once all [`SH_BUFFER_SLOTS`] are in use
+    pub fn allocate(&mut self, lighting: SphericalHarmonics) -> Option<ShSlot> {
+        if let Some(index) = self.free.pop() {
+            self.slots[index as usize] = lighting;
+            self.band3[index as usize] = [Vec3::ZERO; 7];
+            return Some(ShSlot(index));
+        }
+        if self.slots.len() >= SH_BUFFER_SLOTS {
+            return None;
+        }
+        self.slots.push(lighting);
+        self.band3.push([Vec3::ZERO; 7]);
+        Some(ShSlot(self.slots.len() as u32 - 1))
+    }
+
+    /// Has the shaders scale the SH by the view's exposure relative to `exposure`, like Bevy's
+    /// lights, or leave it as is with `None`
+    pub fn set_reference_exposure(&mut self, exposure: Option<f32>) {
+        self.inverse_exposure = exposure.map_or(0.0, |exposure| 1.0 / exposure);
+    }
+
+    /// Scales the SH of every slot by `brightness`, and `PbrMaterial` emission with it
+    pub fn set_brightness(&mut self, brightness: f32) {
+        self.brightness = brightness;
+    }
+
+    /// Returns `slot` to the pool, materials still pointing at it keep reading stale data
+    pub fn free(&mut self, slot: ShSlot) {
+        if slot != ShSlot::GLOBAL && (slot.0 as usize) < self.slots.len() {
+            self.free.push(slot.0);
+        }
+    }
+
+    fn texel_data(&self) -> Vec<u8> {
+        let mut data = Vec::with_capacity(SH_BUFFER_SLOTS * (SH_COEFFICIENTS * TEXEL_SIZE) as usize);
+        for slot in 0..SH_BUFFER_SLOTS {
+            let index = if slot < self.slots.len() { slot } else { 0 };
+            let coefficients = self.slots[index].coefficients.into_iter();
+            #[cfg(feature = "sh3")]
+            let coefficients = coefficients.chain(self.band3[index]);
+            // The otherwise unused alphas of the first texels carry the exposure and brightness
+            for (i, c) in coefficients.enumerate() {
+                let w = match i {
+                    0 => self.inverse_exposure,
+                    1 => self.brightness,
+                    _ => 0.0,
+                };
+                let c = c * self.brightness;
+                for v in [c.x, c.y, c.z, w] {
+                    #[cfg(not(feature = "packed-uniforms"))]
+                    data.extend_from_slice(&v.to_le_bytes());
+                    #[cfg(feature = "packed-uniforms")]
+                    data.extend_from_slice(&half::f16::from_f32(v).to_le_bytes());
+                }
+            }
+        }
+        data
+    }
+
+    fn to_image(&self) -> Image {
+        Image::new(
+            Extent3d {
+                width: SH_COEFFICIENTS,
+                height: SH_BUFFER_SLOTS as u32,
+                depth_or_array_layers: 1,
+            },
+            TextureDimension::D2,
+            self.texel_data(),
+            TEXEL_FORMAT,
+            RenderAssetUsages::default(),
+        )
+    }
+}
+
+/// Slots and `PbrMaterial` copies of entities lit with their own SH, kept in a `Local` by
+/// the systems lighting them.
+///
+/// Converted materials are shared between entities, so each one gets a copy of its material
+/// pointed at its slot instead of moving the shared material from slot to slot. The resources
+/// are only marked changed when something in them does.
+#[derive(Default)]
+pub(crate) struct ShReceivers(HashMap<Entity, ShReceiver>);
+
+struct ShReceiver {
+    slot: ShSlot,
+    /// `None` while the original material is still loading
+    material: Option<Handle<PbrMaterial>>,
+}
+
+impl ShReceivers {
+    /// Points the copies of `entities` back at the global lighting and frees their slots
+    pub(crate) fn release(
+        &mut self,
+        entities: impl IntoIterator<Item = Entity>,
+        buffer: &mut ResMut<ShLightingBuffer>,
+        materials: &mut ResMut<Assets<PbrMaterial>>,
+    ) {
+        for entity in entities {
+            let Some(receiver) = self.0.remove(&entity) else {
+                continue;
+            };
+            // Despawned entities drop their copy along with them
+            if let Some(material) = receiver.material.and_then(|m| materials.get_mut(&m)) {
+                material.lighting = ShSlot::GLOBAL;
+            }
+            buffer.free(receiver.slot);
+        }
+    }
+
+    /// Lights `entity` with `lighting`, giving it a slot and a copy of `material` first
+    pub(crate) fn set(
+        &mut self,
+        commands: &mut Commands,
+        entity: Entity,
+        material: &Handle<PbrMaterial>,
+        lighting: SphericalHarmonics,
+        buffer: &mut ResMut<ShLightingBuffer>,
+        materials: &mut ResMut<Assets<PbrMaterial>>,
+    ) {
+        let slot = match self.0.get(&entity) {
+            Some(receiver) => receiver.slot,
+            None => {
+                let Some(slot) = buffer.allocate(lighting) else {
+                    warn!("Out of SH lighting slots, {entity:?} keeps its lighting");
+                    return;
+                };
+                slot
+            }
+        };
+        if buffer.get(slot) != Some(&lighting) {
+            buffer.set(slot, lighting);
+        }
+        let own = self.0.get(&entity).and_then(|receiver| receiver.material.as_ref());
+        if own == Some(material) {
+            return;
+        }
+        // Tried again every frame until the original has loaded
+        let copy = materials.get(material).cloned().map(|copy| {
+            let copy = materials.add(PbrMaterial {
+                lighting: slot,
+                ..copy
+            });
+            commands.entity(entity).insert(copy.clone());
+            copy
+        });
+        self.0.insert(
+            entity,
+            ShReceiver {
+                slot,
+                material: copy,
+            },
+        );
+    }
+}
+
+/// What [`write_sh_buffer`] last put into the GPU texture
+#[derive(Default)]
+struct UploadedShBuffer {
+    texture: Option<TextureId>,
+    data: Vec<u8>,
+}
+
+/// Writes the extracted buffer straight into the existing GPU texture, so the bind groups of
+/// the materials sampling it stay valid and no material is touched when the lighting changes.
+/// Only the rows of slots that changed are uploaded, so a light estimate updating the global
+/// slot every frame costs one row.
+fn write_sh_buffer(
+    buffer: Res<ShLightingBuffer>,
+    images: Res<RenderAssets<GpuImage>>,
+    queue: Res<RenderQueue>,
+    mut uploaded: Local<UploadedShBuffer>,
+) {
+    let Some(gpu_image) = images.get(&SH_BUFFER_IMAGE_HANDLE) else {
+        return;
+    };
+    let texture_id = gpu_image.texture.id();
+    let recreated = uploaded.texture != Some(texture_id);
+    if !buffer.is_changed() && !recreated {
+        return;
+    }
+
+    let data = buffer.texel_data();
+    let row_size = (SH_COEFFICIENTS * TEXEL_SIZE) as usize;
+    let changed = |row: usize| {
+        let bytes = row * row_size..(row + 1) * row_size;
+        recreated || uploaded.data.get(bytes.clone()) != Some(&data[bytes])
+    };
+    let mut row = 0;
+    while row < SH_BUFFER_SLOTS {
+        if !changed(row) {
+            row += 1;
+            continue;
+        }
+        // One write per run of changed rows
+        let first = row;
+        while row < SH_BUFFER_SLOTS && changed(row) {
+            row += 1;
+        }
+        queue.write_texture(
+            ImageCopyTexture {
+                origin: Origin3d {
+                    x: 0,
+                    y: first as u32,
+                    z: 0,
+                },
+                ..gpu_image.texture.as_image_copy()
+            },
+            &data[first * row_size..row * row_size],
+            ImageDataLayout {
+                offset: 0,
+                bytes_per_row: Some(row_size as u32),
+                rows_per_image: None,
+            },
+            Extent3d {
+                width: SH_COEFFICIENTS,
+                height: (row - first) as u32,
+                depth_or_array_layers: 1,
+            },
+        );
+    }
+    *uploaded = UploadedShBuffer {
+        texture: Some(texture_id),
+        data,
+    };
+}
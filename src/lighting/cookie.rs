@@ -0,0 +1,279 @@
+use crate::skytex::read_texels;
+use bevy::prelude::*;
+use bevy::render::render_asset::RenderAssetUsages;
+use bevy::render::render_resource::{
+    Extent3d, TextureDimension, TextureFormat, TextureViewDescriptor, TextureViewDimension,
+};
+use bevy::render::texture::{ImageAddressMode, ImageSampler, ImageSamplerDescriptor};
+
+/// Lights that can carry a [`LightCookie`] at once, the others are drawn without theirs
+pub const MAX_LIGHT_COOKIES: usize = 8;
+/// Width and height every cookie texture is resampled to
+const COOKIE_SIZE: u32 = 256;
+
+/// Texture array with one layer per [`LightCookie`], bound to every `PbrMaterial`
+pub const LIGHT_COOKIES_IMAGE_HANDLE: Handle<Image> = Handle::weak_from_u128(0x3b61c9e0f4a7);
+/// Projection of every [`LightCookie`], one row of 4 texels per light, see `sk_lights.wgsl`
+pub const LIGHT_COOKIE_DATA_IMAGE_HANDLE: Handle<Image> = Handle::weak_from_u128(0x3b61c9e0f4a8);
+
+/// Projects [`LightCookie`]s onto `PbrMaterial` surfaces, added by `PbrPlugin`
+pub struct LightCookiePlugin;
+
+impl Plugin for LightCookiePlugin {
+    fn build(&self, app: &mut App) {
+        let mut images = app.world_mut().resource_mut::<Assets<Image>>();
+        images.insert(LIGHT_COOKIES_IMAGE_HANDLE.id(), cookies_image());
+        images.insert(LIGHT_COOKIE_DATA_IMAGE_HANDLE.id(), cookie_data_image(&[]));
+        app.register_type::<LightCookie>();
+        app.add_systems(
+            PostUpdate,
+            update_light_cookies.after(TransformSystem::TransformPropagate),
+        );
+    }
+}
+
+/// Projector texture ("gobo") for a `SpotLight` or `DirectionalLight` on the same entity.
+///
+/// The texture is projected along the light's forward axis and multiplies the light's color.
+/// It only affects `PbrMaterial` surfaces through the analytic lights in `sk_lights.wgsl`; the
+/// SH ambient term is never cookied. Up to [`MAX_LIGHT_COOKIES`] lights carry one at a time,
+/// and none do with the `webgl2` feature, which has no texture units left for them.
+#[derive(Component, Reflect, Clone, Debug)]
+#[reflect(Component)]
+pub struct LightCookie {
+    pub texture: Handle<Image>,
+    /// World-space size of one texture repeat for directional lights, ignored for spot lights
+    /// which stretch the texture over their outer cone
+    pub size: Vec2,
+}
+
+impl LightCookie {
+    pub fn new(texture: Handle<Image>) -> Self {
+        Self {
+            texture,
+            size: Vec2::splat(10.0),
+        }
+    }
+}
+
+// Keep in sync with the kinds in `sk_light_cookie`, 0 ends the rows
+const DIRECTIONAL_COOKIE: f32 = 1.0;
+const SPOT_COOKIE: f32 = 2.0;
+
+/// The row of a cookie: the light's position and kind, its forward axis and layer, then its
+/// right and up axes scaled to map the cookie to -1..1. Spot lights divide by the depth along
+/// forward first, so their axes are scaled by the outer cone.
+fn cookie_row(
+    cookie: &LightCookie,
+    transform: &GlobalTransform,
+    spot: Option<&SpotLight>,
+    layer: usize,
+) -> [Vec4; 4] {
+    let (kind, half_size) = match spot {
+        Some(spot) => (SPOT_COOKIE, Vec2::splat(spot.outer_angle.tan())),
+        None => (DIRECTIONAL_COOKIE, cookie.size * 0.5),
+    };
+    let right = Vec3::from(transform.right()) / half_size.x;
+    let up = Vec3::from(transform.up()) / half_size.y;
+    [
+        transform.translation().extend(kind),
+        Vec3::from(transform.forward()).extend(layer as f32),
+        right.extend(0.0),
+        up.extend(0.0),
+    ]
+}
+
+fn cookie_data(rows: &[[Vec4; 4]]) -> Vec<u8> {
+    let mut data = vec![0; 4 * 16 * MAX_LIGHT_COOKIES];
+    let texels = rows.iter().flatten().flat_map(|texel| texel.to_array());
+    for (bytes, value) in data.chunks_exact_mut(4).zip(texels) {
+        bytes.copy_from_slice(&value.to_le_bytes());
+    }
+    data
+}
+
+fn cookie_data_image(rows: &[[Vec4; 4]]) -> Image {
+    Image::new(
+        Extent3d {
+            width: 4,
+            height: MAX_LIGHT_COOKIES as u32,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        cookie_data(rows),
+        TextureFormat::Rgba32Float,
+        RenderAssetUsages::default(),
+    )
+}
+
+fn cookies_image() -> Image {
+    let mut image = Image::new_fill(
+        Extent3d {
+            width: COOKIE_SIZE,
+            height: COOKIE_SIZE,
+            depth_or_array_layers: MAX_LIGHT_COOKIES as u32,
+        },
+        TextureDimension::D2,
+        &[255; 4],
+        TextureFormat::Rgba8Unorm,
+        RenderAssetUsages::default(),
+    );
+    image.texture_view_descriptor = Some(TextureViewDescriptor {
+        dimension: Some(TextureViewDimension::D2Array),
+        ..default()
+    });
+    // Directional cookies repeat over the world
+    image.sampler = ImageSampler::Descriptor(ImageSamplerDescriptor {
+        address_mode_u: ImageAddressMode::Repeat,
+        address_mode_v: ImageAddressMode::Repeat,
+        ..ImageSamplerDescriptor::linear()
+    });
+    image
+}
+
+/// The first mip of `image` resampled to a layer of the cookie array, white if its format
+/// can't be read back
+fn cookie_layer(image: &Image) -> Vec<u8> {
+    let (width, height) = (image.width(), image.height());
+    let texels = read_texels(image).filter(|texels| texels.len() >= (width * height) as usize);
+    let Some(texels) = texels else {
+        warn!(
+            "LightCookie texture format {:?} can't be read, drawing it white",
+            image.texture_descriptor.format
+        );
+        return vec![255; (COOKIE_SIZE * COOKIE_SIZE * 4) as usize];
+    };
+    (0..COOKIE_SIZE * COOKIE_SIZE)
+        .flat_map(|i| {
+            let x = (i % COOKIE_SIZE) * width / COOKIE_SIZE;
+            let y = (i / COOKIE_SIZE) * height / COOKIE_SIZE;
+            let texel = texels[(y * width + x) as usize];
+            texel.to_array().map(|c| (c.clamp(0.0, 1.0) * 255.0).round() as u8)
+        })
+        .collect()
+}
+
+fn update_light_cookies(
+    cookies: Query<(
+        Entity,
+        &LightCookie,
+        &GlobalTransform,
+        Option<&SpotLight>,
+        Has<DirectionalLight>,
+    )>,
+    mut image_events: EventReader<AssetEvent<Image>>,
+    mut written: Local<Vec<AssetId<Image>>>,
+    mut images: ResMut<Assets<Image>>,
+) {
+    let mut lights: Vec<_> = cookies
+        .iter()
+        .filter(|(.., spot, directional)| spot.is_some() || *directional)
+        .collect();
+    lights.sort_by_key(|(entity, ..)| *entity);
+    if lights.len() > MAX_LIGHT_COOKIES {
+        warn_once!("More than {MAX_LIGHT_COOKIES} lights have a LightCookie, ignoring the rest");
+        lights.truncate(MAX_LIGHT_COOKIES);
+    }
+
+    let rows: Vec<_> = lights
+        .iter()
+        .enumerate()
+        .map(|(layer, (_, cookie, transform, spot, _))| {
+            cookie_row(cookie, transform, *spot, layer)
+        })
+        .collect();
+    let data = cookie_data(&rows);
+    if images
+        .get(&LIGHT_COOKIE_DATA_IMAGE_HANDLE)
+        .is_some_and(|image| image.data != data)
+    {
+        images.get_mut(&LIGHT_COOKIE_DATA_IMAGE_HANDLE).unwrap().data = data;
+    }
+
+    // Textures that weren't loaded yet are drawn white until their asset event
+    let textures: Vec<_> = lights.iter().map(|(_, cookie, ..)| cookie.texture.id()).collect();
+    let loaded = image_events
+        .read()
+        .filter(|event| {
+            matches!(
+                event,
+                AssetEvent::Added { id } | AssetEvent::Modified { id } if textures.contains(id)
+            )
+        })
+        .count()
+        > 0;
+    if *written == textures && !loaded {
+        return;
+    }
+    let layers: Vec<_> = textures
+        .iter()
+        .map(|id| images.get(*id).map(cookie_layer))
+        .collect();
+    let Some(cookies) = images.get_mut(&LIGHT_COOKIES_IMAGE_HANDLE) else {
+        return;
+    };
+    let layer_size = (COOKIE_SIZE * COOKIE_SIZE * 4) as usize;
+    for (layer, texels) in cookies.data.chunks_exact_mut(layer_size).zip(layers) {
+        match texels {
+            Some(texels) => layer.copy_from_slice(&texels),
+            None => layer.fill(255),
+        }
+    }
+    *written = textures;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Where `sk_light_cookie` samples the cookie of `row` at `world_position`
+    fn cookie_uv(row: [Vec4; 4], world_position: Vec3) -> Vec2 {
+        let mut offset = world_position - row[0].truncate();
+        if row[0].w == SPOT_COOKIE {
+            offset /= offset.dot(row[1].truncate());
+        }
+        Vec2::new(offset.dot(row[2].truncate()), -offset.dot(row[3].truncate())) * 0.5 + 0.5
+    }
+
+    #[test]
+    fn spot_cookies_span_the_outer_cone() {
+        let cookie = LightCookie::new(Handle::default());
+        let spot = SpotLight {
+            outer_angle: 0.5,
+            ..default()
+        };
+        let transform = GlobalTransform::from(Transform::from_xyz(1.0, 2.0, 3.0));
+        let row = cookie_row(&cookie, &transform, Some(&spot), 3);
+        assert_eq!(row[0].w, SPOT_COOKIE);
+        assert_eq!(row[1].w, 3.0);
+
+        // The light looks down -Z, the cone's right edge is at x = tan(outer_angle) * depth
+        let depth = 4.0;
+        let edge = Vec3::new(1.0 + 0.5f32.tan() * depth, 2.0, 3.0 - depth);
+        assert!(cookie_uv(row, edge).distance(Vec2::new(1.0, 0.5)) < 1e-5);
+        let center = Vec3::new(1.0, 2.0, 3.0 - depth);
+        assert!(cookie_uv(row, center).distance(Vec2::splat(0.5)) < 1e-5);
+    }
+
+    #[test]
+    fn directional_cookies_repeat_every_size() {
+        let cookie = LightCookie {
+            size: Vec2::new(4.0, 2.0),
+            ..LightCookie::new(Handle::default())
+        };
+        let transform = GlobalTransform::from(Transform::default());
+        let row = cookie_row(&cookie, &transform, None, 0);
+        assert_eq!(row[0].w, DIRECTIONAL_COOKIE);
+
+        let uv = cookie_uv(row, Vec3::new(2.0, 1.0, -7.0));
+        assert!(uv.distance(Vec2::new(1.0, 0.0)) < 1e-5);
+    }
+
+    #[test]
+    fn unused_rows_end_the_data() {
+        let data = cookie_data(&[[Vec4::ONE; 4]]);
+        assert_eq!(data.len(), 4 * 16 * MAX_LIGHT_COOKIES);
+        assert_eq!(data[12..16], 1.0f32.to_le_bytes());
+        assert!(data[64..].iter().all(|&byte| byte == 0));
+    }
+}
@@ -0,0 +1,142 @@
+use crate::skytex::sun::{sync_sky_sun, SkySun, SkySunPlugin, SkySunSettings};
+use crate::skytex::{sync_sky_lighting, SkyLighting, SphericalHarmonics};
+use bevy::prelude::*;
+
+/// Drives [`SkyLighting`], the generated skybox and the [`SkySun`] light from
+/// [`LightEstimate`]s pushed by an AR light estimation provider, e.g. ARCore or an OpenXR
+/// light estimation extension.
+pub struct LightEstimationPlugin;
+
+impl Plugin for LightEstimationPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<LightEstimate>();
+        app.init_resource::<LightEstimationSettings>();
+        app.init_resource::<SmoothedLightEstimate>();
+        if !app.is_plugin_added::<SkySunPlugin>() {
+            app.add_plugins(SkySunPlugin);
+        }
+        app.register_type::<(LightEstimationSettings, SmoothedLightEstimate)>();
+        app.add_systems(
+            Update,
+            (
+                smooth_light_estimates.before(sync_sky_lighting),
+                apply_estimated_light.after(sync_sky_sun),
+            ),
+        );
+    }
+}
+
+/// One frame of estimated real world lighting, either part may be missing
+#[derive(Event, Clone, Copy, Debug, Default)]
+pub struct LightEstimate {
+    /// Ambient lighting in the crate's SH convention
+    pub lighting: Option<SphericalHarmonics>,
+    pub primary_light: Option<EstimatedLight>,
+}
+
+#[derive(Reflect, Clone, Copy, Debug, PartialEq)]
+pub struct EstimatedLight {
+    /// Direction from the viewer towards the light
+    pub direction: Vec3,
+    pub color: Color,
+    /// Lux
+    pub illuminance: f32,
+}
+
+#[derive(Resource, Reflect, Clone, Copy, Debug, PartialEq)]
+#[reflect(Resource)]
+pub struct LightEstimationSettings {
+    /// Seconds for the smoothed estimate to move halfway to a new one, 0 disables smoothing
+    pub half_life: f32,
+    /// Minimum seconds between [`SkyLighting`] updates, each one regenerates the sky
+    pub sky_update_interval: f32,
+}
+
+impl Default for LightEstimationSettings {
+    fn default() -> Self {
+        Self {
+            half_life: 0.5,
+            sky_update_interval: 0.2,
+        }
+    }
+}
+
+/// The estimate after smoothing, `None` until the provider sent one
+#[derive(Resource, Reflect, Clone, Copy, Debug, Default, PartialEq)]
+#[reflect(Resource)]
+pub struct SmoothedLightEstimate {
+    pub lighting: Option<SphericalHarmonics>,
+    pub primary_light: Option<EstimatedLight>,
+}
+
+fn smooth_light_estimates(
+    time: Res<Time>,
+    settings: Res<LightEstimationSettings>,
+    mut estimates: EventReader<LightEstimate>,
+    mut smoothed: ResMut<SmoothedLightEstimate>,
+    mut lighting: ResMut<SkyLighting>,
+    mut since_sky_update: Local<f32>,
+    mut target: Local<LightEstimate>,
+) {
+    for estimate in estimates.read() {
+        target.lighting = estimate.lighting.or(target.lighting);
+        target.primary_light = estimate.primary_light.or(target.primary_light);
+    }
+
+    let dt = time.delta_seconds();
+    let t = if settings.half_life > 0.0 {
+        1.0 - 0.5f32.powf(dt / settings.half_life)
+    } else {
+        1.0
+    };
+
+    let current = *smoothed;
+    let next = SmoothedLightEstimate {
+        lighting: match (current.lighting, target.lighting) {
+            (Some(from), Some(to)) => Some(from.lerp(&to, t)),
+            (_, to) => to,
+        },
+        primary_light: match (current.primary_light, target.primary_light) {
+            (Some(from), Some(to)) => Some(EstimatedLight {
+                direction: from
+                    .direction
+                    .lerp(to.direction, t)
+                    .try_normalize()
+                    .unwrap_or(to.direction),
+                color: from.color.mix(&to.color, t),
+                illuminance: from.illuminance + (to.illuminance - from.illuminance) * t,
+            }),
+            (_, to) => to,
+        },
+    };
+    smoothed.set_if_neq(next);
+
+    *since_sky_update += dt;
+    if *since_sky_update < settings.sky_update_interval {
+        return;
+    }
+    if let Some(sh) = smoothed.lighting {
+        *since_sky_update = 0.0;
+        lighting.set_if_neq(SkyLighting(sh));
+    }
+}
+
+/// Overrides the SH derived [`SkySun`], which `SkySunPlugin` spawns, with the estimated light
+fn apply_estimated_light(
+    smoothed: Res<SmoothedLightEstimate>,
+    settings: Res<SkySunSettings>,
+    mut suns: Query<(&mut DirectionalLight, &mut Transform), With<SkySun>>,
+) {
+    let Some(estimate) = smoothed.primary_light else {
+        return;
+    };
+    for (mut light, mut transform) in suns.iter_mut() {
+        *light = DirectionalLight {
+            color: estimate.color,
+            illuminance: estimate.illuminance,
+            shadows_enabled: settings.shadows_enabled,
+            ..default()
+        };
+        *transform = Transform::IDENTITY.looking_to(-estimate.direction, Vec3::Y);
+    }
+}
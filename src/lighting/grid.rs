@@ -0,0 +1,178 @@
+use crate::lighting::buffer::{ShLightingBuffer, ShReceivers, ShSlot};
+use crate::lighting::volume::{blend_volumes_at, ShVolume};
+use crate::materials::pbr::PbrMaterial;
+use crate::skytex::SphericalHarmonics;
+use bevy::prelude::*;
+
+/// Bakes [`LightProbeGrid`]s and interpolates them into the lighting of every
+/// [`LightProbeGridReceiver`]
+pub struct LightProbeGridPlugin;
+
+impl Plugin for LightProbeGridPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<(LightProbeGrid, BakeLightProbeGrid, LightProbeGridReceiver)>();
+        app.add_systems(
+            PostUpdate,
+            (bake_light_probe_grids, sample_light_probe_grids)
+                .chain()
+                .after(TransformSystem::TransformPropagate),
+        );
+    }
+}
+
+/// Box of SH probes on a regular grid, centered on the entity in its local space, e.g.
+/// spanning a large interior.
+///
+/// Fill `cells` yourself with [`LightProbeGrid::bake_with`], or add [`BakeLightProbeGrid`]
+/// to bake it from the global lighting and the [`ShVolume`]s around each cell.
+#[derive(Component, Reflect, Clone, Debug)]
+#[reflect(Component)]
+pub struct LightProbeGrid {
+    pub half_extents: Vec3,
+    /// Probes along each axis, at least 2
+    pub resolution: UVec3,
+    /// Probe SH in x, then y, then z order
+    pub cells: Vec<SphericalHarmonics>,
+    /// Distance inside the boundary over which the grid fades in over the global lighting
+    pub blend_distance: f32,
+}
+
+impl LightProbeGrid {
+    /// A grid of `resolution` probes holding `lighting`
+    pub fn new(half_extents: Vec3, resolution: UVec3, lighting: SphericalHarmonics) -> Self {
+        let resolution = resolution.max(UVec3::splat(2));
+        Self {
+            half_extents,
+            resolution,
+            cells: vec![lighting; (resolution.x * resolution.y * resolution.z) as usize],
+            blend_distance: 0.5,
+        }
+    }
+
+    fn index(&self, cell: UVec3) -> usize {
+        let r = self.resolution;
+        (cell.x + r.x * (cell.y + r.y * cell.z)) as usize
+    }
+
+    /// Local position of the probe in `cell`
+    pub fn cell_position(&self, cell: UVec3) -> Vec3 {
+        let t = cell.as_vec3() / (self.resolution - 1).max(UVec3::ONE).as_vec3();
+        (t * 2.0 - 1.0) * self.half_extents
+    }
+
+    /// Sets every probe to `bake` of its world position
+    pub fn bake_with(
+        &mut self,
+        transform: &GlobalTransform,
+        mut bake: impl FnMut(Vec3) -> SphericalHarmonics,
+    ) {
+        let r = self.resolution;
+        self.cells.resize((r.x * r.y * r.z) as usize, SphericalHarmonics::default());
+        for z in 0..r.z {
+            for y in 0..r.y {
+                for x in 0..r.x {
+                    let cell = UVec3::new(x, y, z);
+                    let point = transform.transform_point(self.cell_position(cell));
+                    let index = self.index(cell);
+                    self.cells[index] = bake(point);
+                }
+            }
+        }
+    }
+
+    /// Trilinearly interpolated SH at world `point` and the grid's weight there, `None` when
+    /// outside the grid or the cells don't match the resolution
+    pub fn sample(
+        &self,
+        transform: &GlobalTransform,
+        point: Vec3,
+    ) -> Option<(SphericalHarmonics, f32)> {
+        let r = self.resolution;
+        if r.cmplt(UVec3::splat(2)).any() || self.cells.len() != (r.x * r.y * r.z) as usize {
+            return None;
+        }
+        let local = transform.affine().inverse().transform_point3(point);
+        let distance = (self.half_extents - local.abs()).min_element();
+        if distance < 0.0 {
+            return None;
+        }
+        let weight = if self.blend_distance <= 0.0 {
+            1.0
+        } else {
+            (distance / self.blend_distance).clamp(0.0, 1.0)
+        };
+
+        let t = (local / self.half_extents.max(Vec3::splat(f32::EPSILON)) * 0.5 + 0.5)
+            .clamp(Vec3::ZERO, Vec3::ONE)
+            * (r - 1).as_vec3();
+        let low = t.floor().as_uvec3().min(r - 2);
+        let f = t - low.as_vec3();
+
+        let mut result = SphericalHarmonics::default();
+        for corner in 0..8u32 {
+            let offset = UVec3::new(corner & 1, (corner >> 1) & 1, corner >> 2);
+            let w = Vec3::select(offset.cmpeq(UVec3::ONE), f, 1.0 - f);
+            let w = w.x * w.y * w.z;
+            let cell = &self.cells[self.index(low + offset)];
+            for (out, c) in result.coefficients.iter_mut().zip(cell.coefficients) {
+                *out += c * w;
+            }
+        }
+        Some((result, weight))
+    }
+}
+
+/// Rebakes the [`LightProbeGrid`] on this entity from the global lighting and [`ShVolume`]s,
+/// removed once baked
+#[derive(Component, Reflect, Clone, Copy, Debug, Default)]
+#[reflect(Component)]
+pub struct BakeLightProbeGrid;
+
+/// Marks an entity whose `PbrMaterial` is lit by the [`LightProbeGrid`] containing it.
+///
+/// The entity gets its own copy of the material pointed at a dedicated [`ShSlot`], so others
+/// sharing the material keep their lighting.
+#[derive(Component, Reflect, Clone, Copy, Debug, Default)]
+#[reflect(Component)]
+pub struct LightProbeGridReceiver;
+
+fn bake_light_probe_grids(
+    mut commands: Commands,
+    mut grids: Query<(Entity, &mut LightProbeGrid, &GlobalTransform), With<BakeLightProbeGrid>>,
+    volumes: Query<(&ShVolume, &GlobalTransform)>,
+    buffer: Res<ShLightingBuffer>,
+) {
+    let base = *buffer.get(ShSlot::GLOBAL).unwrap();
+    for (entity, mut grid, transform) in grids.iter_mut() {
+        grid.bake_with(transform, |point| blend_volumes_at(base, volumes.iter(), point));
+        commands.entity(entity).remove::<BakeLightProbeGrid>();
+    }
+}
+
+fn sample_light_probe_grids(
+    mut commands: Commands,
+    grids: Query<(&LightProbeGrid, &GlobalTransform)>,
+    receivers: Query<
+        (Entity, &GlobalTransform, &Handle<PbrMaterial>),
+        With<LightProbeGridReceiver>,
+    >,
+    mut removed: RemovedComponents<LightProbeGridReceiver>,
+    mut lit: Local<ShReceivers>,
+    mut buffer: ResMut<ShLightingBuffer>,
+    mut materials: ResMut<Assets<PbrMaterial>>,
+) {
+    lit.release(removed.read(), &mut buffer, &mut materials);
+
+    let base = *buffer.get(ShSlot::GLOBAL).unwrap();
+    for (entity, transform, material) in receivers.iter() {
+        let point = transform.translation();
+        // The grid the receiver is deepest inside wins where grids overlap
+        let lighting = grids
+            .iter()
+            .filter_map(|(grid, grid_transform)| grid.sample(grid_transform, point))
+            .max_by(|a, b| a.1.total_cmp(&b.1))
+            .map_or(base, |(lighting, weight)| base.lerp(&lighting, weight));
+
+        lit.set(&mut commands, entity, material, lighting, &mut buffer, &mut materials);
+    }
+}
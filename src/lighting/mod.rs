@@ -0,0 +1,9 @@
+pub mod ambient;
+pub mod buffer;
+pub mod cookie;
+pub mod estimation;
+pub mod grid;
+pub mod overrides;
+pub mod probes;
+pub mod volume;
+pub mod zones;
@@ -0,0 +1,38 @@
+use crate::lighting::buffer::{ShLightingBuffer, ShReceivers};
+use crate::materials::pbr::PbrMaterial;
+use crate::skytex::SphericalHarmonics;
+use bevy::prelude::*;
+
+/// Lights the `PbrMaterial` of every [`OverrideLighting`] entity with its own SH
+pub struct OverrideLightingPlugin;
+
+impl Plugin for OverrideLightingPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<OverrideLighting>();
+        app.add_systems(PostUpdate, apply_override_lighting);
+    }
+}
+
+/// SH this entity's `PbrMaterial` is lit with instead of the environment, e.g. for a held
+/// lantern or a cursed artifact.
+///
+/// The entity gets its own copy of the material pointed at a dedicated
+/// [`ShSlot`](crate::lighting::buffer::ShSlot), so others sharing the material keep their
+/// lighting. Don't combine it with an `ShVolumeReceiver`.
+#[derive(Component, Reflect, Clone, Copy, Debug, PartialEq, Deref, DerefMut)]
+#[reflect(Component)]
+pub struct OverrideLighting(pub SphericalHarmonics);
+
+fn apply_override_lighting(
+    mut commands: Commands,
+    entities: Query<(Entity, &OverrideLighting, &Handle<PbrMaterial>)>,
+    mut removed: RemovedComponents<OverrideLighting>,
+    mut overridden: Local<ShReceivers>,
+    mut buffer: ResMut<ShLightingBuffer>,
+    mut materials: ResMut<Assets<PbrMaterial>>,
+) {
+    overridden.release(removed.read(), &mut buffer, &mut materials);
+    for (entity, lighting, material) in entities.iter() {
+        overridden.set(&mut commands, entity, material, lighting.0, &mut buffer, &mut materials);
+    }
+}
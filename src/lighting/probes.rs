@@ -0,0 +1,225 @@
+use crate::capture::{CaptureCubemap, CubemapCaptureSet, CubemapCapturePlugin, CubemapCaptured};
+use crate::lighting::volume::{ShVolume, ShVolumeShape};
+use crate::materials::pbr::PbrMaterial;
+use crate::skytex::{SetupSkyTex, SphericalHarmonics};
+use bevy::core_pipeline::Skybox;
+use bevy::pbr::environment_map::EnvironmentMapLight;
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+
+/// Picks the two most relevant [`ReflectionSource`]s for every [`ReflectionProbeReceiver`]
+/// and blends between them in `pbr.wgsl`, with the generated sky as the outermost source.
+pub struct ReflectionProbePlugin;
+
+impl Plugin for ReflectionProbePlugin {
+    fn build(&self, app: &mut App) {
+        if !app.is_plugin_added::<CubemapCapturePlugin>() {
+            app.add_plugins(CubemapCapturePlugin);
+        }
+        app.register_type::<(
+            ReflectionSource,
+            ReflectionProbeReceiver,
+            ReflectionProbe,
+            RecaptureReflectionProbe,
+            ReflectionProbeLighting,
+        )>();
+        app.add_systems(
+            PostUpdate,
+            (
+                schedule_probe_captures.before(CubemapCaptureSet),
+                apply_probe_captures.after(CubemapCaptureSet),
+                select_reflection_probes,
+            )
+                .chain()
+                .after(TransformSystem::TransformPropagate),
+        );
+    }
+}
+
+/// A local cubemap to reflect, e.g. one captured inside a room
+#[derive(Component, Reflect, Clone, Debug)]
+#[reflect(Component)]
+pub struct ReflectionSource {
+    /// Cube texture
+    pub image: Handle<Image>,
+    /// Distance from the entity at which the probe has faded out completely
+    pub radius: f32,
+}
+
+impl ReflectionSource {
+    /// Influence of the probe at `point`, 1 at its center down to 0 at `radius`
+    pub fn weight(&self, transform: &GlobalTransform, point: Vec3) -> f32 {
+        if self.radius <= 0.0 {
+            return 0.0;
+        }
+        (1.0 - transform.translation().distance(point) / self.radius).clamp(0.0, 1.0)
+    }
+}
+
+/// Captures the scene around the entity into a [`ReflectionSource`] and an [`ShVolume`] of
+/// its projected SH, so [`ReflectionProbeReceiver`]s and `ShVolumeReceiver`s inside `radius`
+/// are lit by the room they're in rather than the sky.
+#[derive(Component, Reflect, Clone, Copy, Debug, PartialEq)]
+#[reflect(Component)]
+pub struct ReflectionProbe {
+    pub face_size: u32,
+    /// Radius of the resulting `ReflectionSource` and `ShVolume`
+    pub radius: f32,
+    pub refresh: ReflectionProbeRefresh,
+}
+
+impl Default for ReflectionProbe {
+    fn default() -> Self {
+        Self {
+            face_size: 64,
+            radius: 5.0,
+            refresh: ReflectionProbeRefresh::Once,
+        }
+    }
+}
+
+/// When a [`ReflectionProbe`] captures the scene, besides on [`RecaptureReflectionProbe`]
+#[derive(Reflect, Clone, Copy, Debug, Default, PartialEq)]
+pub enum ReflectionProbeRefresh {
+    /// Once after the probe is added
+    #[default]
+    Once,
+    /// Only on [`RecaptureReflectionProbe`]
+    OnDemand,
+    /// Every given number of seconds
+    Every(f32),
+}
+
+/// Recaptures a [`ReflectionProbe`], removed once the capture started
+#[derive(Component, Reflect, Clone, Copy, Debug, Default)]
+#[reflect(Component)]
+pub struct RecaptureReflectionProbe;
+
+/// SH projected from the last capture of a [`ReflectionProbe`]
+#[derive(Component, Reflect, Clone, Copy, Debug, PartialEq, Deref)]
+#[reflect(Component)]
+pub struct ReflectionProbeLighting(pub SphericalHarmonics);
+
+/// Marks an entity whose `PbrMaterial` reflects the probes around it.
+///
+/// The probes are written into the material itself, so give every receiver its own material.
+#[derive(Component, Reflect, Clone, Copy, Debug, Default)]
+#[reflect(Component)]
+pub struct ReflectionProbeReceiver;
+
+/// The two sources to blend at `point` and the weight of the second one.
+///
+/// A single probe fades towards `sky` near its edge, two overlapping probes are blended by
+/// their relative weight so swapping the nearest one never pops.
+pub fn select_probes_at<'a>(
+    sky: Option<&'a Handle<Image>>,
+    sources: impl Iterator<Item = (&'a ReflectionSource, &'a GlobalTransform)>,
+    point: Vec3,
+) -> Option<(&'a Handle<Image>, &'a Handle<Image>, f32)> {
+    let mut nearest: Vec<(&Handle<Image>, f32)> = sources
+        .map(|(source, transform)| (&source.image, source.weight(transform, point)))
+        .filter(|(_, weight)| *weight > 0.0)
+        .collect();
+    nearest.sort_by(|a, b| b.1.total_cmp(&a.1));
+
+    match (nearest.first(), nearest.get(1)) {
+        (Some(&(a, wa)), Some(&(b, wb))) => Some((a, b, wb / (wa + wb))),
+        (Some(&(a, wa)), None) => Some((a, sky.unwrap_or(a), 1.0 - wa)),
+        _ => sky.map(|sky| (sky, sky, 0.0)),
+    }
+}
+
+fn select_reflection_probes(
+    sky: Query<(&Skybox, Option<&EnvironmentMapLight>), With<SetupSkyTex>>,
+    sources: Query<(&ReflectionSource, &GlobalTransform)>,
+    receivers: Query<(&GlobalTransform, &Handle<PbrMaterial>), With<ReflectionProbeReceiver>>,
+    mut materials: ResMut<Assets<PbrMaterial>>,
+) {
+    // The prefiltered environment has the roughness mips, the plain skybox is a fallback
+    let sky = sky
+        .iter()
+        .next()
+        .map(|(skybox, environment)| environment.map_or(&skybox.image, |e| &e.specular_map));
+    for (transform, material) in receivers.iter() {
+        let selection = select_probes_at(sky, sources.iter(), transform.translation());
+        let (a, b, blend) = match selection {
+            Some((a, b, blend)) => (Some(a.clone()), Some(b.clone()), blend),
+            None => (None, None, 0.0),
+        };
+
+        let Some(current) = materials.get(material) else {
+            continue;
+        };
+        if current.reflection_probe_a != a
+            || current.reflection_probe_b != b
+            || current.reflection_blend != blend
+        {
+            let material = materials.get_mut(material).unwrap();
+            material.reflection_probe_a = a;
+            material.reflection_probe_b = b;
+            material.reflection_blend = blend;
+        }
+    }
+}
+
+fn schedule_probe_captures(
+    mut commands: Commands,
+    time: Res<Time>,
+    probes: Query<(
+        Entity,
+        &ReflectionProbe,
+        Has<ReflectionProbeLighting>,
+        Has<RecaptureReflectionProbe>,
+    )>,
+    mut since_capture: Local<HashMap<Entity, f32>>,
+) {
+    since_capture.retain(|entity, _| probes.contains(*entity));
+    for (entity, probe, captured, recapture) in probes.iter() {
+        let elapsed = since_capture.entry(entity).or_insert(f32::INFINITY);
+        *elapsed += time.delta_seconds();
+        let due = match probe.refresh {
+            ReflectionProbeRefresh::Once => !captured && elapsed.is_infinite(),
+            ReflectionProbeRefresh::OnDemand => false,
+            ReflectionProbeRefresh::Every(interval) => *elapsed >= interval,
+        };
+        if due || recapture {
+            *elapsed = 0.0;
+            commands
+                .entity(entity)
+                .remove::<RecaptureReflectionProbe>()
+                .insert(CaptureCubemap {
+                    face_size: probe.face_size,
+                });
+        }
+    }
+}
+
+fn apply_probe_captures(
+    mut commands: Commands,
+    mut captured: EventReader<CubemapCaptured>,
+    probes: Query<&ReflectionProbe>,
+) {
+    for capture in captured.read() {
+        let Ok(probe) = probes.get(capture.entity) else {
+            continue;
+        };
+        let Some(lighting) = SphericalHarmonics::from_cubemap(&capture.image) else {
+            continue;
+        };
+        commands.entity(capture.entity).insert((
+            ReflectionSource {
+                image: capture.cubemap.clone(),
+                radius: probe.radius,
+            },
+            ShVolume {
+                shape: ShVolumeShape::Sphere {
+                    radius: probe.radius,
+                },
+                lighting,
+                priority: 0,
+                blend_distance: probe.radius * 0.5,
+            },
+            ReflectionProbeLighting(lighting),
+        ));
+    }
+}
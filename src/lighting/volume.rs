@@ -0,0 +1,131 @@
+use crate::lighting::buffer::{ShLightingBuffer, ShReceivers, ShSlot};
+use crate::materials::pbr::PbrMaterial;
+use crate::skytex::SphericalHarmonics;
+use bevy::prelude::*;
+
+/// Blends [`ShVolume`]s into the lighting of every [`ShVolumeReceiver`]
+pub struct ShVolumePlugin;
+
+impl Plugin for ShVolumePlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<(ShVolume, ShVolumeReceiver)>();
+        app.add_systems(PostUpdate, blend_sh_volumes.after(TransformSystem::TransformPropagate));
+    }
+}
+
+#[derive(Reflect, Clone, Copy, Debug, PartialEq)]
+pub enum ShVolumeShape {
+    /// Box centered on the entity, in its local space
+    Box { half_extents: Vec3 },
+    /// Sphere centered on the entity, in its local space
+    Sphere { radius: f32 },
+}
+
+/// Region with its own ambient lighting, e.g. a room, cave or vehicle interior
+#[derive(Component, Reflect, Clone, Debug)]
+#[reflect(Component)]
+pub struct ShVolume {
+    pub shape: ShVolumeShape,
+    pub lighting: SphericalHarmonics,
+    /// Volumes with a higher priority are blended on top of lower ones
+    pub priority: i32,
+    /// Distance inside the boundary over which the volume fades in
+    pub blend_distance: f32,
+}
+
+impl ShVolume {
+    /// How far `point` lies inside the volume, negative when outside
+    pub fn inside_distance(&self, transform: &GlobalTransform, point: Vec3) -> f32 {
+        let local = transform.affine().inverse().transform_point3(point);
+        match self.shape {
+            ShVolumeShape::Box { half_extents } => (half_extents - local.abs()).min_element(),
+            ShVolumeShape::Sphere { radius } => radius - local.length(),
+        }
+    }
+
+    /// Blend weight of this volume at `point`
+    pub fn weight(&self, transform: &GlobalTransform, point: Vec3) -> f32 {
+        let distance = self.inside_distance(transform, point);
+        if self.blend_distance <= 0.0 {
+            return if distance >= 0.0 { 1.0 } else { 0.0 };
+        }
+        (distance / self.blend_distance).clamp(0.0, 1.0)
+    }
+}
+
+/// Marks an entity (usually the camera) whose lighting is driven by the volumes around it.
+///
+/// If the entity also has a `Handle<PbrMaterial>`, it gets its own copy of that material
+/// pointed at a dedicated [`ShSlot`] which receives the blended SH.
+#[derive(Component, Reflect, Clone, Copy, Debug, Default)]
+#[reflect(Component)]
+pub struct ShVolumeReceiver;
+
+/// Effective SH of a [`ShVolumeReceiver`] after blending the volumes containing it
+#[derive(Component, Clone, Copy, Debug, PartialEq, Deref)]
+pub struct ShVolumeLighting(pub SphericalHarmonics);
+
+/// Blends every volume containing `point` on top of `base`, lowest priority first
+pub fn blend_volumes_at<'a>(
+    base: SphericalHarmonics,
+    volumes: impl Iterator<Item = (&'a ShVolume, &'a GlobalTransform)>,
+    point: Vec3,
+) -> SphericalHarmonics {
+    let mut containing: Vec<(&ShVolume, f32)> = volumes
+        .map(|(volume, transform)| (volume, volume.weight(transform, point)))
+        .filter(|(_, weight)| *weight > 0.0)
+        .collect();
+    containing.sort_by_key(|(volume, _)| volume.priority);
+
+    let mut result = base;
+    for (volume, weight) in containing {
+        for (out, target) in result
+            .coefficients
+            .iter_mut()
+            .zip(volume.lighting.coefficients)
+        {
+            *out = out.lerp(target, weight);
+        }
+    }
+    result
+}
+
+fn blend_sh_volumes(
+    mut commands: Commands,
+    volumes: Query<(&ShVolume, &GlobalTransform)>,
+    mut receivers: Query<
+        (
+            Entity,
+            &GlobalTransform,
+            Option<&mut ShVolumeLighting>,
+            Option<&Handle<PbrMaterial>>,
+        ),
+        With<ShVolumeReceiver>,
+    >,
+    mut removed: RemovedComponents<ShVolumeReceiver>,
+    mut lit: Local<ShReceivers>,
+    mut buffer: ResMut<ShLightingBuffer>,
+    mut materials: ResMut<Assets<PbrMaterial>>,
+) {
+    lit.release(removed.read(), &mut buffer, &mut materials);
+
+    let base = *buffer.get(ShSlot::GLOBAL).unwrap();
+    for (entity, transform, current, material) in receivers.iter_mut() {
+        let lighting = blend_volumes_at(base, volumes.iter(), transform.translation());
+
+        match current {
+            Some(mut current) => {
+                if current.0 != lighting {
+                    current.0 = lighting;
+                }
+            }
+            None => {
+                commands.entity(entity).insert(ShVolumeLighting(lighting));
+            }
+        }
+
+        if let Some(material) = material {
+            lit.set(&mut commands, entity, material, lighting, &mut buffer, &mut materials);
+        }
+    }
+}
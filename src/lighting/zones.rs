@@ -0,0 +1,121 @@
+use crate::lighting::volume::{blend_volumes_at, ShVolume};
+use crate::skytex::{sync_sky_lighting, SkyLighting, SphericalHarmonics};
+use bevy::prelude::*;
+
+/// Blends the [`LightingZone`]s around [`LightingZones::listener`] into [`SkyLighting`], so
+/// the sky and the global material SH change together, e.g. walking out of a red-lit room
+/// into daylight
+pub struct LightingZonePlugin;
+
+impl Plugin for LightingZonePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<LightingZones>();
+        app.register_type::<(LightingZone, LightingZones)>();
+        app.add_systems(
+            Update,
+            blend_lighting_zones
+                .before(sync_sky_lighting)
+                .run_if(resource_exists::<SkyLighting>),
+        );
+    }
+}
+
+/// An [`ShVolume`] whose lighting replaces the scene's [`SkyLighting`] while the listener is
+/// inside, rather than the lighting of `ShVolumeReceiver`s. `blend_distance` fades between
+/// zones and the outside at their boundary.
+#[derive(Component, Reflect, Clone, Debug)]
+#[reflect(Component)]
+pub struct LightingZone(pub ShVolume);
+
+/// Where and how quickly [`LightingZone`]s are blended.
+///
+/// While zones exist they own [`SkyLighting`], change `outside` instead of it, e.g. for a
+/// time of day.
+#[derive(Resource, Reflect, Clone, Debug, PartialEq)]
+#[reflect(Resource)]
+pub struct LightingZones {
+    /// Entity whose position picks the zones, `None` uses the first active 3d camera
+    pub listener: Option<Entity>,
+    /// Lighting outside every zone, `None` captures the current [`SkyLighting`] once a zone
+    /// shows up
+    pub outside: Option<SphericalHarmonics>,
+    /// Seconds for the lighting to move halfway to the zones' blend, 0 snaps
+    pub half_life: f32,
+    /// Minimum seconds between updates of [`SkyLighting`], each of which regenerates the
+    /// generated skyboxes
+    pub step_interval: f32,
+    smoothed: Option<SphericalHarmonics>,
+    since_step: f32,
+}
+
+impl Default for LightingZones {
+    fn default() -> Self {
+        Self {
+            listener: None,
+            outside: None,
+            half_life: 0.5,
+            step_interval: 0.1,
+            smoothed: None,
+            since_step: f32::INFINITY,
+        }
+    }
+}
+
+fn blend_lighting_zones(
+    time: Res<Time>,
+    mut settings: ResMut<LightingZones>,
+    zones: Query<(&LightingZone, &GlobalTransform)>,
+    listeners: Query<&GlobalTransform>,
+    cameras: Query<(&Camera, &GlobalTransform), With<Camera3d>>,
+    mut lighting: ResMut<SkyLighting>,
+) {
+    let settings = settings.bypass_change_detection();
+    if zones.is_empty() {
+        // The last zone went away, hand the sky back to the outside lighting
+        if settings.smoothed.take().is_some() {
+            if let Some(outside) = settings.outside {
+                lighting.set_if_neq(SkyLighting(outside));
+            }
+        }
+        return;
+    }
+    let listener = match settings.listener {
+        Some(entity) => listeners.get(entity).ok(),
+        None => cameras
+            .iter()
+            .find(|(camera, _)| camera.is_active)
+            .map(|(_, transform)| transform),
+    };
+    let Some(listener) = listener else {
+        return;
+    };
+
+    let outside = *settings.outside.get_or_insert(lighting.0);
+    let zones = zones.iter().map(|(zone, transform)| (&zone.0, transform));
+    let target = blend_volumes_at(outside, zones, listener.translation());
+    let t = if settings.half_life <= 0.0 {
+        1.0
+    } else {
+        1.0 - 0.5f32.powf(time.delta_seconds() / settings.half_life)
+    };
+    let mut smoothed = settings.smoothed.map_or(target, |s| s.lerp(&target, t));
+    // Settles on the target, so the sky stops regenerating
+    let settled = smoothed
+        .coefficients
+        .iter()
+        .zip(target.coefficients)
+        .all(|(a, b)| a.abs_diff_eq(b, 1e-3));
+    if settled {
+        smoothed = target;
+    }
+    settings.smoothed = Some(smoothed);
+
+    settings.since_step += time.delta_seconds();
+    if settings.since_step < settings.step_interval && !settled {
+        return;
+    }
+    if lighting.0 != smoothed {
+        settings.since_step = 0.0;
+        lighting.0 = smoothed;
+    }
+}
@@ -0,0 +1,54 @@
+#import bevy_pbr::{
+    mesh_functions,
+    forward_io::{Vertex, VertexOutput},
+    mesh_view_bindings::view,
+    view_transformations::position_world_to_clip,
+}
+#import bevy_sk::pbr_types::sk_alpha_output
+
+struct SkLineMaterial {
+    color: vec4<f32>,
+    flags: u32,
+};
+
+@group(2) @binding(0)
+var<uniform> material: SkLineMaterial;
+
+@vertex
+fn vertex(vertex: Vertex) -> VertexOutput {
+    var out: VertexOutput;
+
+    let world_from_local = mesh_functions::get_world_from_local(vertex.instance_index);
+    let center = (world_from_local * vec4(vertex.position, 1.0)).xyz;
+    let to_camera = view.world_position - center;
+    // The line direction is stored as the normal
+    var along = (world_from_local * vec4(vertex.normal, 0.0)).xyz;
+    var side = cross(along, to_camera);
+    // Looking straight down the line any side works
+    if (dot(side, side) < 1e-12) {
+        side = view.world_from_view[0].xyz;
+    }
+
+    // Widened at the camera position rather than in view space, so both eyes agree
+    let half_thickness = vertex.uv.y;
+    out.world_position = vec4(center + normalize(side) * half_thickness, 1.0);
+    out.position = position_world_to_clip(out.world_position.xyz);
+    out.world_normal = normalize(to_camera);
+    out.uv = vec2(vertex.uv.x, sign(half_thickness));
+#ifdef VERTEX_COLORS
+    out.color = vertex.color;
+#endif
+#ifdef VERTEX_OUTPUT_INSTANCE_INDEX
+    out.instance_index = vertex.instance_index;
+#endif
+    return out;
+}
+
+@fragment
+fn fragment(in: VertexOutput) -> @location(0) vec4<f32> {
+    var color = material.color;
+#ifdef VERTEX_COLORS
+    color *= in.color;
+#endif
+    return sk_alpha_output(material.flags, color.rgb, color.a);
+}
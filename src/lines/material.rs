@@ -0,0 +1,82 @@
+use crate::materials::pbr::alpha_mode_flags;
+use bevy::prelude::*;
+use bevy::render::render_asset::RenderAssets;
+use bevy::render::render_resource::{AsBindGroup, AsBindGroupShaderType, ShaderRef, ShaderType};
+use bevy::render::texture::GpuImage;
+
+const SHADER_HANDLE: Handle<Shader> = Handle::weak_from_u128(0x5e2a7c81b3f9);
+
+/// Registers [`SkLineMaterial`], added by `LinesPlugin`
+pub struct SkLineMaterialPlugin;
+
+impl Plugin for SkLineMaterialPlugin {
+    fn build(&self, app: &mut App) {
+        load_shader!(app, SHADER_HANDLE, "line.wgsl");
+        app.add_plugins(MaterialPlugin::<SkLineMaterial> {
+            prepass_enabled: false,
+            shadows_enabled: false,
+            ..default()
+        });
+        app.register_asset_reflect::<SkLineMaterial>();
+    }
+}
+
+/// Unlit vertex colored lines, widening the meshes built by `LinesPlugin` into quads facing
+/// each eye. Expects their attributes: the position on the line, the line direction as the
+/// normal and the signed half thickness in `uv.y`.
+#[derive(Asset, AsBindGroup, Reflect, PartialEq, Debug, Clone)]
+#[uniform(0, SkLineMaterialUniform)]
+pub struct SkLineMaterial {
+    /// Multiplies the vertex colors
+    pub color: Color,
+    pub alpha_mode: AlphaMode,
+}
+
+impl Default for SkLineMaterial {
+    fn default() -> Self {
+        Self {
+            color: Color::WHITE,
+            alpha_mode: AlphaMode::Blend,
+        }
+    }
+}
+
+#[derive(Clone, Default, ShaderType)]
+pub struct SkLineMaterialUniform {
+    pub color: Vec4,
+    pub flags: u32,
+}
+
+impl AsBindGroupShaderType<SkLineMaterialUniform> for SkLineMaterial {
+    fn as_bind_group_shader_type(&self, _: &RenderAssets<GpuImage>) -> SkLineMaterialUniform {
+        SkLineMaterialUniform {
+            color: self.color.to_linear().to_vec4(),
+            flags: alpha_mode_flags(self.alpha_mode).0.bits(),
+        }
+    }
+}
+
+impl Material for SkLineMaterial {
+    fn vertex_shader() -> ShaderRef {
+        SHADER_HANDLE.into()
+    }
+
+    fn fragment_shader() -> ShaderRef {
+        SHADER_HANDLE.into()
+    }
+
+    fn alpha_mode(&self) -> AlphaMode {
+        self.alpha_mode
+    }
+
+    fn specialize(
+        _pipeline: &bevy::pbr::MaterialPipeline<Self>,
+        descriptor: &mut bevy::render::render_resource::RenderPipelineDescriptor,
+        _layout: &bevy::render::mesh::MeshVertexBufferLayoutRef,
+        _key: bevy::pbr::MaterialPipelineKey<Self>,
+    ) -> Result<(), bevy::render::render_resource::SpecializedMeshPipelineError> {
+        // The quads turn towards each eye, their winding depends on the side they're seen from
+        descriptor.primitive.cull_mode = None;
+        Ok(())
+    }
+}
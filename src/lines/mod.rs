@@ -0,0 +1,260 @@
+pub mod material;
+
+use crate::lines::material::{SkLineMaterial, SkLineMaterialPlugin};
+use bevy::pbr::{NotShadowCaster, NotShadowReceiver};
+use bevy::prelude::*;
+use bevy::render::mesh::{Indices, PrimitiveTopology};
+use bevy::render::primitives::Aabb;
+use bevy::render::render_asset::RenderAssetUsages;
+use bevy::render::view::NoFrustumCulling;
+use bevy::transform::TransformSystem;
+
+/// StereoKit-style lines, thick camera facing quads that hold up in a headset where gizmos
+/// are thin and aliased. Drawn with a [`SkLineMaterial`].
+///
+/// Lines added to [`Lines`] are drawn for a single frame, entities with a [`LineStrip`] keep
+/// theirs until it changes.
+pub struct LinesPlugin;
+
+impl Plugin for LinesPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins(SkLineMaterialPlugin);
+        app.init_resource::<Lines>();
+        app.register_type::<(LineStrip, LinePoint)>();
+        app.add_systems(Startup, spawn_immediate_lines);
+        app.add_systems(
+            PostUpdate,
+            (draw_immediate_lines, update_line_strips)
+                .before(TransformSystem::TransformPropagate),
+        );
+    }
+}
+
+/// A point along a line, the line blends color and thickness between its points
+#[derive(Reflect, Clone, Copy, Debug, PartialEq)]
+pub struct LinePoint {
+    pub position: Vec3,
+    pub color: Color,
+    /// Width of the line in meters, whatever the scale of the entity
+    pub thickness: f32,
+}
+
+impl LinePoint {
+    pub fn new(position: Vec3, color: Color, thickness: f32) -> Self {
+        Self {
+            position,
+            color,
+            thickness,
+        }
+    }
+}
+
+/// A line through `points`, in the local space of the entity, for pointers and trajectories
+/// that stay around. Spawn it with a `SpatialBundle`, the mesh and a shared material are added
+/// by `LinesPlugin`.
+#[derive(Component, Reflect, Clone, Debug, Default, PartialEq)]
+#[reflect(Component)]
+pub struct LineStrip {
+    pub points: Vec<LinePoint>,
+}
+
+impl LineStrip {
+    pub fn new(points: impl IntoIterator<Item = LinePoint>) -> Self {
+        Self {
+            points: points.into_iter().collect(),
+        }
+    }
+}
+
+/// Immediate mode world space lines, drawn for the frame they were added in, like
+/// StereoKit's `Lines.Add`. Add them during `Update`.
+#[derive(Resource, Default)]
+pub struct Lines {
+    strips: Vec<Vec<LinePoint>>,
+}
+
+impl Lines {
+    /// A line from `start` to `end`, blending their colors along it
+    pub fn add(&mut self, start: Vec3, end: Vec3, color: (Color, Color), thickness: f32) {
+        self.strips.push(vec![
+            LinePoint::new(start, color.0, thickness),
+            LinePoint::new(end, color.1, thickness),
+        ]);
+    }
+
+    /// A ray from its origin `length` meters along its direction, fading out towards the end
+    pub fn add_ray(&mut self, ray: Ray3d, length: f32, color: Color, thickness: f32) {
+        let end = ray.get_point(length);
+        self.add(ray.origin, end, (color, color.with_alpha(0.0)), thickness);
+    }
+
+    /// One line through every point
+    pub fn add_strip(&mut self, points: impl IntoIterator<Item = LinePoint>) {
+        let points: Vec<LinePoint> = points.into_iter().collect();
+        if points.len() >= 2 {
+            self.strips.push(points);
+        }
+    }
+
+    /// Red, green and blue lines along the X, Y and Z axes of `pose`, for debugging
+    pub fn add_axis(&mut self, pose: Transform, size: f32, thickness: f32) {
+        let axes = [
+            (pose.right(), Color::srgb(1.0, 0.0, 0.0)),
+            (pose.up(), Color::srgb(0.0, 1.0, 0.0)),
+            (pose.back(), Color::srgb(0.0, 0.0, 1.0)),
+        ];
+        for (axis, color) in axes {
+            let end = pose.translation + *axis * size;
+            self.add(pose.translation, end, (color, color), thickness);
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.strips.is_empty()
+    }
+}
+
+/// Two vertices per point, pushed apart by the vertex shader: the line direction is stored as
+/// the normal and the signed half thickness in `uv.y`
+fn line_mesh<'a>(strips: impl IntoIterator<Item = &'a [LinePoint]>) -> Mesh {
+    let mut positions = Vec::new();
+    let mut normals = Vec::new();
+    let mut uvs = Vec::new();
+    let mut colors = Vec::new();
+    let mut indices = Vec::new();
+
+    for points in strips {
+        if points.len() < 2 {
+            continue;
+        }
+        let first = positions.len() as u32;
+        let last = points.len() - 1;
+        let mut along = 0.0;
+        for (i, point) in points.iter().enumerate() {
+            // Joints point halfway between their two segments
+            let before = points[i.saturating_sub(1)].position;
+            let after = points[(i + 1).min(last)].position;
+            let direction = (after - before).normalize_or_zero();
+            if i > 0 {
+                along += point.position.distance(before);
+            }
+            let color = point.color.to_linear().to_f32_array();
+            for side in [-0.5, 0.5] {
+                positions.push(point.position.to_array());
+                normals.push(direction.to_array());
+                uvs.push([along, side * point.thickness]);
+                colors.push(color);
+            }
+        }
+        for i in 0..last as u32 {
+            let (a, b) = (first + i * 2, first + i * 2 + 1);
+            let (c, d) = (a + 2, b + 2);
+            indices.extend([a, c, b, b, c, d]);
+        }
+    }
+
+    Mesh::new(PrimitiveTopology::TriangleList, RenderAssetUsages::RENDER_WORLD)
+        .with_inserted_attribute(Mesh::ATTRIBUTE_POSITION, positions)
+        .with_inserted_attribute(Mesh::ATTRIBUTE_NORMAL, normals)
+        .with_inserted_attribute(Mesh::ATTRIBUTE_UV_0, uvs)
+        .with_inserted_attribute(Mesh::ATTRIBUTE_COLOR, colors)
+        .with_inserted_indices(Indices::U32(indices))
+}
+
+/// Bounds of the points, grown by half the thickest line
+fn line_aabb(points: &[LinePoint]) -> Aabb {
+    let (min, max, thickness) = points.iter().fold(
+        (Vec3::MAX, Vec3::MIN, 0.0f32),
+        |(min, max, thickness), point| {
+            let thickness = thickness.max(point.thickness);
+            (min.min(point.position), max.max(point.position), thickness)
+        },
+    );
+    if points.is_empty() {
+        return Aabb::default();
+    }
+    Aabb::from_min_max(min - thickness * 0.5, max + thickness * 0.5)
+}
+
+/// Draws every line of [`Lines`]
+#[derive(Component)]
+struct ImmediateLines;
+
+/// The material shared by all lines
+#[derive(Resource)]
+struct LineMaterial(Handle<SkLineMaterial>);
+
+fn spawn_immediate_lines(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<SkLineMaterial>>,
+) {
+    let material = materials.add(SkLineMaterial::default());
+    commands.spawn((
+        MaterialMeshBundle {
+            mesh: meshes.add(line_mesh([])),
+            material: material.clone(),
+            visibility: Visibility::Hidden,
+            ..default()
+        },
+        ImmediateLines,
+        // The lines are all over the world
+        NoFrustumCulling,
+        NotShadowCaster,
+        NotShadowReceiver,
+    ));
+    commands.insert_resource(LineMaterial(material));
+}
+
+fn draw_immediate_lines(
+    mut lines: ResMut<Lines>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut immediate: Query<(&Handle<Mesh>, &mut Visibility), With<ImmediateLines>>,
+    mut drawn: Local<bool>,
+) {
+    // Nothing to do while no lines come and go
+    if lines.is_empty() && !*drawn {
+        return;
+    }
+    for (mesh, mut visibility) in immediate.iter_mut() {
+        if lines.is_empty() {
+            visibility.set_if_neq(Visibility::Hidden);
+        } else {
+            meshes.insert(mesh, line_mesh(lines.strips.iter().map(Vec::as_slice)));
+            visibility.set_if_neq(Visibility::Inherited);
+        }
+    }
+    *drawn = !lines.is_empty();
+    lines.strips.clear();
+}
+
+fn update_line_strips(
+    mut commands: Commands,
+    material: Option<Res<LineMaterial>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    strips: Query<
+        (Entity, &LineStrip, Option<&Handle<Mesh>>, Has<Handle<SkLineMaterial>>),
+        Changed<LineStrip>,
+    >,
+) {
+    let Some(material) = material else {
+        return;
+    };
+    for (entity, strip, mesh, has_material) in strips.iter() {
+        let line = line_mesh([strip.points.as_slice()]);
+        let mut entity = commands.entity(entity);
+        // Bevy only computes the bounds of a mesh once
+        entity.insert(line_aabb(&strip.points));
+        match mesh {
+            Some(mesh) => {
+                meshes.insert(mesh, line);
+            }
+            None => {
+                entity.insert((meshes.add(line), NotShadowCaster, NotShadowReceiver));
+            }
+        }
+        if !has_material {
+            entity.insert(material.0.clone());
+        }
+    }
+}
@@ -0,0 +1,128 @@
+use crate::materials::pbr::PbrMaterial;
+use bevy::prelude::*;
+use bevy::render::extract_resource::{ExtractResource, ExtractResourcePlugin};
+use bevy::render::render_resource::Sampler;
+use bevy::render::renderer::RenderDevice;
+use bevy::render::texture::{ImageFilterMode, ImageSampler, ImageSamplerDescriptor};
+use bevy::render::RenderApp;
+use bevy::utils::HashMap;
+use std::sync::Mutex;
+
+/// Samples `PbrMaterial` textures with the anisotropic filtering of
+/// [`SkMaterialFeatures::anisotropy`](crate::quality::SkMaterialFeatures::anisotropy), added
+/// by `PbrPlugin`.
+///
+/// The materials bind their own anisotropic copies of their textures' samplers, the `Image`s
+/// are left alone so they aren't uploaded again and other users of them keep their sampler.
+pub struct TextureAnisotropyPlugin;
+
+impl Plugin for TextureAnisotropyPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<TextureSamplers>();
+        app.add_plugins(ExtractResourcePlugin::<TextureSamplers>::default());
+        app.add_systems(Update, collect_texture_samplers);
+        if let Some(render_app) = app.get_sub_app_mut(RenderApp) {
+            render_app.init_resource::<AnisotropicSamplers>();
+        }
+    }
+}
+
+/// Sampler of every texture of a `PbrMaterial`, which the render world only has as a wgpu
+/// `Sampler` it can't derive others from
+#[derive(Resource, ExtractResource, Clone, Default)]
+pub struct TextureSamplers(HashMap<AssetId<Image>, ImageSamplerDescriptor>);
+
+/// The anisotropic samplers created from [`TextureSamplers`], by texture and clamp
+#[derive(Resource, Default)]
+pub struct AnisotropicSamplers(Mutex<HashMap<(AssetId<Image>, u16), AnisotropicSampler>>);
+
+struct AnisotropicSampler {
+    descriptor: ImageSamplerDescriptor,
+    sampler: Sampler,
+}
+
+impl AnisotropicSamplers {
+    /// The sampler of `image` with anisotropic filtering clamped to `anisotropy`, `None` for
+    /// textures of no `PbrMaterial`
+    pub(crate) fn get(
+        &self,
+        render_device: &RenderDevice,
+        samplers: &TextureSamplers,
+        image: AssetId<Image>,
+        anisotropy: u16,
+    ) -> Option<Sampler> {
+        let descriptor = anisotropic_descriptor(samplers.0.get(&image)?, anisotropy);
+        let mut created = self.0.lock().unwrap();
+        match created.get(&(image, anisotropy)) {
+            Some(created) if created.descriptor == descriptor => Some(created.sampler.clone()),
+            _ => {
+                let sampler = render_device.create_sampler(&descriptor.as_wgpu());
+                let entry = AnisotropicSampler {
+                    descriptor,
+                    sampler: sampler.clone(),
+                };
+                created.insert((image, anisotropy), entry);
+                Some(sampler)
+            }
+        }
+    }
+}
+
+fn anisotropic_descriptor(
+    descriptor: &ImageSamplerDescriptor,
+    anisotropy: u16,
+) -> ImageSamplerDescriptor {
+    let mut descriptor = descriptor.clone();
+    // wgpu only allows anisotropic filtering with all filters set to linear
+    descriptor.mag_filter = ImageFilterMode::Linear;
+    descriptor.min_filter = ImageFilterMode::Linear;
+    descriptor.mipmap_filter = ImageFilterMode::Linear;
+    descriptor.anisotropy_clamp = anisotropy;
+    descriptor
+}
+
+fn collect_texture_samplers(
+    mut material_events: EventReader<AssetEvent<PbrMaterial>>,
+    mut image_events: EventReader<AssetEvent<Image>>,
+    materials: Res<Assets<PbrMaterial>>,
+    images: Res<Assets<Image>>,
+    mut samplers: ResMut<TextureSamplers>,
+) {
+    let material_changed = material_events
+        .read()
+        .any(|e| matches!(e, AssetEvent::Added { .. } | AssetEvent::Modified { .. }));
+    let image_changed = image_events.read().any(|e| {
+        matches!(e, AssetEvent::LoadedWithDependencies { .. } | AssetEvent::Modified { .. })
+    });
+    if !material_changed && !image_changed {
+        return;
+    }
+
+    let textures = materials.iter().flat_map(|(_, material)| material.textures());
+    let descriptors: HashMap<_, _> = textures
+        .filter_map(|texture| {
+            let descriptor = match &images.get(texture)?.sampler {
+                ImageSampler::Descriptor(descriptor) => descriptor.clone(),
+                ImageSampler::Default => ImageSamplerDescriptor::linear(),
+            };
+            Some((texture.id(), descriptor))
+        })
+        .collect();
+    if samplers.0 != descriptors {
+        samplers.0 = descriptors;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn anisotropic_samplers_filter_linearly() {
+        let descriptor = anisotropic_descriptor(&ImageSamplerDescriptor::nearest(), 8);
+        assert_eq!(descriptor.anisotropy_clamp, 8);
+        assert_eq!(descriptor.min_filter, ImageFilterMode::Linear);
+        assert_eq!(descriptor.mipmap_filter, ImageFilterMode::Linear);
+        assert_eq!(descriptor.address_mode_u, ImageSamplerDescriptor::nearest().address_mode_u);
+    }
+}
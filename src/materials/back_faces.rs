@@ -0,0 +1,144 @@
+use crate::materials::extension::ExtendedSkMaterial;
+use crate::materials::pbr::PbrMaterial;
+use bevy::pbr::{MaterialExtension, NotShadowCaster};
+use bevy::prelude::*;
+use bevy::render::mesh::skinning::SkinnedMesh;
+use bevy::render::render_resource::Face;
+use bevy::utils::HashMap;
+
+/// Sorts the back faces just behind the front faces of the same mesh
+const BACK_FACE_SORT_BIAS: f32 = 0.001;
+
+/// Child drawing the back faces of its parent's double sided blended material, see
+/// [`needs_back_face_pass`]
+#[derive(Component, Reflect, Clone, Copy, Debug, Default)]
+#[reflect(Component)]
+pub struct BackFacePass;
+
+/// The [`BackFacePass`] child of an entity
+#[derive(Component, Clone, Copy, Debug)]
+pub struct BackFaces(pub Entity);
+
+/// Materials shaded by a `PbrMaterial`, which can be drawn in a back and a front face pass
+pub(crate) trait PbrBaseMaterial: Material + Clone {
+    fn pbr(&self) -> &PbrMaterial;
+    fn pbr_mut(&mut self) -> &mut PbrMaterial;
+}
+
+impl PbrBaseMaterial for PbrMaterial {
+    fn pbr(&self) -> &PbrMaterial {
+        self
+    }
+
+    fn pbr_mut(&mut self) -> &mut PbrMaterial {
+        self
+    }
+}
+
+impl<E: MaterialExtension> PbrBaseMaterial for ExtendedSkMaterial<E> {
+    fn pbr(&self) -> &PbrMaterial {
+        &self.base
+    }
+
+    fn pbr_mut(&mut self) -> &mut PbrMaterial {
+        &mut self.base
+    }
+}
+
+/// Whether a material is drawn in two passes, back faces first, so its blended surfaces
+/// overlap in the right order. That's double sided materials that blend and cull back faces,
+/// with a `cull_mode` of `None` both sides are drawn in one pass in mesh order instead.
+pub fn needs_back_face_pass(material: &PbrMaterial) -> bool {
+    material.double_sided
+        && material.cull_mode == Some(Face::Back)
+        && !material.dithered
+        && matches!(material.alpha_mode, AlphaMode::Blend | AlphaMode::Premultiplied)
+}
+
+/// Keeps a [`BackFacePass`] child with a front culled copy of the material under every entity
+/// whose material [`needs_back_face_pass`]
+pub(crate) fn draw_back_faces<M: PbrBaseMaterial>(
+    mut commands: Commands,
+    entities: Query<
+        (
+            Entity,
+            Ref<Handle<M>>,
+            Ref<Handle<Mesh>>,
+            Option<&SkinnedMesh>,
+            Option<&BackFaces>,
+        ),
+        Without<BackFacePass>,
+    >,
+    mut events: EventReader<AssetEvent<M>>,
+    mut materials: ResMut<Assets<M>>,
+    mut back_materials: Local<HashMap<AssetId<M>, Handle<M>>>,
+) {
+    let mut changed = Vec::new();
+    for event in events.read() {
+        match event {
+            AssetEvent::Added { id }
+            | AssetEvent::Modified { id }
+            | AssetEvent::LoadedWithDependencies { id } => changed.push(*id),
+            AssetEvent::Removed { id } | AssetEvent::Unused { id } => {
+                back_materials.remove(id);
+            }
+        }
+    }
+
+    for (entity, material, mesh, skin, back_faces) in entities.iter() {
+        let material_changed = changed.contains(&material.id());
+        if !material.is_changed() && !mesh.is_changed() && !material_changed {
+            continue;
+        }
+        let back_material = match materials.get(&*material) {
+            Some(front) if needs_back_face_pass(front.pbr()) => {
+                let mut back = front.clone();
+                back.pbr_mut().cull_mode = Some(Face::Front);
+                back.pbr_mut().depth_bias -= BACK_FACE_SORT_BIAS;
+                Some(back)
+            }
+            _ => None,
+        };
+        let Some(back_material) = back_material else {
+            if let Some(BackFaces(child)) = back_faces {
+                commands.entity(*child).despawn_recursive();
+                commands.entity(entity).remove::<BackFaces>();
+            }
+            continue;
+        };
+
+        let handle = match back_materials.get(&material.id()) {
+            Some(handle) if materials.contains(handle) => {
+                if material_changed {
+                    materials.insert(handle, back_material);
+                }
+                handle.clone()
+            }
+            _ => {
+                let handle = materials.add(back_material);
+                back_materials.insert(material.id(), handle.clone());
+                handle
+            }
+        };
+        let pass = (mesh.clone(), handle);
+        match back_faces.and_then(|BackFaces(child)| commands.get_entity(*child)) {
+            Some(mut child) => {
+                child.insert(pass);
+            }
+            None => {
+                // The front faces already cast the mesh's shadow
+                let mut child = commands.spawn((
+                    pass,
+                    BackFacePass,
+                    NotShadowCaster,
+                    SpatialBundle::default(),
+                ));
+                if let Some(skin) = skin {
+                    child.insert(skin.clone());
+                }
+                let child = child.set_parent(entity).id();
+                commands.entity(entity).insert(BackFaces(child));
+            }
+        }
+    }
+}
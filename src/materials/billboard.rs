@@ -0,0 +1,115 @@
+use crate::lighting::buffer::{ShSlot, SH_BUFFER_IMAGE_HANDLE};
+use crate::materials::pbr::alpha_mode_flags;
+use bevy::prelude::*;
+use bevy::render::render_asset::RenderAssets;
+use bevy::render::render_resource::{AsBindGroup, AsBindGroupShaderType, ShaderRef, ShaderType};
+use bevy::render::texture::GpuImage;
+
+const SHADER_HANDLE: Handle<Shader> = Handle::weak_from_u128(0xb61e03d94a7f);
+
+/// Registers [`SkBillboardMaterial`], added by `PbrPlugin`
+pub struct SkBillboardMaterialPlugin;
+
+impl Plugin for SkBillboardMaterialPlugin {
+    fn build(&self, app: &mut App) {
+        load_shader!(app, SHADER_HANDLE, "billboard.wgsl");
+        app.add_plugins(MaterialPlugin::<SkBillboardMaterial>::default());
+        app.register_asset_reflect::<SkBillboardMaterial>();
+    }
+}
+
+/// Turns a mesh in local XY, e.g. a `Rectangle`, towards the camera around its origin, for
+/// labels, particles and far away imposters. The entity's rotation is ignored, its scale kept.
+#[derive(Asset, AsBindGroup, Reflect, PartialEq, Debug, Clone)]
+#[uniform(0, SkBillboardMaterialUniform)]
+pub struct SkBillboardMaterial {
+    pub color: Color,
+    #[texture(1)]
+    #[sampler(2)]
+    pub color_texture: Option<Handle<Image>>,
+    /// Only turns around world Y, keeping the quad upright like a tree imposter
+    pub lock_y: bool,
+    /// How much the SH ambient of `lighting` shades the quad, 0 leaves it unlit
+    pub ambient_blend: f32,
+    /// Slot of the shared `ShLightingBuffer` blended in by `ambient_blend`
+    pub lighting: ShSlot,
+    pub alpha_mode: AlphaMode,
+    /// The shared SH texture, leave this at its default
+    #[texture(3, sample_type = "float", filterable = false)]
+    pub sh_buffer: Handle<Image>,
+}
+
+impl Default for SkBillboardMaterial {
+    fn default() -> Self {
+        Self {
+            color: Color::WHITE,
+            color_texture: None,
+            lock_y: false,
+            ambient_blend: 0.0,
+            lighting: ShSlot::GLOBAL,
+            alpha_mode: AlphaMode::Blend,
+            sh_buffer: SH_BUFFER_IMAGE_HANDLE,
+        }
+    }
+}
+
+impl SkBillboardMaterial {
+    pub fn new(color_texture: Handle<Image>) -> Self {
+        Self {
+            color_texture: Some(color_texture),
+            ..default()
+        }
+    }
+}
+
+#[derive(Clone, Default, ShaderType)]
+pub struct SkBillboardMaterialUniform {
+    pub color: Vec4,
+    pub lock_y: u32,
+    pub ambient_blend: f32,
+    pub sh_slot: u32,
+    pub flags: u32,
+    pub alpha_cutoff: f32,
+}
+
+impl AsBindGroupShaderType<SkBillboardMaterialUniform> for SkBillboardMaterial {
+    fn as_bind_group_shader_type(
+        &self,
+        _: &RenderAssets<GpuImage>,
+    ) -> SkBillboardMaterialUniform {
+        let (flags, alpha_cutoff) = alpha_mode_flags(self.alpha_mode);
+        SkBillboardMaterialUniform {
+            color: self.color.to_linear().to_vec4(),
+            lock_y: self.lock_y as u32,
+            ambient_blend: self.ambient_blend,
+            sh_slot: self.lighting.0,
+            flags: flags.bits(),
+            alpha_cutoff,
+        }
+    }
+}
+
+impl Material for SkBillboardMaterial {
+    fn vertex_shader() -> ShaderRef {
+        SHADER_HANDLE.into()
+    }
+
+    fn fragment_shader() -> ShaderRef {
+        SHADER_HANDLE.into()
+    }
+
+    fn alpha_mode(&self) -> AlphaMode {
+        self.alpha_mode
+    }
+
+    fn specialize(
+        _pipeline: &bevy::pbr::MaterialPipeline<Self>,
+        descriptor: &mut bevy::render::render_resource::RenderPipelineDescriptor,
+        _layout: &bevy::render::mesh::MeshVertexBufferLayoutRef,
+        _key: bevy::pbr::MaterialPipelineKey<Self>,
+    ) -> Result<(), bevy::render::render_resource::SpecializedMeshPipelineError> {
+        // Each eye may see the quad turned slightly away from it
+        descriptor.primitive.cull_mode = None;
+        Ok(())
+    }
+}
@@ -0,0 +1,92 @@
+#import bevy_pbr::{
+    mesh_functions,
+    forward_io::{Vertex, VertexOutput},
+    mesh_view_bindings::view,
+    view_transformations::position_world_to_clip,
+}
+#import bevy_sk::{
+    lighting::{sk_lighting, sk_sh_exposure},
+    pbr_types::{sk_has_flag, sk_alpha_output, SK_MATERIAL_FLAGS_ALPHA_MODE_MASK_BIT},
+}
+
+struct SkBillboardMaterial {
+    color: vec4<f32>,
+    lock_y: u32,
+    ambient_blend: f32,
+    sh_slot: u32,
+    flags: u32,
+    alpha_cutoff: f32,
+};
+
+@group(2) @binding(0)
+var<uniform> material: SkBillboardMaterial;
+@group(2) @binding(1)
+var color_texture: texture_2d<f32>;
+@group(2) @binding(2)
+var color_sampler: sampler;
+@group(2) @binding(3)
+var sh_buffer: texture_2d<f32>;
+
+@vertex
+fn vertex(vertex: Vertex) -> VertexOutput {
+    var out: VertexOutput;
+
+    let world_from_local = mesh_functions::get_world_from_local(vertex.instance_index);
+    let center = world_from_local[3].xyz;
+    let scale = vec2(length(world_from_local[0].xyz), length(world_from_local[1].xyz));
+
+    // Facing the camera position rather than its view plane, so both eyes agree
+    var forward = view.world_position - center;
+    var up = vec3(0.0, 1.0, 0.0);
+    if (material.lock_y != 0u) {
+        forward.y = 0.0;
+    } else {
+        up = normalize(view.world_from_view[1].xyz);
+    }
+    // Straight above a Y locked quad any direction works
+    if (dot(forward, forward) < 1e-8) {
+        forward = vec3(0.0, 0.0, 1.0);
+    }
+    forward = normalize(forward);
+    let right = normalize(cross(up, forward));
+    up = cross(forward, right);
+
+    let offset = vertex.position.xy * scale;
+    out.world_position = vec4(center + right * offset.x + up * offset.y, 1.0);
+    out.position = position_world_to_clip(out.world_position.xyz);
+    out.world_normal = forward;
+#ifdef VERTEX_UVS_A
+    out.uv = vertex.uv;
+#endif
+#ifdef VERTEX_COLORS
+    out.color = vertex.color;
+#endif
+#ifdef VERTEX_OUTPUT_INSTANCE_INDEX
+    out.instance_index = vertex.instance_index;
+#endif
+    return out;
+}
+
+@fragment
+fn fragment(in: VertexOutput) -> @location(0) vec4<f32> {
+    var color = material.color * textureSample(color_texture, color_sampler, in.uv);
+#ifdef VERTEX_COLORS
+    color *= in.color;
+#endif
+    if (sk_has_flag(material.flags, SK_MATERIAL_FLAGS_ALPHA_MODE_MASK_BIT)
+        && color.a < material.alpha_cutoff) {
+        discard;
+    }
+
+    if (material.ambient_blend > 0.0) {
+        let slot = i32(material.sh_slot);
+        let exposure = sk_sh_exposure(textureLoad(sh_buffer, vec2(0, slot), 0).a, view.exposure);
+        var sh: array<vec3<f32>, 9>;
+        for (var i = 0u; i < 9u; i += 1u) {
+            sh[i] = textureLoad(sh_buffer, vec2<i32>(i32(i), slot), 0).rgb * exposure;
+        }
+        let ambient = sk_lighting(normalize(in.world_normal), sh);
+        color = vec4(color.rgb * mix(vec3(1.0), ambient, material.ambient_blend), color.a);
+    }
+    return sk_alpha_output(material.flags, color.rgb, color.a);
+}
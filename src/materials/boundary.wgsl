@@ -0,0 +1,27 @@
+#import bevy_pbr::{
+    forward_io::VertexOutput,
+    mesh_view_bindings::view,
+}
+#import bevy_sk::grid::sk_grid
+
+struct SkBoundaryMaterial {
+    line_color: vec4<f32>,
+    cell_size: f32,
+    line_width: f32,
+    fade_start: f32,
+    fade_end: f32,
+};
+
+@group(2) @binding(0)
+var<uniform> material: SkBoundaryMaterial;
+
+@fragment
+fn fragment(in: VertexOutput) -> @location(0) vec4<f32> {
+    // The wall's UVs are in meters, along the boundary and up from the floor
+    let line = sk_grid(in.uv, material.cell_size, material.line_width);
+
+    // Only the part of the wall close to the head shows up
+    let distance = length(in.world_position.xyz - view.world_position);
+    let fade = 1.0 - smoothstep(material.fade_start, material.fade_end, distance);
+    return vec4(material.line_color.rgb, material.line_color.a * line * fade);
+}
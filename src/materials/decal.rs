@@ -0,0 +1,132 @@
+use crate::lighting::buffer::{ShSlot, SH_BUFFER_IMAGE_HANDLE};
+use bevy::pbr::{NotShadowCaster, NotShadowReceiver};
+use bevy::prelude::*;
+use bevy::render::render_asset::RenderAssets;
+use bevy::render::render_resource::{
+    AsBindGroup, AsBindGroupShaderType, CompareFunction, Face, ShaderRef, ShaderType,
+};
+use bevy::render::texture::GpuImage;
+
+const SHADER_HANDLE: Handle<Shader> = Handle::weak_from_u128(0x71d4a03e6c58);
+
+/// Registers [`SkDecalMaterial`] and gives every [`DecalProjector`] its box, added by
+/// `PbrPlugin`
+pub struct SkDecalPlugin;
+
+impl Plugin for SkDecalPlugin {
+    fn build(&self, app: &mut App) {
+        load_shader!(app, SHADER_HANDLE, "decal.wgsl");
+        app.add_plugins(MaterialPlugin::<SkDecalMaterial>::default());
+        app.register_asset_reflect::<SkDecalMaterial>();
+        app.register_type::<DecalProjector>();
+        app.add_systems(Update, setup_decal_projectors);
+    }
+}
+
+/// Projects the `Handle<SkDecalMaterial>` on this entity onto the geometry inside its unit
+/// box, along local -Z and sized by the transform's scale.
+///
+/// The geometry is reconstructed from the depth prepass, so cameras need a `DepthPrepass`.
+#[derive(Component, Reflect, Clone, Copy, Debug, Default)]
+#[reflect(Component)]
+pub struct DecalProjector;
+
+/// A texture projected by a [`DecalProjector`] and lit by the SH ambient, e.g. for bullet
+/// holes, stickers and AR annotations
+#[derive(Asset, AsBindGroup, Reflect, PartialEq, Debug, Clone)]
+#[uniform(0, SkDecalMaterialUniform)]
+pub struct SkDecalMaterial {
+    pub color: Color,
+    #[texture(1)]
+    #[sampler(2)]
+    pub color_texture: Option<Handle<Image>>,
+    /// Surfaces facing the projector less than this cosine fade out, keeping the decal off
+    /// walls it would stretch across
+    pub normal_threshold: f32,
+    /// Slot of the shared `ShLightingBuffer` this decal is lit by
+    pub lighting: ShSlot,
+    /// The shared SH texture, leave this at its default
+    #[texture(3, sample_type = "float", filterable = false)]
+    pub sh_buffer: Handle<Image>,
+}
+
+impl Default for SkDecalMaterial {
+    fn default() -> Self {
+        Self {
+            color: Color::WHITE,
+            color_texture: None,
+            normal_threshold: 0.3,
+            lighting: ShSlot::GLOBAL,
+            sh_buffer: SH_BUFFER_IMAGE_HANDLE,
+        }
+    }
+}
+
+impl SkDecalMaterial {
+    pub fn new(color_texture: Handle<Image>) -> Self {
+        Self {
+            color_texture: Some(color_texture),
+            ..default()
+        }
+    }
+}
+
+#[derive(Clone, Default, ShaderType)]
+pub struct SkDecalMaterialUniform {
+    pub color: Vec4,
+    pub normal_threshold: f32,
+    pub sh_slot: u32,
+}
+
+impl AsBindGroupShaderType<SkDecalMaterialUniform> for SkDecalMaterial {
+    fn as_bind_group_shader_type(&self, _: &RenderAssets<GpuImage>) -> SkDecalMaterialUniform {
+        SkDecalMaterialUniform {
+            color: self.color.to_linear().to_vec4(),
+            normal_threshold: self.normal_threshold,
+            sh_slot: self.lighting.0,
+        }
+    }
+}
+
+impl Material for SkDecalMaterial {
+    fn fragment_shader() -> ShaderRef {
+        SHADER_HANDLE.into()
+    }
+
+    fn alpha_mode(&self) -> AlphaMode {
+        AlphaMode::Blend
+    }
+
+    fn specialize(
+        _pipeline: &bevy::pbr::MaterialPipeline<Self>,
+        descriptor: &mut bevy::render::render_resource::RenderPipelineDescriptor,
+        _layout: &bevy::render::mesh::MeshVertexBufferLayoutRef,
+        _key: bevy::pbr::MaterialPipelineKey<Self>,
+    ) -> Result<(), bevy::render::render_resource::SpecializedMeshPipelineError> {
+        // The back faces of the box cover the geometry inside it even with the camera inside,
+        // which they are behind, so the depth test is left to the shader
+        descriptor.primitive.cull_mode = Some(Face::Front);
+        if let Some(depth_stencil) = &mut descriptor.depth_stencil {
+            depth_stencil.depth_compare = CompareFunction::Always;
+        }
+        descriptor.vertex.shader_defs.push("VERTEX_OUTPUT_INSTANCE_INDEX".into());
+        if let Some(fragment) = &mut descriptor.fragment {
+            fragment.shader_defs.push("VERTEX_OUTPUT_INSTANCE_INDEX".into());
+        }
+        Ok(())
+    }
+}
+
+fn setup_decal_projectors(
+    mut commands: Commands,
+    projectors: Query<Entity, Added<DecalProjector>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut mesh: Local<Option<Handle<Mesh>>>,
+) {
+    for entity in projectors.iter() {
+        let mesh = mesh.get_or_insert_with(|| meshes.add(Cuboid::default()));
+        commands
+            .entity(entity)
+            .insert((mesh.clone(), NotShadowCaster, NotShadowReceiver));
+    }
+}
@@ -0,0 +1,71 @@
+#import bevy_pbr::{
+    forward_io::VertexOutput,
+    mesh_bindings::mesh,
+    mesh_functions::get_world_from_local,
+    mesh_view_bindings::view,
+    view_transformations::{frag_coord_to_ndc, position_ndc_to_world},
+}
+#import bevy_render::maths::mat2x4_f32_to_mat3x3_unpack
+#import bevy_sk::lighting::{sk_lighting, sk_sh_exposure}
+#ifdef DEPTH_PREPASS
+#import bevy_pbr::prepass_utils::prepass_depth
+#endif
+
+struct SkDecalMaterial {
+    color: vec4<f32>,
+    normal_threshold: f32,
+    sh_slot: u32,
+};
+
+@group(2) @binding(0)
+var<uniform> material: SkDecalMaterial;
+@group(2) @binding(1)
+var color_texture: texture_2d<f32>;
+@group(2) @binding(2)
+var color_sampler: sampler;
+@group(2) @binding(3)
+var sh_buffer: texture_2d<f32>;
+
+@fragment
+fn fragment(in: VertexOutput) -> @location(0) vec4<f32> {
+#ifndef DEPTH_PREPASS
+    // Nothing to project onto without the scene depth
+    return vec4(0.0);
+#else
+    // The scene surface behind this pixel
+    let depth = prepass_depth(in.position, 0u);
+    let world_position = position_ndc_to_world(
+        frag_coord_to_ndc(vec4(in.position.xy, depth, 1.0))
+    );
+    // Surface normal from neighbouring pixels, taken before any discard
+    var N = normalize(cross(dpdx(world_position), dpdy(world_position)));
+    if (dot(N, view.world_position - world_position) < 0.0) {
+        N = -N;
+    }
+
+    let world_from_local = get_world_from_local(in.instance_index);
+    let local_from_world = transpose(mat2x4_f32_to_mat3x3_unpack(
+        mesh[in.instance_index].local_from_world_transpose_a,
+        mesh[in.instance_index].local_from_world_transpose_b,
+    ));
+    let local = local_from_world * (world_position - world_from_local[3].xyz);
+    if (any(abs(local) > vec3(0.5))) {
+        discard;
+    }
+
+    // Projected along -Z, surfaces turned away from the projector fade out
+    let facing = dot(N, normalize(world_from_local[2].xyz));
+    let fade = smoothstep(material.normal_threshold, material.normal_threshold + 0.2, facing);
+
+    let uv = vec2(local.x + 0.5, 0.5 - local.y);
+    let color = textureSample(color_texture, color_sampler, uv) * material.color;
+
+    let slot = i32(material.sh_slot);
+    let exposure = sk_sh_exposure(textureLoad(sh_buffer, vec2(0, slot), 0).a, view.exposure);
+    var sh: array<vec3<f32>, 9>;
+    for (var i = 0u; i < 9u; i += 1u) {
+        sh[i] = textureLoad(sh_buffer, vec2<i32>(i32(i), slot), 0).rgb * exposure;
+    }
+    return vec4(color.rgb * sk_lighting(N, sh), color.a * fade);
+#endif
+}
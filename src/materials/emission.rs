@@ -0,0 +1,88 @@
+use crate::materials::pbr::PbrMaterial;
+use bevy::prelude::*;
+use std::f32::consts::TAU;
+
+/// Animates the `PbrMaterial::emission_strength` of every [`EmissionAnimator`]
+pub struct EmissionAnimatorPlugin;
+
+impl Plugin for EmissionAnimatorPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<EmissionAnimator>();
+        app.add_systems(Update, animate_emission);
+    }
+}
+
+/// Pulses or ramps the emission of this entity's `PbrMaterial`, e.g. for indicator lights.
+///
+/// The material asset itself is changed, so entities sharing it light up together.
+#[derive(Component, Reflect, Clone, Copy, Debug, PartialEq)]
+#[reflect(Component)]
+pub struct EmissionAnimator {
+    pub curve: EmissionCurve,
+    pub min_strength: f32,
+    /// May exceed 1.0 for bloom
+    pub max_strength: f32,
+    /// Seconds per pulse or ramp
+    pub period: f32,
+    /// Seconds since the animation started, set it to 0.0 to restart
+    pub time: f32,
+}
+
+#[derive(Reflect, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum EmissionCurve {
+    /// Smoothly between the strengths and back
+    #[default]
+    Pulse,
+    /// From the minimum to the maximum, then jumps back
+    Ramp,
+    /// From the minimum to the maximum once, then stays there
+    RampOnce,
+}
+
+impl EmissionAnimator {
+    pub fn pulse(min_strength: f32, max_strength: f32, period: f32) -> Self {
+        Self {
+            curve: EmissionCurve::Pulse,
+            min_strength,
+            max_strength,
+            period,
+            time: 0.0,
+        }
+    }
+
+    pub fn ramp(min_strength: f32, max_strength: f32, period: f32) -> Self {
+        Self {
+            curve: EmissionCurve::Ramp,
+            ..Self::pulse(min_strength, max_strength, period)
+        }
+    }
+
+    /// The emission strength at `time`
+    pub fn strength(&self) -> f32 {
+        let phase = if self.period > 0.0 {
+            self.time / self.period
+        } else {
+            1.0
+        };
+        let t = match self.curve {
+            EmissionCurve::Pulse => 0.5 - 0.5 * (phase * TAU).cos(),
+            EmissionCurve::Ramp => phase.fract(),
+            EmissionCurve::RampOnce => phase.min(1.0),
+        };
+        self.min_strength + (self.max_strength - self.min_strength) * t
+    }
+}
+
+fn animate_emission(
+    time: Res<Time>,
+    mut animators: Query<(&mut EmissionAnimator, &Handle<PbrMaterial>)>,
+    mut materials: ResMut<Assets<PbrMaterial>>,
+) {
+    for (mut animator, handle) in animators.iter_mut() {
+        animator.time += time.delta_seconds();
+        let strength = animator.strength();
+        if materials.get(handle).is_some_and(|m| m.emission_strength != strength) {
+            materials.get_mut(handle).unwrap().emission_strength = strength;
+        }
+    }
+}
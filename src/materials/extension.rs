@@ -0,0 +1,33 @@
+use crate::materials::back_faces::draw_back_faces;
+use crate::materials::pbr::PbrMaterial;
+use bevy::pbr::{ExtendedMaterial, MaterialExtension};
+use bevy::prelude::*;
+use std::marker::PhantomData;
+
+/// `PbrMaterial` with a [`MaterialExtension`] running custom WGSL after the sk shading.
+///
+/// The extension's fragment shader imports `bevy_sk::pbr_fragment`, calls
+/// `sk_pbr_fragment(in, is_front)`, changes the returned `SkPbrResult`, which also carries the
+/// SH ambient, and writes it with `sk_pbr_output(in, result)`. Extension bindings start at 100
+/// like with Bevy's `ExtendedMaterial`.
+pub type ExtendedSkMaterial<E> = ExtendedMaterial<PbrMaterial, E>;
+
+/// Adds the pipelines of an [`ExtendedSkMaterial`], needs `PbrPlugin`
+pub struct ExtendedSkMaterialPlugin<E>(PhantomData<E>);
+
+impl<E> Default for ExtendedSkMaterialPlugin<E> {
+    fn default() -> Self {
+        Self(PhantomData)
+    }
+}
+
+impl<E: MaterialExtension> Plugin for ExtendedSkMaterialPlugin<E>
+where
+    <ExtendedSkMaterial<E> as bevy::render::render_resource::AsBindGroup>::Data:
+        PartialEq + Eq + std::hash::Hash + Clone,
+{
+    fn build(&self, app: &mut App) {
+        app.add_plugins(MaterialPlugin::<ExtendedSkMaterial<E>>::default());
+        app.add_systems(Update, draw_back_faces::<ExtendedSkMaterial<E>>);
+    }
+}
@@ -0,0 +1,215 @@
+use bevy::pbr::NotShadowCaster;
+use bevy::prelude::*;
+use bevy::render::render_asset::RenderAssets;
+use bevy::render::render_resource::{AsBindGroup, AsBindGroupShaderType, ShaderRef, ShaderType};
+use bevy::render::texture::GpuImage;
+
+const SHADER_HANDLE: Handle<Shader> = Handle::weak_from_u128(0x1e9f74b2c05a);
+const BOUNDARY_SHADER_HANDLE: Handle<Shader> = Handle::weak_from_u128(0x63d0f8a21c7e);
+/// `bevy_sk::grid`, the anti-aliased grid lines
+const GRID_SHADER_HANDLE: Handle<Shader> = Handle::weak_from_u128(0xa93c5e07b14d);
+
+/// Registers [`SkFloorMaterial`] and [`SkBoundaryMaterial`] and sets up every [`SkFloor`],
+/// added by `PbrPlugin`
+pub struct SkFloorPlugin;
+
+impl Plugin for SkFloorPlugin {
+    fn build(&self, app: &mut App) {
+        load_shader!(app, GRID_SHADER_HANDLE, "grid.wgsl");
+        load_shader!(app, SHADER_HANDLE, "floor.wgsl");
+        load_shader!(app, BOUNDARY_SHADER_HANDLE, "boundary.wgsl");
+        app.add_plugins(MaterialPlugin::<SkFloorMaterial>::default());
+        app.add_plugins(MaterialPlugin::<SkBoundaryMaterial> {
+            prepass_enabled: false,
+            shadows_enabled: false,
+            ..default()
+        });
+        app.register_asset_reflect::<SkFloorMaterial>();
+        app.register_asset_reflect::<SkBoundaryMaterial>();
+        app.register_type::<SkFloor>();
+        app.add_systems(Update, setup_floors);
+    }
+}
+
+/// A grid floor like StereoKit's default one, a square plane of `extent` meters at the
+/// entity's transform with a default [`SkFloorMaterial`] unless it already has one
+#[derive(Component, Reflect, Clone, Copy, Debug)]
+#[reflect(Component)]
+pub struct SkFloor {
+    pub extent: f32,
+}
+
+impl Default for SkFloor {
+    fn default() -> Self {
+        Self { extent: 40.0 }
+    }
+}
+
+/// Anti-aliased world space grid, fading out with the distance to the camera
+#[derive(Asset, AsBindGroup, Reflect, PartialEq, Debug, Clone)]
+#[uniform(0, SkFloorMaterialUniform)]
+pub struct SkFloorMaterial {
+    /// Between the lines
+    pub color: Color,
+    pub line_color: Color,
+    /// Meters between the major lines
+    pub cell_size: f32,
+    /// Minor lines per cell, 1 or less draws none
+    pub subdivisions: u32,
+    /// Line width in pixels
+    pub line_width: f32,
+    /// Distance from the camera at which the grid starts fading out, and where it is gone
+    pub fade_start: f32,
+    pub fade_end: f32,
+    /// Cuts the plane to the circle touching its edges instead of a square
+    pub circular: bool,
+}
+
+impl Default for SkFloorMaterial {
+    fn default() -> Self {
+        Self {
+            color: Color::srgba(0.0, 0.0, 0.0, 0.0),
+            line_color: Color::srgba(1.0, 1.0, 1.0, 0.5),
+            cell_size: 1.0,
+            subdivisions: 10,
+            line_width: 1.5,
+            fade_start: 5.0,
+            fade_end: 15.0,
+            circular: true,
+        }
+    }
+}
+
+#[derive(Clone, Default, ShaderType)]
+pub struct SkFloorMaterialUniform {
+    pub color: Vec4,
+    pub line_color: Vec4,
+    pub cell_size: f32,
+    pub subdivisions: u32,
+    pub line_width: f32,
+    pub fade_start: f32,
+    pub fade_end: f32,
+    pub circular: u32,
+}
+
+impl AsBindGroupShaderType<SkFloorMaterialUniform> for SkFloorMaterial {
+    fn as_bind_group_shader_type(&self, _: &RenderAssets<GpuImage>) -> SkFloorMaterialUniform {
+        SkFloorMaterialUniform {
+            color: self.color.to_linear().to_vec4(),
+            line_color: self.line_color.to_linear().to_vec4(),
+            cell_size: self.cell_size,
+            subdivisions: self.subdivisions,
+            line_width: self.line_width,
+            fade_start: self.fade_start,
+            fade_end: self.fade_end.max(self.fade_start + 0.001),
+            circular: self.circular as u32,
+        }
+    }
+}
+
+impl Material for SkFloorMaterial {
+    fn fragment_shader() -> ShaderRef {
+        SHADER_HANDLE.into()
+    }
+
+    fn alpha_mode(&self) -> AlphaMode {
+        AlphaMode::Blend
+    }
+}
+
+/// The floor's grid on a wall, showing up only around the camera as it comes close, for play
+/// area boundaries. Expects UVs in meters, see `PlayBoundaryPlugin`.
+#[derive(Asset, AsBindGroup, Reflect, PartialEq, Debug, Clone)]
+#[uniform(0, SkBoundaryMaterialUniform)]
+pub struct SkBoundaryMaterial {
+    pub line_color: Color,
+    /// Meters between the lines
+    pub cell_size: f32,
+    /// Line width in pixels
+    pub line_width: f32,
+    /// Distance from the camera within which the wall is fully shown, and where it is gone
+    pub fade_start: f32,
+    pub fade_end: f32,
+}
+
+impl Default for SkBoundaryMaterial {
+    fn default() -> Self {
+        Self {
+            line_color: Color::srgba(0.3, 0.6, 1.0, 0.9),
+            cell_size: 0.1,
+            line_width: 1.5,
+            fade_start: 0.2,
+            fade_end: 0.8,
+        }
+    }
+}
+
+#[derive(Clone, Default, ShaderType)]
+pub struct SkBoundaryMaterialUniform {
+    pub line_color: Vec4,
+    pub cell_size: f32,
+    pub line_width: f32,
+    pub fade_start: f32,
+    pub fade_end: f32,
+}
+
+impl AsBindGroupShaderType<SkBoundaryMaterialUniform> for SkBoundaryMaterial {
+    fn as_bind_group_shader_type(
+        &self,
+        _: &RenderAssets<GpuImage>,
+    ) -> SkBoundaryMaterialUniform {
+        SkBoundaryMaterialUniform {
+            line_color: self.line_color.to_linear().to_vec4(),
+            cell_size: self.cell_size,
+            line_width: self.line_width,
+            fade_start: self.fade_start,
+            fade_end: self.fade_end.max(self.fade_start + 0.001),
+        }
+    }
+}
+
+impl Material for SkBoundaryMaterial {
+    fn fragment_shader() -> ShaderRef {
+        BOUNDARY_SHADER_HANDLE.into()
+    }
+
+    fn alpha_mode(&self) -> AlphaMode {
+        AlphaMode::Blend
+    }
+
+    fn specialize(
+        _pipeline: &bevy::pbr::MaterialPipeline<Self>,
+        descriptor: &mut bevy::render::render_resource::RenderPipelineDescriptor,
+        _layout: &bevy::render::mesh::MeshVertexBufferLayoutRef,
+        _key: bevy::pbr::MaterialPipelineKey<Self>,
+    ) -> Result<(), bevy::render::render_resource::SpecializedMeshPipelineError> {
+        // Seen from inside the play area and from outside after walking through it
+        descriptor.primitive.cull_mode = None;
+        Ok(())
+    }
+}
+
+fn setup_floors(
+    mut commands: Commands,
+    floors: Query<
+        (Entity, &SkFloor, Has<Handle<SkFloorMaterial>>, Has<Transform>, Has<Visibility>),
+        Changed<SkFloor>,
+    >,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<SkFloorMaterial>>,
+) {
+    for (entity, floor, has_material, has_transform, has_visibility) in floors.iter() {
+        let mut entity = commands.entity(entity);
+        let mesh = Plane3d::default().mesh().size(floor.extent, floor.extent);
+        entity.insert((meshes.add(mesh), NotShadowCaster));
+        if !has_material {
+            entity.insert(materials.add(SkFloorMaterial::default()));
+        }
+        if !has_transform {
+            entity.insert(TransformBundle::default());
+        }
+        if !has_visibility {
+            entity.insert(VisibilityBundle::default());
+        }
+    }
+}
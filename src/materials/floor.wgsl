@@ -0,0 +1,39 @@
+#import bevy_pbr::{
+    forward_io::VertexOutput,
+    mesh_view_bindings::view,
+}
+#import bevy_sk::grid::sk_grid
+
+struct SkFloorMaterial {
+    color: vec4<f32>,
+    line_color: vec4<f32>,
+    cell_size: f32,
+    subdivisions: u32,
+    line_width: f32,
+    fade_start: f32,
+    fade_end: f32,
+    circular: u32,
+};
+
+@group(2) @binding(0)
+var<uniform> material: SkFloorMaterial;
+
+@fragment
+fn fragment(in: VertexOutput) -> @location(0) vec4<f32> {
+    let position = in.world_position.xz;
+    var line = sk_grid(position, material.cell_size, material.line_width);
+    if (material.subdivisions > 1u) {
+        let minor_cell = material.cell_size / f32(material.subdivisions);
+        line = max(line, 0.5 * sk_grid(position, minor_cell, material.line_width * 0.75));
+    }
+    var color = mix(material.color, material.line_color, line);
+
+    let distance = length(position - view.world_position.xz);
+    var fade = 1.0 - smoothstep(material.fade_start, material.fade_end, distance);
+    if (material.circular != 0u) {
+        // The plane's UVs span it once, so their center distance reaches 1 at the edges
+        let radius = length(in.uv - 0.5) * 2.0;
+        fade *= 1.0 - smoothstep(0.9, 1.0, radius);
+    }
+    return vec4(color.rgb, color.a * fade);
+}
@@ -0,0 +1,509 @@
+use crate::materials::pbr::PbrMaterial;
+use bevy::prelude::*;
+use bevy::render::render_resource::{Extent3d, TextureDimension, TextureFormat};
+use bevy::render::renderer::RenderDevice;
+
+/// Makes `PbrMaterial` textures loadable on every device.
+///
+/// Textures in a compressed format the GPU can't sample, e.g. BC on Android or ETC2 on
+/// desktop, would otherwise fail bind group creation. BC1, BC3, BC4, BC5, ETC2 and unsigned
+/// EAC are decompressed on the CPU. Anything else, like ASTC, is logged as an error per texture
+/// and swapped for a magenta texel that stands out. Basis and KTX2 supercompressed textures
+/// are already transcoded to a supported format by the loader.
+pub struct TextureFormatNegotiationPlugin;
+
+impl Plugin for TextureFormatNegotiationPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, negotiate_texture_formats);
+    }
+}
+
+/// Whether `device` can sample textures in `format`
+pub fn is_format_supported(device: &RenderDevice, format: TextureFormat) -> bool {
+    device.features().contains(format.required_features())
+}
+
+/// The texture formats a device can sample, implemented by `RenderDevice` and by fakes that
+/// test the negotiation without a GPU
+pub trait TextureFormatSupport {
+    fn supports(&self, format: TextureFormat) -> bool;
+}
+
+impl TextureFormatSupport for RenderDevice {
+    fn supports(&self, format: TextureFormat) -> bool {
+        is_format_supported(self, format)
+    }
+}
+
+/// Makes every texture of `materials` sampleable by `support`, see [`make_image_supported`]
+pub fn negotiate_material_textures(
+    support: &impl TextureFormatSupport,
+    materials: &Assets<PbrMaterial>,
+    images: &mut Assets<Image>,
+) {
+    for (_, material) in materials.iter() {
+        for texture in material.textures() {
+            let unsupported = images
+                .get(texture)
+                .is_some_and(|image| !support.supports(image.texture_descriptor.format));
+            if !unsupported {
+                continue;
+            }
+            let image = images.get_mut(texture).unwrap();
+            let format = image.texture_descriptor.format;
+            if make_image_supported(image) {
+                debug!("Decompressed {texture:?} from unsupported {format:?}");
+            } else {
+                error!("{texture:?} uses {format:?} which this device can't sample or decode");
+            }
+        }
+    }
+}
+
+/// Rewrites `image` into a format that needs no extra device features.
+///
+/// Returns `false` if the format couldn't be decoded and `image` was replaced by a magenta
+/// placeholder.
+pub fn make_image_supported(image: &mut Image) -> bool {
+    let format = image.texture_descriptor.format;
+    let size = image.texture_descriptor.size;
+    let mips = image.texture_descriptor.mip_level_count;
+    let layers = size.depth_or_array_layers;
+
+    // Bevy lays out texel data per layer and per mip, only the first mip of every layer is
+    // kept and mips are only skippable when there is a single layer
+    let decoded = (mips == 1 || layers == 1)
+        .then(|| decode_compressed(format, size.width, size.height, layers, &image.data))
+        .flatten();
+
+    let Some((data, decoded_format)) = decoded else {
+        *image = placeholder_image(image);
+        return false;
+    };
+    image.data = data;
+    image.texture_descriptor.format = decoded_format;
+    image.texture_descriptor.mip_level_count = 1;
+    true
+}
+
+fn placeholder_image(image: &Image) -> Image {
+    let mut placeholder = Image::new_fill(
+        Extent3d::default(),
+        TextureDimension::D2,
+        &[255, 0, 255, 255],
+        TextureFormat::Rgba8Unorm,
+        image.asset_usage,
+    );
+    placeholder.sampler = image.sampler.clone();
+    placeholder
+}
+
+fn negotiate_texture_formats(
+    device: Option<Res<RenderDevice>>,
+    mut material_events: EventReader<AssetEvent<PbrMaterial>>,
+    mut image_events: EventReader<AssetEvent<Image>>,
+    materials: Res<Assets<PbrMaterial>>,
+    mut images: ResMut<Assets<Image>>,
+) {
+    let material_changed = material_events
+        .read()
+        .any(|e| matches!(e, AssetEvent::Added { .. } | AssetEvent::Modified { .. }));
+    let image_loaded = image_events
+        .read()
+        .any(|e| matches!(e, AssetEvent::Added { .. } | AssetEvent::LoadedWithDependencies { .. }));
+    let Some(device) = device else {
+        return;
+    };
+    if !material_changed && !image_loaded {
+        return;
+    }
+    negotiate_material_textures(device.as_ref(), &materials, &mut images);
+}
+
+/// Decodes the first mip of every layer, returning the texels and their uncompressed format
+fn decode_compressed(
+    format: TextureFormat,
+    width: u32,
+    height: u32,
+    layers: u32,
+    data: &[u8],
+) -> Option<(Vec<u8>, TextureFormat)> {
+    let (block_bytes, texel_bytes, out_format) = match format {
+        TextureFormat::Bc1RgbaUnorm => (8, 4, TextureFormat::Rgba8Unorm),
+        TextureFormat::Bc1RgbaUnormSrgb => (8, 4, TextureFormat::Rgba8UnormSrgb),
+        TextureFormat::Bc3RgbaUnorm => (16, 4, TextureFormat::Rgba8Unorm),
+        TextureFormat::Bc3RgbaUnormSrgb => (16, 4, TextureFormat::Rgba8UnormSrgb),
+        TextureFormat::Bc4RUnorm => (8, 1, TextureFormat::R8Unorm),
+        TextureFormat::Bc5RgUnorm => (16, 2, TextureFormat::Rg8Unorm),
+        TextureFormat::Etc2Rgb8Unorm | TextureFormat::Etc2Rgb8A1Unorm => {
+            (8, 4, TextureFormat::Rgba8Unorm)
+        }
+        TextureFormat::Etc2Rgb8UnormSrgb | TextureFormat::Etc2Rgb8A1UnormSrgb => {
+            (8, 4, TextureFormat::Rgba8UnormSrgb)
+        }
+        TextureFormat::Etc2Rgba8Unorm => (16, 4, TextureFormat::Rgba8Unorm),
+        TextureFormat::Etc2Rgba8UnormSrgb => (16, 4, TextureFormat::Rgba8UnormSrgb),
+        TextureFormat::EacR11Unorm => (8, 1, TextureFormat::R8Unorm),
+        TextureFormat::EacRg11Unorm => (16, 2, TextureFormat::Rg8Unorm),
+        _ => return None,
+    };
+
+    let blocks_x = width.div_ceil(4) as usize;
+    let blocks_y = height.div_ceil(4) as usize;
+    let layer_bytes = blocks_x * blocks_y * block_bytes;
+    let mips_bytes = data.len() / layers as usize;
+    if mips_bytes < layer_bytes {
+        return None;
+    }
+
+    let (width, height) = (width as usize, height as usize);
+    let mut out = vec![0; width * height * texel_bytes * layers as usize];
+    for layer in 0..layers as usize {
+        let src = &data[layer * mips_bytes..layer * mips_bytes + layer_bytes];
+        let dst = &mut out[layer * width * height * texel_bytes..][..width * height * texel_bytes];
+        for (i, block) in src.chunks_exact(block_bytes).enumerate() {
+            let texels = decode_block(format, block);
+            let (bx, by) = (i % blocks_x * 4, i / blocks_x * 4);
+            for (j, texel) in texels.iter().enumerate() {
+                let (x, y) = (bx + j % 4, by + j / 4);
+                if x < width && y < height {
+                    let offset = (y * width + x) * texel_bytes;
+                    dst[offset..offset + texel_bytes].copy_from_slice(&texel[..texel_bytes]);
+                }
+            }
+        }
+    }
+    Some((out, out_format))
+}
+
+/// Texels of one 4x4 block in row major order
+fn decode_block(format: TextureFormat, block: &[u8]) -> [[u8; 4]; 16] {
+    match format {
+        TextureFormat::Bc1RgbaUnorm | TextureFormat::Bc1RgbaUnormSrgb => {
+            decode_color_block(block, true)
+        }
+        TextureFormat::Bc3RgbaUnorm | TextureFormat::Bc3RgbaUnormSrgb => {
+            let mut texels = decode_color_block(&block[8..], false);
+            for (texel, alpha) in texels.iter_mut().zip(decode_alpha_block(&block[..8])) {
+                texel[3] = alpha;
+            }
+            texels
+        }
+        TextureFormat::Bc4RUnorm => decode_alpha_block(block).map(|r| [r, 0, 0, 0]),
+        TextureFormat::Bc5RgUnorm => {
+            let r = decode_alpha_block(&block[..8]);
+            let g = decode_alpha_block(&block[8..]);
+            std::array::from_fn(|i| [r[i], g[i], 0, 0])
+        }
+        TextureFormat::Etc2Rgb8Unorm | TextureFormat::Etc2Rgb8UnormSrgb => {
+            decode_etc2_block(block, false)
+        }
+        TextureFormat::Etc2Rgb8A1Unorm | TextureFormat::Etc2Rgb8A1UnormSrgb => {
+            decode_etc2_block(block, true)
+        }
+        TextureFormat::Etc2Rgba8Unorm | TextureFormat::Etc2Rgba8UnormSrgb => {
+            let mut texels = decode_etc2_block(&block[8..], false);
+            for (texel, alpha) in texels.iter_mut().zip(decode_eac_block(&block[..8], false)) {
+                texel[3] = alpha;
+            }
+            texels
+        }
+        TextureFormat::EacR11Unorm => decode_eac_block(block, true).map(|r| [r, 0, 0, 0]),
+        _ => {
+            let r = decode_eac_block(&block[..8], true);
+            let g = decode_eac_block(&block[8..], true);
+            std::array::from_fn(|i| [r[i], g[i], 0, 0])
+        }
+    }
+}
+
+fn rgb565(c: u16) -> [u8; 4] {
+    let r = ((c >> 11) & 0x1f) as u32;
+    let g = ((c >> 5) & 0x3f) as u32;
+    let b = (c & 0x1f) as u32;
+    [((r * 527 + 23) >> 6) as u8, ((g * 259 + 33) >> 6) as u8, ((b * 527 + 23) >> 6) as u8, 255]
+}
+
+fn mix_color(a: [u8; 4], b: [u8; 4], wa: u32, wb: u32) -> [u8; 4] {
+    let mix = |a: u8, b: u8| ((a as u32 * wa + b as u32 * wb) / (wa + wb)) as u8;
+    [mix(a[0], b[0]), mix(a[1], b[1]), mix(a[2], b[2]), 255]
+}
+
+/// BC1 color endpoints and 2 bit indices, BC1 allows a punch-through mode when `c0 <= c1`
+fn decode_color_block(block: &[u8], punch_through: bool) -> [[u8; 4]; 16] {
+    let c0 = u16::from_le_bytes([block[0], block[1]]);
+    let c1 = u16::from_le_bytes([block[2], block[3]]);
+    let (p0, p1) = (rgb565(c0), rgb565(c1));
+    let palette = if c0 > c1 || !punch_through {
+        [p0, p1, mix_color(p0, p1, 2, 1), mix_color(p0, p1, 1, 2)]
+    } else {
+        [p0, p1, mix_color(p0, p1, 1, 1), [0, 0, 0, 0]]
+    };
+
+    let indices = u32::from_le_bytes([block[4], block[5], block[6], block[7]]);
+    std::array::from_fn(|i| palette[((indices >> (2 * i)) & 0b11) as usize])
+}
+
+/// BC3 alpha / BC4 channel endpoints and 3 bit indices
+fn decode_alpha_block(block: &[u8]) -> [u8; 16] {
+    let (a0, a1) = (block[0] as u32, block[1] as u32);
+    let palette: [u8; 8] = std::array::from_fn(|i| {
+        let i = i as u32;
+        match i {
+            0 => a0 as u8,
+            1 => a1 as u8,
+            _ if a0 > a1 => (((8 - i) * a0 + (i - 1) * a1) / 7) as u8,
+            6 => 0,
+            7 => 255,
+            _ => (((6 - i) * a0 + (i - 1) * a1) / 5) as u8,
+        }
+    });
+
+    let mut bits = [0u8; 8];
+    bits[..6].copy_from_slice(&block[2..8]);
+    let indices = u64::from_le_bytes(bits);
+    std::array::from_fn(|i| palette[((indices >> (3 * i)) & 0b111) as usize])
+}
+
+/// ETC1 intensity modifiers per table codeword, negated for the upper two indices
+const ETC_MODIFIERS: [[i32; 2]; 8] = [
+    [2, 8],
+    [5, 17],
+    [9, 29],
+    [13, 42],
+    [18, 60],
+    [24, 80],
+    [33, 106],
+    [47, 183],
+];
+
+/// Distances between the paint colors of the ETC2 T and H modes
+const ETC_DISTANCES: [i32; 8] = [3, 6, 11, 16, 23, 32, 41, 64];
+
+/// EAC modifiers per table index
+const EAC_MODIFIERS: [[i32; 8]; 16] = [
+    [-3, -6, -9, -15, 2, 5, 8, 14],
+    [-3, -7, -10, -13, 2, 6, 9, 12],
+    [-2, -5, -8, -13, 1, 4, 7, 12],
+    [-2, -4, -6, -13, 1, 3, 5, 12],
+    [-3, -6, -8, -12, 2, 5, 7, 11],
+    [-3, -7, -9, -11, 2, 6, 8, 10],
+    [-4, -7, -8, -11, 3, 6, 7, 10],
+    [-3, -5, -8, -11, 2, 4, 7, 10],
+    [-2, -6, -8, -10, 1, 5, 7, 9],
+    [-2, -5, -8, -10, 1, 4, 7, 9],
+    [-2, -4, -8, -10, 1, 3, 7, 9],
+    [-2, -5, -7, -10, 1, 4, 6, 9],
+    [-3, -4, -7, -10, 2, 3, 6, 9],
+    [-1, -2, -3, -10, 0, 1, 2, 9],
+    [-4, -6, -8, -9, 3, 5, 7, 8],
+    [-3, -5, -7, -9, 2, 4, 6, 8],
+];
+
+/// ETC2 RGB block in any of its modes. With `punch_through`, like RGB8A1, the differential
+/// bit marks opaque blocks and index 2 of the others is transparent.
+fn decode_etc2_block(block: &[u8], punch_through: bool) -> [[u8; 4]; 16] {
+    let bits = u64::from_be_bytes(block[..8].try_into().unwrap());
+    let field = |shift: u32, len: u32| ((bits >> shift) & ((1 << len) - 1)) as i32;
+    let extend4 = |v: i32| v * 17;
+    let extend5 = |v: i32| (v << 3) | (v >> 2);
+    let differential = field(33, 1) == 1;
+    let opaque = !punch_through || differential;
+
+    // Punch-through blocks are always differential
+    if !differential && !punch_through {
+        let base = [
+            [field(60, 4), field(52, 4), field(44, 4)].map(extend4),
+            [field(56, 4), field(48, 4), field(40, 4)].map(extend4),
+        ];
+        return decode_etc1_texels(bits, base, opaque);
+    }
+
+    let signed3 = |v: i32| (v << 29) >> 29;
+    let (r, g, b) = (field(59, 5), field(51, 5), field(43, 5));
+    let r2 = r + signed3(field(56, 3));
+    let g2 = g + signed3(field(48, 3));
+    let b2 = b + signed3(field(40, 3));
+    let offset = |c: [i32; 3], d: i32| etc_color([c[0] + d, c[1] + d, c[2] + d]);
+    if !(0..32).contains(&r2) {
+        // T mode
+        let c1 = [(field(59, 2) << 2) | field(56, 2), field(52, 4), field(48, 4)].map(extend4);
+        let c2 = [field(44, 4), field(40, 4), field(36, 4)].map(extend4);
+        let d = ETC_DISTANCES[((field(34, 2) << 1) | field(32, 1)) as usize];
+        let paint = [etc_color(c1), offset(c2, d), etc_color(c2), offset(c2, -d)];
+        decode_etc_paint_texels(bits, paint, opaque)
+    } else if !(0..32).contains(&g2) {
+        // H mode, the order of the base colors carries the lowest distance bit
+        let c1 = [
+            field(59, 4),
+            (field(56, 3) << 1) | field(52, 1),
+            (field(51, 1) << 3) | field(47, 3),
+        ]
+        .map(extend4);
+        let c2 = [field(43, 4), field(39, 4), field(35, 4)].map(extend4);
+        let value = |c: [i32; 3]| (c[0] << 16) | (c[1] << 8) | c[2];
+        let order = (value(c1) >= value(c2)) as i32;
+        let d = ETC_DISTANCES[((field(34, 1) << 2) | (field(32, 1) << 1) | order) as usize];
+        let paint = [offset(c1, d), offset(c1, -d), offset(c2, d), offset(c2, -d)];
+        decode_etc_paint_texels(bits, paint, opaque)
+    } else if !(0..32).contains(&b2) {
+        decode_etc_planar_texels(field)
+    } else {
+        let base = [[r, g, b].map(extend5), [r2, g2, b2].map(extend5)];
+        decode_etc1_texels(bits, base, opaque)
+    }
+}
+
+fn etc_color(c: [i32; 3]) -> [u8; 4] {
+    [c[0].clamp(0, 255) as u8, c[1].clamp(0, 255) as u8, c[2].clamp(0, 255) as u8, 255]
+}
+
+/// 2 bit index of texel `i` in row major order, ETC stores them column major with the high
+/// bits in the upper half of the low word
+fn etc_index(bits: u64, i: usize) -> usize {
+    let p = (i % 4) * 4 + i / 4;
+    ((((bits >> (16 + p)) & 1) << 1) | ((bits >> p) & 1)) as usize
+}
+
+/// Individual and differential mode, two base colors for the halves split by the flip bit
+fn decode_etc1_texels(bits: u64, base: [[i32; 3]; 2], opaque: bool) -> [[u8; 4]; 16] {
+    let flip = (bits >> 32) & 1 == 1;
+    let codewords = [(bits >> 37) & 0b111, (bits >> 34) & 0b111];
+    std::array::from_fn(|i| {
+        let (x, y) = (i % 4, i / 4);
+        let half = if flip { y / 2 } else { x / 2 };
+        let [a, b] = ETC_MODIFIERS[codewords[half] as usize];
+        let modifier = match etc_index(bits, i) {
+            0 if opaque => a,
+            0 => 0,
+            1 => b,
+            2 if opaque => -a,
+            2 => return [0; 4],
+            _ => -b,
+        };
+        let c = base[half];
+        etc_color([c[0] + modifier, c[1] + modifier, c[2] + modifier])
+    })
+}
+
+/// T and H mode, every texel picks one of four paint colors
+fn decode_etc_paint_texels(bits: u64, paint: [[u8; 4]; 4], opaque: bool) -> [[u8; 4]; 16] {
+    std::array::from_fn(|i| match etc_index(bits, i) {
+        2 if !opaque => [0; 4],
+        index => paint[index],
+    })
+}
+
+/// Planar mode, a gradient through the colors at the origin, right and bottom of the block
+fn decode_etc_planar_texels(field: impl Fn(u32, u32) -> i32) -> [[u8; 4]; 16] {
+    let extend6 = |v: i32| (v << 2) | (v >> 4);
+    let extend7 = |v: i32| (v << 1) | (v >> 6);
+    let origin = [
+        extend6(field(57, 6)),
+        extend7((field(56, 1) << 6) | field(49, 6)),
+        extend6((field(48, 1) << 5) | (field(43, 2) << 3) | (field(40, 2) << 1) | field(39, 1)),
+    ];
+    let horizontal = [
+        extend6((field(34, 5) << 1) | field(32, 1)),
+        extend7(field(25, 7)),
+        extend6((field(24, 1) << 5) | field(19, 5)),
+    ];
+    let vertical = [extend6(field(13, 6)), extend7(field(6, 7)), extend6(field(0, 6))];
+    std::array::from_fn(|i| {
+        let (x, y) = ((i % 4) as i32, (i / 4) as i32);
+        etc_color(std::array::from_fn(|c| {
+            let (o, h, v) = (origin[c], horizontal[c], vertical[c]);
+            (x * (h - o) + y * (v - o) + 4 * o + 2) >> 2
+        }))
+    })
+}
+
+/// EAC alpha, or an 11 bit R11 channel, each rounded to 8 bits, in row major order
+fn decode_eac_block(block: &[u8], eleven_bit: bool) -> [u8; 16] {
+    let bits = u64::from_be_bytes(block[..8].try_into().unwrap());
+    let base = block[0] as i32;
+    let multiplier = (block[1] >> 4) as i32;
+    let modifiers = EAC_MODIFIERS[(block[1] & 0xf) as usize];
+    std::array::from_fn(|i| {
+        // 3 bit indices, column major from the top bits
+        let p = (i % 4) * 4 + i / 4;
+        let modifier = modifiers[((bits >> (45 - 3 * p)) & 0b111) as usize];
+        if !eleven_bit {
+            return (base + modifier * multiplier).clamp(0, 255) as u8;
+        }
+        let step = if multiplier == 0 { 1 } else { multiplier * 8 };
+        let value = (base * 8 + 4 + modifier * step).clamp(0, 2047);
+        ((value * 255 + 1023) / 2047) as u8
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn decode_one(format: TextureFormat, block: &[u8]) -> Vec<u8> {
+        decode_compressed(format, 4, 4, 1, block).unwrap().0
+    }
+
+    #[test]
+    fn bc1_blocks_are_decoded() {
+        // Red endpoints, every texel on the first one
+        let texels = decode_one(TextureFormat::Bc1RgbaUnorm, &[0, 0xf8, 0, 0xf8, 0, 0, 0, 0]);
+        assert!(texels.chunks(4).all(|texel| texel == [255, 0, 0, 255]));
+    }
+
+    #[test]
+    fn bc4_blocks_interpolate_their_endpoints() {
+        // Endpoints 255 and 0, first texel on the second interpolated value
+        let texels = decode_one(TextureFormat::Bc4RUnorm, &[255, 0, 2, 0, 0, 0, 0, 0]);
+        assert_eq!(texels[0], (6 * 255 / 7) as u8);
+        assert_eq!(texels[4], 255);
+    }
+
+    #[test]
+    fn etc2_differential_blocks_apply_their_modifiers() {
+        // Red 16 in both halves, table 0, every texel on index 0
+        let texels = decode_one(TextureFormat::Etc2Rgb8Unorm, &[0x80, 0, 0, 0x02, 0, 0, 0, 0]);
+        assert!(texels.chunks(4).all(|texel| texel == [134, 2, 2, 255]));
+    }
+
+    #[test]
+    fn etc2_t_mode_blocks_use_their_paint_colors() {
+        // Red overflows from 31 by 1, every texel on the first paint color
+        let texels = decode_one(TextureFormat::Etc2Rgb8Unorm, &[0xf9, 0, 0, 0x02, 0, 0, 0, 0]);
+        assert!(texels.chunks(4).all(|texel| texel == [221, 0, 0, 255]));
+    }
+
+    #[test]
+    fn etc2_punch_through_blocks_are_transparent_at_index_2() {
+        let block = [0x80, 0, 0, 0, 0xff, 0xff, 0, 0];
+        let texels = decode_one(TextureFormat::Etc2Rgb8A1Unorm, &block);
+        assert!(texels.chunks(4).all(|texel| texel == [0, 0, 0, 0]));
+    }
+
+    #[test]
+    fn eac_alpha_is_decoded_with_the_color() {
+        // Alpha 100 with multiplier 1 and table 0, index 0 subtracts 3
+        let mut block = [0; 16];
+        block[..2].copy_from_slice(&[100, 0x10]);
+        block[8..].copy_from_slice(&[0x80, 0, 0, 0x02, 0, 0, 0, 0]);
+        let texels = decode_one(TextureFormat::Etc2Rgba8Unorm, &block);
+        assert!(texels.chunks(4).all(|texel| texel == [134, 2, 2, 97]));
+    }
+
+    #[test]
+    fn undecodable_formats_become_a_placeholder() {
+        // BC7 isn't decoded on the CPU
+        let mut image = Image::default();
+        image.texture_descriptor.format = TextureFormat::Bc7RgbaUnorm;
+        image.texture_descriptor.size = Extent3d {
+            width: 4,
+            height: 4,
+            depth_or_array_layers: 1,
+        };
+        image.data = vec![0; 16];
+        assert!(!make_image_supported(&mut image));
+        assert_eq!(image.data, [255, 0, 255, 255]);
+        assert_eq!(image.texture_descriptor.format, TextureFormat::Rgba8Unorm);
+    }
+}
@@ -0,0 +1,94 @@
+use crate::materials::pbr::PbrMaterial;
+use bevy::gltf::Gltf;
+use bevy::prelude::*;
+use bevy::render::render_resource::Face;
+use bevy::utils::HashMap;
+
+/// `PbrMaterial`s built straight from the material definitions of loaded glTFs, keyed by the
+/// `StandardMaterial` Bevy's loader made of the same definition.
+///
+/// Only glTFs loaded with `GltfLoaderSettings::include_source` keep their definitions. Scene
+/// entities using one of these share its `PbrMaterial`, later edits to the `StandardMaterial`
+/// are not carried over to it.
+#[derive(Resource, Default, Debug)]
+pub struct GltfPbrMaterials(pub HashMap<AssetId<StandardMaterial>, Handle<PbrMaterial>>);
+
+pub(crate) fn build_gltf_materials(
+    mut events: EventReader<AssetEvent<Gltf>>,
+    gltfs: Res<Assets<Gltf>>,
+    standard_materials: Res<Assets<StandardMaterial>>,
+    mut materials: ResMut<Assets<PbrMaterial>>,
+    mut built: ResMut<GltfPbrMaterials>,
+) {
+    for event in events.read() {
+        let AssetEvent::LoadedWithDependencies { id } = event else {
+            continue;
+        };
+        let Some(gltf) = gltfs.get(*id) else {
+            continue;
+        };
+        let Some(source) = &gltf.source else {
+            continue;
+        };
+        // Bevy's loader adds one StandardMaterial per definition, in order
+        for (definition, handle) in source.materials().zip(&gltf.materials) {
+            // KHR_materials_unlit stays with SkUnlitMaterial
+            let Some(standard) = standard_materials.get(handle).filter(|m| !m.unlit) else {
+                continue;
+            };
+            let material = pbr_material_from_gltf(&definition, standard);
+            match built.0.get(&handle.id()) {
+                Some(existing) => materials.insert(existing, material),
+                None => {
+                    let material = materials.add(material);
+                    built.0.insert(handle.id(), material);
+                }
+            }
+        }
+    }
+}
+
+/// A `PbrMaterial` of a glTF material definition, with the textures Bevy loaded for its
+/// `StandardMaterial`
+pub fn pbr_material_from_gltf(
+    definition: &gltf::Material,
+    standard: &StandardMaterial,
+) -> PbrMaterial {
+    let mut material = PbrMaterial::from_standard(standard);
+
+    let pbr = definition.pbr_metallic_roughness();
+    let [r, g, b, a] = pbr.base_color_factor();
+    material.color = Color::linear_rgba(r, g, b, a);
+    material.metallic = pbr.metallic_factor();
+    material.roughness = pbr.roughness_factor();
+    material.alpha_mode = match definition.alpha_mode() {
+        gltf::material::AlphaMode::Opaque => AlphaMode::Opaque,
+        gltf::material::AlphaMode::Mask => {
+            AlphaMode::Mask(definition.alpha_cutoff().unwrap_or(0.5))
+        }
+        gltf::material::AlphaMode::Blend => AlphaMode::Blend,
+    };
+    material.double_sided = definition.double_sided();
+    let [r, g, b] = definition.emissive_factor();
+    material.emission_factor = Color::linear_rgb(r, g, b);
+    material.emission_strength = definition.emissive_strength().unwrap_or(1.0);
+    if let Some(ior) = definition.ior() {
+        material.reflectance = PbrMaterial::reflectance_from_ior(ior);
+    }
+    if let Some(specular) = definition.specular() {
+        let [r, g, b] = specular.specular_color_factor();
+        material.specular_factor = specular.specular_factor();
+        material.specular_tint = Color::linear_rgb(r, g, b);
+    }
+    material.cull_mode = (!material.double_sided).then_some(Face::Back);
+    // Bevy drops the normal map scale, glTF's default is 1
+    if let Some(normal) = definition.normal_texture() {
+        material.normal_scale = normal.scale();
+    }
+    // KHR_texture_transform of the base color, without the rotation PbrMaterial can't express
+    if let Some(transform) = pbr.base_color_texture().and_then(|t| t.texture_transform()) {
+        material.uv_scale = Vec2::from(transform.scale());
+        material.uv_offset = Vec2::from(transform.offset());
+    }
+    material
+}
@@ -0,0 +1,12 @@
+#define_import_path bevy_sk::grid
+
+// Coverage of the lines every `cell` meters, about `width` pixels wide at any distance
+fn sk_grid(position: vec2<f32>, cell: f32, width: f32) -> f32 {
+    let coord = position / cell;
+    let derivative = fwidth(coord);
+    let distance = abs(fract(coord - 0.5) - 0.5) / max(derivative, vec2(1e-6));
+    let line = 1.0 - min(min(distance.x, distance.y) / width, 1.0);
+    // Lines closer than a few pixels turn into a flat average instead of shimmering
+    let density = max(derivative.x, derivative.y) * width;
+    return line * (1.0 - smoothstep(0.2, 0.5, density));
+}
@@ -0,0 +1,105 @@
+use crate::materials::pbr::alpha_mode_flags;
+use bevy::prelude::*;
+use bevy::render::render_asset::RenderAssets;
+use bevy::render::render_resource::{AsBindGroup, AsBindGroupShaderType, ShaderRef, ShaderType};
+use bevy::render::texture::GpuImage;
+
+const SHADER_HANDLE: Handle<Shader> = Handle::weak_from_u128(0x2f68c1b7e04d);
+
+/// Registers [`SkHologramMaterial`], added by `PbrPlugin`
+pub struct SkHologramMaterialPlugin;
+
+impl Plugin for SkHologramMaterialPlugin {
+    fn build(&self, app: &mut App) {
+        load_shader!(app, SHADER_HANDLE, "hologram.wgsl");
+        app.add_plugins(MaterialPlugin::<SkHologramMaterial>::default());
+        app.register_asset_reflect::<SkHologramMaterial>();
+    }
+}
+
+/// Stylized see-through hologram with scrolling scanlines and a fresnel rim, which dissolves
+/// along a noise pattern, e.g. for objects spawning in or ghost previews
+#[derive(Asset, AsBindGroup, Reflect, PartialEq, Debug, Clone)]
+#[uniform(0, SkHologramMaterialUniform)]
+pub struct SkHologramMaterial {
+    pub color: Color,
+    /// Color added towards the silhouette
+    pub rim_color: Color,
+    /// Higher values keep the rim closer to the silhouette
+    pub rim_power: f32,
+    /// Scanlines per meter along world Y, 0 disables them
+    pub scanline_density: f32,
+    /// Meters per second the scanlines scroll upwards
+    pub scanline_speed: f32,
+    /// 0 shows the whole object, 1 has it dissolved completely
+    pub dissolve: f32,
+    /// Width in noise values of the glowing edge along the dissolve
+    pub dissolve_edge_width: f32,
+    pub dissolve_edge_color: Color,
+    /// Noise driving the dissolve from its red channel, a hashed value noise without one
+    #[texture(1)]
+    #[sampler(2)]
+    pub noise_texture: Option<Handle<Image>>,
+    pub alpha_mode: AlphaMode,
+}
+
+impl Default for SkHologramMaterial {
+    fn default() -> Self {
+        Self {
+            color: Color::srgba(0.3, 0.8, 1.0, 0.4),
+            rim_color: Color::srgb(0.6, 0.95, 1.0),
+            rim_power: 2.0,
+            scanline_density: 40.0,
+            scanline_speed: 0.2,
+            dissolve: 0.0,
+            dissolve_edge_width: 0.05,
+            dissolve_edge_color: LinearRgba::rgb(2.0, 4.0, 6.0).into(),
+            noise_texture: None,
+            alpha_mode: AlphaMode::Add,
+        }
+    }
+}
+
+#[derive(Clone, Default, ShaderType)]
+pub struct SkHologramMaterialUniform {
+    pub color: Vec4,
+    pub rim_color: Vec4,
+    pub dissolve_edge_color: Vec4,
+    pub rim_power: f32,
+    pub scanline_density: f32,
+    pub scanline_speed: f32,
+    pub dissolve: f32,
+    pub dissolve_edge_width: f32,
+    pub has_noise_texture: u32,
+    pub flags: u32,
+}
+
+impl AsBindGroupShaderType<SkHologramMaterialUniform> for SkHologramMaterial {
+    fn as_bind_group_shader_type(
+        &self,
+        _: &RenderAssets<GpuImage>,
+    ) -> SkHologramMaterialUniform {
+        SkHologramMaterialUniform {
+            color: self.color.to_linear().to_vec4(),
+            rim_color: self.rim_color.to_linear().to_vec4(),
+            dissolve_edge_color: self.dissolve_edge_color.to_linear().to_vec4(),
+            rim_power: self.rim_power,
+            scanline_density: self.scanline_density,
+            scanline_speed: self.scanline_speed,
+            dissolve: self.dissolve,
+            dissolve_edge_width: self.dissolve_edge_width,
+            has_noise_texture: self.noise_texture.is_some() as u32,
+            flags: alpha_mode_flags(self.alpha_mode).0.bits(),
+        }
+    }
+}
+
+impl Material for SkHologramMaterial {
+    fn fragment_shader() -> ShaderRef {
+        SHADER_HANDLE.into()
+    }
+
+    fn alpha_mode(&self) -> AlphaMode {
+        self.alpha_mode
+    }
+}
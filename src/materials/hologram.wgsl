@@ -0,0 +1,80 @@
+#import bevy_pbr::{
+    forward_io::VertexOutput,
+    mesh_view_bindings::{view, globals},
+}
+#import bevy_sk::pbr_types::sk_alpha_output
+
+struct SkHologramMaterial {
+    color: vec4<f32>,
+    rim_color: vec4<f32>,
+    dissolve_edge_color: vec4<f32>,
+    rim_power: f32,
+    scanline_density: f32,
+    scanline_speed: f32,
+    dissolve: f32,
+    dissolve_edge_width: f32,
+    has_noise_texture: u32,
+    flags: u32,
+};
+
+@group(2) @binding(0)
+var<uniform> material: SkHologramMaterial;
+@group(2) @binding(1)
+var noise_texture: texture_2d<f32>;
+@group(2) @binding(2)
+var noise_sampler: sampler;
+
+fn sk_hash(p: vec2<f32>) -> f32 {
+    return fract(sin(dot(p, vec2(127.1, 311.7))) * 43758.5453);
+}
+
+// Smoothed value noise, the fallback without a noise texture
+fn sk_value_noise(p: vec2<f32>) -> f32 {
+    let i = floor(p);
+    let f = fract(p);
+    let u = f * f * (3.0 - 2.0 * f);
+    let a = sk_hash(i);
+    let b = sk_hash(i + vec2(1.0, 0.0));
+    let c = sk_hash(i + vec2(0.0, 1.0));
+    let d = sk_hash(i + vec2(1.0, 1.0));
+    return mix(mix(a, b, u.x), mix(c, d, u.x), u.y);
+}
+
+@fragment
+fn fragment(in: VertexOutput, @builtin(front_facing) is_front: bool) -> @location(0) vec4<f32> {
+    // Sampled before the dissolve discard, which makes the control flow non-uniform
+    var noise = textureSample(noise_texture, noise_sampler, in.uv).r;
+    if (material.has_noise_texture == 0u) {
+        noise = sk_value_noise(in.uv * 16.0) * 0.65 + sk_value_noise(in.uv * 48.0) * 0.35;
+    }
+    // Reaches slightly past the noise range so 1 dissolves the edge too
+    let threshold = material.dissolve * (1.0 + material.dissolve_edge_width);
+    if (noise < threshold) {
+        discard;
+    }
+
+    var N = normalize(in.world_normal);
+    if (!is_front) {
+        N = -N;
+    }
+    let V = normalize(view.world_position - in.world_position.xyz);
+    let rim = pow(1.0 - saturate(dot(N, V)), material.rim_power);
+
+    var scanline = 1.0;
+    if (material.scanline_density > 0.0) {
+        let y = in.world_position.y - globals.time * material.scanline_speed;
+        scanline = 0.6 + 0.4 * sin(y * material.scanline_density * 6.2831853);
+    }
+
+    var color = material.color.rgb * scanline + material.rim_color.rgb * rim;
+    var alpha = saturate(material.color.a * scanline + rim * material.rim_color.a);
+    let edge = 1.0 - saturate((noise - threshold) / max(material.dissolve_edge_width, 0.0001));
+    if (material.dissolve > 0.0) {
+        color = mix(color, material.dissolve_edge_color.rgb, edge);
+        alpha = max(alpha, edge * material.dissolve_edge_color.a);
+    }
+#ifdef VERTEX_COLORS
+    color *= in.color.rgb;
+#endif
+    return sk_alpha_output(material.flags, color, alpha);
+}
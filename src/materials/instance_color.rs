@@ -0,0 +1,89 @@
+use bevy::pbr::Lightmap;
+use bevy::prelude::*;
+
+/// Placeholder lightmap of every [`InstanceColor`] entity, which also lets them batch together.
+/// The shader tells it from real lightmaps by its single texel.
+const INSTANCE_COLOR_LIGHTMAP_HANDLE: Handle<Image> = Handle::weak_from_u128(0x4b91e6d02f7c);
+
+/// Passes every [`InstanceColor`] to `PbrMaterial`, added by `PbrPlugin`
+pub struct InstanceColorPlugin;
+
+impl Plugin for InstanceColorPlugin {
+    fn build(&self, app: &mut App) {
+        app.world_mut()
+            .resource_mut::<Assets<Image>>()
+            .insert(INSTANCE_COLOR_LIGHTMAP_HANDLE.id(), Image::default());
+        app.register_type::<InstanceColor>();
+        app.add_systems(PostUpdate, apply_instance_colors);
+    }
+}
+
+/// Per instance tint multiplied into the albedo of the entity's `PbrMaterial`, like the color
+/// of StereoKit's `render_add_mesh`, so copies of a mesh can share one material and still
+/// batch.
+///
+/// The color rides in the instance's lightmap UV rect, as 16 bit unorm channels, so linear
+/// channels are clamped to 0..1: HDR tints can't brighten past the material's own color, use
+/// its emission for that. `PbrMaterial` doesn't sample lightmaps, so the entity gets a
+/// `Lightmap` that only carries the color, which also makes it use the lightmapped pipeline.
+/// Entities that already have a real `Lightmap` keep it and aren't tinted.
+#[derive(Component, Reflect, Clone, Copy, Debug, PartialEq)]
+#[reflect(Component)]
+pub struct InstanceColor(pub Color);
+
+impl Default for InstanceColor {
+    fn default() -> Self {
+        Self(Color::WHITE)
+    }
+}
+
+impl InstanceColor {
+    /// The lightmap carrying this color, ordered like the rect `bevy_sk::pbr_fragment` unpacks
+    pub fn lightmap(&self) -> Lightmap {
+        // Bevy packs the rect into 16 bits per channel, out of range values would spill into
+        // the neighbouring channel
+        let color = self.0.to_linear().to_f32_array().map(|c| c.clamp(0.0, 1.0));
+        Lightmap {
+            image: INSTANCE_COLOR_LIGHTMAP_HANDLE,
+            // Not `Rect::new`, which would reorder the channels into a valid rect
+            uv_rect: Rect {
+                min: Vec2::new(color[0], color[1]),
+                max: Vec2::new(color[2], color[3]),
+            },
+        }
+    }
+}
+
+fn apply_instance_colors(
+    mut commands: Commands,
+    colors: Query<(Entity, &InstanceColor, Option<&Lightmap>), Changed<InstanceColor>>,
+    lightmaps: Query<&Lightmap>,
+    mut removed: RemovedComponents<InstanceColor>,
+) {
+    // Lightmaps of other images are real ones, left alone
+    let carries_color = |lightmap: &Lightmap| lightmap.image == INSTANCE_COLOR_LIGHTMAP_HANDLE;
+    for (entity, color, lightmap) in colors.iter() {
+        if lightmap.is_some_and(|lightmap| !carries_color(lightmap)) {
+            warn!("{entity:?} has a Lightmap, its InstanceColor isn't applied");
+            continue;
+        }
+        commands.entity(entity).insert(color.lightmap());
+    }
+    for entity in removed.read() {
+        if lightmaps.get(entity).is_ok_and(carries_color) {
+            commands.entity(entity).remove::<Lightmap>();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn colors_are_clamped_to_unorm() {
+        let lightmap = InstanceColor(Color::linear_rgba(2.0, -1.0, 0.5, 1.0)).lightmap();
+        assert_eq!(lightmap.uv_rect.min, Vec2::new(1.0, 0.0));
+        assert_eq!(lightmap.uv_rect.max, Vec2::new(0.5, 1.0));
+    }
+}
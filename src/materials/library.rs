@@ -0,0 +1,213 @@
+use crate::materials::pbr::PbrMaterial;
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+
+/// Named `PbrMaterial` presets, filled with the [`SkMaterialBuilder`] presets under their
+/// names: `"metal"`, `"plastic"`, `"glass"`, `"emissive"`, `"hologram"` and `"occluder"`.
+/// Inserted by `PbrPlugin`, add your own next to them.
+#[derive(Resource, Reflect, Clone, Debug)]
+#[reflect(Resource)]
+pub struct SkMaterialLibrary {
+    presets: HashMap<String, PbrMaterial>,
+}
+
+impl Default for SkMaterialLibrary {
+    fn default() -> Self {
+        let mut library = Self {
+            presets: HashMap::new(),
+        };
+        library.insert("metal", SkMaterialBuilder::metal().build());
+        library.insert("plastic", SkMaterialBuilder::plastic().build());
+        library.insert("glass", SkMaterialBuilder::glass().build());
+        library.insert("emissive", SkMaterialBuilder::emissive().build());
+        library.insert("hologram", SkMaterialBuilder::hologram().build());
+        library.insert("occluder", SkMaterialBuilder::occluder().build());
+        library
+    }
+}
+
+impl SkMaterialLibrary {
+    pub fn get(&self, name: &str) -> Option<&PbrMaterial> {
+        self.presets.get(name)
+    }
+
+    /// Adds or replaces the preset called `name`
+    pub fn insert(&mut self, name: impl Into<String>, material: PbrMaterial) {
+        self.presets.insert(name.into(), material);
+    }
+
+    pub fn remove(&mut self, name: &str) -> Option<PbrMaterial> {
+        self.presets.remove(name)
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.presets.keys().map(String::as_str)
+    }
+
+    /// A builder starting from the preset called `name`, to make a variant of it
+    pub fn builder(&self, name: &str) -> Option<SkMaterialBuilder> {
+        self.get(name).cloned().map(SkMaterialBuilder::from)
+    }
+
+    /// Adds a copy of the preset called `name` to `materials`
+    pub fn add(
+        &self,
+        name: &str,
+        materials: &mut Assets<PbrMaterial>,
+    ) -> Option<Handle<PbrMaterial>> {
+        self.get(name)
+            .map(|material| materials.add(material.clone()))
+    }
+}
+
+/// Chainable setters for the `PbrMaterial` fields that matter most, starting from its
+/// defaults or a preset
+#[derive(Clone, Debug, Default)]
+pub struct SkMaterialBuilder {
+    material: PbrMaterial,
+}
+
+impl From<PbrMaterial> for SkMaterialBuilder {
+    fn from(material: PbrMaterial) -> Self {
+        Self { material }
+    }
+}
+
+impl SkMaterialBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Polished bare metal
+    pub fn metal() -> Self {
+        Self::new()
+            .color(Color::srgb(0.9, 0.9, 0.92))
+            .metallic(1.0)
+            .roughness(0.2)
+    }
+
+    /// Matte dielectric with a soft highlight
+    pub fn plastic() -> Self {
+        Self::new()
+            .color(Color::srgb(0.8, 0.8, 0.8))
+            .metallic(0.0)
+            .roughness(0.5)
+    }
+
+    /// Clear blended glass, mostly reflections
+    pub fn glass() -> Self {
+        Self::new()
+            .color(Color::srgba(0.9, 0.95, 1.0, 0.15))
+            .metallic(0.0)
+            .roughness(0.05)
+            .reflectance(PbrMaterial::reflectance_from_ior(1.5))
+            .alpha_mode(AlphaMode::Blend)
+    }
+
+    /// Glows on its own, bright enough to feed bloom
+    pub fn emissive() -> Self {
+        Self::new().color(Color::BLACK).emission(Color::WHITE, 4.0)
+    }
+
+    /// Additive see-through look with a bright rim, like a simple `SkHologramMaterial`
+    /// without the scanlines and dissolve
+    pub fn hologram() -> Self {
+        Self::new()
+            .color(Color::srgba(0.3, 0.8, 1.0, 0.4))
+            .rim(Color::srgb(0.6, 0.95, 1.0), 2.0)
+            .alpha_mode(AlphaMode::Add)
+            .cast_shadows(false)
+    }
+
+    /// Opaque black without reflections, which hides what's behind it and shows as see-through
+    /// on additive displays. `SkOccluderMaterial` punches through blended passthrough instead.
+    pub fn occluder() -> Self {
+        Self::new()
+            .color(Color::BLACK)
+            .roughness(1.0)
+            .metallic(0.0)
+            .specular_factor(0.0)
+            .cast_shadows(false)
+    }
+
+    pub fn color(mut self, color: impl Into<Color>) -> Self {
+        self.material.color = color.into();
+        self
+    }
+
+    pub fn metallic(mut self, metallic: f32) -> Self {
+        self.material.metallic = metallic;
+        self
+    }
+
+    pub fn roughness(mut self, roughness: f32) -> Self {
+        self.material.roughness = roughness;
+        self
+    }
+
+    /// See [`PbrMaterial::reflectance`]
+    pub fn reflectance(mut self, reflectance: f32) -> Self {
+        self.material.reflectance = reflectance;
+        self
+    }
+
+    pub fn specular_factor(mut self, specular_factor: f32) -> Self {
+        self.material.specular_factor = specular_factor;
+        self
+    }
+
+    /// Emission color and the strength multiplying it, above 1 feeds bloom
+    pub fn emission(mut self, color: impl Into<Color>, strength: f32) -> Self {
+        self.material.emission_factor = color.into();
+        self.material.emission_strength = strength;
+        self
+    }
+
+    pub fn rim(mut self, color: impl Into<Color>, power: f32) -> Self {
+        self.material.rim_color = color.into();
+        self.material.rim_power = power;
+        self
+    }
+
+    pub fn clearcoat(mut self, clearcoat: f32, roughness: f32) -> Self {
+        self.material.clearcoat = clearcoat;
+        self.material.clearcoat_roughness = roughness;
+        self
+    }
+
+    pub fn alpha_mode(mut self, alpha_mode: AlphaMode) -> Self {
+        self.material.alpha_mode = alpha_mode;
+        self
+    }
+
+    pub fn double_sided(mut self, double_sided: bool) -> Self {
+        self.material.double_sided = double_sided;
+        self
+    }
+
+    pub fn cast_shadows(mut self, cast_shadows: bool) -> Self {
+        self.material.cast_shadows = cast_shadows;
+        self
+    }
+
+    /// Albedo texture, multiplied by `color`
+    pub fn color_texture(mut self, texture: Handle<Image>) -> Self {
+        self.material.color_texture = Some(texture);
+        self
+    }
+
+    /// Tangent space normal map, needs meshes with tangents
+    pub fn normal_texture(mut self, texture: Handle<Image>) -> Self {
+        self.material.normal_texture = Some(texture);
+        self
+    }
+
+    pub fn emission_texture(mut self, texture: Handle<Image>) -> Self {
+        self.material.emission_texture = Some(texture);
+        self
+    }
+
+    pub fn build(self) -> PbrMaterial {
+        self.material
+    }
+}
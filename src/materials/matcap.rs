@@ -0,0 +1,80 @@
+use crate::lighting::buffer::{ShSlot, SH_BUFFER_IMAGE_HANDLE};
+use bevy::prelude::*;
+use bevy::render::render_asset::RenderAssets;
+use bevy::render::render_resource::{AsBindGroup, AsBindGroupShaderType, ShaderRef, ShaderType};
+use bevy::render::texture::GpuImage;
+
+const SHADER_HANDLE: Handle<Shader> = Handle::weak_from_u128(0x3e95d06a7b2c);
+
+/// Registers [`SkMatcapMaterial`], added by `PbrPlugin`
+pub struct SkMatcapMaterialPlugin;
+
+impl Plugin for SkMatcapMaterialPlugin {
+    fn build(&self, app: &mut App) {
+        load_shader!(app, SHADER_HANDLE, "matcap.wgsl");
+        app.add_plugins(MaterialPlugin::<SkMatcapMaterial>::default());
+        app.register_asset_reflect::<SkMatcapMaterial>();
+    }
+}
+
+/// Looks up the view space normal in a "material capture" sphere texture, baked lighting that
+/// costs a single texture sample
+#[derive(Asset, AsBindGroup, Reflect, PartialEq, Debug, Clone)]
+#[uniform(0, SkMatcapMaterialUniform)]
+pub struct SkMatcapMaterial {
+    #[texture(1)]
+    #[sampler(2)]
+    pub matcap_texture: Option<Handle<Image>>,
+    pub tint: Color,
+    /// How much the SH ambient of `lighting` darkens or colors the matcap, 0 ignores it
+    pub ambient_blend: f32,
+    /// Slot of the shared `ShLightingBuffer` blended in by `ambient_blend`
+    pub lighting: ShSlot,
+    /// The shared SH texture, leave this at its default
+    #[texture(3, sample_type = "float", filterable = false)]
+    pub sh_buffer: Handle<Image>,
+}
+
+impl Default for SkMatcapMaterial {
+    fn default() -> Self {
+        Self {
+            matcap_texture: None,
+            tint: Color::WHITE,
+            ambient_blend: 0.0,
+            lighting: ShSlot::GLOBAL,
+            sh_buffer: SH_BUFFER_IMAGE_HANDLE,
+        }
+    }
+}
+
+impl SkMatcapMaterial {
+    pub fn new(matcap_texture: Handle<Image>) -> Self {
+        Self {
+            matcap_texture: Some(matcap_texture),
+            ..default()
+        }
+    }
+}
+
+#[derive(Clone, Default, ShaderType)]
+pub struct SkMatcapMaterialUniform {
+    pub tint: Vec4,
+    pub ambient_blend: f32,
+    pub sh_slot: u32,
+}
+
+impl AsBindGroupShaderType<SkMatcapMaterialUniform> for SkMatcapMaterial {
+    fn as_bind_group_shader_type(&self, _: &RenderAssets<GpuImage>) -> SkMatcapMaterialUniform {
+        SkMatcapMaterialUniform {
+            tint: self.tint.to_linear().to_vec4(),
+            ambient_blend: self.ambient_blend,
+            sh_slot: self.lighting.0,
+        }
+    }
+}
+
+impl Material for SkMatcapMaterial {
+    fn fragment_shader() -> ShaderRef {
+        SHADER_HANDLE.into()
+    }
+}
@@ -0,0 +1,46 @@
+#import bevy_pbr::{
+    forward_io::VertexOutput,
+    mesh_view_bindings::view,
+}
+#import bevy_sk::lighting::{sk_lighting, sk_sh_exposure}
+
+struct SkMatcapMaterial {
+    tint: vec4<f32>,
+    ambient_blend: f32,
+    sh_slot: u32,
+};
+
+@group(2) @binding(0)
+var<uniform> material: SkMatcapMaterial;
+@group(2) @binding(1)
+var matcap_texture: texture_2d<f32>;
+@group(2) @binding(2)
+var matcap_sampler: sampler;
+@group(2) @binding(3)
+var sh_buffer: texture_2d<f32>;
+
+@fragment
+fn fragment(in: VertexOutput, @builtin(front_facing) is_front: bool) -> @location(0) vec4<f32> {
+    var N = normalize(in.world_normal);
+    if (!is_front) {
+        N = -N;
+    }
+    let view_normal = normalize((view.view_from_world * vec4(N, 0.0)).xyz);
+    let uv = view_normal.xy * vec2(0.5, -0.5) + 0.5;
+    var color = textureSample(matcap_texture, matcap_sampler, uv) * material.tint;
+#ifdef VERTEX_COLORS
+    color *= in.color;
+#endif
+
+    if (material.ambient_blend > 0.0) {
+        let slot = i32(material.sh_slot);
+        let exposure = sk_sh_exposure(textureLoad(sh_buffer, vec2(0, slot), 0).a, view.exposure);
+        var sh: array<vec3<f32>, 9>;
+        for (var i = 0u; i < 9u; i += 1u) {
+            sh[i] = textureLoad(sh_buffer, vec2<i32>(i32(i), slot), 0).rgb * exposure;
+        }
+        let ambient = sk_lighting(N, sh);
+        color = vec4(color.rgb * mix(vec3(1.0), ambient, material.ambient_blend), color.a);
+    }
+    return color;
+}
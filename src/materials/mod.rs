@@ -1 +1,23 @@
+pub mod anisotropy;
+pub mod back_faces;
+pub mod billboard;
+pub mod decal;
+pub mod emission;
+pub mod extension;
+pub mod floor;
+pub mod formats;
+pub mod gltf_materials;
+pub mod hologram;
+pub mod instance_color;
+pub mod library;
+pub mod matcap;
+pub mod occluder;
+pub mod packing;
 pub mod pbr;
+pub mod sh_extension;
+pub mod tangents;
+pub mod text;
+pub mod texture_arrays;
+pub mod ui;
+pub mod unlit;
+pub mod warmup;
@@ -0,0 +1,75 @@
+use bevy::prelude::*;
+use bevy::render::render_resource::{AsBindGroup, ColorWrites, Face, ShaderRef};
+
+const SHADER_HANDLE: Handle<Shader> = Handle::weak_from_u128(0x7d3e91a60c5f);
+
+/// Registers [`SkOccluderMaterial`], added by `PbrPlugin`
+pub struct SkOccluderMaterialPlugin;
+
+impl Plugin for SkOccluderMaterialPlugin {
+    fn build(&self, app: &mut App) {
+        load_shader!(app, SHADER_HANDLE, "occluder.wgsl");
+        app.add_plugins(MaterialPlugin::<SkOccluderMaterial>::default());
+        app.register_asset_reflect::<SkOccluderMaterial>();
+    }
+}
+
+/// Writes depth and no color, so meshes of the real world, e.g. from scene understanding,
+/// hide the virtual content behind them in AR. Add `NotShadowCaster` to keep the room from
+/// shadowing the scene.
+#[derive(Asset, AsBindGroup, Reflect, PartialEq, Debug, Clone)]
+#[bind_group_data(SkOccluderMaterialKey)]
+pub struct SkOccluderMaterial {
+    /// Also writes transparent black, so an alpha blended passthrough shows through where the
+    /// occluder covers the clear color
+    pub punch_through: bool,
+    pub double_sided: bool,
+}
+
+impl Default for SkOccluderMaterial {
+    fn default() -> Self {
+        Self {
+            punch_through: true,
+            double_sided: false,
+        }
+    }
+}
+
+/// Pipeline specialization of a `SkOccluderMaterial`
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub struct SkOccluderMaterialKey {
+    cull_mode: Option<Face>,
+    punch_through: bool,
+}
+
+impl From<&SkOccluderMaterial> for SkOccluderMaterialKey {
+    fn from(material: &SkOccluderMaterial) -> Self {
+        SkOccluderMaterialKey {
+            cull_mode: (!material.double_sided).then_some(Face::Back),
+            punch_through: material.punch_through,
+        }
+    }
+}
+
+impl Material for SkOccluderMaterial {
+    fn fragment_shader() -> ShaderRef {
+        SHADER_HANDLE.into()
+    }
+
+    fn specialize(
+        _pipeline: &bevy::pbr::MaterialPipeline<Self>,
+        descriptor: &mut bevy::render::render_resource::RenderPipelineDescriptor,
+        _layout: &bevy::render::mesh::MeshVertexBufferLayoutRef,
+        key: bevy::pbr::MaterialPipelineKey<Self>,
+    ) -> Result<(), bevy::render::render_resource::SpecializedMeshPipelineError> {
+        descriptor.primitive.cull_mode = key.bind_group_data.cull_mode;
+        if !key.bind_group_data.punch_through {
+            if let Some(fragment) = &mut descriptor.fragment {
+                for target in fragment.targets.iter_mut().flatten() {
+                    target.write_mask = ColorWrites::empty();
+                }
+            }
+        }
+        Ok(())
+    }
+}
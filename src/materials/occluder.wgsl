@@ -0,0 +1,7 @@
+#import bevy_pbr::forward_io::VertexOutput
+
+// Opaque, so this replaces the color target with transparent black where it isn't masked off
+@fragment
+fn fragment(in: VertexOutput) -> @location(0) vec4<f32> {
+    return vec4(0.0);
+}
@@ -0,0 +1,61 @@
+//! Field types of `PbrMaterialUniform`, packed at reduced precision with `packed-uniforms`
+
+#[cfg(not(feature = "packed-uniforms"))]
+mod layout {
+    use bevy::math::{Vec2, Vec4};
+
+    pub type UnormColor = Vec4;
+    pub type HdrColor = Vec4;
+    pub type Unorm2 = Vec2;
+
+    pub fn unorm_color(color: Vec4) -> UnormColor {
+        color
+    }
+
+    pub fn hdr_color(color: Vec4) -> HdrColor {
+        color
+    }
+
+    pub fn unorm2(v: Vec2) -> Unorm2 {
+        v
+    }
+}
+
+#[cfg(feature = "packed-uniforms")]
+mod layout {
+    use bevy::math::{UVec2, Vec2, Vec4};
+    use half::f16;
+
+    /// `unpack4x8unorm` in WGSL
+    pub type UnormColor = u32;
+    /// Two `unpack2x16float`s in WGSL
+    pub type HdrColor = UVec2;
+    /// `unpack2x16unorm` in WGSL
+    pub type Unorm2 = u32;
+
+    fn unorm8(v: f32) -> u32 {
+        (v.clamp(0.0, 1.0) * 255.0).round() as u32
+    }
+
+    fn unorm16(v: f32) -> u32 {
+        (v.clamp(0.0, 1.0) * 65535.0).round() as u32
+    }
+
+    fn half2(a: f32, b: f32) -> u32 {
+        f16::from_f32(a).to_bits() as u32 | (f16::from_f32(b).to_bits() as u32) << 16
+    }
+
+    pub fn unorm_color(color: Vec4) -> UnormColor {
+        unorm8(color.x) | unorm8(color.y) << 8 | unorm8(color.z) << 16 | unorm8(color.w) << 24
+    }
+
+    pub fn hdr_color(color: Vec4) -> HdrColor {
+        UVec2::new(half2(color.x, color.y), half2(color.z, color.w))
+    }
+
+    pub fn unorm2(v: Vec2) -> Unorm2 {
+        unorm16(v.x) | unorm16(v.y) << 16
+    }
+}
+
+pub use layout::*;
@@ -1,6 +1,50 @@
-use crate::skytex::{SphericalHarmonics, DEFAULT_LIGHTING};
-use bevy::asset::load_internal_asset;
-use bevy::render::render_resource::Face;
+use crate::color::{
+    apply_ambient_exposure, apply_sk_exposure, apply_tonemapping, SkColorSettings, SkExposure,
+};
+use crate::lighting::buffer::{ShLightingBufferPlugin, ShSlot, SH_BUFFER_IMAGE_HANDLE};
+use crate::lighting::cookie::{
+    LightCookiePlugin, LIGHT_COOKIES_IMAGE_HANDLE, LIGHT_COOKIE_DATA_IMAGE_HANDLE,
+};
+use crate::materials::anisotropy::{AnisotropicSamplers, TextureAnisotropyPlugin, TextureSamplers};
+use crate::materials::back_faces::{draw_back_faces, BackFacePass};
+use crate::materials::billboard::SkBillboardMaterialPlugin;
+use crate::materials::decal::SkDecalPlugin;
+use crate::materials::emission::EmissionAnimatorPlugin;
+use crate::materials::floor::SkFloorPlugin;
+use crate::materials::formats::TextureFormatNegotiationPlugin;
+use crate::materials::gltf_materials::{build_gltf_materials, GltfPbrMaterials};
+use crate::materials::hologram::SkHologramMaterialPlugin;
+use crate::materials::instance_color::InstanceColorPlugin;
+use crate::materials::library::SkMaterialLibrary;
+use crate::materials::matcap::SkMatcapMaterialPlugin;
+use crate::materials::occluder::SkOccluderMaterialPlugin;
+use crate::materials::packing::{self, HdrColor, Unorm2, UnormColor};
+use crate::materials::sh_extension::{
+    ShExtendedStandardMaterial, ShExtendedStandardMaterialPlugin, ShExtension,
+};
+use crate::materials::tangents::{generate_missing_tangents, GenerateMissingTangents};
+use crate::materials::text::SkTextPlugin;
+use crate::materials::texture_arrays::TextureArrayLayers;
+use crate::materials::ui::SkUiMaterialsPlugin;
+use crate::materials::unlit::{SkUnlitMaterial, SkUnlitMaterialPlugin};
+use crate::quality::{SkMaterialFeatures, SkQuality};
+use crate::XrFoveation;
+use bevy::ecs::system::lifetimeless::SRes;
+use bevy::ecs::system::{EntityCommands, SystemParam, SystemParamItem};
+use bevy::pbr::{NotShadowCaster, OpaqueRendererMethod};
+use bevy::render::render_resource::encase::UniformBuffer;
+use bevy::render::render_resource::{
+    AsBindGroupError, BindGroupEntry, BindGroupLayout, BindGroupLayoutEntry, BindingType,
+    BufferBindingType, BufferInitDescriptor, BufferUsages, Face, OwnedBindingResource,
+    PreparedBindGroup, SamplerBindingType, ShaderDefVal, ShaderStages, TextureSampleType,
+    TextureViewDimension, UnpreparedBindGroup,
+};
+use bevy::render::renderer::RenderDevice;
+use bevy::render::texture::FallbackImage;
+use bevy::utils::{HashMap, HashSet};
+use std::sync::{Mutex, OnceLock};
+#[cfg(feature = "inspector")]
+use bevy_inspector_egui::prelude::*;
 use bevy::{
     prelude::*,
     render::{
@@ -11,89 +55,927 @@ use bevy::{
 };
 
 const SHADER_HANDLE: Handle<Shader> = Handle::weak_from_u128(0x2d86c30a165b);
+const VERTEX_SHADER_HANDLE: Handle<Shader> = Handle::weak_from_u128(0x58e0b7c3a91f);
+const PREPASS_SHADER_HANDLE: Handle<Shader> = Handle::weak_from_u128(0xe5a01c7f93b2);
+/// `bevy_sk::pbr_types`, the `PbrMaterial` uniform layout and flag bits
+pub const SK_TYPES_SHADER_HANDLE: Handle<Shader> = Handle::weak_from_u128(0x6f1c2e94b07d);
+/// `bevy_sk::lighting`, SH evaluation
+pub const SK_LIGHTING_SHADER_HANDLE: Handle<Shader> = Handle::weak_from_u128(0x93a4d1f0c25e);
+/// `bevy_sk::brdf`, the sk specular BRDF
+pub const SK_BRDF_SHADER_HANDLE: Handle<Shader> = Handle::weak_from_u128(0x1b7e5a3d8c46);
+/// `bevy_sk::lights`, Bevy's lights and shadows evaluated with the sk BRDF
+pub const SK_LIGHTS_SHADER_HANDLE: Handle<Shader> = Handle::weak_from_u128(0x7d3f92c6e1a8);
+/// `bevy_sk::pbr_fragment`, the `PbrMaterial` bindings and evaluation for extension shaders
+pub const SK_PBR_FRAGMENT_SHADER_HANDLE: Handle<Shader> =
+    Handle::weak_from_u128(0xa2c85e1f4d07);
 
-/// Replaces all StandardMaterial with PbrMaterial
+/// Replaces all StandardMaterial with PbrMaterial, or SkUnlitMaterial when unlit, see
+/// [`ReplaceMaterialsMode`] to limit that and [`StandardMaterialConversion`] to extend them
+/// with the SH ambient instead
+///
+/// Also registers the `bevy_sk::pbr_types`, `bevy_sk::lighting`, `bevy_sk::brdf`,
+/// `bevy_sk::lights` and `bevy_sk::pbr_fragment` WGSL modules so custom materials can
+/// `#import` the same lighting model.
 pub struct PbrPlugin;
 
 impl Plugin for PbrPlugin {
     fn build(&self, app: &mut App) {
-        load_internal_asset!(app, SHADER_HANDLE, "pbr.wgsl", Shader::from_wgsl);
-        app.add_plugins(MaterialPlugin::<PbrMaterial>::default());
-        app.add_systems(Update, replace_materials);
+        load_shader!(app, SK_TYPES_SHADER_HANDLE, "sk_types.wgsl");
+        load_shader!(app, SK_LIGHTING_SHADER_HANDLE, "sk_lighting.wgsl");
+        load_shader!(app, SK_BRDF_SHADER_HANDLE, "sk_brdf.wgsl");
+        load_shader!(app, SK_LIGHTS_SHADER_HANDLE, "sk_lights.wgsl");
+        load_shader!(app, SK_PBR_FRAGMENT_SHADER_HANDLE, "pbr_fragment.wgsl");
+        load_shader!(app, SHADER_HANDLE, "pbr.wgsl");
+        load_shader!(app, VERTEX_SHADER_HANDLE, "pbr_vertex.wgsl");
+        load_shader!(app, PREPASS_SHADER_HANDLE, "pbr_prepass.wgsl");
+        app.add_plugins((
+            ShLightingBufferPlugin,
+            TextureFormatNegotiationPlugin,
+            MaterialPlugin::<PbrMaterial>::default(),
+            SkUnlitMaterialPlugin,
+            SkMatcapMaterialPlugin,
+            SkDecalPlugin,
+            SkHologramMaterialPlugin,
+            SkOccluderMaterialPlugin,
+            SkUiMaterialsPlugin,
+            SkTextPlugin,
+            SkBillboardMaterialPlugin,
+            SkFloorPlugin,
+            ShExtendedStandardMaterialPlugin,
+            EmissionAnimatorPlugin,
+            InstanceColorPlugin,
+        ));
+        app.add_plugins((LightCookiePlugin, TextureAnisotropyPlugin));
+        app.register_asset_reflect::<PbrMaterial>();
+        app.add_event::<MaterialReplaced>();
+        app.init_resource::<SkQuality>();
+        app.init_resource::<SkColorSettings>();
+        app.init_resource::<SkExposure>();
+        app.init_resource::<ReplaceMaterialsMode>();
+        app.init_resource::<StandardMaterialConversion>();
+        app.init_resource::<ShareConvertedMaterials>();
+        app.init_resource::<GltfPbrMaterials>();
+        app.init_resource::<GenerateMissingTangents>();
+        app.init_resource::<SkMaterialLibrary>();
+        app.register_type::<(
+            SkQuality,
+            SkColorSettings,
+            SkExposure,
+            ReplaceMaterialsMode,
+            StandardMaterialConversion,
+            ShareConvertedMaterials,
+            GenerateMissingTangents,
+            SkMaterialLibrary,
+            KeepStandardMaterial,
+            ReplaceStandardMaterial,
+            ConvertedStandardMaterial,
+            BackFacePass,
+        )>();
+        app.add_systems(
+            Update,
+            (
+                build_gltf_materials.before(replace_materials),
+                replace_materials.run_if(replace_materials_needed),
+                warn_unloaded_materials,
+                apply_material_quality,
+                apply_material_shadow_casting,
+                generate_missing_tangents.after(replace_materials),
+                draw_back_faces::<PbrMaterial>,
+                apply_ambient_exposure,
+                apply_sk_exposure,
+                apply_tonemapping,
+            ),
+        );
     }
 }
 
-fn replace_materials(
+/// Which `StandardMaterial`s [`PbrPlugin`] replaces
+#[derive(Resource, Reflect, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[reflect(Resource)]
+pub enum ReplaceMaterialsMode {
+    /// Everything not under a [`KeepStandardMaterial`]
+    #[default]
+    All,
+    /// Only entities under a [`ReplaceStandardMaterial`]
+    OnlyMarked,
+    /// Nothing, markers included
+    Off,
+}
+
+/// What [`PbrPlugin`] turns the `StandardMaterial`s picked by [`ReplaceMaterialsMode`] into
+#[derive(Resource, Reflect, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[reflect(Resource)]
+pub enum StandardMaterialConversion {
+    /// A `PbrMaterial`, or `SkUnlitMaterial` when unlit, shaded like StereoKit
+    #[default]
+    Replace,
+    /// A [`ShExtendedStandardMaterial`], keeping Bevy's shading and adding the SH ambient
+    Extend,
+}
+
+/// Keeps the `StandardMaterial` of this entity and its descendants, e.g. on a scene root
+/// rendered by another material plugin
+#[derive(Component, Reflect, Clone, Copy, Debug, Default)]
+#[reflect(Component)]
+pub struct KeepStandardMaterial;
+
+/// Replaces the `StandardMaterial` of this entity and its descendants even in
+/// [`ReplaceMaterialsMode::OnlyMarked`]
+#[derive(Component, Reflect, Clone, Copy, Debug, Default)]
+#[reflect(Component)]
+pub struct ReplaceStandardMaterial;
+
+/// The `StandardMaterial` a material was converted from, edits to it are carried over
+#[derive(Component, Reflect, Clone, Debug)]
+#[reflect(Component)]
+pub struct ConvertedStandardMaterial(pub Handle<StandardMaterial>);
+
+/// Sent when [`PbrPlugin`] gives an entity a `PbrMaterial` converted from its
+/// `StandardMaterial`, unlit and extended conversions aren't reported
+#[derive(Event, Clone, Debug)]
+pub struct MaterialReplaced {
+    pub entity: Entity,
+    pub old: Handle<StandardMaterial>,
+    pub new: Handle<PbrMaterial>,
+}
+
+/// Whether entities sharing a `StandardMaterial` also share the material it's converted to,
+/// which lets Bevy batch their draws into instanced ones. Turn it off when converted
+/// materials are edited per entity, e.g. by a `ReflectionProbeReceiver` or an
+/// `EmissionAnimator`, or give those entities their own copy.
+#[derive(Resource, Reflect, Clone, Copy, Debug, PartialEq, Eq)]
+#[reflect(Resource)]
+pub struct ShareConvertedMaterials(pub bool);
+
+impl Default for ShareConvertedMaterials {
+    fn default() -> Self {
+        Self(true)
+    }
+}
+
+/// Materials already converted from each `StandardMaterial`, reused while
+/// [`ShareConvertedMaterials`] is on
+#[derive(Default)]
+struct ConvertedMaterialCache {
+    pbr: HashMap<AssetId<StandardMaterial>, Handle<PbrMaterial>>,
+    unlit: HashMap<AssetId<StandardMaterial>, Handle<SkUnlitMaterial>>,
+    extended: HashMap<AssetId<StandardMaterial>, Handle<ShExtendedStandardMaterial>>,
+}
+
+impl ConvertedMaterialCache {
+    fn remove(&mut self, id: AssetId<StandardMaterial>) {
+        self.pbr.remove(&id);
+        self.unlit.remove(&id);
+        self.extended.remove(&id);
+    }
+}
+
+/// The material assets a `StandardMaterial` can be converted to
+#[derive(SystemParam)]
+struct ConvertedMaterials<'w, 's> {
+    pbr: ResMut<'w, Assets<PbrMaterial>>,
+    unlit: ResMut<'w, Assets<SkUnlitMaterial>>,
+    extended: ResMut<'w, Assets<ShExtendedStandardMaterial>>,
+    gltf: Res<'w, GltfPbrMaterials>,
+    share: Res<'w, ShareConvertedMaterials>,
+    cache: Local<'s, ConvertedMaterialCache>,
+}
+
+/// Whether [`replace_materials`] has anything to do, so a settled scene costs nothing
+fn replace_materials_needed(
+    mode: Res<ReplaceMaterialsMode>,
+    conversion: Res<StandardMaterialConversion>,
+    gltf: Res<GltfPbrMaterials>,
+    share: Res<ShareConvertedMaterials>,
+    mut events: EventReader<AssetEvent<StandardMaterial>>,
+    handles: Query<(), Changed<Handle<StandardMaterial>>>,
+) -> bool {
+    // Counts every event, so the reader is caught up for the next frame
+    events.read().count() > 0
+        || mode.is_changed()
+        || conversion.is_changed()
+        || gltf.is_changed()
+        || share.is_changed()
+        || !handles.is_empty()
+}
+
+pub(crate) fn replace_materials(
     mut commands: Commands,
-    query: Query<(Entity, &Handle<StandardMaterial>)>,
-    mut pbr_material: ResMut<Assets<PbrMaterial>>,
+    mode: Res<ReplaceMaterialsMode>,
+    conversion: Res<StandardMaterialConversion>,
+    query: Query<(Entity, Ref<Handle<StandardMaterial>>)>,
+    converted: Query<(
+        Entity,
+        &ConvertedStandardMaterial,
+        Option<&Handle<PbrMaterial>>,
+        Option<&Handle<SkUnlitMaterial>>,
+        Option<&Handle<ShExtendedStandardMaterial>>,
+    )>,
+    markers: Query<(Has<KeepStandardMaterial>, Has<ReplaceStandardMaterial>)>,
+    parents: Query<&Parent>,
+    mut events: EventReader<AssetEvent<StandardMaterial>>,
+    mut materials: ConvertedMaterials,
     standard_material: Res<Assets<StandardMaterial>>,
+    mut warned: Local<HashSet<AssetId<StandardMaterial>>>,
+    mut replaced: EventWriter<MaterialReplaced>,
 ) {
-    for (e, m) in query.iter() {
-        let m = standard_material.get(m).unwrap();
-        commands.entity(e).insert(pbr_material.add(PbrMaterial {
-            color: m.base_color,
-            emission_factor: Default::default(),
-            metallic: m.metallic,
-            roughness: m.perceptual_roughness,
-            tex_scale: 1.0,
-            alpha_mode:/* m.alpha_mode*/ AlphaMode::Opaque,
-            double_sided: false,
-            spherical_harmonics: DEFAULT_LIGHTING,
-            diffuse_texture: /*m.diffuse_transmission_texture.clone()*/ Default::default(),
-            emission_texture: m.emissive_texture.clone(),
-            metal_texture: m.metallic_roughness_texture.clone(),
-            occlusion_texture: m.occlusion_texture.clone(),
-            color_texture: m.base_color_texture.clone(),
-        }));
-        commands.entity(e).remove::<Handle<StandardMaterial>>();
+    let conversion_changed = conversion.is_changed();
+    let conversion = *conversion;
+    // Extended materials keep every StandardMaterial feature
+    let mut warn_unsupported = |id: AssetId<StandardMaterial>, m: &StandardMaterial| {
+        let fields = unsupported_standard_fields(m);
+        if conversion == StandardMaterialConversion::Replace
+            && !fields.is_empty()
+            && warned.insert(id)
+        {
+            warn!("StandardMaterial {id:?} uses {fields:?}, which PbrMaterial ignores");
+        }
+    };
+    let mut changed = HashSet::new();
+    for event in events.read() {
+        match event {
+            AssetEvent::Added { id }
+            | AssetEvent::Modified { id }
+            | AssetEvent::LoadedWithDependencies { id } => {
+                changed.insert(*id);
+            }
+            AssetEvent::Removed { id } | AssetEvent::Unused { id } => {
+                materials.cache.remove(*id);
+            }
+        }
+    }
+    if materials.share.is_changed() {
+        *materials.cache = default();
+    }
+    if *mode == ReplaceMaterialsMode::Off {
+        return;
+    }
+
+    let share_changed = materials.share.is_changed() && !materials.share.is_added();
+    for (e, source, pbr, unlit, extended) in converted.iter() {
+        if !changed.contains(&source.0.id())
+            && !conversion_changed
+            && !share_changed
+            && !materials.gltf.is_changed()
+        {
+            continue;
+        }
+        let Some(m) = standard_material.get(&source.0) else {
+            continue;
+        };
+        warn_unsupported(source.0.id(), m);
+        let mut report = |new: Option<Handle<PbrMaterial>>| {
+            if let Some(new) = new {
+                replaced.send(MaterialReplaced {
+                    entity: e,
+                    old: source.0.clone(),
+                    new,
+                });
+            }
+        };
+        match (conversion, m.unlit, pbr, unlit, extended) {
+            // Split up or merge the materials of entities converted from the same one
+            _ if share_changed => report(insert_converted(
+                &mut commands.entity(e),
+                source.0.id(),
+                m,
+                conversion,
+                &mut materials,
+            )),
+            (StandardMaterialConversion::Extend, _, _, _, Some(extended)) => {
+                if let Some(material) = materials.extended.get_mut(extended) {
+                    material.base = m.clone();
+                }
+            }
+            (StandardMaterialConversion::Replace, false, Some(pbr), _, _)
+                if !materials.gltf.0.contains_key(&source.0.id()) =>
+            {
+                if let Some(material) = materials.pbr.get_mut(pbr) {
+                    apply_standard_material(material, m);
+                }
+            }
+            // Built from the glTF definition, which the StandardMaterial can't improve on
+            (StandardMaterialConversion::Replace, false, Some(pbr), _, _)
+                if materials.gltf.0.get(&source.0.id()) == Some(pbr) => {}
+            (StandardMaterialConversion::Replace, true, _, Some(unlit), _) => {
+                if let Some(material) = materials.unlit.get_mut(unlit) {
+                    *material = SkUnlitMaterial::from(m);
+                }
+            }
+            // `unlit` or the conversion was changed, swap the material type
+            _ => report(insert_converted(
+                &mut commands.entity(e),
+                source.0.id(),
+                m,
+                conversion,
+                &mut materials,
+            )),
+        }
+    }
+
+    for (e, handle) in query.iter() {
+        // Unchanged entities were either kept or are still waiting for their asset
+        if !handle.is_changed() && !changed.contains(&handle.id()) && !mode.is_changed() {
+            continue;
+        }
+        // The closest marked ancestor decides, unmarked entities follow the mode
+        let replace = std::iter::once(e)
+            .chain(parents.iter_ancestors(e))
+            .find_map(|entity| match markers.get(entity) {
+                Ok((true, _)) => Some(false),
+                Ok((_, true)) => Some(true),
+                _ => None,
+            })
+            .unwrap_or(*mode == ReplaceMaterialsMode::All);
+        if !replace {
+            continue;
+        }
+        // Still loading, its `LoadedWithDependencies` event brings the entity back here
+        let Some(m) = standard_material.get(&*handle) else {
+            continue;
+        };
+        warn_unsupported(handle.id(), m);
+        let mut entity = commands.entity(e);
+        let new = insert_converted(&mut entity, handle.id(), m, conversion, &mut materials);
+        entity
+            .insert(ConvertedStandardMaterial(handle.clone()))
+            .remove::<Handle<StandardMaterial>>();
+        if let Some(new) = new {
+            replaced.send(MaterialReplaced {
+                entity: e,
+                old: handle.clone(),
+                new,
+            });
+        }
     }
 }
 
-#[derive(Asset, AsBindGroup, PartialEq, Debug, Clone, TypePath)]
-/*#[bind_group_data(PbrMaterialKey)]*/
-#[uniform(0, PbrMaterialUniform)]
+/// Seconds a `StandardMaterial` may take to load before [`warn_unloaded_materials`] reports it
+const MATERIAL_LOAD_TIMEOUT: f32 = 10.0;
+
+/// Warns about `StandardMaterial`s that never finish loading, which keeps their entities from
+/// being replaced
+fn warn_unloaded_materials(
+    time: Res<Time>,
+    mode: Res<ReplaceMaterialsMode>,
+    handles: Query<&Handle<StandardMaterial>, Changed<Handle<StandardMaterial>>>,
+    standard_material: Res<Assets<StandardMaterial>>,
+    mut waiting: Local<HashMap<AssetId<StandardMaterial>, f32>>,
+) {
+    if *mode == ReplaceMaterialsMode::Off {
+        waiting.clear();
+        return;
+    }
+    let now = time.elapsed_seconds();
+    for handle in handles.iter() {
+        if !standard_material.contains(handle) {
+            waiting.entry(handle.id()).or_insert(now);
+        }
+    }
+    waiting.retain(|id, since| {
+        if standard_material.contains(*id) {
+            return false;
+        }
+        if now - *since < MATERIAL_LOAD_TIMEOUT {
+            return true;
+        }
+        warn!(
+            "StandardMaterial {id:?} isn't loaded after {MATERIAL_LOAD_TIMEOUT}s, its entities \
+             are replaced once it is"
+        );
+        false
+    });
+}
+
+/// Gives the entity a material made from `m`, a `PbrMaterial` or, for unlit materials,
+/// `SkUnlitMaterial` unless `conversion` extends it. `PbrMaterial`s built from a glTF's
+/// definitions are shared, and so are the others unless [`ShareConvertedMaterials`] is off.
+/// Returns the `PbrMaterial` if it got one.
+fn insert_converted(
+    entity: &mut EntityCommands,
+    id: AssetId<StandardMaterial>,
+    m: &StandardMaterial,
+    conversion: StandardMaterialConversion,
+    materials: &mut ConvertedMaterials,
+) -> Option<Handle<PbrMaterial>> {
+    entity.remove::<(
+        Handle<PbrMaterial>,
+        Handle<SkUnlitMaterial>,
+        Handle<ShExtendedStandardMaterial>,
+    )>();
+    let share = materials.share.0;
+    let cache = &mut *materials.cache;
+    if conversion == StandardMaterialConversion::Extend {
+        let add = || ShExtendedStandardMaterial {
+            base: m.clone(),
+            extension: ShExtension::default(),
+        };
+        entity.insert(shared(share, &mut cache.extended, id, &mut materials.extended, add));
+        return None;
+    }
+    if m.unlit {
+        let add = || SkUnlitMaterial::from(m);
+        entity.insert(shared(share, &mut cache.unlit, id, &mut materials.unlit, add));
+        return None;
+    }
+    let material = match materials.gltf.0.get(&id) {
+        Some(material) => material.clone(),
+        None => {
+            let add = || PbrMaterial::from_standard(m);
+            shared(share, &mut cache.pbr, id, &mut materials.pbr, add)
+        }
+    };
+    entity.insert(material.clone());
+    Some(material)
+}
+
+/// The material cached for `id` while sharing, otherwise or if there's none a new one
+fn shared<M: Asset>(
+    share: bool,
+    cache: &mut HashMap<AssetId<StandardMaterial>, Handle<M>>,
+    id: AssetId<StandardMaterial>,
+    assets: &mut Assets<M>,
+    material: impl FnOnce() -> M,
+) -> Handle<M> {
+    if !share {
+        return assets.add(material());
+    }
+    match cache.get(&id).filter(|handle| assets.contains(*handle)) {
+        Some(handle) => handle.clone(),
+        None => {
+            let handle = assets.add(material());
+            cache.insert(id, handle.clone());
+            handle
+        }
+    }
+}
+
+/// Turns every `PbrMaterial` back into a `StandardMaterial` with
+/// [`PbrMaterial::to_standard_material`], for editor tooling or when dropping the sk shading at
+/// runtime. Not added by [`PbrPlugin`], add it to a schedule and gate it with a run condition.
+///
+/// The entities get a [`KeepStandardMaterial`] so they aren't converted again. Entities that
+/// shared a `PbrMaterial` share the restored `StandardMaterial`.
+pub fn restore_standard_materials(
+    mut commands: Commands,
+    entities: Query<(Entity, &Handle<PbrMaterial>)>,
+    materials: Res<Assets<PbrMaterial>>,
+    mut standard_materials: ResMut<Assets<StandardMaterial>>,
+) {
+    let mut restored = HashMap::new();
+    for (entity, handle) in entities.iter() {
+        let Some(material) = materials.get(handle) else {
+            continue;
+        };
+        let standard = restored
+            .entry(handle.id())
+            .or_insert_with(|| standard_materials.add(material.to_standard_material()))
+            .clone();
+        commands
+            .entity(entity)
+            .remove::<(Handle<PbrMaterial>, ConvertedStandardMaterial)>()
+            .insert((standard, KeepStandardMaterial));
+    }
+}
+
+/// Overwrites the fields of `material` that mirror a `StandardMaterial`, leaving the lighting,
+/// LOD and reflection probe state alone
+fn apply_standard_material(material: &mut PbrMaterial, m: &StandardMaterial) {
+    // Keep the emissive color in 0..1 and move anything brighter into the strength
+    let emission_strength = m.emissive.red.max(m.emissive.green).max(m.emissive.blue).max(1.0);
+    material.color = m.base_color;
+    material.emission_factor = LinearRgba::rgb(
+        m.emissive.red / emission_strength,
+        m.emissive.green / emission_strength,
+        m.emissive.blue / emission_strength,
+    )
+    .into();
+    material.emission_strength = emission_strength;
+    material.metallic = m.metallic;
+    material.roughness = m.perceptual_roughness;
+    material.alpha_mode = m.alpha_mode;
+    material.depth_bias = m.depth_bias;
+    material.double_sided = m.double_sided;
+    material.fog_enabled = m.fog_enabled;
+    // Rotation and shear don't fit in a scale and offset, the diagonal is the closest match
+    let uv = m.uv_transform.matrix2;
+    material.uv_scale = Vec2::new(uv.x_axis.x, uv.y_axis.y);
+    material.uv_offset = m.uv_transform.translation;
+    material.cull_mode = m.cull_mode;
+    // StandardMaterial only refracts with `ior`, a changed one is taken as KHR_materials_ior
+    material.reflectance = if m.ior != 1.5 {
+        PbrMaterial::reflectance_from_ior(m.ior)
+    } else {
+        m.reflectance
+    };
+    material.emission_texture = m.emissive_texture.clone();
+    material.metal_texture = m.metallic_roughness_texture.clone();
+    material.occlusion_texture = m.occlusion_texture.clone();
+    material.color_texture = m.base_color_texture.clone();
+    material.normal_texture = m.normal_map_texture.clone();
+    material.depth_texture = m.depth_map.clone();
+    material.parallax_depth_scale = m.parallax_depth_scale;
+    material.max_parallax_layer_count = m.max_parallax_layer_count;
+    // The clear coat and anisotropy textures only exist with Bevy's
+    // `pbr_multi_layer_material_textures` and `pbr_anisotropy_texture` features
+    material.clearcoat = m.clearcoat;
+    material.clearcoat_roughness = m.clearcoat_perceptual_roughness;
+    material.anisotropy_strength = m.anisotropy_strength;
+    material.anisotropy_rotation = m.anisotropy_rotation;
+}
+
+/// Names of the `StandardMaterial` features in use that have no `PbrMaterial` equivalent
+fn unsupported_standard_fields(m: &StandardMaterial) -> Vec<&'static str> {
+    [
+        ("diffuse_transmission", m.diffuse_transmission > 0.0),
+        ("specular_transmission", m.specular_transmission > 0.0),
+        ("flip_normal_map_y", m.flip_normal_map_y),
+        (
+            "uv_transform rotation",
+            m.uv_transform.matrix2.x_axis.y != 0.0 || m.uv_transform.matrix2.y_axis.x != 0.0,
+        ),
+    ]
+    .into_iter()
+    .filter_map(|(name, used)| used.then_some(name))
+    .collect()
+}
+
+/// Marks a `NotShadowCaster` that was added because of `PbrMaterial::cast_shadows`, so one
+/// added by hand is never removed
+#[derive(Component)]
+struct MaterialNotShadowCaster;
+
+fn apply_material_shadow_casting(
+    mut commands: Commands,
+    mut events: EventReader<AssetEvent<PbrMaterial>>,
+    materials: Res<Assets<PbrMaterial>>,
+    entities: Query<(
+        Entity,
+        &Handle<PbrMaterial>,
+        Has<NotShadowCaster>,
+        Has<MaterialNotShadowCaster>,
+    )>,
+    changed: Query<Entity, Changed<Handle<PbrMaterial>>>,
+) {
+    // `Assets::is_changed` is set by any mutable access, the events only name the materials
+    // that actually changed
+    let modified: HashSet<_> = events
+        .read()
+        .filter_map(|event| match event {
+            AssetEvent::Added { id } | AssetEvent::Modified { id } => Some(*id),
+            _ => None,
+        })
+        .collect();
+    type Item<'a> = (Entity, &'a Handle<PbrMaterial>, bool, bool);
+    let mut apply = |(entity, handle, not_caster, from_material): Item| {
+        let casts = materials.get(handle).map_or(true, |m| m.cast_shadows);
+        if !casts && !not_caster {
+            commands
+                .entity(entity)
+                .insert((NotShadowCaster, MaterialNotShadowCaster));
+        } else if casts && from_material {
+            commands
+                .entity(entity)
+                .remove::<(NotShadowCaster, MaterialNotShadowCaster)>();
+        }
+    };
+    if modified.is_empty() {
+        entities.iter_many(changed.iter()).for_each(apply);
+    } else {
+        entities
+            .iter()
+            .filter(|(entity, handle, ..)| {
+                modified.contains(&handle.id()) || changed.contains(*entity)
+            })
+            .for_each(apply);
+    }
+}
+
+/// Pushes the [`SkQuality`] LOD distance and features into every material that follows the
+/// quality preset
+fn apply_material_quality(
+    quality: Res<SkQuality>,
+    mut events: EventReader<AssetEvent<PbrMaterial>>,
+    mut materials: ResMut<Assets<PbrMaterial>>,
+) {
+    let added = events
+        .read()
+        .any(|e| matches!(e, AssetEvent::Added { .. }));
+    if !quality.is_changed() && !added {
+        return;
+    }
+
+    let settings = quality.settings();
+    let distance = settings.material_lod_distance;
+    let features = settings.material_features();
+    let outdated: Vec<_> = materials
+        .iter()
+        .filter(|(_, m)| {
+            m.lod_from_quality && (m.lod_distance != distance || m.quality_features != features)
+        })
+        .map(|(id, _)| id)
+        .collect();
+    for id in outdated {
+        let material = materials.get_mut(id).unwrap();
+        material.lod_distance = distance;
+        material.quality_features = features;
+    }
+}
+
+#[derive(Asset, Reflect, PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "inspector", derive(InspectorOptions), reflect(InspectorOptions))]
 pub struct PbrMaterial {
     pub color: Color,
     pub emission_factor: Color,
+    /// Multiplier for `emission_factor`, values above 1.0 produce HDR output that feeds bloom
+    #[cfg_attr(feature = "inspector", inspector(min = 0.0))]
+    pub emission_strength: f32,
+    #[cfg_attr(feature = "inspector", inspector(min = 0.0, max = 1.0))]
     pub metallic: f32,
+    #[cfg_attr(feature = "inspector", inspector(min = 0.0, max = 1.0))]
     pub roughness: f32,
-    pub tex_scale: f32,
+    /// Texture coordinates are `uv * uv_scale + uv_offset`
+    pub uv_scale: Vec2,
+    pub uv_offset: Vec2,
     pub alpha_mode: AlphaMode,
+    /// Cuts the material out in a per pixel noise pattern with the alpha as coverage instead
+    /// of blending it, which needs no sorting and looks the same in both eyes. Overrides
+    /// `alpha_mode`.
+    pub dithered: bool,
+    /// Moves the material towards the camera when sorting transparent meshes and in the depth
+    /// test, like `StandardMaterial::depth_bias`
+    pub depth_bias: f32,
     pub double_sided: bool,
-    pub spherical_harmonics: SphericalHarmonics,
+    /// Whether the camera's `FogSettings` cover this material
+    pub fog_enabled: bool,
+    /// Whether meshes using this material are drawn into shadow maps, clear it instead of
+    /// adding `NotShadowCaster` to every mesh
+    pub cast_shadows: bool,
+    /// Faces culled when not `double_sided`, like `StandardMaterial::cull_mode`. Double sided
+    /// blended materials culling back faces draw them in a `BackFacePass` first, set `None`
+    /// to draw both sides in one pass.
+    #[reflect(ignore)]
+    pub cull_mode: Option<Face>,
+    /// Specular reflectance of dielectrics, 0.5 is the usual 4% at normal incidence, see
+    /// [`PbrMaterial::reflectance_from_ior`]
+    #[cfg_attr(feature = "inspector", inspector(min = 0.0, max = 1.0))]
+    pub reflectance: f32,
+    /// Scales the dielectric specular, like `KHR_materials_specular`'s `specularFactor`
+    #[cfg_attr(feature = "inspector", inspector(min = 0.0, max = 1.0))]
+    pub specular_factor: f32,
+    /// Colors the dielectric specular at normal incidence, like `KHR_materials_specular`'s
+    /// `specularColorFactor`
+    pub specular_tint: Color,
+    /// Widens roughness where the normal varies quickly on screen to reduce specular shimmer
+    pub specular_antialiasing: bool,
+    /// Strength of the thin-film iridescence layer, 0 disables it
+    #[cfg_attr(feature = "inspector", inspector(min = 0.0, max = 1.0))]
+    pub iridescence: f32,
+    /// Index of refraction of the thin film
+    pub iridescence_ior: f32,
+    /// Film thickness in nanometers
+    pub iridescence_thickness: f32,
+    /// Added towards the silhouette, e.g. to highlight a hovered object, black disables it
+    pub rim_color: Color,
+    /// Higher values keep the rim closer to the silhouette
+    pub rim_power: f32,
+    /// Distance from the camera beyond which only SH diffuse is evaluated, 0 disables it
+    pub lod_distance: f32,
+    /// Keeps `lod_distance` and `quality_features` in sync with [`SkQuality`], clear this to
+    /// set them by hand
+    pub lod_from_quality: bool,
+    /// Optional features this material may use, the ones turned off are skipped even when
+    /// their textures or factors are set
+    pub quality_features: SkMaterialFeatures,
+    /// Only SH diffuse towards the edges of the view, kept in sync with
+    /// [`XrUsefulSetup::foveation`](crate::XrUsefulSetup::foveation)
+    pub foveation: Option<XrFoveation>,
+    /// Slot of the shared `ShLightingBuffer` this material is lit by
+    pub lighting: ShSlot,
+    /// The shared SH texture, leave this at its default
+    pub sh_buffer: Handle<Image>,
 
-    #[texture(1)]
-    #[sampler(2)]
     pub diffuse_texture: Option<Handle<Image>>,
-    #[texture(3)]
-    #[sampler(4)]
     pub emission_texture: Option<Handle<Image>>,
-    #[texture(5)]
-    #[sampler(6)]
     pub metal_texture: Option<Handle<Image>>,
-    #[texture(7)]
-    #[sampler(8)]
     pub occlusion_texture: Option<Handle<Image>>,
-    #[texture(9)]
-    #[sampler(10)]
     pub color_texture: Option<Handle<Image>>,
+    /// Tangent space normal map, needs meshes with tangents
+    pub normal_texture: Option<Handle<Image>>,
+    /// Strength of the normal map's XY perturbation, like glTF's `normalTexture.scale`
+    pub normal_scale: f32,
+    /// Height map for parallax occlusion mapping, 1 is deepest, needs meshes with tangents
+    pub depth_texture: Option<Handle<Image>>,
+    /// Depth of the `depth_texture` relief in UV units, like `StandardMaterial`
+    pub parallax_depth_scale: f32,
+    /// Layers stepped through at grazing angles, fewer are used looking straight on
+    pub max_parallax_layer_count: f32,
+    /// Strength of a varnish layer over the material, like `KHR_materials_clearcoat`
+    #[cfg_attr(feature = "inspector", inspector(min = 0.0, max = 1.0))]
+    pub clearcoat: f32,
+    /// Perceptual roughness of the clear coat
+    #[cfg_attr(feature = "inspector", inspector(min = 0.0, max = 1.0))]
+    pub clearcoat_roughness: f32,
+    /// Multiplies `clearcoat` by its red channel
+    pub clearcoat_texture: Option<Handle<Image>>,
+    /// Multiplies `clearcoat_roughness` by its green channel
+    pub clearcoat_roughness_texture: Option<Handle<Image>>,
+    /// How far highlights stretch along the tangent, for brushed metal and hair, needs meshes
+    /// with tangents
+    #[cfg_attr(feature = "inspector", inspector(min = 0.0, max = 1.0))]
+    pub anisotropy_strength: f32,
+    /// Counterclockwise rotation of the stretch direction from the tangent, in radians
+    pub anisotropy_rotation: f32,
+    /// Direction in red and green, rotated by `anisotropy_rotation`, and strength in blue,
+    /// like `KHR_materials_anisotropy`
+    pub anisotropy_texture: Option<Handle<Image>>,
+    /// Albedo tiled over the color texture for close-ups, 0.5 gray leaves the color unchanged
+    pub detail_color_texture: Option<Handle<Image>>,
+    /// Tangent space normal map tiled over `normal_texture`, needs meshes with tangents
+    pub detail_normal_texture: Option<Handle<Image>>,
+    /// Tiling of the detail textures relative to the UVs of the other textures
+    pub detail_uv_scale: Vec2,
+    /// Strength of the detail textures, 0 hides them
+    #[cfg_attr(feature = "inspector", inspector(min = 0.0, max = 1.0))]
+    pub detail_blend: f32,
+    /// Texture array the color texture was packed into, see
+    /// [`TextureArrayPackingPlugin`](crate::materials::texture_arrays::TextureArrayPackingPlugin)
+    pub color_array: Option<Handle<Image>>,
+    /// Texture array the metal and occlusion textures were packed into
+    pub data_array: Option<Handle<Image>>,
+    /// The shared [`LightCookie`](crate::lighting::cookie::LightCookie) textures, leave this at
+    /// its default
+    pub light_cookies: Handle<Image>,
+    /// Projections of the shared cookie textures, leave this at its default
+    pub light_cookie_data: Handle<Image>,
+    /// Layers of `color_array` and `data_array` sampled in place of the plain textures, which
+    /// take precedence when set
+    pub array_layers: TextureArrayLayers,
+
+    /// Cube texture reflected in place of the SH, usually set by `ReflectionProbePlugin`
+    pub reflection_probe_a: Option<Handle<Image>>,
+    /// Cube texture blended over `reflection_probe_a` by `reflection_blend`
+    pub reflection_probe_b: Option<Handle<Image>>,
+    #[cfg_attr(feature = "inspector", inspector(min = 0.0, max = 1.0))]
+    pub reflection_blend: f32,
 }
 
 #[derive(Clone, Default, ShaderType)]
 pub struct PbrMaterialUniform {
-    pub color: Vec4,
-    pub emission_factor: Vec4,
-    pub metallic: f32,
-    pub roughness: f32,
-    pub tex_scale: f32,
+    pub color: UnormColor,
+    pub emission_factor: HdrColor,
+    /// x: metallic, y: roughness
+    pub metallic_roughness: Unorm2,
+    pub uv_scale: Vec2,
+    pub uv_offset: Vec2,
     pub flags: u32,
-    pub spherical_harmonics: [Vec3; 9],
+    pub sh_slot: u32,
+    pub iridescence: f32,
+    pub iridescence_ior: f32,
+    pub iridescence_thickness: f32,
+    pub lod_distance: f32,
+    pub reflection_blend: f32,
+    pub normal_scale: f32,
+    pub alpha_cutoff: f32,
+    pub reflectance: f32,
+    pub specular: HdrColor,
+    pub parallax_depth_scale: f32,
+    pub max_parallax_layer_count: f32,
+    pub clearcoat: f32,
+    pub clearcoat_roughness: f32,
+    pub anisotropy_strength: f32,
+    pub anisotropy_rotation: f32,
+    pub detail_uv_scale: Vec2,
+    pub detail_blend: f32,
+    pub rim_color: HdrColor,
+    pub rim_power: f32,
+    /// Color, metal and occlusion layer of the texture arrays in bytes 0 to 2, 255 for none
+    pub array_layers: u32,
+}
+
+impl PbrMaterial {
+    /// The `reflectance` of a dielectric with this index of refraction, like
+    /// `KHR_materials_ior`
+    pub fn reflectance_from_ior(ior: f32) -> f32 {
+        // F0 = ((ior - 1) / (ior + 1))^2 = 0.16 * reflectance^2
+        ((ior - 1.0) / (ior + 1.0)).abs() / 0.4
+    }
+
+    /// The `PbrMaterial` [`PbrPlugin`] replaces `m` with, for custom replacement policies or
+    /// materials spawned by hand. Features `PbrMaterial` has no equivalent for are dropped.
+    pub fn from_standard(m: &StandardMaterial) -> Self {
+        let mut material = PbrMaterial::default();
+        apply_standard_material(&mut material, m);
+        material
+    }
+
+    /// The closest `StandardMaterial`, for tools that only understand Bevy's materials. The sk
+    /// only features like iridescence, detail textures, rim lighting and the SH lighting slot
+    /// are dropped, dithering becomes an alpha mask.
+    pub fn to_standard_material(&self) -> StandardMaterial {
+        StandardMaterial {
+            base_color: self.color,
+            base_color_texture: self.color_texture.clone(),
+            emissive: self.emission_factor.to_linear() * self.emission_strength,
+            emissive_texture: self.emission_texture.clone(),
+            perceptual_roughness: self.roughness,
+            metallic: self.metallic,
+            metallic_roughness_texture: self.metal_texture.clone(),
+            reflectance: self.reflectance,
+            normal_map_texture: self.normal_texture.clone(),
+            occlusion_texture: self.occlusion_texture.clone(),
+            depth_map: self.depth_texture.clone(),
+            parallax_depth_scale: self.parallax_depth_scale,
+            max_parallax_layer_count: self.max_parallax_layer_count,
+            clearcoat: self.clearcoat,
+            clearcoat_perceptual_roughness: self.clearcoat_roughness,
+            anisotropy_strength: self.anisotropy_strength,
+            anisotropy_rotation: self.anisotropy_rotation,
+            double_sided: self.double_sided,
+            cull_mode: if self.double_sided {
+                None
+            } else {
+                self.cull_mode
+            },
+            alpha_mode: if self.dithered {
+                AlphaMode::Mask(0.5)
+            } else {
+                self.alpha_mode
+            },
+            depth_bias: self.depth_bias,
+            fog_enabled: self.fog_enabled,
+            uv_transform: Affine2::from_mat2_translation(
+                Mat2::from_diagonal(self.uv_scale),
+                self.uv_offset,
+            ),
+            ..default()
+        }
+    }
+
+    /// Whether the normal, parallax, detail normal or anisotropy shading is used, which needs
+    /// the mesh to have tangents
+    pub fn needs_tangents(&self) -> bool {
+        let features = self.quality_features;
+        (features.normal_maps && self.normal_texture.is_some())
+            || (features.parallax && self.depth_texture.is_some())
+            || (features.normal_maps
+                && features.detail_textures
+                && self.detail_normal_texture.is_some())
+            || self.anisotropy_strength > 0.0
+    }
+
+    /// Every optional texture slot, set or not, in the order of [`TEXTURE_SHADER_DEFS`]
+    fn optional_textures(&self) -> [&Option<Handle<Image>>; 16] {
+        [
+            &self.diffuse_texture,
+            &self.emission_texture,
+            &self.metal_texture,
+            &self.occlusion_texture,
+            &self.color_texture,
+            &self.normal_texture,
+            &self.depth_texture,
+            &self.clearcoat_texture,
+            &self.clearcoat_roughness_texture,
+            &self.anisotropy_texture,
+            &self.detail_color_texture,
+            &self.detail_normal_texture,
+            &self.reflection_probe_a,
+            &self.reflection_probe_b,
+            &self.color_array,
+            &self.data_array,
+        ]
+    }
+
+    /// All texture slots that are set on this material
+    pub fn textures(&self) -> impl Iterator<Item = &Handle<Image>> {
+        [
+            &self.diffuse_texture,
+            &self.emission_texture,
+            &self.metal_texture,
+            &self.occlusion_texture,
+            &self.color_texture,
+            &self.normal_texture,
+            &self.depth_texture,
+            &self.clearcoat_texture,
+            &self.clearcoat_roughness_texture,
+            &self.anisotropy_texture,
+            &self.detail_color_texture,
+            &self.detail_normal_texture,
+            &self.color_array,
+            &self.data_array,
+        ]
+        .into_iter()
+        .flatten()
+    }
 }
 
 impl AsBindGroupShaderType<PbrMaterialUniform> for PbrMaterial {
     fn as_bind_group_shader_type(&self, images: &RenderAssets<GpuImage>) -> PbrMaterialUniform {
         let mut flags = PbrMaterialFlags::empty();
+        let textures = quality_textures(self.quality_features);
+        let sampled = |texture: &Option<Handle<Image>>, bit: usize| {
+            texture.is_some() && textures & (1 << bit) != 0
+        };
 
         if self.diffuse_texture.is_some() {
             flags |= PbrMaterialFlags::DIFFUSE_TEXTURE;
@@ -110,64 +992,537 @@ impl AsBindGroupShaderType<PbrMaterialUniform> for PbrMaterial {
         if self.double_sided {
             flags |= PbrMaterialFlags::DOUBLE_SIDED;
         }
-
-        match self.alpha_mode {
-            AlphaMode::Opaque => flags |= PbrMaterialFlags::ALPHA_MODE_OPAQUE,
-            AlphaMode::Mask(_) => flags |= PbrMaterialFlags::ALPHA_MODE_MASK,
-            _ => {}
+        if self.specular_antialiasing {
+            flags |= PbrMaterialFlags::SPECULAR_AA;
+        }
+        if self.iridescence > 0.0 {
+            flags |= PbrMaterialFlags::IRIDESCENCE;
+        }
+        if self.reflection_probe_a.is_some() {
+            flags |= PbrMaterialFlags::REFLECTION_PROBE;
+        }
+        if self.reflection_probe_b.is_some() {
+            flags |= PbrMaterialFlags::REFLECTION_PROBE_BLEND;
+        }
+        if sampled(&self.normal_texture, 5) {
+            flags |= PbrMaterialFlags::NORMAL_TEXTURE;
+        }
+        if sampled(&self.depth_texture, 6) {
+            flags |= PbrMaterialFlags::DEPTH_TEXTURE;
+        }
+        if self.fog_enabled {
+            flags |= PbrMaterialFlags::FOG_ENABLED;
+        }
+        if self.clearcoat > 0.0 && self.quality_features.clearcoat {
+            flags |= PbrMaterialFlags::CLEARCOAT;
+        }
+        if sampled(&self.clearcoat_texture, 7) {
+            flags |= PbrMaterialFlags::CLEARCOAT_TEXTURE;
+        }
+        if sampled(&self.clearcoat_roughness_texture, 8) {
+            flags |= PbrMaterialFlags::CLEARCOAT_ROUGHNESS_TEXTURE;
+        }
+        if self.anisotropy_strength > 0.0 {
+            flags |= PbrMaterialFlags::ANISOTROPY;
+        }
+        if self.anisotropy_texture.is_some() {
+            flags |= PbrMaterialFlags::ANISOTROPY_TEXTURE;
+        }
+        if sampled(&self.detail_color_texture, 10) {
+            flags |= PbrMaterialFlags::DETAIL_COLOR_TEXTURE;
+        }
+        if sampled(&self.detail_normal_texture, 11) {
+            flags |= PbrMaterialFlags::DETAIL_NORMAL_TEXTURE;
+        }
+        let rim_color = self.rim_color.to_linear().with_alpha(1.0);
+        if rim_color.red > 0.0 || rim_color.green > 0.0 || rim_color.blue > 0.0 {
+            flags |= PbrMaterialFlags::RIM;
         }
 
+        let (alpha_flags, alpha_cutoff) = if self.dithered {
+            (PbrMaterialFlags::DITHERED | PbrMaterialFlags::ALPHA_MODE_OPAQUE, 0.5)
+        } else {
+            alpha_mode_flags(self.alpha_mode)
+        };
+        flags |= alpha_flags;
+
         PbrMaterialUniform {
-            color: self.color.to_linear().to_f32_array().into(),
-            emission_factor: self.emission_factor.to_linear().to_f32_array().into(),
-            metallic: self.metallic,
-            roughness: self.roughness,
-            tex_scale: self.tex_scale,
+            color: packing::unorm_color(self.color.to_linear().to_f32_array().into()),
+            emission_factor: packing::hdr_color(
+                (self.emission_factor.to_linear() * self.emission_strength)
+                    .with_alpha(1.0)
+                    .to_f32_array()
+                    .into(),
+            ),
+            metallic_roughness: packing::unorm2(Vec2::new(self.metallic, self.roughness)),
+            uv_scale: self.uv_scale,
+            uv_offset: self.uv_offset,
             flags: flags.bits(),
-            spherical_harmonics: self.spherical_harmonics.coefficients,
+            sh_slot: self.lighting.0,
+            iridescence: self.iridescence,
+            iridescence_ior: self.iridescence_ior,
+            iridescence_thickness: self.iridescence_thickness,
+            lod_distance: self.lod_distance,
+            reflection_blend: self.reflection_blend,
+            normal_scale: self.normal_scale,
+            alpha_cutoff,
+            reflectance: self.reflectance,
+            specular: packing::hdr_color(
+                self.specular_tint
+                    .to_linear()
+                    .with_alpha(self.specular_factor)
+                    .to_f32_array()
+                    .into(),
+            ),
+            parallax_depth_scale: self.parallax_depth_scale,
+            max_parallax_layer_count: self.max_parallax_layer_count,
+            clearcoat: self.clearcoat,
+            clearcoat_roughness: self.clearcoat_roughness,
+            anisotropy_strength: self.anisotropy_strength,
+            anisotropy_rotation: self.anisotropy_rotation,
+            detail_uv_scale: self.detail_uv_scale,
+            detail_blend: self.detail_blend,
+            rim_color: packing::hdr_color(rim_color.to_f32_array().into()),
+            rim_power: self.rim_power,
+            array_layers: self.array_layers.packed(),
+        }
+    }
+}
+
+/// Flags selecting `alpha_mode` in `sk_alpha_output` and the mask cutoff, 0.5 unless masked
+pub(crate) fn alpha_mode_flags(alpha_mode: AlphaMode) -> (PbrMaterialFlags, f32) {
+    match alpha_mode {
+        AlphaMode::Opaque => (PbrMaterialFlags::ALPHA_MODE_OPAQUE, 0.5),
+        AlphaMode::Mask(cutoff) => (PbrMaterialFlags::ALPHA_MODE_MASK, cutoff),
+        AlphaMode::Blend => (PbrMaterialFlags::ALPHA_MODE_BLEND, 0.5),
+        AlphaMode::Premultiplied => (PbrMaterialFlags::ALPHA_MODE_PREMULTIPLIED, 0.5),
+        AlphaMode::Add => (PbrMaterialFlags::ALPHA_MODE_ADD, 0.5),
+        AlphaMode::Multiply => (PbrMaterialFlags::ALPHA_MODE_MULTIPLY, 0.5),
+        AlphaMode::AlphaToCoverage => (PbrMaterialFlags::empty(), 0.5),
+    }
+}
+
+/// Shader def of each optional `PbrMaterial` texture, in [`PbrMaterial::optional_textures`]
+/// order
+const TEXTURE_SHADER_DEFS: [&str; 16] = [
+    "SK_DIFFUSE_TEXTURE",
+    "SK_EMISSION_TEXTURE",
+    "SK_METAL_TEXTURE",
+    "SK_OCCLUSION_TEXTURE",
+    "SK_COLOR_TEXTURE",
+    "SK_NORMAL_TEXTURE",
+    "SK_DEPTH_TEXTURE",
+    "SK_CLEARCOAT_TEXTURE",
+    "SK_CLEARCOAT_ROUGHNESS_TEXTURE",
+    "SK_ANISOTROPY_TEXTURE",
+    "SK_DETAIL_COLOR_TEXTURE",
+    "SK_DETAIL_NORMAL_TEXTURE",
+    "SK_REFLECTION_PROBE_A",
+    "SK_REFLECTION_PROBE_B",
+    "SK_COLOR_ARRAY",
+    "SK_DATA_ARRAY",
+];
+
+/// The `TEXTURE_SHADER_DEFS` bits of the textures bound with the `webgl2` feature, emission,
+/// metal, occlusion, color, normal and the first reflection probe. WebGL2 allows 16 textures
+/// per shader stage, Bevy's view and mesh bindings leave room for these and the SH texture,
+/// see `SkCompatibility`.
+const WEBGL2_TEXTURES: u16 = 0b01_0000_0011_1110;
+
+/// The `TEXTURE_SHADER_DEFS` bits of every texture a `PbrMaterial` can bind
+const ALL_TEXTURES: u16 = if cfg!(feature = "webgl2") {
+    WEBGL2_TEXTURES
+} else {
+    u16::MAX
+};
+
+/// Texture binding, sampler binding and view dimension of each optional texture, in
+/// [`TEXTURE_SHADER_DEFS`] order
+const TEXTURE_BINDINGS: [(u32, u32, TextureViewDimension); 16] = [
+    (1, 2, TextureViewDimension::D2),
+    (3, 4, TextureViewDimension::D2),
+    (5, 6, TextureViewDimension::D2),
+    (7, 8, TextureViewDimension::D2),
+    (9, 10, TextureViewDimension::D2),
+    (16, 17, TextureViewDimension::D2),
+    (18, 19, TextureViewDimension::D2),
+    (20, 21, TextureViewDimension::D2),
+    (22, 23, TextureViewDimension::D2),
+    (24, 25, TextureViewDimension::D2),
+    (26, 27, TextureViewDimension::D2),
+    (28, 29, TextureViewDimension::D2),
+    (12, 13, TextureViewDimension::Cube),
+    (14, 15, TextureViewDimension::Cube),
+    (30, 31, TextureViewDimension::D2Array),
+    (32, 33, TextureViewDimension::D2Array),
+];
+
+/// Bind group layouts of `PbrMaterial`s by the `textures` of their key, shared by their bind
+/// groups and pipelines. `Material::specialize` has no `RenderDevice` to create them, so they
+/// are created when the first material with those textures is prepared.
+static MATERIAL_LAYOUTS: OnceLock<Mutex<HashMap<u16, BindGroupLayout>>> = OnceLock::new();
+
+fn material_layout(render_device: &RenderDevice, textures: u16) -> BindGroupLayout {
+    let mut layouts = MATERIAL_LAYOUTS.get_or_init(default).lock().unwrap();
+    layouts
+        .entry(textures)
+        .or_insert_with(|| {
+            render_device
+                .create_bind_group_layout("pbr_material_layout", &layout_entries(textures))
+        })
+        .clone()
+}
+
+/// The uniform, the shared SH and cookie textures and the optional textures in `textures`
+fn layout_entries(textures: u16) -> Vec<BindGroupLayoutEntry> {
+    let entry = |binding, ty| BindGroupLayoutEntry {
+        binding,
+        visibility: ShaderStages::all(),
+        ty,
+        count: None,
+    };
+    let texture = |binding, view_dimension, filterable| {
+        let sample_type = TextureSampleType::Float { filterable };
+        entry(
+            binding,
+            BindingType::Texture {
+                sample_type,
+                view_dimension,
+                multisampled: false,
+            },
+        )
+    };
+    let sampler = |binding| entry(binding, BindingType::Sampler(SamplerBindingType::Filtering));
+
+    let uniform = BindingType::Buffer {
+        ty: BufferBindingType::Uniform,
+        has_dynamic_offset: false,
+        min_binding_size: Some(PbrMaterialUniform::min_size()),
+    };
+    let mut entries = vec![entry(0, uniform), texture(11, TextureViewDimension::D2, false)];
+    if !cfg!(feature = "webgl2") {
+        entries.extend([
+            texture(34, TextureViewDimension::D2Array, true),
+            sampler(35),
+            texture(36, TextureViewDimension::D2, false),
+        ]);
+    }
+    for (i, &(texture_binding, sampler_binding, dimension)) in TEXTURE_BINDINGS.iter().enumerate()
+    {
+        if textures & (1 << i) != 0 {
+            entries.extend([texture(texture_binding, dimension, true), sampler(sampler_binding)]);
+        }
+    }
+    entries
+}
+
+impl AsBindGroup for PbrMaterial {
+    type Data = PbrMaterialKey;
+    type Param = (
+        SRes<RenderAssets<GpuImage>>,
+        SRes<FallbackImage>,
+        SRes<TextureSamplers>,
+        SRes<AnisotropicSamplers>,
+    );
+
+    /// Binds only the textures of the material's key, in the layout its pipelines get
+    fn as_bind_group(
+        &self,
+        layout: &BindGroupLayout,
+        render_device: &RenderDevice,
+        param: &mut SystemParamItem<'_, '_, Self::Param>,
+    ) -> Result<PreparedBindGroup<Self::Data>, AsBindGroupError> {
+        let UnpreparedBindGroup { bindings, data } =
+            self.unprepared_bind_group(layout, render_device, param)?;
+        let entries = layout_entries(data.textures);
+        let bindings: Vec<_> = bindings
+            .into_iter()
+            .filter(|(binding, _)| entries.iter().any(|entry| entry.binding == *binding))
+            .collect();
+        let bind_group = render_device.create_bind_group(
+            "pbr_material_bind_group",
+            &material_layout(render_device, data.textures),
+            &bindings
+                .iter()
+                .map(|(binding, resource)| BindGroupEntry {
+                    binding: *binding,
+                    resource: resource.get_binding(),
+                })
+                .collect::<Vec<_>>(),
+        );
+        Ok(PreparedBindGroup {
+            bindings,
+            bind_group,
+            data,
+        })
+    }
+
+    /// Every binding of [`ALL_TEXTURES`], the ones left out of the key bound to Bevy's
+    /// fallback image. `ExtendedSkMaterial` binds these in its combined layout.
+    fn unprepared_bind_group(
+        &self,
+        _layout: &BindGroupLayout,
+        render_device: &RenderDevice,
+        (images, fallback_image, samplers, anisotropic): &mut SystemParamItem<'_, '_, Self::Param>,
+    ) -> Result<UnpreparedBindGroup<Self::Data>, AsBindGroupError> {
+        let images: &RenderAssets<GpuImage> = images;
+        let key = PbrMaterialKey::from(self);
+        let gpu_image = |handle: &Handle<Image>| {
+            images.get(handle).ok_or(AsBindGroupError::RetryNextUpdate)
+        };
+
+        let mut uniform = UniformBuffer::new(Vec::new());
+        uniform.write(&self.as_bind_group_shader_type(images)).unwrap();
+        let buffer = render_device.create_buffer_with_data(&BufferInitDescriptor {
+            label: Some("pbr_material_uniform"),
+            contents: uniform.as_ref(),
+            usage: BufferUsages::COPY_DST | BufferUsages::UNIFORM,
+        });
+        let sh_buffer = gpu_image(&self.sh_buffer)?;
+        let mut bindings = vec![
+            (0, OwnedBindingResource::Buffer(buffer)),
+            (11, OwnedBindingResource::TextureView(sh_buffer.texture_view.clone())),
+        ];
+        if !cfg!(feature = "webgl2") {
+            let cookies = gpu_image(&self.light_cookies)?;
+            let cookie_data = gpu_image(&self.light_cookie_data)?;
+            bindings.extend([
+                (34, OwnedBindingResource::TextureView(cookies.texture_view.clone())),
+                (35, OwnedBindingResource::Sampler(cookies.sampler.clone())),
+                (36, OwnedBindingResource::TextureView(cookie_data.texture_view.clone())),
+            ]);
+        }
+
+        // Reflection probes have no entry in `TextureSamplers` and keep their sampler
+        let anisotropy = self.quality_features.anisotropy;
+        let optional = TEXTURE_BINDINGS.iter().zip(self.optional_textures());
+        for (i, (&(texture_binding, sampler_binding, dimension), texture)) in optional.enumerate() {
+            if ALL_TEXTURES & (1 << i) == 0 {
+                continue;
+            }
+            let (image, sampler) = match texture {
+                Some(texture) if key.textures & (1 << i) != 0 => {
+                    let image = gpu_image(texture)?;
+                    let sampler = if anisotropy > 1 {
+                        anisotropic.get(render_device, samplers, texture.id(), anisotropy)
+                    } else {
+                        None
+                    };
+                    (image, sampler.unwrap_or_else(|| image.sampler.clone()))
+                }
+                _ => {
+                    let image = match dimension {
+                        TextureViewDimension::Cube => &fallback_image.cube,
+                        TextureViewDimension::D2Array => &fallback_image.d2_array,
+                        _ => &fallback_image.d2,
+                    };
+                    (image, image.sampler.clone())
+                }
+            };
+            bindings.extend([
+                (texture_binding, OwnedBindingResource::TextureView(image.texture_view.clone())),
+                (sampler_binding, OwnedBindingResource::Sampler(sampler)),
+            ]);
         }
+        Ok(UnpreparedBindGroup {
+            bindings,
+            data: key,
+        })
+    }
+
+    fn bind_group_layout(render_device: &RenderDevice) -> BindGroupLayout
+    where
+        Self: Sized,
+    {
+        material_layout(render_device, ALL_TEXTURES)
+    }
+
+    fn bind_group_layout_entries(_render_device: &RenderDevice) -> Vec<BindGroupLayoutEntry>
+    where
+        Self: Sized,
+    {
+        layout_entries(ALL_TEXTURES)
+    }
+}
+
+/// The `TEXTURE_SHADER_DEFS` bits of the textures `features` leaves on
+fn quality_textures(features: SkMaterialFeatures) -> u16 {
+    let mut textures = u16::MAX;
+    if !features.normal_maps {
+        textures &= !(1 << 5 | 1 << 11);
+    }
+    if !features.parallax {
+        textures &= !(1 << 6);
+    }
+    if !features.clearcoat {
+        textures &= !(1 << 7 | 1 << 8);
     }
+    if !features.detail_textures {
+        textures &= !(1 << 10 | 1 << 11);
+    }
+    textures
 }
 
+/// Pipeline specialization of a `PbrMaterial`, double sided materials skip face culling
+/// unless they blend, then `cull_mode` picks the faces of their two passes.
+///
+/// Each combination of set textures gets its own shader and bind group layout, which only
+/// declare and bind those textures, so there are no per texture branches and no unused
+/// texture units counting against the per stage limits.
 #[derive(Clone, PartialEq, Eq, Hash)]
 pub struct PbrMaterialKey {
     cull_mode: Option<Face>,
-    alpha_mode: bool,
+    /// Bit `i` set for the texture of `TEXTURE_SHADER_DEFS[i]`
+    textures: u16,
+    foveation: Option<XrFoveation>,
+    /// `SkMaterialFeatures::sh_order`, from 1 to 3
+    sh_order: u8,
 }
 
 impl From<&PbrMaterial> for PbrMaterialKey {
     fn from(material: &PbrMaterial) -> Self {
+        let blended = !material.dithered
+            && matches!(material.alpha_mode, AlphaMode::Blend | AlphaMode::Premultiplied);
+        let textures = material
+            .optional_textures()
+            .iter()
+            .enumerate()
+            .filter(|(_, texture)| texture.is_some())
+            .fold(0, |bits, (i, _)| bits | 1 << i)
+            & quality_textures(material.quality_features);
+        let textures = if cfg!(feature = "webgl2") {
+            textures & WEBGL2_TEXTURES
+        } else {
+            textures
+        };
         PbrMaterialKey {
-            cull_mode: if material.double_sided {
+            cull_mode: if material.double_sided && !blended {
                 None
             } else {
-                Some(Face::Back)
+                material.cull_mode
             },
-            alpha_mode: material.alpha_mode == AlphaMode::Blend,
+            textures,
+            foveation: material.foveation,
+            sh_order: material.quality_features.sh_order.clamp(1, 3),
         }
     }
 }
 
+impl From<&StandardMaterial> for PbrMaterial {
+    fn from(m: &StandardMaterial) -> Self {
+        PbrMaterial::from_standard(m)
+    }
+}
+
+impl From<&PbrMaterial> for StandardMaterial {
+    fn from(material: &PbrMaterial) -> Self {
+        material.to_standard_material()
+    }
+}
+
 impl Material for PbrMaterial {
+    fn vertex_shader() -> ShaderRef {
+        VERTEX_SHADER_HANDLE.into()
+    }
+
     fn fragment_shader() -> ShaderRef {
         SHADER_HANDLE.into()
     }
 
+    fn prepass_fragment_shader() -> ShaderRef {
+        PREPASS_SHADER_HANDLE.into()
+    }
+
+    /// Always forward, the analytic lights, shadows and cookies of `sk_lights` only run there
+    /// and a deferred gbuffer entry would only carry the SH lighting
+    fn opaque_render_method(&self) -> OpaqueRendererMethod {
+        OpaqueRendererMethod::Forward
+    }
+
     fn alpha_mode(&self) -> AlphaMode {
-        self.alpha_mode
+        if self.dithered {
+            // Drawn with the cutout materials, the shader discards the uncovered pixels
+            AlphaMode::Mask(0.5)
+        } else {
+            self.alpha_mode
+        }
     }
 
-    /*fn specialize(
-        _pipeline: &bevy::pbr::MaterialPipeline<Self>,
+    fn depth_bias(&self) -> f32 {
+        self.depth_bias
+    }
+
+    fn specialize(
+        pipeline: &bevy::pbr::MaterialPipeline<Self>,
         descriptor: &mut bevy::render::render_resource::RenderPipelineDescriptor,
         _layout: &bevy::render::mesh::MeshVertexBufferLayoutRef,
         key: bevy::pbr::MaterialPipelineKey<Self>,
     ) -> Result<(), bevy::render::render_resource::SpecializedMeshPipelineError> {
         descriptor.primitive.cull_mode = key.bind_group_data.cull_mode;
+        // Only plain `PbrMaterial`s, an `ExtendedSkMaterial` binds everything in its own layout
+        if let Some(layouts) = MATERIAL_LAYOUTS.get() {
+            let layouts = layouts.lock().unwrap();
+            let all = layouts.get(&ALL_TEXTURES);
+            if let Some(keyed) = layouts.get(&key.bind_group_data.textures) {
+                if all.is_some_and(|all| all.id() == pipeline.material_layout.id()) {
+                    descriptor.layout[2] = keyed.clone();
+                }
+            }
+        }
+        // Batched uniforms instead of storage buffers give away a WebGL2 or GLES device
+        let downlevel = cfg!(feature = "webgl2")
+            || pipeline.mesh_pipeline.per_object_buffer_batch_size.is_some();
+        // InstanceColor is read per instance in the fragment shader
+        if key.mesh_key.contains(bevy::pbr::MeshPipelineKey::LIGHTMAPPED) {
+            descriptor.vertex.shader_defs.push("VERTEX_OUTPUT_INSTANCE_INDEX".into());
+            if let Some(fragment) = descriptor.fragment.as_mut() {
+                fragment.shader_defs.push("VERTEX_OUTPUT_INSTANCE_INDEX".into());
+            }
+        }
+        if let Some(fragment) = descriptor.fragment.as_mut() {
+            let has_def = |name: &str| {
+                fragment.shader_defs.iter().any(
+                    |def| matches!(def, ShaderDefVal::Bool(def, true) if def.as_str() == name),
+                )
+            };
+            // The full evaluation, which the depth, normal and motion vector prepasses skip
+            if !has_def("PREPASS_PIPELINE") {
+                fragment.shader_defs.push("SK_PBR_SHADING".into());
+            }
+            for (i, def) in TEXTURE_SHADER_DEFS.iter().enumerate() {
+                if key.bind_group_data.textures & (1 << i) != 0 {
+                    fragment.shader_defs.push((*def).into());
+                }
+            }
+            if let Some(foveation) = key.bind_group_data.foveation {
+                // Shader defs only hold integers, the shader divides by 100 again
+                let radius = (foveation.radius() * 100.0).round() as u32;
+                fragment
+                    .shader_defs
+                    .push(ShaderDefVal::UInt("SK_FOVEATION_RADIUS".into(), radius));
+            }
+            if downlevel {
+                fragment.shader_defs.push("SK_DOWNLEVEL".into());
+            }
+            if cfg!(feature = "packed-uniforms") {
+                fragment.shader_defs.push("SK_PACKED_UNIFORMS".into());
+            }
+            let sh_order = key.bind_group_data.sh_order;
+            if cfg!(feature = "sh3") && sh_order >= 3 {
+                fragment.shader_defs.push("SK_SH3".into());
+            }
+            if sh_order < 2 {
+                fragment.shader_defs.push("SK_SH_SKIP_BAND2".into());
+            }
+            if !cfg!(feature = "webgl2") {
+                fragment.shader_defs.push("SK_LIGHT_COOKIES".into());
+            }
+        }
         Ok(())
-    }*/
+    }
 }
 
+// Keep in sync with the SK_MATERIAL_FLAGS_* constants in sk_types.wgsl
 bitflags::bitflags! {
     #[repr(transparent)]
     pub struct PbrMaterialFlags: u32 {
@@ -178,6 +1533,26 @@ bitflags::bitflags! {
         const EMISSION_TEXTURE   = (1 << 4);
         const METAL_TEXTURE      = (1 << 5);
         const OCCLUSION_TEXTURE  = (1 << 6);
+        const SPECULAR_AA        = (1 << 7);
+        const IRIDESCENCE        = (1 << 8);
+        const REFLECTION_PROBE   = (1 << 9);
+        const REFLECTION_PROBE_BLEND = (1 << 10);
+        const NORMAL_TEXTURE     = (1 << 11);
+        const ALPHA_MODE_BLEND   = (1 << 12);
+        const ALPHA_MODE_PREMULTIPLIED = (1 << 13);
+        const ALPHA_MODE_ADD     = (1 << 14);
+        const ALPHA_MODE_MULTIPLY = (1 << 15);
+        const DEPTH_TEXTURE      = (1 << 16);
+        const FOG_ENABLED        = (1 << 17);
+        const DITHERED           = (1 << 18);
+        const CLEARCOAT          = (1 << 19);
+        const CLEARCOAT_TEXTURE  = (1 << 20);
+        const CLEARCOAT_ROUGHNESS_TEXTURE = (1 << 21);
+        const ANISOTROPY         = (1 << 22);
+        const ANISOTROPY_TEXTURE = (1 << 23);
+        const DETAIL_COLOR_TEXTURE = (1 << 24);
+        const DETAIL_NORMAL_TEXTURE = (1 << 25);
+        const RIM = (1 << 26);
     }
 }
 
@@ -186,17 +1561,124 @@ impl Default for PbrMaterial {
         Self {
             color: Color::WHITE,
             emission_factor: Color::BLACK,
+            emission_strength: 1.0,
             metallic: 0.0,
             roughness: 0.0,
-            tex_scale: 1.0,
+            uv_scale: Vec2::ONE,
+            uv_offset: Vec2::ZERO,
             alpha_mode: AlphaMode::Opaque,
+            dithered: false,
+            depth_bias: 0.0,
             double_sided: false,
-            spherical_harmonics: DEFAULT_LIGHTING,
+            fog_enabled: true,
+            cast_shadows: true,
+            cull_mode: Some(Face::Back),
+            reflectance: 0.5,
+            specular_factor: 1.0,
+            specular_tint: Color::WHITE,
+            specular_antialiasing: true,
+            iridescence: 0.0,
+            iridescence_ior: 1.3,
+            iridescence_thickness: 400.0,
+            lod_distance: 0.0,
+            lod_from_quality: true,
+            quality_features: SkMaterialFeatures::default(),
+            foveation: None,
+            lighting: ShSlot::GLOBAL,
+            sh_buffer: SH_BUFFER_IMAGE_HANDLE,
             diffuse_texture: None,
             emission_texture: None,
             metal_texture: None,
             occlusion_texture: None,
             color_texture: None,
+            normal_texture: None,
+            normal_scale: 1.0,
+            depth_texture: None,
+            parallax_depth_scale: 0.1,
+            max_parallax_layer_count: 16.0,
+            clearcoat: 0.0,
+            clearcoat_roughness: 0.5,
+            clearcoat_texture: None,
+            clearcoat_roughness_texture: None,
+            anisotropy_strength: 0.0,
+            anisotropy_rotation: 0.0,
+            anisotropy_texture: None,
+            detail_color_texture: None,
+            detail_normal_texture: None,
+            detail_uv_scale: Vec2::splat(8.0),
+            detail_blend: 1.0,
+            color_array: None,
+            data_array: None,
+            light_cookies: LIGHT_COOKIES_IMAGE_HANDLE,
+            light_cookie_data: LIGHT_COOKIE_DATA_IMAGE_HANDLE,
+            array_layers: TextureArrayLayers::default(),
+            rim_color: Color::BLACK,
+            rim_power: 2.0,
+            reflection_probe_a: None,
+            reflection_probe_b: None,
+            reflection_blend: 0.0,
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bindings(material: &PbrMaterial) -> Vec<u32> {
+        let key = PbrMaterialKey::from(material);
+        let mut bindings: Vec<_> = layout_entries(key.textures)
+            .iter()
+            .map(|entry| entry.binding)
+            .collect();
+        bindings.sort();
+        bindings
+    }
+
+    #[test]
+    fn layouts_only_bind_the_set_textures() {
+        let shared = if cfg!(feature = "webgl2") {
+            vec![0, 11]
+        } else {
+            vec![0, 11, 34, 35, 36]
+        };
+        assert_eq!(bindings(&PbrMaterial::default()), shared);
+
+        let textured = PbrMaterial {
+            color_texture: Some(Handle::default()),
+            ..default()
+        };
+        let mut expected = [shared, vec![9, 10]].concat();
+        expected.sort();
+        assert_eq!(bindings(&textured), expected);
+    }
+
+    #[test]
+    fn low_quality_binds_fewer_textures() {
+        let material = |quality: SkQuality| PbrMaterial {
+            normal_texture: Some(Handle::default()),
+            detail_color_texture: Some(Handle::default()),
+            clearcoat_texture: Some(Handle::default()),
+            quality_features: quality.settings().material_features(),
+            ..default()
+        };
+        let low = bindings(&material(SkQuality::Low));
+        for binding in [16, 17, 20, 21, 26, 27] {
+            assert!(!low.contains(&binding), "{binding} in {low:?}");
+        }
+        let high = bindings(&material(SkQuality::High));
+        assert!(high.contains(&16) && high.len() > low.len(), "{high:?}");
+    }
+
+    #[test]
+    fn sh_order_follows_the_quality() {
+        let key = |quality: SkQuality| {
+            PbrMaterialKey::from(&PbrMaterial {
+                quality_features: quality.settings().material_features(),
+                ..default()
+            })
+        };
+        assert_eq!(key(SkQuality::Low).sh_order, 2);
+        assert_eq!(key(SkQuality::High).sh_order, 3);
+    }
+}
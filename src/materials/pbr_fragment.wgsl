@@ -0,0 +1,598 @@
+#define_import_path bevy_sk::pbr_fragment
+
+#import bevy_pbr::mesh_view_bindings
+#import bevy_pbr::mesh_bindings
+#import bevy_pbr::utils
+#import bevy_pbr::{
+    pbr_types::PbrInput,
+    mesh_view_bindings::view,
+}
+// Depth, normal and motion vector prepasses only get the bindings and the helpers that don't
+// need the full vertex output, see `pbr_prepass.wgsl`
+#ifdef SK_PBR_SHADING
+#import bevy_pbr::pbr_fragment::pbr_input_from_vertex_output
+#endif
+#ifdef LIGHTMAP
+#import bevy_pbr::lightmap::lightmaps_texture
+#endif
+
+#import bevy_sk::{
+    pbr_types::{
+        PbrMaterial, sk_has_flag, sk_material_color, sk_material_emission,
+        sk_material_metallic_roughness, sk_material_specular, sk_material_rim_color,
+        SK_MATERIAL_FLAGS_SPECULAR_AA_BIT, SK_MATERIAL_FLAGS_IRIDESCENCE_BIT,
+        SK_MATERIAL_FLAGS_ALPHA_MODE_MASK_BIT, SK_MATERIAL_FLAGS_DOUBLE_SIDED_BIT,
+        SK_MATERIAL_FLAGS_FOG_ENABLED_BIT, SK_MATERIAL_FLAGS_DITHERED_BIT,
+        SK_MATERIAL_FLAGS_CLEARCOAT_BIT, SK_MATERIAL_FLAGS_ANISOTROPY_BIT,
+        SK_MATERIAL_FLAGS_RIM_BIT,
+        sk_alpha_output,
+    },
+    lighting::{sk_lighting, sk_lighting_band3, sk_sh_exposure},
+    brdf::{
+        sk_pbr_fresnel_schlick_roughness, sk_pbr_brdf_appx, sk_specular_aa,
+        sk_iridescence_fresnel, sk_multiscatter_compensation, sk_clearcoat_fresnel,
+    },
+}
+
+#ifdef PREPASS_PIPELINE
+#import bevy_pbr::prepass_io::{VertexOutput, FragmentOutput}
+#else
+#import bevy_pbr::{
+    forward_io::{VertexOutput, FragmentOutput},
+    fog::apply_fog,
+    mesh_view_bindings::fog,
+    mesh_view_types::FOG_MODE_OFF,
+}
+#import bevy_sk::lights::{SkSurface, sk_lights}
+#endif
+
+// The optional textures are only declared and bound when the material sets them, see
+// PbrMaterialKey
+@group(2) @binding(0)
+var<uniform> material: PbrMaterial;
+#ifdef SK_DIFFUSE_TEXTURE
+@group(2) @binding(1)
+var diffuse_texture: texture_2d<f32>;
+@group(2) @binding(2)
+var diffuse_sampler: sampler;
+#endif
+#ifdef SK_EMISSION_TEXTURE
+@group(2) @binding(3)
+var emission_texture: texture_2d<f32>;
+@group(2) @binding(4)
+var emission_sampler: sampler;
+#endif
+#ifdef SK_METAL_TEXTURE
+@group(2) @binding(5)
+var metal_texture: texture_2d<f32>;
+@group(2) @binding(6)
+var metal_sampler: sampler;
+#endif
+#ifdef SK_OCCLUSION_TEXTURE
+@group(2) @binding(7)
+var occlusion_texture: texture_2d<f32>;
+@group(2) @binding(8)
+var occlusion_sampler: sampler;
+#endif
+#ifdef SK_COLOR_TEXTURE
+@group(2) @binding(9)
+var color_texture: texture_2d<f32>;
+@group(2) @binding(10)
+var color_sampler: sampler;
+#endif
+@group(2) @binding(11)
+var sh_buffer: texture_2d<f32>;
+#ifdef SK_REFLECTION_PROBE_A
+@group(2) @binding(12)
+var reflection_probe_a: texture_cube<f32>;
+@group(2) @binding(13)
+var reflection_sampler_a: sampler;
+#endif
+#ifdef SK_REFLECTION_PROBE_B
+@group(2) @binding(14)
+var reflection_probe_b: texture_cube<f32>;
+@group(2) @binding(15)
+var reflection_sampler_b: sampler;
+#endif
+#ifdef SK_NORMAL_TEXTURE
+@group(2) @binding(16)
+var normal_texture: texture_2d<f32>;
+@group(2) @binding(17)
+var normal_sampler: sampler;
+#endif
+#ifdef SK_DEPTH_TEXTURE
+@group(2) @binding(18)
+var depth_texture: texture_2d<f32>;
+@group(2) @binding(19)
+var depth_sampler: sampler;
+#endif
+#ifdef SK_CLEARCOAT_TEXTURE
+@group(2) @binding(20)
+var clearcoat_texture: texture_2d<f32>;
+@group(2) @binding(21)
+var clearcoat_sampler: sampler;
+#endif
+#ifdef SK_CLEARCOAT_ROUGHNESS_TEXTURE
+@group(2) @binding(22)
+var clearcoat_roughness_texture: texture_2d<f32>;
+@group(2) @binding(23)
+var clearcoat_roughness_sampler: sampler;
+#endif
+#ifdef SK_ANISOTROPY_TEXTURE
+@group(2) @binding(24)
+var anisotropy_texture: texture_2d<f32>;
+@group(2) @binding(25)
+var anisotropy_sampler: sampler;
+#endif
+#ifdef SK_DETAIL_COLOR_TEXTURE
+@group(2) @binding(26)
+var detail_color_texture: texture_2d<f32>;
+@group(2) @binding(27)
+var detail_color_sampler: sampler;
+#endif
+#ifdef SK_DETAIL_NORMAL_TEXTURE
+@group(2) @binding(28)
+var detail_normal_texture: texture_2d<f32>;
+@group(2) @binding(29)
+var detail_normal_sampler: sampler;
+#endif
+#ifdef SK_COLOR_ARRAY
+@group(2) @binding(30)
+var color_array: texture_2d_array<f32>;
+@group(2) @binding(31)
+var color_array_sampler: sampler;
+#endif
+#ifdef SK_DATA_ARRAY
+@group(2) @binding(32)
+var data_array: texture_2d_array<f32>;
+@group(2) @binding(33)
+var data_array_sampler: sampler;
+#endif
+
+// Layer of the color (0), metal (1) or occlusion (2) texture in its texture array, 255 when
+// it isn't packed
+fn sk_array_layer(texture: u32) -> u32 {
+    return (material.array_layers >> (texture * 8u)) & 255u;
+}
+
+// Scale of a slot's SH under the view's exposure, see SkColorSettings
+fn sk_material_sh_exposure(slot: u32) -> f32 {
+    return sk_sh_exposure(textureLoad(sh_buffer, vec2<i32>(0, i32(slot)), 0).a, view.exposure);
+}
+
+// SkExposure brightness of a slot, already applied to its SH but not to emission
+fn sk_material_brightness(slot: u32) -> f32 {
+    return textureLoad(sh_buffer, vec2<i32>(1, i32(slot)), 0).a;
+}
+
+// Reads the SH of a ShLightingBuffer slot, one row of 9 texels per slot
+fn sk_material_sh(slot: u32) -> array<vec3<f32>, 9> {
+    let exposure = sk_material_sh_exposure(slot);
+    var sh: array<vec3<f32>, 9>;
+    for (var i = 0u; i < 9u; i += 1u) {
+        sh[i] = textureLoad(sh_buffer, vec2<i32>(i32(i), i32(slot)), 0).rgb * exposure;
+    }
+    return sh;
+}
+
+#ifdef SK_SH3
+// Band 3 of a slot, stored in texels 9 to 15 of its row
+fn sk_material_sh_band3(slot: u32) -> array<vec3<f32>, 7> {
+    let exposure = sk_material_sh_exposure(slot);
+    var band3: array<vec3<f32>, 7>;
+    for (var i = 0u; i < 7u; i += 1u) {
+        band3[i] = textureLoad(sh_buffer, vec2<i32>(i32(i + 9u), i32(slot)), 0).rgb * exposure;
+    }
+    return band3;
+}
+#endif
+
+
+// Samples a probe with rougher surfaces reading blurrier mips. Explicit LOD, so this is
+// safe inside non-uniform control flow.
+fn sk_sample_probe(probe: texture_cube<f32>, probe_sampler: sampler, R: vec3<f32>, roughness: f32) -> vec3<f32> {
+#ifdef SK_DOWNLEVEL
+    // GLSL ES 3.0 can't query the mip count, probes have a full mip chain
+    let levels = floor(log2(f32(textureDimensions(probe).x))) + 1.0;
+#else
+    let levels = f32(textureNumLevels(probe));
+#endif
+    let lod = roughness * (levels - 1.0);
+    return textureSampleLevel(probe, probe_sampler, R, lod).rgb;
+}
+
+/*struct FragmentInput {
+    @builtin(front_facing) is_front: bool,
+    @builtin(position) frag_coord: vec4<f32>,
+    @location(0) world_position: vec4<f32>,
+    @location(1) world_normal: vec3<f32>,
+    @location(2) uv: vec2<f32>,
+};*/
+
+#ifdef VERTEX_TANGENTS
+#ifdef SK_DEPTH_TEXTURE
+fn sk_sample_depth(uv: vec2<f32>) -> f32 {
+    // The number of steps varies per fragment, so derivatives aren't available in the loop
+    return textureSampleLevel(depth_texture, depth_sampler, uv, 0.0).r;
+}
+
+// Steep parallax mapping refined by interpolating between the last two layers, like
+// StandardMaterial's occlusion mapping. Vt points from the camera to the fragment in tangent
+// space.
+fn sk_parallaxed_uv(uv_in: vec2<f32>, Vt: vec3<f32>) -> vec2<f32> {
+    if (material.max_parallax_layer_count < 1.0) {
+        return uv_in;
+    }
+    let view_steepness = abs(Vt.z);
+    let layer_count = mix(material.max_parallax_layer_count, 1.0, view_steepness);
+    let layer_depth = 1.0 / layer_count;
+    let delta_uv = material.parallax_depth_scale * layer_depth * Vt.xy * vec2(1.0, -1.0)
+        / max(view_steepness, 0.0001);
+
+    var uv = uv_in;
+    var current_depth = 0.0;
+    var texture_depth = sk_sample_depth(uv);
+    for (var i = 0u; i < u32(layer_count) && texture_depth > current_depth; i += 1u) {
+        current_depth += layer_depth;
+        uv += delta_uv;
+        texture_depth = sk_sample_depth(uv);
+    }
+
+    let previous_uv = uv - delta_uv;
+    let next_depth = texture_depth - current_depth;
+    let previous_depth = sk_sample_depth(previous_uv) - current_depth + layer_depth;
+    let weight = next_depth / (next_depth - previous_depth);
+    return mix(uv, previous_uv, saturate(weight));
+}
+#endif
+#endif
+
+// Interleaved gradient noise (Jimenez), a per pixel threshold in 0..1 that spreads the kept
+// pixels of dithered materials evenly
+fn sk_dither_threshold(frag_coord: vec2<f32>) -> f32 {
+    return fract(52.9829189 * fract(dot(floor(frag_coord), vec2(0.06711056, 0.00583715))));
+}
+
+// Whether a fragment is close enough for the full BRDF, a lod_distance of 0 never simplifies
+fn sk_full_shading(lod_distance: f32, world_position: vec3<f32>) -> bool {
+    return lod_distance <= 0.0 || distance(view.world_position.xyz, world_position) < lod_distance;
+}
+
+// Whether a fragment lies inside the fully shaded circle of a foveated view, the radius is
+// relative to half the viewport
+fn sk_foveal(frag_coord: vec2<f32>) -> bool {
+#ifdef SK_FOVEATION_RADIUS
+    let radius = f32(SK_FOVEATION_RADIUS) / 100.0;
+    let offset = (frag_coord - view.viewport.xy) / view.viewport.zw * 2.0 - 1.0;
+    return dot(offset, offset) < radius * radius;
+#else
+    return true;
+#endif
+}
+
+// The evaluated PbrMaterial of a fragment, before it is written out
+struct SkPbrResult {
+    // Lit color including emission, linear and not yet exposed for blending
+    color: vec3<f32>,
+    alpha: f32,
+    // SH irradiance at the shading normal, before the albedo is applied
+    ambient: vec3<f32>,
+    albedo: vec4<f32>,
+    // Shading normal and the direction towards the camera
+    N: vec3<f32>,
+    V: vec3<f32>,
+    // x: roughness, y: metallic
+    metal_rough: vec2<f32>,
+    uv: vec2<f32>,
+    pbr_input: PbrInput,
+};
+
+#ifdef LIGHTMAP
+// InstanceColor, packed into the lightmap UV rect as 16 bit unorm rg and ba, so in 0..1
+fn sk_instance_color(instance_index: u32) -> vec4<f32> {
+    // Only the single texel placeholder carries a color, real lightmaps leave the albedo alone
+    if any(textureDimensions(lightmaps_texture) != vec2(1u)) {
+        return vec4(1.0);
+    }
+    let packed = mesh_bindings::mesh[instance_index].lightmap_uv_rect;
+    return vec4<f32>(vec4<u32>(
+        packed.x & 0xffffu, packed.x >> 16u, packed.y & 0xffffu, packed.y >> 16u
+    )) / 65535.0;
+}
+#endif
+
+// The material's UV of a fragment, shifted by the parallax of its depth texture
+fn sk_pbr_uv(in: VertexOutput, world_normal: vec3<f32>) -> vec2<f32> {
+    var uv = in.uv * material.uv_scale + material.uv_offset;
+#ifdef VERTEX_TANGENTS
+#ifdef SK_DEPTH_TEXTURE
+    let V = normalize(view.world_position.xyz - in.world_position.xyz);
+    let Ng = normalize(world_normal);
+    let Tg = normalize(in.world_tangent.xyz - Ng * dot(in.world_tangent.xyz, Ng));
+    let Bg = cross(Ng, Tg) * in.world_tangent.w;
+    uv = sk_parallaxed_uv(uv, -vec3(dot(V, Tg), dot(V, Bg), dot(V, Ng)));
+#endif
+#endif
+    return uv;
+}
+
+// Base color and alpha of a fragment, with its textures and InstanceColor applied
+fn sk_pbr_albedo(in: VertexOutput, uv: vec2<f32>) -> vec4<f32> {
+    var albedo = sk_material_color(material);
+    /*if ((material.flags & 4u) != 0u) {
+        albedo *= textureSample(diffuse_texture, diffuse_sampler, uv);
+    }*/
+
+#ifdef SK_COLOR_TEXTURE
+    albedo *= textureSample(color_texture, color_sampler, uv);
+#else ifdef SK_COLOR_ARRAY
+    let color_layer = sk_array_layer(0u);
+    if (color_layer != 255u) {
+        albedo *= textureSample(color_array, color_array_sampler, uv, color_layer);
+    }
+#endif
+#ifdef SK_DETAIL_COLOR_TEXTURE
+    // Detail albedo is centered on 0.5 gray, which leaves the base color unchanged
+    let detail_uv = uv * material.detail_uv_scale;
+    let detail = textureSample(detail_color_texture, detail_color_sampler, detail_uv).rgb;
+    albedo = vec4(albedo.rgb * mix(vec3(1.0), detail * 2.0, material.detail_blend), albedo.a);
+#endif
+#ifdef LIGHTMAP
+    albedo *= sk_instance_color(in.instance_index);
+#endif
+    return albedo;
+}
+
+// Discards the fragments cut out by the alpha mask or the dither pattern
+fn sk_pbr_alpha_discard(in: VertexOutput, alpha: f32) {
+    if (sk_has_flag(material.flags, SK_MATERIAL_FLAGS_ALPHA_MODE_MASK_BIT)
+        && alpha < material.alpha_cutoff) {
+        discard;
+    }
+    if (sk_has_flag(material.flags, SK_MATERIAL_FLAGS_DITHERED_BIT)
+        && alpha <= sk_dither_threshold(in.position.xy)) {
+        discard;
+    }
+}
+
+// Shading normal with the normal and detail normal textures applied, `flip` is -1 on the back
+// faces of double sided materials
+fn sk_pbr_normal(
+    in: VertexOutput, world_normal: vec3<f32>, uv: vec2<f32>, flip: f32
+) -> vec3<f32> {
+    var N = normalize(world_normal);
+#ifdef VERTEX_TANGENTS
+    var Nt = vec3(0.0, 0.0, 1.0);
+    var perturbed = false;
+#ifdef SK_NORMAL_TEXTURE
+    Nt = textureSample(normal_texture, normal_sampler, uv).rgb * 2.0 - 1.0;
+    Nt = vec3(Nt.xy * material.normal_scale, Nt.z);
+    perturbed = true;
+#endif
+#ifdef SK_DETAIL_NORMAL_TEXTURE
+    // Adding the slopes keeps both layers' bumps
+    let detail_uv = uv * material.detail_uv_scale;
+    let Nd = textureSample(detail_normal_texture, detail_normal_sampler, detail_uv).rgb;
+    Nt = vec3(Nt.xy + (Nd.xy * 2.0 - 1.0) * material.detail_blend, Nt.z);
+    perturbed = true;
+#endif
+    if (perturbed) {
+        // Gram-Schmidt keeps the interpolated tangent perpendicular to the normal
+        let T = normalize(in.world_tangent.xyz - N * dot(in.world_tangent.xyz, N)) * flip;
+        let B = cross(N, T) * in.world_tangent.w * flip;
+        N = normalize(Nt.x * T + Nt.y * B + Nt.z * N);
+    }
+#endif
+    return N;
+}
+
+#ifdef SK_PBR_SHADING
+// Evaluates the material for a fragment, `ExtendedSkMaterial` shaders call this, adjust the
+// result and hand it to `sk_pbr_output`
+fn sk_pbr_fragment(in: VertexOutput, is_front: bool) -> SkPbrResult {
+    // Double sided materials light back faces with the flipped normal
+    let double_sided = sk_has_flag(material.flags, SK_MATERIAL_FLAGS_DOUBLE_SIDED_BIT);
+    var pbr_input = pbr_input_from_vertex_output(in, is_front, double_sided);
+
+    let uv = sk_pbr_uv(in, pbr_input.world_normal);
+    let V = normalize(view.world_position.xyz - in.world_position.xyz);
+    let albedo = sk_pbr_albedo(in, uv);
+    sk_pbr_alpha_discard(in, albedo.a);
+
+    // emission_factor already carries emission_strength and may exceed 1.0 for bloom
+    var emissive = sk_material_emission(material);
+#ifdef SK_EMISSION_TEXTURE
+    emissive *= textureSample(emission_texture, emission_sampler, uv).rgb;
+#endif
+    emissive *= sk_material_brightness(material.sh_slot);
+
+    // x: roughness, y: metallic, glTF packs them into the green and blue channels
+    var metal_rough = sk_material_metallic_roughness(material).yx;
+#ifdef SK_METAL_TEXTURE
+    metal_rough *= textureSample(metal_texture, metal_sampler, uv).gb;
+#else ifdef SK_DATA_ARRAY
+    let metal_layer = sk_array_layer(1u);
+    if (metal_layer != 255u) {
+        metal_rough *= textureSample(data_array, data_array_sampler, uv, metal_layer).gb;
+    }
+#endif
+
+    var ao = 1.0;
+#ifdef SK_OCCLUSION_TEXTURE
+    ao = textureSample(occlusion_texture, occlusion_sampler, uv).r;
+#else ifdef SK_DATA_ARRAY
+    let occlusion_layer = sk_array_layer(2u);
+    if (occlusion_layer != 255u) {
+        ao = textureSample(data_array, data_array_sampler, uv, occlusion_layer).r;
+    }
+#endif
+
+    // Intensity and roughness of the clear coat, glTF reads them from the red and green
+    var clearcoat = vec2(0.0);
+    if (sk_has_flag(material.flags, SK_MATERIAL_FLAGS_CLEARCOAT_BIT)) {
+        clearcoat = vec2(material.clearcoat, material.clearcoat_roughness);
+#ifdef SK_CLEARCOAT_TEXTURE
+        clearcoat.x *= textureSample(clearcoat_texture, clearcoat_sampler, uv).r;
+#endif
+#ifdef SK_CLEARCOAT_ROUGHNESS_TEXTURE
+        clearcoat.y *= textureSample(
+            clearcoat_roughness_texture, clearcoat_roughness_sampler, uv
+        ).g;
+#endif
+    }
+
+    let flip = select(1.0, -1.0, double_sided && !is_front);
+    var N = sk_pbr_normal(in, pbr_input.world_normal, uv, flip);
+
+    // Direction the highlights stretch along and how much, like KHR_materials_anisotropy,
+    // which needs tangents
+    var anisotropy = 0.0;
+    var aniso_T = vec3(0.0);
+    var aniso_B = vec3(0.0);
+#ifdef VERTEX_TANGENTS
+    if (sk_has_flag(material.flags, SK_MATERIAL_FLAGS_ANISOTROPY_BIT)) {
+        anisotropy = material.anisotropy_strength;
+        var direction = vec2(cos(material.anisotropy_rotation), sin(material.anisotropy_rotation));
+#ifdef SK_ANISOTROPY_TEXTURE
+        // Red and green hold a tangent space direction rotated by the material's, blue the
+        // strength
+        let texel = textureSample(anisotropy_texture, anisotropy_sampler, uv).rgb;
+        let rotation = mat2x2(direction.x, direction.y, -direction.y, direction.x);
+        direction = rotation * (texel.rg * 2.0 - 1.0);
+        anisotropy *= texel.b;
+#endif
+        let Ts = normalize(in.world_tangent.xyz - N * dot(in.world_tangent.xyz, N));
+        let Bs = cross(N, Ts) * in.world_tangent.w;
+        aniso_T = normalize(Ts * direction.x + Bs * direction.y);
+        aniso_B = cross(N, aniso_T);
+    }
+#endif
+
+    if (sk_has_flag(material.flags, SK_MATERIAL_FLAGS_SPECULAR_AA_BIT)) {
+        metal_rough.x = sk_specular_aa(N, metal_rough.x);
+    }
+
+    var R = reflect(-V, N);
+    if (anisotropy > 0.0) {
+        // Bends the reflection towards the stretched highlight (Filament)
+        let aniso_tangent = cross(aniso_B, V);
+        let aniso_normal = cross(aniso_tangent, aniso_B);
+        let bend = anisotropy * saturate(5.0 * metal_rough.x);
+        R = reflect(-V, normalize(mix(N, aniso_normal, bend)));
+    }
+
+    let spherical_harmonics = sk_material_sh(material.sh_slot);
+    let irradiance = sk_lighting(N, spherical_harmonics);
+
+    let diffuse = albedo.rgb * irradiance;
+
+    // KHR_materials_specular tints and scales only the dielectric part
+    let khr_specular = sk_material_specular(material);
+    let dielectric_f0 = min(
+        0.16 * material.reflectance * material.reflectance * khr_specular.rgb,
+        vec3(1.0)
+    ) * khr_specular.a;
+    let F0 = mix(dielectric_f0, albedo.rgb, metal_rough.y);
+
+    // Past the LOD distance and in the foveated periphery only the SH diffuse term is kept.
+    // This branch is not uniform, so nothing inside it may sample textures with implicit
+    // derivatives.
+    let full_shading = sk_full_shading(material.lod_distance, in.world_position.xyz)
+        && sk_foveal(in.position.xy);
+    var color = diffuse * ao;
+    if (full_shading) {
+        let ndotv = max(dot(N, V), 0.0001);
+
+        var F = sk_pbr_fresnel_schlick_roughness(ndotv, F0, metal_rough.x);
+        if (sk_has_flag(material.flags, SK_MATERIAL_FLAGS_IRIDESCENCE_BIT)) {
+            let film = sk_iridescence_fresnel(
+                ndotv, material.iridescence_thickness, material.iridescence_ior, F
+            );
+            F = mix(F, film, material.iridescence);
+        }
+        let kS = F;
+        var kD = vec3(1.0) - kS;
+        kD *= 1.0 - metal_rough.y;
+
+        // Without a probe the SH along the reflection vector stands in for the environment
+        var prefiltered_color = sk_lighting(R, spherical_harmonics);
+#ifdef SK_SH3
+        // Band 3 has no diffuse part, it only sharpens the reflected SH
+        prefiltered_color += sk_lighting_band3(R, sk_material_sh_band3(material.sh_slot));
+#endif
+#ifdef SK_REFLECTION_PROBE_A
+        prefiltered_color = sk_sample_probe(reflection_probe_a, reflection_sampler_a, R, metal_rough.x);
+#ifdef SK_REFLECTION_PROBE_B
+        let b = sk_sample_probe(reflection_probe_b, reflection_sampler_b, R, metal_rough.x);
+        prefiltered_color = mix(prefiltered_color, b, material.reflection_blend);
+#endif
+#endif
+
+        let env_brdf = sk_pbr_brdf_appx(metal_rough.x, ndotv);
+        let specular = prefiltered_color * (F * env_brdf.x + env_brdf.y)
+            * sk_multiscatter_compensation(F0, env_brdf);
+
+        color = (kD * diffuse + specular) * ao;
+
+        if (clearcoat.x > 0.0) {
+            // A 4% reflective varnish on the unperturbed normal, what it reflects is taken
+            // from the layers below
+            let Ng = normalize(pbr_input.world_normal);
+            let coat_ndotv = max(dot(Ng, V), 0.0001);
+            let Rc = reflect(-V, Ng);
+            var coat_color = sk_lighting(Rc, spherical_harmonics);
+#ifdef SK_REFLECTION_PROBE_A
+            coat_color = sk_sample_probe(
+                reflection_probe_a, reflection_sampler_a, Rc, clearcoat.y
+            );
+#endif
+            let coat_fresnel = sk_clearcoat_fresnel(coat_ndotv) * clearcoat.x;
+            let coat_brdf = sk_pbr_brdf_appx(clearcoat.y, coat_ndotv);
+            color = color * (1.0 - coat_fresnel)
+                + coat_color * (0.04 * coat_brdf.x + coat_brdf.y) * clearcoat.x * ao;
+        }
+    }
+
+    // Bevy's lights on top of the SH ambient
+    let surface = SkSurface(
+        in.position,
+        in.world_position,
+        N,
+        normalize(pbr_input.world_normal),
+        V,
+        albedo.rgb * (1.0 - metal_rough.y),
+        F0,
+        metal_rough.x,
+        clearcoat.x,
+        clearcoat.y,
+        normalize(pbr_input.world_normal),
+        anisotropy,
+        aniso_T,
+        aniso_B,
+        pbr_input.flags,
+    );
+    color += sk_lights(surface, full_shading);
+    color += emissive;
+    if (sk_has_flag(material.flags, SK_MATERIAL_FLAGS_RIM_BIT)) {
+        let rim = pow(1.0 - saturate(dot(N, V)), material.rim_power);
+        color += sk_material_rim_color(material) * rim;
+    }
+
+    return SkPbrResult(
+        color, albedo.a, irradiance, albedo, N, V, metal_rough, uv, pbr_input
+    );
+}
+
+// Writes an evaluated fragment for the current pass
+fn sk_pbr_output(in: VertexOutput, result: SkPbrResult) -> FragmentOutput {
+    var out: FragmentOutput;
+    var color = vec4(result.color, result.alpha);
+    // The camera's FogSettings, like StandardMaterial
+    if (fog.mode != FOG_MODE_OFF
+        && sk_has_flag(material.flags, SK_MATERIAL_FLAGS_FOG_ENABLED_BIT)) {
+        color = apply_fog(fog, color, in.world_position.xyz, view.world_position.xyz);
+    }
+    out.color = sk_alpha_output(material.flags, color.rgb, color.a);
+    return out;
+}
+#endif
@@ -0,0 +1,40 @@
+#import bevy_pbr::{
+    prepass_io::{VertexOutput, FragmentOutput},
+    pbr_prepass_functions::calculate_motion_vector,
+}
+#import bevy_sk::{
+    pbr_types::{sk_has_flag, SK_MATERIAL_FLAGS_DOUBLE_SIDED_BIT},
+    pbr_fragment::{material, sk_pbr_uv, sk_pbr_albedo, sk_pbr_alpha_discard, sk_pbr_normal},
+}
+
+// Cuts out the same pixels as the main pass and writes the normal mapped normals, so SSAO,
+// TAA and contact shadows see the same surface
+@fragment
+fn fragment(in: VertexOutput, @builtin(front_facing) is_front: bool) -> FragmentOutput {
+    var out: FragmentOutput;
+
+#ifdef NORMAL_PREPASS
+    let double_sided = sk_has_flag(material.flags, SK_MATERIAL_FLAGS_DOUBLE_SIDED_BIT);
+    let flip = select(1.0, -1.0, double_sided && !is_front);
+    let uv = sk_pbr_uv(in, in.world_normal * flip);
+#else
+    // Parallax needs tangents, which depth only prepasses don't have
+    let uv = sk_pbr_uv(in, vec3(0.0, 0.0, 1.0));
+#endif
+    sk_pbr_alpha_discard(in, sk_pbr_albedo(in, uv).a);
+
+#ifdef NORMAL_PREPASS
+    let N = sk_pbr_normal(in, in.world_normal * flip, uv, flip);
+    out.normal = vec4(N * 0.5 + vec3(0.5), 1.0);
+#endif
+
+#ifdef DEPTH_CLAMP_ORTHO
+    out.frag_depth = in.clip_position_unclamped.z;
+#endif
+
+#ifdef MOTION_VECTOR_PREPASS
+    out.motion_vector = calculate_motion_vector(in.world_position, in.previous_world_position);
+#endif
+
+    return out;
+}
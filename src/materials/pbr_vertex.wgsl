@@ -0,0 +1,101 @@
+#import bevy_pbr::{
+    mesh_functions,
+    skinning,
+    morph,
+    forward_io::{Vertex, VertexOutput},
+    view_transformations::position_world_to_clip,
+}
+
+#ifdef MORPH_TARGETS
+// Adds every weighted morph target's offsets to the vertex
+fn sk_morph_vertex(vertex_in: Vertex) -> Vertex {
+    var vertex = vertex_in;
+    let weight_count = morph::layer_count();
+    for (var i: u32 = 0u; i < weight_count; i++) {
+        let weight = morph::weight_at(i);
+        if weight == 0.0 {
+            continue;
+        }
+        vertex.position += weight * morph::morph(vertex.index, morph::position_offset, i);
+#ifdef VERTEX_NORMALS
+        vertex.normal += weight * morph::morph(vertex.index, morph::normal_offset, i);
+#endif
+#ifdef VERTEX_TANGENTS
+        let offset = morph::morph(vertex.index, morph::tangent_offset, i);
+        vertex.tangent += vec4(weight * offset, 0.0);
+#endif
+    }
+    return vertex;
+}
+#endif
+
+// Mirrors Bevy's mesh vertex shader, meshes are morphed and then posed by their joints before
+// lighting
+@vertex
+fn vertex(vertex_no_morph: Vertex) -> VertexOutput {
+    var out: VertexOutput;
+
+#ifdef MORPH_TARGETS
+    let vertex = sk_morph_vertex(vertex_no_morph);
+#else
+    let vertex = vertex_no_morph;
+#endif
+
+#ifdef SKINNED
+    let world_from_local = skinning::skin_model(vertex.joint_indices, vertex.joint_weights);
+#else
+    // The unmorphed instance index works around a naga bug on DX12
+    let world_from_local = mesh_functions::get_world_from_local(vertex_no_morph.instance_index);
+#endif
+
+#ifdef VERTEX_NORMALS
+#ifdef SKINNED
+    out.world_normal = skinning::skin_normals(world_from_local, vertex.normal);
+#else
+    out.world_normal = mesh_functions::mesh_normal_local_to_world(
+        vertex.normal,
+        vertex_no_morph.instance_index
+    );
+#endif
+#endif
+
+#ifdef VERTEX_POSITIONS
+    out.world_position = mesh_functions::mesh_position_local_to_world(
+        world_from_local,
+        vec4<f32>(vertex.position, 1.0)
+    );
+    out.position = position_world_to_clip(out.world_position.xyz);
+#endif
+
+#ifdef VERTEX_UVS_A
+    out.uv = vertex.uv;
+#endif
+#ifdef VERTEX_UVS_B
+    out.uv_b = vertex.uv_b;
+#endif
+
+#ifdef VERTEX_TANGENTS
+    out.world_tangent = mesh_functions::mesh_tangent_local_to_world(
+        world_from_local,
+        vertex.tangent,
+        vertex_no_morph.instance_index
+    );
+#endif
+
+#ifdef VERTEX_COLORS
+    out.color = vertex.color;
+#endif
+
+#ifdef VERTEX_OUTPUT_INSTANCE_INDEX
+    out.instance_index = vertex_no_morph.instance_index;
+#endif
+
+#ifdef VISIBILITY_RANGE_DITHER
+    out.visibility_range_dither = mesh_functions::get_visibility_range_dither_level(
+        vertex_no_morph.instance_index,
+        world_from_local[3]
+    );
+#endif
+
+    return out;
+}
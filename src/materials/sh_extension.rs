@@ -0,0 +1,68 @@
+use crate::lighting::buffer::{ShSlot, SH_BUFFER_IMAGE_HANDLE};
+use bevy::pbr::{ExtendedMaterial, MaterialExtension};
+use bevy::prelude::*;
+use bevy::render::render_asset::RenderAssets;
+use bevy::render::render_resource::{AsBindGroup, AsBindGroupShaderType, ShaderRef, ShaderType};
+use bevy::render::texture::GpuImage;
+
+const SHADER_HANDLE: Handle<Shader> = Handle::weak_from_u128(0x5b0e7d29c3f4);
+
+/// `StandardMaterial` with the sk SH ambient added on top of Bevy's own lighting, so shadows,
+/// clustered lights and every other `StandardMaterial` feature keep working.
+///
+/// Bevy's `AmbientLight` still applies, set its brightness to 0 to light only by the SH.
+pub type ShExtendedStandardMaterial = ExtendedMaterial<StandardMaterial, ShExtension>;
+
+/// Registers [`ShExtendedStandardMaterial`], added by `PbrPlugin`
+pub struct ShExtendedStandardMaterialPlugin;
+
+impl Plugin for ShExtendedStandardMaterialPlugin {
+    fn build(&self, app: &mut App) {
+        load_shader!(app, SHADER_HANDLE, "sh_extension.wgsl");
+        app.add_plugins(MaterialPlugin::<ShExtendedStandardMaterial>::default());
+        app.register_type::<ShExtension>();
+    }
+}
+
+/// The SH lighting of a [`ShExtendedStandardMaterial`]
+#[derive(Asset, AsBindGroup, Reflect, PartialEq, Debug, Clone)]
+#[uniform(100, ShExtensionUniform)]
+pub struct ShExtension {
+    /// Slot of the shared `ShLightingBuffer` this material is lit by
+    pub lighting: ShSlot,
+    /// The shared SH texture, leave this at its default
+    #[texture(101, sample_type = "float", filterable = false)]
+    pub sh_buffer: Handle<Image>,
+}
+
+impl Default for ShExtension {
+    fn default() -> Self {
+        Self {
+            lighting: ShSlot::GLOBAL,
+            sh_buffer: SH_BUFFER_IMAGE_HANDLE,
+        }
+    }
+}
+
+#[derive(Clone, Default, ShaderType)]
+pub struct ShExtensionUniform {
+    pub sh_slot: u32,
+}
+
+impl AsBindGroupShaderType<ShExtensionUniform> for ShExtension {
+    fn as_bind_group_shader_type(&self, _: &RenderAssets<GpuImage>) -> ShExtensionUniform {
+        ShExtensionUniform {
+            sh_slot: self.lighting.0,
+        }
+    }
+}
+
+impl MaterialExtension for ShExtension {
+    fn fragment_shader() -> ShaderRef {
+        SHADER_HANDLE.into()
+    }
+
+    fn deferred_fragment_shader() -> ShaderRef {
+        SHADER_HANDLE.into()
+    }
+}
@@ -0,0 +1,94 @@
+#import bevy_pbr::{
+    pbr_fragment::pbr_input_from_standard_material,
+    pbr_functions::alpha_discard,
+    pbr_types::{PbrInput, STANDARD_MATERIAL_FLAGS_UNLIT_BIT},
+    mesh_view_bindings::view,
+}
+#import bevy_sk::{
+    lighting::{sk_lighting, sk_sh_exposure},
+    brdf::{sk_pbr_fresnel_schlick_roughness, sk_pbr_brdf_appx},
+}
+
+#ifdef PREPASS_PIPELINE
+#import bevy_pbr::{
+    prepass_io::{VertexOutput, FragmentOutput},
+    pbr_deferred_functions::deferred_output,
+}
+#else
+#import bevy_pbr::{
+    forward_io::{VertexOutput, FragmentOutput},
+    pbr_functions::{apply_pbr_lighting, main_pass_post_lighting_processing},
+}
+#endif
+
+struct ShExtension {
+    sh_slot: u32,
+};
+
+@group(2) @binding(100)
+var<uniform> sh_extension: ShExtension;
+@group(2) @binding(101)
+var sh_buffer: texture_2d<f32>;
+
+// Reads the SH of a ShLightingBuffer slot, one row of 9 texels per slot
+fn sk_extension_sh(slot: u32) -> array<vec3<f32>, 9> {
+    let first = textureLoad(sh_buffer, vec2<i32>(0, i32(slot)), 0);
+    let exposure = sk_sh_exposure(first.a, view.exposure);
+    var sh: array<vec3<f32>, 9>;
+    for (var i = 0u; i < 9u; i += 1u) {
+        sh[i] = textureLoad(sh_buffer, vec2<i32>(i32(i), i32(slot)), 0).rgb * exposure;
+    }
+    return sh;
+}
+
+// The SH diffuse and reflection of a StandardMaterial, shaded like PbrMaterial's ambient
+fn sk_sh_ambient(pbr_input: PbrInput) -> vec3<f32> {
+    let material = pbr_input.material;
+    let N = pbr_input.N;
+    let V = pbr_input.V;
+    let roughness = material.perceptual_roughness;
+    let spherical_harmonics = sk_extension_sh(sh_extension.sh_slot);
+
+    let dielectric_f0 = 0.16 * material.reflectance * material.reflectance;
+    let F0 = mix(vec3(dielectric_f0), material.base_color.rgb, material.metallic);
+    let ndotv = max(dot(N, V), 0.0001);
+    let F = sk_pbr_fresnel_schlick_roughness(ndotv, F0, roughness);
+    let kD = (vec3(1.0) - F) * (1.0 - material.metallic);
+
+    let diffuse = kD * material.base_color.rgb * sk_lighting(N, spherical_harmonics);
+    let env_brdf = sk_pbr_brdf_appx(roughness, ndotv);
+    let specular = sk_lighting(reflect(-V, N), spherical_harmonics)
+        * (F * env_brdf.x + env_brdf.y);
+    return diffuse * pbr_input.diffuse_occlusion + specular * pbr_input.specular_occlusion;
+}
+
+@fragment
+fn fragment(in: VertexOutput, @builtin(front_facing) is_front: bool) -> FragmentOutput {
+    var pbr_input = pbr_input_from_standard_material(in, is_front);
+    pbr_input.material.base_color = alpha_discard(
+        pbr_input.material,
+        pbr_input.material.base_color
+    );
+
+    // The SH rides along as emission, which both the forward and deferred lighting add as is
+    if ((pbr_input.material.flags & STANDARD_MATERIAL_FLAGS_UNLIT_BIT) == 0u) {
+        let ambient = sk_sh_ambient(pbr_input);
+        pbr_input.material.emissive = vec4(
+            pbr_input.material.emissive.rgb + ambient,
+            pbr_input.material.emissive.a
+        );
+    }
+
+#ifdef PREPASS_PIPELINE
+    let out = deferred_output(in, pbr_input);
+#else
+    var out: FragmentOutput;
+    if ((pbr_input.material.flags & STANDARD_MATERIAL_FLAGS_UNLIT_BIT) == 0u) {
+        out.color = apply_pbr_lighting(pbr_input);
+    } else {
+        out.color = pbr_input.material.base_color;
+    }
+    out.color = main_pass_post_lighting_processing(pbr_input, out.color);
+#endif
+    return out;
+}
@@ -0,0 +1,137 @@
+#define_import_path bevy_sk::brdf
+
+fn sk_pbr_fresnel_schlick_roughness(ndotv: f32, F0: vec3<f32>, roughness: f32) -> vec3<f32> {
+    return F0 + (max(vec3(1.0 - roughness), F0) - F0) * pow(1.0 - ndotv, 5.0);
+}
+
+fn sk_pbr_brdf_appx(roughness: f32, ndotv: f32) -> vec2<f32> {
+    let c0 = vec4(-1.0, -0.0275, -0.572, 0.022);
+    let c1 = vec4(1.0, 0.0425, 1.04, -0.04);
+    let r = roughness * c0 + c1;
+    let a004 = min(r.x * r.x, exp2(-9.28 * ndotv)) * r.x + r.y;
+    return vec2(-1.04, 1.04) * a004 + r.zw;
+}
+
+// Single scattering GGX loses the energy that bounces between microfacets more than once,
+// which darkens rough metals. Scaling by 1 + F0 * (1 / Ess - 1) puts it back (Fdez-Aguera,
+// as used by Filament and StandardMaterial), where Ess = A + B of the split sum.
+fn sk_multiscatter_compensation(F0: vec3<f32>, env_brdf: vec2<f32>) -> vec3<f32> {
+    let ess = max(env_brdf.x + env_brdf.y, 0.0001);
+    return 1.0 + F0 * (1.0 / ess - 1.0);
+}
+
+const SPECULAR_AA_VARIANCE: f32 = 0.25;
+const SPECULAR_AA_THRESHOLD: f32 = 0.18;
+
+// Widens roughness by the screen-space variance of the shading normal (Kaplanyan/Tokuyoshi),
+// which also covers normal map detail since it works on the final normal.
+fn sk_specular_aa(normal: vec3<f32>, roughness: f32) -> f32 {
+    let du = dpdx(normal);
+    let dv = dpdy(normal);
+    let variance = SPECULAR_AA_VARIANCE * (dot(du, du) + dot(dv, dv));
+    let kernel = min(2.0 * variance, SPECULAR_AA_THRESHOLD);
+    let a = roughness * roughness;
+    let a2 = clamp(a * a + kernel, 0.0, 1.0);
+    return sqrt(sqrt(a2));
+}
+
+// Thin-film interference: the path difference between the reflections off the film's top and
+// bottom shifts the phase per wavelength, tinting the base fresnel with angle dependent bands.
+fn sk_iridescence_fresnel(ndotv: f32, thickness: f32, film_ior: f32, F: vec3<f32>) -> vec3<f32> {
+    let sin2_t = (1.0 - ndotv * ndotv) / (film_ior * film_ior);
+    let cos_t = sqrt(max(1.0 - sin2_t, 0.0));
+    // Optical path difference in nanometers, against the R, G and B wavelengths
+    let opd = 2.0 * film_ior * thickness * cos_t;
+    let phase = 6.2831853 * opd / vec3(650.0, 510.0, 475.0);
+    let interference = 0.5 + 0.5 * cos(phase);
+    return clamp(F * interference * 2.0, vec3(0.0), vec3(1.0));
+}
+
+// Schlick fresnel of a clear coat with the usual 1.5 IOR
+fn sk_clearcoat_fresnel(ndotv: f32) -> f32 {
+    return 0.04 + 0.96 * pow(1.0 - ndotv, 5.0);
+}
+
+// GGX specular lobe of a clear coat with normal Nc, scaled by its intensity and NdotL
+fn sk_clearcoat_brdf(Nc: vec3<f32>, V: vec3<f32>, L: vec3<f32>, roughness: f32) -> f32 {
+    let ndotl = dot(Nc, L);
+    if (ndotl <= 0.0) {
+        return 0.0;
+    }
+    let H = normalize(L + V);
+    let ndoth = saturate(dot(Nc, H));
+    let a = max(roughness * roughness, 0.002);
+    let a2 = a * a;
+    let d = ndoth * ndoth * (a2 - 1.0) + 1.0;
+    let D = a2 / (3.14159265 * d * d);
+    // Kelemen visibility, cheap and good enough for a thin layer
+    let ldoth = saturate(dot(L, H));
+    let vis = 0.25 / max(ldoth * ldoth, 0.0001);
+    let F = sk_clearcoat_fresnel(ldoth);
+    return D * vis * F * ndotl;
+}
+
+// Lambert diffuse and, when `specular` is set, GGX specular with height correlated Smith
+// visibility for a light arriving from L. Multiply by the light's color, NdotL is included.
+fn sk_direct_brdf(
+    N: vec3<f32>,
+    V: vec3<f32>,
+    L: vec3<f32>,
+    diffuse_color: vec3<f32>,
+    F0: vec3<f32>,
+    roughness: f32,
+    specular: bool,
+) -> vec3<f32> {
+    let ndotl = dot(N, L);
+    if (ndotl <= 0.0) {
+        return vec3(0.0);
+    }
+    var result = diffuse_color * (1.0 / 3.14159265);
+    if (specular) {
+        let H = normalize(L + V);
+        let ndoth = saturate(dot(N, H));
+        let ndotv = max(dot(N, V), 0.0001);
+        let a = max(roughness * roughness, 0.002);
+        let a2 = a * a;
+        let d = ndoth * ndoth * (a2 - 1.0) + 1.0;
+        let D = a2 / (3.14159265 * d * d);
+        let vis = 0.5 / (ndotl * sqrt(ndotv * ndotv * (1.0 - a2) + a2)
+            + ndotv * sqrt(ndotl * ndotl * (1.0 - a2) + a2));
+        let F = F0 + (1.0 - F0) * pow(1.0 - saturate(dot(L, H)), 5.0);
+        result = result * (1.0 - F) + D * vis * F;
+    }
+    return result * ndotl;
+}
+
+// sk_direct_brdf with an anisotropic GGX lobe stretched along T, anisotropy in 0..1 (Filament)
+fn sk_direct_brdf_anisotropic(
+    N: vec3<f32>,
+    V: vec3<f32>,
+    L: vec3<f32>,
+    T: vec3<f32>,
+    B: vec3<f32>,
+    diffuse_color: vec3<f32>,
+    F0: vec3<f32>,
+    roughness: f32,
+    anisotropy: f32,
+) -> vec3<f32> {
+    let ndotl = dot(N, L);
+    if (ndotl <= 0.0) {
+        return vec3(0.0);
+    }
+    let H = normalize(L + V);
+    let ndoth = saturate(dot(N, H));
+    let ndotv = max(dot(N, V), 0.0001);
+    let a = max(roughness * roughness, 0.002);
+    let at = max(a * (1.0 + anisotropy), 0.002);
+    let ab = max(a * (1.0 - anisotropy), 0.002);
+
+    let a2 = at * ab;
+    let d = vec3(ab * dot(T, H), at * dot(B, H), a2 * ndoth);
+    let w2 = a2 / max(dot(d, d), 0.0000001);
+    let D = a2 * w2 * w2 / 3.14159265;
+    let vis = 0.5 / (ndotl * length(vec3(at * dot(T, V), ab * dot(B, V), ndotv))
+        + ndotv * length(vec3(at * dot(T, L), ab * dot(B, L), ndotl)));
+    let F = F0 + (1.0 - F0) * pow(1.0 - saturate(dot(L, H)), 5.0);
+    return (diffuse_color * (1.0 / 3.14159265) * (1.0 - F) + D * vis * F) * ndotl;
+}
@@ -0,0 +1,45 @@
+#define_import_path bevy_sk::lighting
+
+// Scale of the SH in a ShLightingBuffer slot, whose first texel's alpha holds the reciprocal
+// of the exposure it was made for when it follows the camera exposure, 0 when it doesn't
+fn sk_sh_exposure(texel_alpha: f32, view_exposure: f32) -> f32 {
+    return select(1.0, view_exposure * texel_alpha, texel_alpha > 0.0);
+}
+
+// Evaluates the 2nd order SH uploaded by PbrMaterial in the given direction, only up to band 1
+// with SK_SH_SKIP_BAND2
+fn sk_lighting(normal: vec3<f32>, spherical_harmonics: array<vec3<f32>, 9>) -> vec3<f32> {
+    // Band 0
+    var result = spherical_harmonics[0];
+
+    // Band 1
+    result += spherical_harmonics[1] * normal.y;
+    result += spherical_harmonics[2] * normal.z;
+    result += spherical_harmonics[3] * normal.x;
+
+#ifndef SK_SH_SKIP_BAND2
+    // Band 2
+    let n = normal * normal;
+    let n2 = normal.xyz * normal.yzx;
+    result += spherical_harmonics[4] * n2.x;
+    result += spherical_harmonics[5] * n2.y;
+    result += spherical_harmonics[6] * (3.0 * n.z - 1.0);
+    result += spherical_harmonics[7] * n2.z;
+    result += spherical_harmonics[8] * (n.x - n.y);
+#endif
+
+    return result;
+}
+
+// Band 3 of third order SH, premultiplied by its basis constants like Sh3::shader_band3
+fn sk_lighting_band3(dir: vec3<f32>, band3: array<vec3<f32>, 7>) -> vec3<f32> {
+    let n = dir * dir;
+    var result = band3[0] * dir.y * (3.0 * n.x - n.y);
+    result += band3[1] * dir.x * dir.y * dir.z;
+    result += band3[2] * dir.y * (5.0 * n.z - 1.0);
+    result += band3[3] * dir.z * (5.0 * n.z - 3.0);
+    result += band3[4] * dir.x * (5.0 * n.z - 1.0);
+    result += band3[5] * dir.z * (n.x - n.y);
+    result += band3[6] * dir.x * (n.x - 3.0 * n.y);
+    return result;
+}
@@ -0,0 +1,236 @@
+#define_import_path bevy_sk::lights
+
+#import bevy_pbr::{
+    clustering::{fragment_cluster_index, unpack_offset_and_counts, get_light_id},
+    mesh_view_bindings::{view, lights, point_lights},
+    mesh_view_types::{
+        DIRECTIONAL_LIGHT_FLAGS_SHADOWS_ENABLED_BIT, POINT_LIGHT_FLAGS_SHADOWS_ENABLED_BIT,
+        POINT_LIGHT_FLAGS_SPOT_LIGHT_Y_NEGATIVE,
+    },
+    mesh_types::MESH_FLAGS_SHADOW_RECEIVER_BIT,
+    shadows::{fetch_directional_shadow, fetch_point_shadow, fetch_spot_shadow},
+}
+#import bevy_sk::brdf::{
+    sk_direct_brdf, sk_direct_brdf_anisotropic, sk_clearcoat_brdf, sk_clearcoat_fresnel,
+}
+
+// What the direct lights need to know about a shaded point
+struct SkSurface {
+    // Fragment position in framebuffer space, picks the light cluster
+    frag_coord: vec4<f32>,
+    world_position: vec4<f32>,
+    // Shading normal
+    N: vec3<f32>,
+    // Interpolated vertex normal, offsets the shadow lookups
+    geometry_normal: vec3<f32>,
+    V: vec3<f32>,
+    // Albedo with the metallic part removed
+    diffuse_color: vec3<f32>,
+    F0: vec3<f32>,
+    roughness: f32,
+    // Clear coat intensity and roughness, no coat at 0
+    clearcoat: f32,
+    clearcoat_roughness: f32,
+    // Normal of the clear coat
+    clearcoat_normal: vec3<f32>,
+    // Anisotropy strength, isotropic at 0, and the directions of the stretched highlights
+    anisotropy: f32,
+    anisotropy_T: vec3<f32>,
+    anisotropy_B: vec3<f32>,
+    // Mesh flags from `pbr_input.flags`
+    mesh_flags: u32,
+};
+
+fn sk_view_z(world_position: vec4<f32>) -> f32 {
+    return dot(vec4(
+        view.view_from_world[0].z,
+        view.view_from_world[1].z,
+        view.view_from_world[2].z,
+        view.view_from_world[3].z
+    ), world_position);
+}
+
+// The surface's response to a light from L, the clear coat over the base layers. Multiply by
+// the light's color.
+fn sk_surface_brdf(surface: SkSurface, L: vec3<f32>, specular: bool) -> vec3<f32> {
+    var base: vec3<f32>;
+    if (specular && surface.anisotropy > 0.0) {
+        base = sk_direct_brdf_anisotropic(
+            surface.N, surface.V, L, surface.anisotropy_T, surface.anisotropy_B,
+            surface.diffuse_color, surface.F0, surface.roughness, surface.anisotropy
+        );
+    } else {
+        base = sk_direct_brdf(
+            surface.N, surface.V, L, surface.diffuse_color, surface.F0, surface.roughness,
+            specular
+        );
+    }
+    if (!specular || surface.clearcoat <= 0.0) {
+        return base;
+    }
+    let coat_ndotv = max(dot(surface.clearcoat_normal, surface.V), 0.0001);
+    let coat = sk_clearcoat_brdf(
+        surface.clearcoat_normal, surface.V, L, surface.clearcoat_roughness
+    );
+    let attenuation = 1.0 - sk_clearcoat_fresnel(coat_ndotv) * surface.clearcoat;
+    return base * attenuation + vec3(coat * surface.clearcoat);
+}
+
+#ifdef SK_LIGHT_COOKIES
+@group(2) @binding(34)
+var light_cookies: texture_2d_array<f32>;
+@group(2) @binding(35)
+var light_cookies_sampler: sampler;
+// One row per LightCookie: position and kind, forward and layer, then the scaled right and up
+// axes, see `cookie_row` in cookie.rs
+@group(2) @binding(36)
+var light_cookie_data: texture_2d<f32>;
+#endif
+
+// Kinds of lights in the cookie rows, 0 ends them
+const SK_DIRECTIONAL_COOKIE: f32 = 1.0;
+const SK_SPOT_COOKIE: f32 = 2.0;
+
+// Color of the LightCookie projected onto the surface by a light of `kind` at `position`
+// looking along `forward`, white for lights without one. Bevy doesn't tell the shader which
+// entity a light came from, so cookies are matched by their light's direction and position.
+fn sk_light_cookie(
+    kind: f32, position: vec3<f32>, forward: vec3<f32>, world_position: vec3<f32>
+) -> vec3<f32> {
+#ifdef SK_LIGHT_COOKIES
+    for (var i = 0u; i < textureDimensions(light_cookie_data).y; i += 1u) {
+        let origin = textureLoad(light_cookie_data, vec2(0u, i), 0);
+        if (origin.w == 0.0) {
+            break;
+        }
+        let axis = textureLoad(light_cookie_data, vec2(1u, i), 0);
+        if (origin.w != kind || dot(axis.xyz, forward) < 0.9999
+            || (kind == SK_SPOT_COOKIE && distance(origin.xyz, position) > 0.001)) {
+            continue;
+        }
+        let right = textureLoad(light_cookie_data, vec2(2u, i), 0).xyz;
+        let up = textureLoad(light_cookie_data, vec2(3u, i), 0).xyz;
+        var offset = world_position - origin.xyz;
+        if (kind == SK_SPOT_COOKIE) {
+            // Perspective projection, the outer cone fits in the texture
+            let depth = dot(offset, axis.xyz);
+            if (depth <= 0.0) {
+                return vec3(0.0);
+            }
+            offset /= depth;
+        }
+        let uv = vec2(dot(offset, right), -dot(offset, up)) * 0.5 + 0.5;
+        return textureSampleLevel(
+            light_cookies, light_cookies_sampler, uv, i32(axis.w), 0.0
+        ).rgb;
+    }
+#endif
+    return vec3(1.0);
+}
+
+fn sk_receives_shadows(surface: SkSurface) -> bool {
+    return (surface.mesh_flags & MESH_FLAGS_SHADOW_RECEIVER_BIT) != 0u;
+}
+
+// Bevy's directional lights with their cascaded shadow maps, exposed like StandardMaterial.
+// Only the diffuse part is evaluated when `specular` is false.
+fn sk_directional_lights(surface: SkSurface, specular: bool) -> vec3<f32> {
+    let view_z = sk_view_z(surface.world_position);
+    let receives_shadows = sk_receives_shadows(surface);
+    var light_sum = vec3(0.0);
+    for (var i = 0u; i < lights.n_directional_lights; i += 1u) {
+        let light = &lights.directional_lights[i];
+        if ((*light).skip != 0u) {
+            continue;
+        }
+        var shadow = 1.0;
+        if (receives_shadows
+            && ((*light).flags & DIRECTIONAL_LIGHT_FLAGS_SHADOWS_ENABLED_BIT) != 0u) {
+            shadow = fetch_directional_shadow(
+                i, surface.world_position, surface.geometry_normal, view_z
+            );
+        }
+        let cookie = sk_light_cookie(
+            SK_DIRECTIONAL_COOKIE, vec3(0.0), -(*light).direction_to_light,
+            surface.world_position.xyz
+        );
+        let brdf = sk_surface_brdf(surface, (*light).direction_to_light, specular);
+        light_sum += brdf * (*light).color.rgb * shadow * cookie;
+    }
+    return light_sum * view.exposure;
+}
+
+// Smooth range falloff times inverse square attenuation, like StandardMaterial
+fn sk_distance_attenuation(distance_square: f32, inverse_range_squared: f32) -> f32 {
+    let factor = distance_square * inverse_range_squared;
+    let smooth_factor = saturate(1.0 - factor * factor);
+    return smooth_factor * smooth_factor / max(distance_square, 0.0001);
+}
+
+// One of Bevy's clustered point or spot lights
+fn sk_point_light(light_id: u32, surface: SkSurface, specular: bool, spot: bool) -> vec3<f32> {
+    let light = &point_lights.data[light_id];
+    let light_to_frag = (*light).position_radius.xyz - surface.world_position.xyz;
+    let L = normalize(light_to_frag);
+    var attenuation = sk_distance_attenuation(
+        dot(light_to_frag, light_to_frag), (*light).color_inverse_square_range.w
+    );
+
+    var cookie = vec3(1.0);
+    if (spot) {
+        // The spot direction is packed as its xz and the sign of y
+        var spot_dir = vec3((*light).light_custom_data.x, 0.0, (*light).light_custom_data.y);
+        spot_dir.y = sqrt(max(0.0, 1.0 - spot_dir.x * spot_dir.x - spot_dir.z * spot_dir.z));
+        if (((*light).flags & POINT_LIGHT_FLAGS_SPOT_LIGHT_Y_NEGATIVE) != 0u) {
+            spot_dir.y = -spot_dir.y;
+        }
+        let cone = saturate(
+            dot(-spot_dir, L) * (*light).light_custom_data.z + (*light).light_custom_data.w
+        );
+        attenuation *= cone * cone;
+        cookie = sk_light_cookie(
+            SK_SPOT_COOKIE, (*light).position_radius.xyz, spot_dir, surface.world_position.xyz
+        );
+    }
+    if (attenuation <= 0.0) {
+        return vec3(0.0);
+    }
+
+    var shadow = 1.0;
+    if (sk_receives_shadows(surface)
+        && ((*light).flags & POINT_LIGHT_FLAGS_SHADOWS_ENABLED_BIT) != 0u) {
+        if (spot) {
+            shadow = fetch_spot_shadow(light_id, surface.world_position, surface.geometry_normal);
+        } else {
+            shadow = fetch_point_shadow(light_id, surface.world_position, surface.geometry_normal);
+        }
+    }
+
+    let brdf = sk_surface_brdf(surface, L, specular);
+    return brdf * (*light).color_inverse_square_range.rgb * attenuation * shadow * cookie;
+}
+
+// The point and spot lights of the fragment's cluster
+fn sk_clustered_lights(surface: SkSurface, specular: bool) -> vec3<f32> {
+    let is_orthographic = view.clip_from_view[3].w == 1.0;
+    let cluster_index = fragment_cluster_index(
+        surface.frag_coord.xy, sk_view_z(surface.world_position), is_orthographic
+    );
+    let offset_and_counts = unpack_offset_and_counts(cluster_index);
+    let point_end = offset_and_counts[0] + offset_and_counts[1];
+    let spot_end = point_end + offset_and_counts[2];
+
+    var light_sum = vec3(0.0);
+    for (var i = offset_and_counts[0]; i < point_end; i += 1u) {
+        light_sum += sk_point_light(get_light_id(i), surface, specular, false);
+    }
+    for (var i = point_end; i < spot_end; i += 1u) {
+        light_sum += sk_point_light(get_light_id(i), surface, specular, true);
+    }
+    return light_sum * view.exposure;
+}
+
+// Every Bevy light reaching the surface, directional and clustered
+fn sk_lights(surface: SkSurface, specular: bool) -> vec3<f32> {
+    return sk_directional_lights(surface, specular) + sk_clustered_lights(surface, specular);
+}
@@ -0,0 +1,164 @@
+#define_import_path bevy_sk::pbr_types
+
+#ifdef SK_PACKED_UNIFORMS
+struct PbrMaterial {
+    // unorm8 rgba
+    color: u32,
+    // f16 rgba
+    emission_factor: vec2<u32>,
+    // unorm16 metallic, roughness
+    metallic_roughness: u32,
+    uv_scale: vec2<f32>,
+    uv_offset: vec2<f32>,
+    flags: u32,
+    sh_slot: u32,
+    iridescence: f32,
+    iridescence_ior: f32,
+    iridescence_thickness: f32,
+    lod_distance: f32,
+    reflection_blend: f32,
+    normal_scale: f32,
+    alpha_cutoff: f32,
+    reflectance: f32,
+    // f16 rgb tint, a factor
+    specular: vec2<u32>,
+    parallax_depth_scale: f32,
+    max_parallax_layer_count: f32,
+    clearcoat: f32,
+    clearcoat_roughness: f32,
+    anisotropy_strength: f32,
+    anisotropy_rotation: f32,
+    detail_uv_scale: vec2<f32>,
+    detail_blend: f32,
+    // f16 rgba
+    rim_color: vec2<u32>,
+    rim_power: f32,
+    // color, metal and occlusion layer of the texture arrays in bytes 0 to 2, 255 for none
+    array_layers: u32,
+};
+
+fn sk_material_color(material: PbrMaterial) -> vec4<f32> {
+    return unpack4x8unorm(material.color);
+}
+
+fn sk_material_emission(material: PbrMaterial) -> vec3<f32> {
+    let rg = unpack2x16float(material.emission_factor.x);
+    let ba = unpack2x16float(material.emission_factor.y);
+    return vec3(rg, ba.x);
+}
+
+fn sk_material_metallic_roughness(material: PbrMaterial) -> vec2<f32> {
+    return unpack2x16unorm(material.metallic_roughness);
+}
+
+fn sk_material_specular(material: PbrMaterial) -> vec4<f32> {
+    return vec4(unpack2x16float(material.specular.x), unpack2x16float(material.specular.y));
+}
+
+fn sk_material_rim_color(material: PbrMaterial) -> vec3<f32> {
+    return vec3(unpack2x16float(material.rim_color.x), unpack2x16float(material.rim_color.y).x);
+}
+#else
+struct PbrMaterial {
+    color: vec4<f32>,
+    emission_factor: vec4<f32>,
+    // x: metallic, y: roughness
+    metallic_roughness: vec2<f32>,
+    uv_scale: vec2<f32>,
+    uv_offset: vec2<f32>,
+    flags: u32,
+    sh_slot: u32,
+    iridescence: f32,
+    iridescence_ior: f32,
+    iridescence_thickness: f32,
+    lod_distance: f32,
+    reflection_blend: f32,
+    normal_scale: f32,
+    alpha_cutoff: f32,
+    reflectance: f32,
+    // rgb tint, a factor
+    specular: vec4<f32>,
+    parallax_depth_scale: f32,
+    max_parallax_layer_count: f32,
+    clearcoat: f32,
+    clearcoat_roughness: f32,
+    anisotropy_strength: f32,
+    anisotropy_rotation: f32,
+    detail_uv_scale: vec2<f32>,
+    detail_blend: f32,
+    rim_color: vec4<f32>,
+    rim_power: f32,
+    // color, metal and occlusion layer of the texture arrays in bytes 0 to 2, 255 for none
+    array_layers: u32,
+};
+
+fn sk_material_color(material: PbrMaterial) -> vec4<f32> {
+    return material.color;
+}
+
+fn sk_material_emission(material: PbrMaterial) -> vec3<f32> {
+    return material.emission_factor.rgb;
+}
+
+fn sk_material_metallic_roughness(material: PbrMaterial) -> vec2<f32> {
+    return material.metallic_roughness;
+}
+
+fn sk_material_specular(material: PbrMaterial) -> vec4<f32> {
+    return material.specular;
+}
+
+fn sk_material_rim_color(material: PbrMaterial) -> vec3<f32> {
+    return material.rim_color.rgb;
+}
+#endif
+
+// Mirrors PbrMaterialFlags in pbr.rs
+const SK_MATERIAL_FLAGS_ALPHA_MODE_MASK_BIT: u32   = 1u;
+const SK_MATERIAL_FLAGS_ALPHA_MODE_OPAQUE_BIT: u32 = 2u;
+const SK_MATERIAL_FLAGS_DIFFUSE_TEXTURE_BIT: u32   = 4u;
+const SK_MATERIAL_FLAGS_DOUBLE_SIDED_BIT: u32      = 8u;
+const SK_MATERIAL_FLAGS_EMISSION_TEXTURE_BIT: u32  = 16u;
+const SK_MATERIAL_FLAGS_METAL_TEXTURE_BIT: u32     = 32u;
+const SK_MATERIAL_FLAGS_OCCLUSION_TEXTURE_BIT: u32 = 64u;
+const SK_MATERIAL_FLAGS_SPECULAR_AA_BIT: u32       = 128u;
+const SK_MATERIAL_FLAGS_IRIDESCENCE_BIT: u32       = 256u;
+const SK_MATERIAL_FLAGS_REFLECTION_PROBE_BIT: u32  = 512u;
+const SK_MATERIAL_FLAGS_REFLECTION_PROBE_BLEND_BIT: u32 = 1024u;
+const SK_MATERIAL_FLAGS_NORMAL_TEXTURE_BIT: u32    = 2048u;
+const SK_MATERIAL_FLAGS_ALPHA_MODE_BLEND_BIT: u32  = 4096u;
+const SK_MATERIAL_FLAGS_ALPHA_MODE_PREMULTIPLIED_BIT: u32 = 8192u;
+const SK_MATERIAL_FLAGS_ALPHA_MODE_ADD_BIT: u32    = 16384u;
+const SK_MATERIAL_FLAGS_ALPHA_MODE_MULTIPLY_BIT: u32 = 32768u;
+const SK_MATERIAL_FLAGS_DEPTH_TEXTURE_BIT: u32     = 65536u;
+const SK_MATERIAL_FLAGS_FOG_ENABLED_BIT: u32       = 131072u;
+const SK_MATERIAL_FLAGS_DITHERED_BIT: u32          = 262144u;
+const SK_MATERIAL_FLAGS_CLEARCOAT_BIT: u32         = 524288u;
+const SK_MATERIAL_FLAGS_CLEARCOAT_TEXTURE_BIT: u32 = 1048576u;
+const SK_MATERIAL_FLAGS_CLEARCOAT_ROUGHNESS_TEXTURE_BIT: u32 = 2097152u;
+const SK_MATERIAL_FLAGS_ANISOTROPY_BIT: u32        = 4194304u;
+const SK_MATERIAL_FLAGS_ANISOTROPY_TEXTURE_BIT: u32 = 8388608u;
+const SK_MATERIAL_FLAGS_DETAIL_COLOR_TEXTURE_BIT: u32 = 16777216u;
+const SK_MATERIAL_FLAGS_DETAIL_NORMAL_TEXTURE_BIT: u32 = 33554432u;
+const SK_MATERIAL_FLAGS_RIM_BIT: u32 = 67108864u;
+
+// Final color and alpha for the material's alpha mode, matching the blend state Bevy picks
+// for it. Premultiplied, Add and Multiply blend premultiplied, Add keeps the destination.
+fn sk_alpha_output(flags: u32, color: vec3<f32>, alpha: f32) -> vec4<f32> {
+    if (sk_has_flag(flags, SK_MATERIAL_FLAGS_ALPHA_MODE_OPAQUE_BIT)
+        || sk_has_flag(flags, SK_MATERIAL_FLAGS_ALPHA_MODE_MASK_BIT)) {
+        return vec4(color, 1.0);
+    }
+    if (sk_has_flag(flags, SK_MATERIAL_FLAGS_ALPHA_MODE_ADD_BIT)) {
+        return vec4(color * alpha, 0.0);
+    }
+    if (sk_has_flag(flags, SK_MATERIAL_FLAGS_ALPHA_MODE_PREMULTIPLIED_BIT)
+        || sk_has_flag(flags, SK_MATERIAL_FLAGS_ALPHA_MODE_MULTIPLY_BIT)) {
+        return vec4(color * alpha, alpha);
+    }
+    return vec4(color, alpha);
+}
+
+fn sk_has_flag(flags: u32, bit: u32) -> bool {
+    return (flags & bit) != 0u;
+}
@@ -0,0 +1,81 @@
+use crate::materials::pbr::PbrMaterial;
+use bevy::prelude::*;
+use bevy::utils::HashSet;
+
+/// Whether meshes without tangents get them generated once their `PbrMaterial`
+/// [`needs_tangents`](PbrMaterial::needs_tangents), otherwise their normal maps, parallax and
+/// anisotropy are skipped
+#[derive(Resource, Reflect, Clone, Copy, Debug, PartialEq, Eq)]
+#[reflect(Resource)]
+pub struct GenerateMissingTangents(pub bool);
+
+impl Default for GenerateMissingTangents {
+    fn default() -> Self {
+        Self(true)
+    }
+}
+
+pub(crate) fn generate_missing_tangents(
+    settings: Res<GenerateMissingTangents>,
+    entities: Query<(Ref<Handle<Mesh>>, Ref<Handle<PbrMaterial>>)>,
+    mut mesh_events: EventReader<AssetEvent<Mesh>>,
+    mut material_events: EventReader<AssetEvent<PbrMaterial>>,
+    materials: Res<Assets<PbrMaterial>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    // Meshes already generated for or that failed, each is only logged once
+    mut handled: Local<HashSet<AssetId<Mesh>>>,
+) {
+    let changed_meshes: HashSet<AssetId<Mesh>> = mesh_events
+        .read()
+        .filter_map(|e| match e {
+            AssetEvent::Added { id }
+            | AssetEvent::Modified { id }
+            | AssetEvent::LoadedWithDependencies { id } => Some(*id),
+            _ => None,
+        })
+        .collect();
+    let changed_materials: HashSet<AssetId<PbrMaterial>> = material_events
+        .read()
+        .filter_map(|e| match e {
+            AssetEvent::Added { id }
+            | AssetEvent::Modified { id }
+            | AssetEvent::LoadedWithDependencies { id } => Some(*id),
+            _ => None,
+        })
+        .collect();
+    if !settings.0 {
+        return;
+    }
+
+    for (mesh, material) in entities.iter() {
+        let changed = mesh.is_changed()
+            || material.is_changed()
+            || settings.is_changed()
+            || changed_meshes.contains(&mesh.id())
+            || changed_materials.contains(&material.id());
+        if !changed || handled.contains(&mesh.id()) {
+            continue;
+        }
+        if !materials.get(&*material).is_some_and(PbrMaterial::needs_tangents) {
+            continue;
+        }
+        // Meshes only kept in the render world can't be changed anymore
+        let Some(data) = meshes.get(&*mesh) else {
+            continue;
+        };
+        if data.contains_attribute(Mesh::ATTRIBUTE_TANGENT) {
+            continue;
+        }
+        handled.insert(mesh.id());
+        let Some(data) = meshes.get_mut(&*mesh) else {
+            continue;
+        };
+        match data.generate_tangents() {
+            Ok(()) => info!("Generated tangents for mesh {:?}", mesh.id()),
+            Err(error) => warn!(
+                "Mesh {:?} needs tangents for its PbrMaterial, generating them failed: {error}",
+                mesh.id()
+            ),
+        }
+    }
+}
@@ -0,0 +1,214 @@
+use crate::materials::pbr::alpha_mode_flags;
+use bevy::prelude::*;
+use bevy::render::mesh::{Indices, PrimitiveTopology};
+use bevy::render::render_asset::{RenderAssetUsages, RenderAssets};
+use bevy::render::render_resource::{AsBindGroup, AsBindGroupShaderType, ShaderRef, ShaderType};
+use bevy::render::texture::GpuImage;
+use bevy::utils::{HashMap, HashSet};
+
+const SHADER_HANDLE: Handle<Shader> = Handle::weak_from_u128(0x8c52f1e0a7b3);
+
+/// Registers [`SkTextMaterial`] and meshes every [`SkText`], added by `PbrPlugin`
+pub struct SkTextPlugin;
+
+impl Plugin for SkTextPlugin {
+    fn build(&self, app: &mut App) {
+        load_shader!(app, SHADER_HANDLE, "text.wgsl");
+        app.init_asset::<SdfFont>();
+        app.add_plugins(MaterialPlugin::<SkTextMaterial>::default());
+        app.register_asset_reflect::<SkTextMaterial>();
+        app.register_type::<SkText>();
+        app.add_systems(Update, update_text_meshes);
+    }
+}
+
+/// Signed distance field font atlas, single channel like StereoKit's or multi channel like
+/// msdf-atlas-gen's, with its glyph metrics in em
+#[derive(Asset, TypePath, Clone, Debug, Default)]
+pub struct SdfFont {
+    pub atlas: Handle<Image>,
+    pub glyphs: HashMap<char, SdfGlyph>,
+    /// Distance between baselines
+    pub line_height: f32,
+}
+
+/// Placement of a glyph of an [`SdfFont`]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SdfGlyph {
+    /// The glyph's rectangle in the atlas, in UVs with y down
+    pub uv: Rect,
+    /// The glyph's quad relative to the pen on the baseline, y up
+    pub bounds: Rect,
+    /// Pen movement to the next glyph
+    pub advance: f32,
+}
+
+/// Horizontal alignment of the lines of an [`SkText`] around its origin
+#[derive(Reflect, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum TextAlign {
+    #[default]
+    Left,
+    Center,
+    Right,
+}
+
+impl SdfFont {
+    /// Quads of the glyphs of `text` in local XY facing +Z, `size` meters per em. The first
+    /// baseline starts at the origin and every `\n` starts a new line below it.
+    pub fn text_mesh(&self, text: &str, size: f32, align: TextAlign) -> Mesh {
+        let mut positions = Vec::new();
+        let mut uvs = Vec::new();
+        let mut indices = Vec::new();
+        for (line_index, line) in text.lines().enumerate() {
+            let glyphs = line.chars().filter_map(|c| self.glyphs.get(&c));
+            let width: f32 = glyphs.clone().map(|glyph| glyph.advance).sum();
+            let mut pen = Vec2::new(
+                match align {
+                    TextAlign::Left => 0.0,
+                    TextAlign::Center => -width * 0.5,
+                    TextAlign::Right => -width,
+                },
+                -(line_index as f32) * self.line_height,
+            );
+            for glyph in glyphs {
+                // Spaces only advance
+                if glyph.bounds.width() > 0.0 && glyph.bounds.height() > 0.0 {
+                    let min = (pen + glyph.bounds.min) * size;
+                    let max = (pen + glyph.bounds.max) * size;
+                    let base = positions.len() as u32;
+                    positions.extend([
+                        [min.x, min.y, 0.0],
+                        [max.x, min.y, 0.0],
+                        [max.x, max.y, 0.0],
+                        [min.x, max.y, 0.0],
+                    ]);
+                    let uv = glyph.uv;
+                    uvs.extend([
+                        [uv.min.x, uv.max.y],
+                        [uv.max.x, uv.max.y],
+                        [uv.max.x, uv.min.y],
+                        [uv.min.x, uv.min.y],
+                    ]);
+                    indices.extend([base, base + 1, base + 2, base, base + 2, base + 3]);
+                }
+                pen.x += glyph.advance;
+            }
+        }
+        let normals = vec![[0.0, 0.0, 1.0]; positions.len()];
+        Mesh::new(PrimitiveTopology::TriangleList, RenderAssetUsages::default())
+            .with_inserted_attribute(Mesh::ATTRIBUTE_POSITION, positions)
+            .with_inserted_attribute(Mesh::ATTRIBUTE_NORMAL, normals)
+            .with_inserted_attribute(Mesh::ATTRIBUTE_UV_0, uvs)
+            .with_inserted_indices(Indices::U32(indices))
+    }
+}
+
+/// World space text, meshed from its [`SdfFont`] whenever either changes. Give the entity an
+/// [`SkTextMaterial`] with the font's atlas to draw it.
+#[derive(Component, Reflect, Clone, Debug, Default)]
+#[reflect(Component)]
+pub struct SkText {
+    pub text: String,
+    pub font: Handle<SdfFont>,
+    /// Meters per em
+    pub size: f32,
+    pub align: TextAlign,
+}
+
+fn update_text_meshes(
+    mut commands: Commands,
+    texts: Query<(Entity, Ref<SkText>, Option<&Handle<Mesh>>)>,
+    mut events: EventReader<AssetEvent<SdfFont>>,
+    fonts: Res<Assets<SdfFont>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+) {
+    let changed: HashSet<AssetId<SdfFont>> = events
+        .read()
+        .filter_map(|e| match e {
+            AssetEvent::Added { id }
+            | AssetEvent::Modified { id }
+            | AssetEvent::LoadedWithDependencies { id } => Some(*id),
+            _ => None,
+        })
+        .collect();
+    for (entity, text, mesh) in texts.iter() {
+        if !text.is_changed() && !changed.contains(&text.font.id()) {
+            continue;
+        }
+        // Texts waiting for their font are meshed once it's added
+        let Some(font) = fonts.get(&text.font) else {
+            continue;
+        };
+        let text_mesh = font.text_mesh(&text.text, text.size, text.align);
+        let mut entity = commands.entity(entity);
+        // Bevy only computes the bounds of a mesh once
+        entity.insert(text_mesh.compute_aabb().unwrap_or_default());
+        match mesh.filter(|mesh| meshes.contains(*mesh)) {
+            Some(mesh) => meshes.insert(mesh, text_mesh),
+            None => {
+                entity.insert(meshes.add(text_mesh));
+            }
+        }
+    }
+}
+
+/// StereoKit's `font` shader, draws the glyphs of an [`SdfFont`] atlas with edges that stay
+/// crisp at any distance
+#[derive(Asset, AsBindGroup, Reflect, PartialEq, Debug, Clone)]
+#[uniform(0, SkTextMaterialUniform)]
+pub struct SkTextMaterial {
+    pub color: Color,
+    #[texture(1)]
+    #[sampler(2)]
+    pub font_atlas: Handle<Image>,
+    /// The atlas stores a multi channel distance field in rgb instead of one in red
+    pub multi_channel: bool,
+    pub alpha_mode: AlphaMode,
+}
+
+impl Default for SkTextMaterial {
+    fn default() -> Self {
+        Self {
+            color: Color::WHITE,
+            font_atlas: Handle::default(),
+            multi_channel: false,
+            alpha_mode: AlphaMode::Blend,
+        }
+    }
+}
+
+impl SkTextMaterial {
+    pub fn new(font: &SdfFont) -> Self {
+        Self {
+            font_atlas: font.atlas.clone(),
+            ..default()
+        }
+    }
+}
+
+#[derive(Clone, Default, ShaderType)]
+pub struct SkTextMaterialUniform {
+    pub color: Vec4,
+    pub flags: u32,
+    pub multi_channel: u32,
+}
+
+impl AsBindGroupShaderType<SkTextMaterialUniform> for SkTextMaterial {
+    fn as_bind_group_shader_type(&self, _: &RenderAssets<GpuImage>) -> SkTextMaterialUniform {
+        SkTextMaterialUniform {
+            color: self.color.to_linear().to_vec4(),
+            flags: alpha_mode_flags(self.alpha_mode).0.bits(),
+            multi_channel: self.multi_channel as u32,
+        }
+    }
+}
+
+impl Material for SkTextMaterial {
+    fn fragment_shader() -> ShaderRef {
+        SHADER_HANDLE.into()
+    }
+
+    fn alpha_mode(&self) -> AlphaMode {
+        self.alpha_mode
+    }
+}
@@ -0,0 +1,40 @@
+#import bevy_pbr::forward_io::VertexOutput
+#import bevy_sk::pbr_types::{sk_has_flag, sk_alpha_output, SK_MATERIAL_FLAGS_ALPHA_MODE_MASK_BIT}
+
+struct SkTextMaterial {
+    color: vec4<f32>,
+    flags: u32,
+    multi_channel: u32,
+};
+
+@group(2) @binding(0)
+var<uniform> material: SkTextMaterial;
+@group(2) @binding(1)
+var font_atlas: texture_2d<f32>;
+@group(2) @binding(2)
+var font_sampler: sampler;
+
+fn sk_median(v: vec3<f32>) -> f32 {
+    return max(min(v.r, v.g), min(max(v.r, v.g), v.b));
+}
+
+@fragment
+fn fragment(in: VertexOutput) -> @location(0) vec4<f32> {
+    let texel = textureSample(font_atlas, font_sampler, in.uv);
+    var distance = texel.r;
+    if (material.multi_channel != 0u) {
+        distance = sk_median(texel.rgb);
+    }
+    // Half a pixel of falloff on each side of the edge, however far away the text is
+    let width = max(fwidth(distance) * 0.5, 0.0001);
+    var color = material.color;
+#ifdef VERTEX_COLORS
+    color *= in.color;
+#endif
+    let alpha = color.a * smoothstep(0.5 - width, 0.5 + width, distance);
+
+    if (sk_has_flag(material.flags, SK_MATERIAL_FLAGS_ALPHA_MODE_MASK_BIT) && alpha < 0.5) {
+        discard;
+    }
+    return sk_alpha_output(material.flags, color.rgb, alpha);
+}
@@ -0,0 +1,217 @@
+use crate::materials::pbr::{
+    replace_materials, ConvertedStandardMaterial, MaterialReplaced, PbrMaterial,
+};
+use bevy::prelude::*;
+use bevy::render::render_asset::RenderAssetUsages;
+use bevy::render::render_resource::{
+    TextureDimension, TextureFormat, TextureViewDescriptor, TextureViewDimension,
+};
+use bevy::utils::{HashMap, HashSet};
+
+/// Packs the color, metal and occlusion textures of `PbrMaterial`s converted from
+/// `StandardMaterial`s into texture arrays they share, with each material picking its layers
+/// through [`PbrMaterial::array_layers`]. Many small materials then bind the same textures,
+/// which saves texture state changes on the tiler GPUs of standalone headsets.
+///
+/// Only textures of the same size, format and mip count share an array, and an array samples
+/// with the sampler of its first layer. Packed materials no longer reference the plain
+/// textures, which `StandardMaterial` conversions put back when their source changes. Needs
+/// `PbrPlugin`, not part of `SkPlugins`, and does nothing with the `webgl2` feature, which
+/// doesn't bind the arrays.
+pub struct TextureArrayPackingPlugin;
+
+impl Plugin for TextureArrayPackingPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<TextureArrayPacking>();
+        app.register_type::<(TextureArrayPacking, TextureArrayLayers)>();
+        app.add_systems(Update, pack_texture_arrays.after(replace_materials));
+    }
+}
+
+/// Settings of [`TextureArrayPackingPlugin`]
+#[derive(Resource, Reflect, Clone, Debug, PartialEq)]
+#[reflect(Resource)]
+pub struct TextureArrayPacking {
+    pub enabled: bool,
+    /// Fewest textures of one size and format worth an array, smaller groups are left alone
+    pub min_layers: usize,
+    /// Layers per array, larger groups are split over several arrays, at most 255
+    pub max_layers: usize,
+}
+
+impl Default for TextureArrayPacking {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            min_layers: 4,
+            max_layers: 64,
+        }
+    }
+}
+
+/// Layers of a `PbrMaterial`'s color texture in its `color_array`, and of its metal and
+/// occlusion textures in its `data_array`
+#[derive(Reflect, Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct TextureArrayLayers {
+    pub color: Option<u8>,
+    pub metal: Option<u8>,
+    pub occlusion: Option<u8>,
+}
+
+impl TextureArrayLayers {
+    /// As read by the shader, a byte per layer with 255 for none
+    pub(crate) fn packed(&self) -> u32 {
+        [self.color, self.metal, self.occlusion]
+            .iter()
+            .enumerate()
+            .fold(0, |bits, (i, layer)| bits | (layer.unwrap_or(255) as u32) << (i * 8))
+    }
+}
+
+/// Color textures are usually sRGB and the others linear, so they go into separate arrays
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+enum ArrayKind {
+    Color,
+    Data,
+}
+
+/// What textures need in common to be layers of one array
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+struct ArrayKey {
+    kind: ArrayKind,
+    size: UVec2,
+    format: TextureFormat,
+    mips: u32,
+}
+
+/// The key of a plain 2d texture whose data is still in the main world
+fn array_key(kind: ArrayKind, image: &Image) -> Option<ArrayKey> {
+    let descriptor = &image.texture_descriptor;
+    let plain = descriptor.dimension == TextureDimension::D2
+        && descriptor.size.depth_or_array_layers == 1
+        && image.texture_view_descriptor.is_none()
+        && !image.data.is_empty();
+    plain.then(|| ArrayKey {
+        kind,
+        size: image.size(),
+        format: descriptor.format,
+        mips: descriptor.mip_level_count,
+    })
+}
+
+/// `layers` stacked into one array texture, which wgpu expects layer by layer with all mips
+fn array_image(layers: &[&Image]) -> Image {
+    let first = layers[0];
+    let mut image = Image {
+        data: layers.iter().flat_map(|layer| layer.data.iter().copied()).collect(),
+        texture_descriptor: first.texture_descriptor.clone(),
+        sampler: first.sampler.clone(),
+        texture_view_descriptor: Some(TextureViewDescriptor {
+            dimension: Some(TextureViewDimension::D2Array),
+            ..default()
+        }),
+        asset_usage: RenderAssetUsages::RENDER_WORLD,
+    };
+    image.texture_descriptor.size.depth_or_array_layers = layers.len() as u32;
+    image
+}
+
+fn pack_texture_arrays(
+    packing: Res<TextureArrayPacking>,
+    mut replaced: EventReader<MaterialReplaced>,
+    mut image_events: EventReader<AssetEvent<Image>>,
+    converted: Query<&Handle<PbrMaterial>, With<ConvertedStandardMaterial>>,
+    mut materials: ResMut<Assets<PbrMaterial>>,
+    mut images: ResMut<Assets<Image>>,
+) {
+    let replaced = replaced.read().count() > 0;
+    let loaded = image_events
+        .read()
+        .any(|e| matches!(e, AssetEvent::LoadedWithDependencies { .. }));
+    let enabled = packing.enabled && !cfg!(feature = "webgl2");
+    if !enabled || !(replaced || loaded || packing.is_changed()) {
+        return;
+    }
+
+    // Plain textures of every converted material, grouped by the array they fit in
+    let ids: HashSet<AssetId<PbrMaterial>> = converted.iter().map(Handle::id).collect();
+    let mut groups: HashMap<ArrayKey, Vec<AssetId<Image>>> = HashMap::new();
+    for material in ids.iter().filter_map(|id| materials.get(*id)) {
+        let key = |kind, texture: &Handle<Image>| array_key(kind, images.get(texture)?);
+        if let Some(texture) = &material.color_texture {
+            if let Some(key) = key(ArrayKind::Color, texture) {
+                groups.entry(key).or_default().push(texture.id());
+            }
+        }
+        // Metal and occlusion share the data array, so both have to fit the same one
+        let data: Vec<_> = [&material.metal_texture, &material.occlusion_texture]
+            .into_iter()
+            .flatten()
+            .collect();
+        let keys: Vec<_> = data.iter().map(|t| key(ArrayKind::Data, t)).collect();
+        if let Some(Some(key)) = keys.first().filter(|first| keys.iter().all(|k| k == *first)) {
+            groups.entry(*key).or_default().extend(data.iter().map(|t| t.id()));
+        }
+    }
+
+    // Array and layer of every packed texture
+    let max_layers = packing.max_layers.clamp(1, 255);
+    let mut layers: HashMap<AssetId<Image>, (Handle<Image>, u8)> = HashMap::new();
+    for textures in groups.values_mut() {
+        let mut seen = HashSet::new();
+        textures.retain(|texture| seen.insert(*texture));
+        if textures.len() < packing.min_layers.max(1) {
+            continue;
+        }
+        for chunk in textures.chunks(max_layers) {
+            let sources: Vec<&Image> = chunk.iter().filter_map(|id| images.get(*id)).collect();
+            let image = array_image(&sources);
+            let array = images.add(image);
+            for (layer, texture) in chunk.iter().enumerate() {
+                layers.insert(*texture, (array.clone(), layer as u8));
+            }
+        }
+    }
+    if layers.is_empty() {
+        return;
+    }
+
+    for id in ids {
+        let Some(material) = materials.get(id) else {
+            continue;
+        };
+        let packed = |texture: &Option<Handle<Image>>| {
+            texture.as_ref().and_then(|texture| layers.get(&texture.id()))
+        };
+        let color = packed(&material.color_texture).cloned();
+        let metal = packed(&material.metal_texture);
+        let occlusion = packed(&material.occlusion_texture);
+        // Chunking can split a material's metal and occlusion over two arrays
+        let all_packed = metal.is_some() == material.metal_texture.is_some()
+            && occlusion.is_some() == material.occlusion_texture.is_some();
+        let data_array = match (metal, occlusion) {
+            _ if !all_packed => None,
+            (Some((a, _)), Some((b, _))) if a != b => None,
+            (Some((array, _)), _) | (_, Some((array, _))) => Some(array.clone()),
+            (None, None) => None,
+        };
+        let data = data_array.map(|array| (array, metal.map(|m| m.1), occlusion.map(|o| o.1)));
+        if color.is_none() && data.is_none() {
+            continue;
+        }
+
+        let material = materials.get_mut(id).unwrap();
+        if let Some((array, layer)) = color {
+            material.color_array = Some(array);
+            material.array_layers.color = Some(layer);
+            material.color_texture = None;
+        }
+        if let Some((array, metal, occlusion)) = data {
+            material.data_array = Some(array);
+            material.array_layers.metal = metal;
+            material.array_layers.occlusion = occlusion;
+            material.metal_texture = None;
+            material.occlusion_texture = None;
+        }
+    }
+}
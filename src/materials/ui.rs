@@ -0,0 +1,386 @@
+use crate::materials::pbr::{alpha_mode_flags, PbrMaterialFlags};
+use bevy::prelude::*;
+use bevy::render::extract_resource::{ExtractResource, ExtractResourcePlugin};
+use bevy::render::render_asset::{RenderAssetUsages, RenderAssets};
+use bevy::render::render_resource::{
+    AsBindGroup, AsBindGroupShaderType, Extent3d, Face, ImageDataLayout, ShaderRef, ShaderType,
+    TextureDimension, TextureFormat, TextureId,
+};
+use bevy::render::renderer::RenderQueue;
+use bevy::render::texture::GpuImage;
+use bevy::render::{Render, RenderApp, RenderSet};
+
+const GLOW_SHADER_HANDLE: Handle<Shader> = Handle::weak_from_u128(0x4a0f9c2d61e7);
+const UI_SHADER_HANDLE: Handle<Shader> = Handle::weak_from_u128(0xd5b3e8170a4c);
+const UI_BOX_SHADER_HANDLE: Handle<Shader> = Handle::weak_from_u128(0x0c7e4f9b3d21);
+const UI_QUADRANT_SHADER_HANDLE: Handle<Shader> = Handle::weak_from_u128(0x96a1d53c0f8e);
+
+/// Shared texture holding the [`FingerTips`], one texel per tip
+pub const FINGER_TIPS_IMAGE_HANDLE: Handle<Image> = Handle::weak_from_u128(0xe83b26c4f159);
+
+/// Fingertips the UI materials glow around, like StereoKit's
+pub const MAX_FINGER_TIPS: usize = 10;
+
+/// Registers the StereoKit UI materials and uploads [`FingerTips`] for their glow, added by
+/// `PbrPlugin`
+pub struct SkUiMaterialsPlugin;
+
+impl Plugin for SkUiMaterialsPlugin {
+    fn build(&self, app: &mut App) {
+        load_shader!(app, GLOW_SHADER_HANDLE, "ui_glow.wgsl");
+        load_shader!(app, UI_SHADER_HANDLE, "ui.wgsl");
+        load_shader!(app, UI_BOX_SHADER_HANDLE, "ui_box.wgsl");
+        load_shader!(app, UI_QUADRANT_SHADER_HANDLE, "ui_quadrant.wgsl");
+        let tips = FingerTips::default();
+        app.world_mut()
+            .resource_mut::<Assets<Image>>()
+            .insert(FINGER_TIPS_IMAGE_HANDLE.id(), tips.to_image());
+        app.insert_resource(tips);
+        app.add_plugins((
+            ExtractResourcePlugin::<FingerTips>::default(),
+            MaterialPlugin::<SkUiMaterial>::default(),
+            MaterialPlugin::<SkUiBoxMaterial>::default(),
+            MaterialPlugin::<SkUiQuadrantMaterial>::default(),
+        ));
+        app.register_asset_reflect::<SkUiMaterial>();
+        app.register_asset_reflect::<SkUiBoxMaterial>();
+        app.register_asset_reflect::<SkUiQuadrantMaterial>();
+
+        if let Some(render_app) = app.get_sub_app_mut(RenderApp) {
+            render_app.add_systems(Render, write_finger_tips.in_set(RenderSet::PrepareResources));
+        }
+    }
+}
+
+/// World space fingertip positions the UI materials glow around, at most
+/// [`MAX_FINGER_TIPS`], fill it from hand tracking or controllers every frame
+#[derive(Resource, ExtractResource, Clone, Debug, Default)]
+pub struct FingerTips {
+    pub positions: Vec<Vec3>,
+}
+
+impl FingerTips {
+    /// xyz of every tip and 1 in w, unused texels stay zero
+    fn texel_data(&self) -> Vec<u8> {
+        let mut data = vec![0; MAX_FINGER_TIPS * 16];
+        for (tip, texel) in self.positions.iter().zip(data.chunks_exact_mut(16)) {
+            for (v, bytes) in [tip.x, tip.y, tip.z, 1.0].iter().zip(texel.chunks_exact_mut(4)) {
+                bytes.copy_from_slice(&v.to_le_bytes());
+            }
+        }
+        data
+    }
+
+    fn to_image(&self) -> Image {
+        Image::new(
+            Extent3d {
+                width: MAX_FINGER_TIPS as u32,
+                height: 1,
+                depth_or_array_layers: 1,
+            },
+            TextureDimension::D2,
+            self.texel_data(),
+            TextureFormat::Rgba32Float,
+            RenderAssetUsages::default(),
+        )
+    }
+}
+
+/// Writes the extracted tips straight into the existing GPU texture, like the SH buffer
+fn write_finger_tips(
+    tips: Res<FingerTips>,
+    images: Res<RenderAssets<GpuImage>>,
+    queue: Res<RenderQueue>,
+    mut written_texture: Local<Option<TextureId>>,
+) {
+    let Some(gpu_image) = images.get(&FINGER_TIPS_IMAGE_HANDLE) else {
+        return;
+    };
+    let texture_id = gpu_image.texture.id();
+    if !tips.is_changed() && *written_texture == Some(texture_id) {
+        return;
+    }
+
+    queue.write_texture(
+        gpu_image.texture.as_image_copy(),
+        &tips.texel_data(),
+        ImageDataLayout {
+            offset: 0,
+            bytes_per_row: Some(MAX_FINGER_TIPS as u32 * 16),
+            rows_per_image: None,
+        },
+        Extent3d {
+            width: MAX_FINGER_TIPS as u32,
+            height: 1,
+            depth_or_array_layers: 1,
+        },
+    );
+    *written_texture = Some(texture_id);
+}
+
+/// Glow shared by the UI materials, brightening surfaces near a [`FingerTips`] entry
+#[derive(Reflect, Clone, Copy, PartialEq, Debug)]
+pub struct FingerGlow {
+    /// Added at full proximity, scaled by its alpha
+    pub color: Color,
+    /// Distance in meters at which the glow starts
+    pub radius: f32,
+}
+
+impl Default for FingerGlow {
+    fn default() -> Self {
+        Self {
+            color: Color::srgba(1.0, 1.0, 1.0, 0.5),
+            radius: 0.05,
+        }
+    }
+}
+
+#[derive(Clone, Default, ShaderType)]
+pub struct FingerGlowUniform {
+    pub color: Vec4,
+    pub radius: f32,
+}
+
+impl From<FingerGlow> for FingerGlowUniform {
+    fn from(glow: FingerGlow) -> Self {
+        let color = glow.color.to_linear();
+        FingerGlowUniform {
+            color: (color * color.alpha).with_alpha(1.0).to_vec4(),
+            radius: glow.radius,
+        }
+    }
+}
+
+/// StereoKit's `ui` shader, an unlit color and texture that glows near the [`FingerTips`]
+#[derive(Asset, AsBindGroup, Reflect, PartialEq, Debug, Clone)]
+#[uniform(0, SkUiMaterialUniform)]
+pub struct SkUiMaterial {
+    pub color: Color,
+    #[texture(1)]
+    #[sampler(2)]
+    pub color_texture: Option<Handle<Image>>,
+    pub glow: FingerGlow,
+    pub alpha_mode: AlphaMode,
+    /// The shared fingertip texture, leave this at its default
+    #[texture(3, sample_type = "float", filterable = false)]
+    pub finger_tips: Handle<Image>,
+}
+
+impl Default for SkUiMaterial {
+    fn default() -> Self {
+        Self {
+            color: Color::WHITE,
+            color_texture: None,
+            glow: FingerGlow::default(),
+            alpha_mode: AlphaMode::Opaque,
+            finger_tips: FINGER_TIPS_IMAGE_HANDLE,
+        }
+    }
+}
+
+#[derive(Clone, Default, ShaderType)]
+pub struct SkUiMaterialUniform {
+    pub color: Vec4,
+    pub glow: FingerGlowUniform,
+    pub flags: u32,
+}
+
+impl AsBindGroupShaderType<SkUiMaterialUniform> for SkUiMaterial {
+    fn as_bind_group_shader_type(&self, _: &RenderAssets<GpuImage>) -> SkUiMaterialUniform {
+        SkUiMaterialUniform {
+            color: self.color.to_linear().to_vec4(),
+            glow: self.glow.into(),
+            flags: alpha_mode_flags(self.alpha_mode).0.bits(),
+        }
+    }
+}
+
+impl Material for SkUiMaterial {
+    fn fragment_shader() -> ShaderRef {
+        UI_SHADER_HANDLE.into()
+    }
+
+    fn alpha_mode(&self) -> AlphaMode {
+        self.alpha_mode
+    }
+}
+
+/// StereoKit's `ui_box` shader, the edges of a unit cube mesh drawn only near the
+/// [`FingerTips`], e.g. the outline of a button volume sized by its transform's scale
+#[derive(Asset, AsBindGroup, Reflect, PartialEq, Debug, Clone)]
+#[uniform(0, SkUiBoxMaterialUniform)]
+pub struct SkUiBoxMaterial {
+    pub color: Color,
+    /// Width of the edges in meters, independent of the box's scale
+    pub border_size: f32,
+    /// Opacity of the edges with no fingertip near
+    pub idle_alpha: f32,
+    pub glow: FingerGlow,
+    /// The shared fingertip texture, leave this at its default
+    #[texture(3, sample_type = "float", filterable = false)]
+    pub finger_tips: Handle<Image>,
+}
+
+impl Default for SkUiBoxMaterial {
+    fn default() -> Self {
+        Self {
+            color: Color::WHITE,
+            border_size: 0.002,
+            idle_alpha: 0.0,
+            glow: FingerGlow {
+                color: Color::WHITE,
+                radius: 0.1,
+            },
+            finger_tips: FINGER_TIPS_IMAGE_HANDLE,
+        }
+    }
+}
+
+#[derive(Clone, Default, ShaderType)]
+pub struct SkUiBoxMaterialUniform {
+    pub color: Vec4,
+    pub glow: FingerGlowUniform,
+    pub border_size: f32,
+    pub idle_alpha: f32,
+}
+
+impl AsBindGroupShaderType<SkUiBoxMaterialUniform> for SkUiBoxMaterial {
+    fn as_bind_group_shader_type(&self, _: &RenderAssets<GpuImage>) -> SkUiBoxMaterialUniform {
+        SkUiBoxMaterialUniform {
+            color: self.color.to_linear().to_vec4(),
+            glow: self.glow.into(),
+            border_size: self.border_size,
+            idle_alpha: self.idle_alpha,
+        }
+    }
+}
+
+impl Material for SkUiBoxMaterial {
+    fn fragment_shader() -> ShaderRef {
+        UI_BOX_SHADER_HANDLE.into()
+    }
+
+    fn alpha_mode(&self) -> AlphaMode {
+        AlphaMode::Blend
+    }
+
+    fn specialize(
+        _pipeline: &bevy::pbr::MaterialPipeline<Self>,
+        descriptor: &mut bevy::render::render_resource::RenderPipelineDescriptor,
+        _layout: &bevy::render::mesh::MeshVertexBufferLayoutRef,
+        _key: bevy::pbr::MaterialPipelineKey<Self>,
+    ) -> Result<(), bevy::render::render_resource::SpecializedMeshPipelineError> {
+        // The far edges show through the box
+        descriptor.primitive.cull_mode = None;
+        descriptor.vertex.shader_defs.push("VERTEX_OUTPUT_INSTANCE_INDEX".into());
+        if let Some(fragment) = &mut descriptor.fragment {
+            fragment.shader_defs.push("VERTEX_OUTPUT_INSTANCE_INDEX".into());
+        }
+        Ok(())
+    }
+}
+
+/// StereoKit's `ui_quadrant` shader, stretches a panel mesh to `size` by moving each of its
+/// quadrants out to the matching corner, so rounded corners and bevels keep their modeled
+/// size
+#[derive(Asset, AsBindGroup, Reflect, PartialEq, Debug, Clone)]
+#[uniform(0, SkUiQuadrantMaterialUniform)]
+#[bind_group_data(SkUiQuadrantMaterialKey)]
+pub struct SkUiQuadrantMaterial {
+    pub color: Color,
+    #[texture(1)]
+    #[sampler(2)]
+    pub color_texture: Option<Handle<Image>>,
+    /// Size of the panel in local XY
+    pub size: Vec2,
+    /// Size in local XY the mesh was modeled at, centered on the origin
+    pub mesh_size: Vec2,
+    pub glow: FingerGlow,
+    pub alpha_mode: AlphaMode,
+    pub double_sided: bool,
+    /// The shared fingertip texture, leave this at its default
+    #[texture(3, sample_type = "float", filterable = false)]
+    pub finger_tips: Handle<Image>,
+}
+
+impl Default for SkUiQuadrantMaterial {
+    fn default() -> Self {
+        Self {
+            color: Color::WHITE,
+            color_texture: None,
+            size: Vec2::ONE,
+            mesh_size: Vec2::ONE,
+            glow: FingerGlow::default(),
+            alpha_mode: AlphaMode::Opaque,
+            double_sided: false,
+            finger_tips: FINGER_TIPS_IMAGE_HANDLE,
+        }
+    }
+}
+
+#[derive(Clone, Default, ShaderType)]
+pub struct SkUiQuadrantMaterialUniform {
+    pub color: Vec4,
+    pub glow: FingerGlowUniform,
+    pub size: Vec2,
+    pub mesh_size: Vec2,
+    pub flags: u32,
+}
+
+impl AsBindGroupShaderType<SkUiQuadrantMaterialUniform> for SkUiQuadrantMaterial {
+    fn as_bind_group_shader_type(
+        &self,
+        _: &RenderAssets<GpuImage>,
+    ) -> SkUiQuadrantMaterialUniform {
+        let (mut flags, _) = alpha_mode_flags(self.alpha_mode);
+        if self.double_sided {
+            flags |= PbrMaterialFlags::DOUBLE_SIDED;
+        }
+        SkUiQuadrantMaterialUniform {
+            color: self.color.to_linear().to_vec4(),
+            glow: self.glow.into(),
+            size: self.size,
+            mesh_size: self.mesh_size,
+            flags: flags.bits(),
+        }
+    }
+}
+
+/// Pipeline specialization of a `SkUiQuadrantMaterial`, double sided panels skip face culling
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub struct SkUiQuadrantMaterialKey {
+    cull_mode: Option<Face>,
+}
+
+impl From<&SkUiQuadrantMaterial> for SkUiQuadrantMaterialKey {
+    fn from(material: &SkUiQuadrantMaterial) -> Self {
+        SkUiQuadrantMaterialKey {
+            cull_mode: (!material.double_sided).then_some(Face::Back),
+        }
+    }
+}
+
+impl Material for SkUiQuadrantMaterial {
+    fn vertex_shader() -> ShaderRef {
+        UI_QUADRANT_SHADER_HANDLE.into()
+    }
+
+    fn fragment_shader() -> ShaderRef {
+        UI_QUADRANT_SHADER_HANDLE.into()
+    }
+
+    fn alpha_mode(&self) -> AlphaMode {
+        self.alpha_mode
+    }
+
+    fn specialize(
+        _pipeline: &bevy::pbr::MaterialPipeline<Self>,
+        descriptor: &mut bevy::render::render_resource::RenderPipelineDescriptor,
+        _layout: &bevy::render::mesh::MeshVertexBufferLayoutRef,
+        key: bevy::pbr::MaterialPipelineKey<Self>,
+    ) -> Result<(), bevy::render::render_resource::SpecializedMeshPipelineError> {
+        descriptor.primitive.cull_mode = key.bind_group_data.cull_mode;
+        Ok(())
+    }
+}
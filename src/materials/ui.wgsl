@@ -0,0 +1,30 @@
+#import bevy_pbr::forward_io::VertexOutput
+#import bevy_sk::{
+    pbr_types::sk_alpha_output,
+    ui::{SkFingerGlow, sk_finger_glow},
+}
+
+struct SkUiMaterial {
+    color: vec4<f32>,
+    glow: SkFingerGlow,
+    flags: u32,
+};
+
+@group(2) @binding(0)
+var<uniform> material: SkUiMaterial;
+@group(2) @binding(1)
+var color_texture: texture_2d<f32>;
+@group(2) @binding(2)
+var color_sampler: sampler;
+@group(2) @binding(3)
+var finger_tips: texture_2d<f32>;
+
+@fragment
+fn fragment(in: VertexOutput) -> @location(0) vec4<f32> {
+    var color = material.color * textureSample(color_texture, color_sampler, in.uv);
+#ifdef VERTEX_COLORS
+    color *= in.color;
+#endif
+    let glow = sk_finger_glow(finger_tips, in.world_position.xyz, material.glow.radius);
+    return sk_alpha_output(material.flags, color.rgb + material.glow.color.rgb * glow, color.a);
+}
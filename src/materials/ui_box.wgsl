@@ -0,0 +1,45 @@
+#import bevy_pbr::{
+    forward_io::VertexOutput,
+    mesh_bindings::mesh,
+    mesh_functions::get_world_from_local,
+}
+#import bevy_render::maths::mat2x4_f32_to_mat3x3_unpack
+#import bevy_sk::ui::{SkFingerGlow, sk_finger_glow}
+
+struct SkUiBoxMaterial {
+    color: vec4<f32>,
+    glow: SkFingerGlow,
+    border_size: f32,
+    idle_alpha: f32,
+};
+
+@group(2) @binding(0)
+var<uniform> material: SkUiBoxMaterial;
+@group(2) @binding(3)
+var finger_tips: texture_2d<f32>;
+
+@fragment
+fn fragment(in: VertexOutput) -> @location(0) vec4<f32> {
+    let world_from_local = get_world_from_local(in.instance_index);
+    let local_from_world = transpose(mat2x4_f32_to_mat3x3_unpack(
+        mesh[in.instance_index].local_from_world_transpose_a,
+        mesh[in.instance_index].local_from_world_transpose_b,
+    ));
+    let local = local_from_world * (in.world_position.xyz - world_from_local[3].xyz);
+    let scale = vec3(
+        length(world_from_local[0].xyz),
+        length(world_from_local[1].xyz),
+        length(world_from_local[2].xyz),
+    );
+
+    // Meters to each pair of faces. On a face one of them is 0, and the next smallest is the
+    // distance to the closest edge.
+    let d = (0.5 - abs(local)) * scale;
+    let edge = max(min(d.x, d.y), min(max(d.x, d.y), d.z));
+    let width = fwidth(edge);
+    let border = 1.0 - smoothstep(material.border_size - width, material.border_size + width, edge);
+
+    let glow = sk_finger_glow(finger_tips, in.world_position.xyz, material.glow.radius);
+    let alpha = border * max(material.idle_alpha, glow) * material.color.a;
+    return vec4(material.color.rgb + material.glow.color.rgb * glow, alpha);
+}
@@ -0,0 +1,20 @@
+#define_import_path bevy_sk::ui
+
+struct SkFingerGlow {
+    // Premultiplied by its alpha
+    color: vec4<f32>,
+    radius: f32,
+};
+
+// Proximity of the closest fingertip in 0..1, reaching 1 where it touches
+fn sk_finger_glow(finger_tips: texture_2d<f32>, world_position: vec3<f32>, radius: f32) -> f32 {
+    var glow = 0.0;
+    let count = textureDimensions(finger_tips).x;
+    for (var i = 0u; i < count; i += 1u) {
+        // Unused tips are all zero, w included
+        let tip = textureLoad(finger_tips, vec2<u32>(i, 0u), 0);
+        let proximity = saturate(1.0 - distance(tip.xyz, world_position) / radius);
+        glow = max(glow, tip.w * proximity * proximity);
+    }
+    return glow;
+}
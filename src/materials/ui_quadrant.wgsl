@@ -0,0 +1,69 @@
+#import bevy_pbr::{
+    mesh_functions,
+    forward_io::{Vertex, VertexOutput},
+    view_transformations::position_world_to_clip,
+}
+#import bevy_sk::{
+    pbr_types::sk_alpha_output,
+    ui::{SkFingerGlow, sk_finger_glow},
+}
+
+struct SkUiQuadrantMaterial {
+    color: vec4<f32>,
+    glow: SkFingerGlow,
+    size: vec2<f32>,
+    mesh_size: vec2<f32>,
+    flags: u32,
+};
+
+@group(2) @binding(0)
+var<uniform> material: SkUiQuadrantMaterial;
+@group(2) @binding(1)
+var color_texture: texture_2d<f32>;
+@group(2) @binding(2)
+var color_sampler: sampler;
+@group(2) @binding(3)
+var finger_tips: texture_2d<f32>;
+
+@vertex
+fn vertex(vertex: Vertex) -> VertexOutput {
+    var out: VertexOutput;
+
+    // Every quadrant keeps its modeled shape and moves out to its corner of the panel
+    var position = vertex.position;
+    let stretch = max(material.size - material.mesh_size, vec2(0.0)) * 0.5;
+    position = vec3(position.xy + sign(position.xy) * stretch, position.z);
+
+    let world_from_local = mesh_functions::get_world_from_local(vertex.instance_index);
+    out.world_position = mesh_functions::mesh_position_local_to_world(
+        world_from_local,
+        vec4<f32>(position, 1.0)
+    );
+    out.position = position_world_to_clip(out.world_position.xyz);
+#ifdef VERTEX_NORMALS
+    out.world_normal = mesh_functions::mesh_normal_local_to_world(
+        vertex.normal,
+        vertex.instance_index
+    );
+#endif
+#ifdef VERTEX_UVS_A
+    out.uv = vertex.uv;
+#endif
+#ifdef VERTEX_COLORS
+    out.color = vertex.color;
+#endif
+#ifdef VERTEX_OUTPUT_INSTANCE_INDEX
+    out.instance_index = vertex.instance_index;
+#endif
+    return out;
+}
+
+@fragment
+fn fragment(in: VertexOutput) -> @location(0) vec4<f32> {
+    var color = material.color * textureSample(color_texture, color_sampler, in.uv);
+#ifdef VERTEX_COLORS
+    color *= in.color;
+#endif
+    let glow = sk_finger_glow(finger_tips, in.world_position.xyz, material.glow.radius);
+    return sk_alpha_output(material.flags, color.rgb + material.glow.color.rgb * glow, color.a);
+}
@@ -0,0 +1,112 @@
+use crate::materials::pbr::{alpha_mode_flags, PbrMaterialFlags};
+use bevy::prelude::*;
+use bevy::render::render_asset::RenderAssets;
+use bevy::render::render_resource::{
+    AsBindGroup, AsBindGroupShaderType, Face, ShaderRef, ShaderType,
+};
+use bevy::render::texture::GpuImage;
+
+const SHADER_HANDLE: Handle<Shader> = Handle::weak_from_u128(0xc4a17e2b950d);
+
+/// Registers [`SkUnlitMaterial`], added by `PbrPlugin`
+pub struct SkUnlitMaterialPlugin;
+
+impl Plugin for SkUnlitMaterialPlugin {
+    fn build(&self, app: &mut App) {
+        load_shader!(app, SHADER_HANDLE, "unlit.wgsl");
+        app.add_plugins(MaterialPlugin::<SkUnlitMaterial>::default());
+        app.register_asset_reflect::<SkUnlitMaterial>();
+    }
+}
+
+/// StereoKit's `unlit` shader, color times texture times vertex color without any lighting.
+///
+/// With `AlphaMode::Mask` it is StereoKit's `unlit_clip`.
+#[derive(Asset, AsBindGroup, Reflect, PartialEq, Debug, Clone)]
+#[bind_group_data(SkUnlitMaterialKey)]
+#[uniform(0, SkUnlitMaterialUniform)]
+pub struct SkUnlitMaterial {
+    pub color: Color,
+    #[texture(1)]
+    #[sampler(2)]
+    pub color_texture: Option<Handle<Image>>,
+    pub alpha_mode: AlphaMode,
+    pub double_sided: bool,
+}
+
+impl Default for SkUnlitMaterial {
+    fn default() -> Self {
+        Self {
+            color: Color::WHITE,
+            color_texture: None,
+            alpha_mode: AlphaMode::Opaque,
+            double_sided: false,
+        }
+    }
+}
+
+impl From<&StandardMaterial> for SkUnlitMaterial {
+    fn from(m: &StandardMaterial) -> Self {
+        Self {
+            color: m.base_color,
+            color_texture: m.base_color_texture.clone(),
+            alpha_mode: m.alpha_mode,
+            double_sided: m.double_sided,
+        }
+    }
+}
+
+#[derive(Clone, Default, ShaderType)]
+pub struct SkUnlitMaterialUniform {
+    pub color: Vec4,
+    pub flags: u32,
+    pub alpha_cutoff: f32,
+}
+
+impl AsBindGroupShaderType<SkUnlitMaterialUniform> for SkUnlitMaterial {
+    fn as_bind_group_shader_type(&self, _: &RenderAssets<GpuImage>) -> SkUnlitMaterialUniform {
+        let (mut flags, alpha_cutoff) = alpha_mode_flags(self.alpha_mode);
+        if self.double_sided {
+            flags |= PbrMaterialFlags::DOUBLE_SIDED;
+        }
+        SkUnlitMaterialUniform {
+            color: self.color.to_linear().to_vec4(),
+            flags: flags.bits(),
+            alpha_cutoff,
+        }
+    }
+}
+
+/// Pipeline specialization of a `SkUnlitMaterial`, double sided materials skip face culling
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub struct SkUnlitMaterialKey {
+    cull_mode: Option<Face>,
+}
+
+impl From<&SkUnlitMaterial> for SkUnlitMaterialKey {
+    fn from(material: &SkUnlitMaterial) -> Self {
+        SkUnlitMaterialKey {
+            cull_mode: (!material.double_sided).then_some(Face::Back),
+        }
+    }
+}
+
+impl Material for SkUnlitMaterial {
+    fn fragment_shader() -> ShaderRef {
+        SHADER_HANDLE.into()
+    }
+
+    fn alpha_mode(&self) -> AlphaMode {
+        self.alpha_mode
+    }
+
+    fn specialize(
+        _pipeline: &bevy::pbr::MaterialPipeline<Self>,
+        descriptor: &mut bevy::render::render_resource::RenderPipelineDescriptor,
+        _layout: &bevy::render::mesh::MeshVertexBufferLayoutRef,
+        key: bevy::pbr::MaterialPipelineKey<Self>,
+    ) -> Result<(), bevy::render::render_resource::SpecializedMeshPipelineError> {
+        descriptor.primitive.cull_mode = key.bind_group_data.cull_mode;
+        Ok(())
+    }
+}
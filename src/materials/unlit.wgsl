@@ -0,0 +1,30 @@
+#import bevy_pbr::forward_io::VertexOutput
+#import bevy_sk::pbr_types::{sk_has_flag, sk_alpha_output, SK_MATERIAL_FLAGS_ALPHA_MODE_MASK_BIT}
+
+struct SkUnlitMaterial {
+    color: vec4<f32>,
+    flags: u32,
+    alpha_cutoff: f32,
+};
+
+@group(2) @binding(0)
+var<uniform> material: SkUnlitMaterial;
+@group(2) @binding(1)
+var color_texture: texture_2d<f32>;
+@group(2) @binding(2)
+var color_sampler: sampler;
+
+@fragment
+fn fragment(in: VertexOutput) -> @location(0) vec4<f32> {
+    var color = material.color * textureSample(color_texture, color_sampler, in.uv);
+#ifdef VERTEX_COLORS
+    color *= in.color;
+#endif
+
+    // unlit_clip
+    if (sk_has_flag(material.flags, SK_MATERIAL_FLAGS_ALPHA_MODE_MASK_BIT)
+        && color.a < material.alpha_cutoff) {
+        discard;
+    }
+    return sk_alpha_output(material.flags, color.rgb, color.a);
+}
@@ -0,0 +1,306 @@
+use crate::materials::pbr::{PbrMaterial, PbrMaterialKey};
+use bevy::ecs::system::{EntityCommands, SystemParam};
+use bevy::pbr::NotShadowCaster;
+use bevy::prelude::*;
+use bevy::render::mesh::morph::MeshMorphWeights;
+use bevy::render::mesh::skinning::{SkinnedMesh, SkinnedMeshInverseBindposes};
+use bevy::render::mesh::{MeshVertexAttributeId, PrimitiveTopology, VertexAttributeValues};
+use bevy::utils::HashSet;
+use std::mem::Discriminant;
+
+/// Compiles the `PbrMaterial` pipelines a scene needs before they first come into view.
+///
+/// While a warm-up runs, one tiny proxy per distinct pipeline variant is drawn right in front
+/// of the camera, so the main and prepass pipelines are specialized during loading instead of
+/// stalling the first frame the real object becomes visible. The common variants, opaque,
+/// masked and blended, single and double sided, skinned or not, are warmed up along with the
+/// scene's unless [`PipelineWarmup::common_variants`] is off.
+pub struct PipelineWarmupPlugin;
+
+impl Plugin for PipelineWarmupPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<PipelineWarmup>();
+        app.add_event::<PipelineWarmupFinished>();
+        app.add_systems(Update, (spawn_warmup_proxies, finish_warmup).chain());
+    }
+}
+
+#[derive(Resource, Clone, Debug)]
+pub struct PipelineWarmup {
+    /// Camera the proxies are drawn in front of, the first `Camera3d` when `None`
+    pub camera: Option<Entity>,
+    /// Frames the proxies stay alive, long enough for asynchronous pipeline compilation
+    pub frames: u32,
+    /// Also warms up the common variants on a plain mesh with positions, normals and UVs
+    pub common_variants: bool,
+    /// Draws the proxies about a pixel large instead of covering none, for drivers that only
+    /// finish a pipeline once it rasterizes something. Best hidden behind a loading fade.
+    pub probe_draws: bool,
+    requested: bool,
+    remaining: Option<u32>,
+}
+
+impl Default for PipelineWarmup {
+    fn default() -> Self {
+        Self {
+            camera: None,
+            frames: 10,
+            common_variants: true,
+            probe_draws: false,
+            requested: false,
+            remaining: None,
+        }
+    }
+}
+
+impl PipelineWarmup {
+    /// Warms up every variant used by the `PbrMaterial` meshes currently in the world, and the
+    /// common ones
+    pub fn start(&mut self) {
+        self.requested = true;
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.requested || self.remaining.is_some()
+    }
+}
+
+#[derive(Event, Clone, Copy, Debug)]
+pub struct PipelineWarmupFinished {
+    pub variants: usize,
+}
+
+#[derive(Component)]
+pub(crate) struct WarmupProxy;
+
+/// Everything that picks a different `PbrMaterial` pipeline
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub(crate) struct PipelineVariant {
+    pub attributes: Vec<MeshVertexAttributeId>,
+    pub topology: PrimitiveTopology,
+    pub alpha_mode: Discriminant<AlphaMode>,
+    pub double_sided: bool,
+    /// Face culling and the set textures
+    pub key: PbrMaterialKey,
+    pub skinned: bool,
+    pub morphed: bool,
+}
+
+impl PipelineVariant {
+    pub fn new(mesh: &Mesh, material: &PbrMaterial, skinned: bool, morphed: bool) -> Self {
+        Self {
+            attributes: mesh.attributes().map(|(id, _)| id).collect(),
+            topology: mesh.primitive_topology(),
+            alpha_mode: std::mem::discriminant(&material.alpha_mode),
+            double_sided: material.double_sided,
+            key: material.into(),
+            skinned,
+            morphed,
+        }
+    }
+}
+
+/// Proxies are parented to the camera a meter ahead, scaled down so they cover no pixels, or
+/// about one at headset resolutions for probe draws
+pub(crate) fn warmup_proxy_transform(probe: bool) -> Transform {
+    let scale = if probe { 2e-3 } else { 1e-4 };
+    Transform::from_xyz(0.0, 0.0, -1.0).with_scale(Vec3::splat(scale))
+}
+
+pub(crate) fn pick_warmup_camera(
+    warmup: &PipelineWarmup,
+    cameras: &Query<Entity, With<Camera3d>>,
+) -> Option<Entity> {
+    warmup.camera.or_else(|| cameras.iter().next())
+}
+
+/// Proxy assets of the common variants, made on the first warm-up that needs them
+struct CommonVariants {
+    mesh: Handle<Mesh>,
+    /// `mesh` bound to a single joint
+    skinned_mesh: Handle<Mesh>,
+    inverse_bindposes: Handle<SkinnedMeshInverseBindposes>,
+    materials: Vec<Handle<PbrMaterial>>,
+}
+
+#[derive(SystemParam)]
+struct WarmupAssets<'w, 's> {
+    meshes: ResMut<'w, Assets<Mesh>>,
+    materials: ResMut<'w, Assets<PbrMaterial>>,
+    inverse_bindposes: ResMut<'w, Assets<SkinnedMeshInverseBindposes>>,
+    common: Local<'s, Option<CommonVariants>>,
+}
+
+impl WarmupAssets<'_, '_> {
+    fn common_variants(&mut self) -> &CommonVariants {
+        if self.common.is_none() {
+            let mesh = Mesh::from(Cuboid::default());
+            let count = mesh.count_vertices();
+            let skinned_mesh = mesh
+                .clone()
+                .with_inserted_attribute(
+                    Mesh::ATTRIBUTE_JOINT_INDEX,
+                    VertexAttributeValues::Uint16x4(vec![[0; 4]; count]),
+                )
+                .with_inserted_attribute(
+                    Mesh::ATTRIBUTE_JOINT_WEIGHT,
+                    vec![[1.0, 0.0, 0.0, 0.0]; count],
+                );
+            let alpha_modes = [AlphaMode::Opaque, AlphaMode::Mask(0.5), AlphaMode::Blend];
+            let materials = alpha_modes
+                .into_iter()
+                .flat_map(|alpha_mode| {
+                    [false, true].map(|double_sided| PbrMaterial {
+                        alpha_mode,
+                        double_sided,
+                        ..default()
+                    })
+                })
+                .map(|material| self.materials.add(material))
+                .collect();
+            *self.common = Some(CommonVariants {
+                mesh: self.meshes.add(mesh),
+                skinned_mesh: self.meshes.add(skinned_mesh),
+                inverse_bindposes: self
+                    .inverse_bindposes
+                    .add(SkinnedMeshInverseBindposes::from(vec![Mat4::IDENTITY])),
+                materials,
+            });
+        }
+        self.common.as_ref().unwrap()
+    }
+}
+
+fn spawn_warmup_proxy<'a>(
+    commands: &'a mut Commands,
+    camera: Entity,
+    mesh: Handle<Mesh>,
+    material: Handle<PbrMaterial>,
+    probe: bool,
+) -> EntityCommands<'a> {
+    let mut proxy = commands.spawn((
+        MaterialMeshBundle {
+            mesh,
+            material,
+            transform: warmup_proxy_transform(probe),
+            ..default()
+        },
+        NotShadowCaster,
+        WarmupProxy,
+    ));
+    proxy.set_parent(camera);
+    proxy
+}
+
+fn spawn_warmup_proxies(
+    mut commands: Commands,
+    mut warmup: ResMut<PipelineWarmup>,
+    cameras: Query<Entity, With<Camera3d>>,
+    scene: Query<
+        (
+            &Handle<Mesh>,
+            &Handle<PbrMaterial>,
+            Option<&SkinnedMesh>,
+            Option<&MeshMorphWeights>,
+        ),
+        Without<WarmupProxy>,
+    >,
+    mut assets: WarmupAssets,
+) {
+    if !warmup.requested {
+        return;
+    }
+    let Some(camera) = pick_warmup_camera(&warmup, &cameras) else {
+        return;
+    };
+    warmup.requested = false;
+    let probe = warmup.probe_draws;
+
+    let mut seen = HashSet::new();
+    for (mesh_handle, material_handle, skin, morph) in scene.iter() {
+        let (Some(mesh), Some(material)) =
+            (assets.meshes.get(mesh_handle), assets.materials.get(material_handle))
+        else {
+            continue;
+        };
+        if !seen.insert(PipelineVariant::new(mesh, material, skin.is_some(), morph.is_some())) {
+            continue;
+        }
+
+        let mut proxy = spawn_warmup_proxy(
+            &mut commands,
+            camera,
+            mesh_handle.clone(),
+            material_handle.clone(),
+            probe,
+        );
+        if let Some(skin) = skin {
+            proxy.insert(skin.clone());
+        }
+        if let Some(morph) = morph {
+            proxy.insert(morph.clone());
+        }
+    }
+
+    if warmup.common_variants {
+        let common = assets.common_variants();
+        let (mesh, skinned_mesh) = (common.mesh.clone(), common.skinned_mesh.clone());
+        let inverse_bindposes = common.inverse_bindposes.clone();
+        for material_handle in common.materials.clone() {
+            for skinned in [false, true] {
+                let mesh_handle = if skinned { &skinned_mesh } else { &mesh };
+                let (Some(mesh), Some(material)) =
+                    (assets.meshes.get(mesh_handle), assets.materials.get(&material_handle))
+                else {
+                    continue;
+                };
+                if !seen.insert(PipelineVariant::new(mesh, material, skinned, false)) {
+                    continue;
+                }
+
+                // The joint follows the proxy, so the skinned proxy ends up in the same place
+                let joint = skinned.then(|| commands.spawn(TransformBundle::default()).id());
+                let mut proxy = spawn_warmup_proxy(
+                    &mut commands,
+                    camera,
+                    mesh_handle.clone(),
+                    material_handle.clone(),
+                    probe,
+                );
+                if let Some(joint) = joint {
+                    proxy.insert(SkinnedMesh {
+                        inverse_bindposes: inverse_bindposes.clone(),
+                        joints: vec![joint],
+                    });
+                    proxy.add_child(joint);
+                }
+            }
+        }
+    }
+
+    warmup.remaining = Some(warmup.frames);
+    debug!("Warming up {} PbrMaterial pipeline variants", seen.len());
+}
+
+fn finish_warmup(
+    mut commands: Commands,
+    mut warmup: ResMut<PipelineWarmup>,
+    proxies: Query<Entity, With<WarmupProxy>>,
+    mut finished: EventWriter<PipelineWarmupFinished>,
+) {
+    let Some(remaining) = warmup.remaining else {
+        return;
+    };
+    if remaining > 0 {
+        warmup.remaining = Some(remaining - 1);
+        return;
+    }
+
+    let mut variants = 0;
+    for proxy in proxies.iter() {
+        commands.entity(proxy).despawn_recursive();
+        variants += 1;
+    }
+    warmup.remaining = None;
+    finished.send(PipelineWarmupFinished { variants });
+}
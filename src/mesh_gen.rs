@@ -0,0 +1,200 @@
+//! Procedural meshes like StereoKit's `Mesh.Generate*`, for UI panels and prototypes that
+//! don't need modeled assets. All of them are centered on the origin with +Y up, and come with
+//! normals, UVs and MikkTSpace tangents. They keep their data in the main world too, so they
+//! can be raycast.
+
+use bevy::prelude::*;
+use bevy::render::mesh::{Indices, PrimitiveTopology};
+use bevy::render::render_asset::RenderAssetUsages;
+use std::f32::consts::{FRAC_PI_2, FRAC_PI_4, PI, TAU};
+
+/// A box with its edges and corners rounded off by `radius`, each rounding made of
+/// `subdivisions` segments. A `radius` of 0 gives a plain box.
+pub fn rounded_cube(size: Vec3, radius: f32, subdivisions: u32) -> Mesh {
+    let half = size * 0.5;
+    let radius = radius.clamp(0.0, half.min_element());
+    let subdivisions = subdivisions.max(1);
+    // Spaced so the rounding is made of equal arcs, each face covers half of every edge's arc
+    let samples = |half: f32| -> Vec<f32> {
+        let corner: Vec<f32> = (0..=subdivisions)
+            .map(|i| (half - radius) + radius * (i as f32 / subdivisions as f32 * FRAC_PI_4).tan())
+            .collect();
+        corner.iter().rev().map(|x| -x).chain(corner.iter().copied()).collect()
+    };
+
+    let mut mesh = MeshData::default();
+    // Each face's normal with two axes across it, whose cross product is the normal
+    let faces = [
+        (Vec3::X, Vec3::NEG_Z, Vec3::Y),
+        (Vec3::NEG_X, Vec3::Z, Vec3::Y),
+        (Vec3::Y, Vec3::X, Vec3::NEG_Z),
+        (Vec3::NEG_Y, Vec3::X, Vec3::Z),
+        (Vec3::Z, Vec3::X, Vec3::Y),
+        (Vec3::NEG_Z, Vec3::NEG_X, Vec3::Y),
+    ];
+    for (normal, u, v) in faces {
+        let size_of = |axis: Vec3| (axis.abs() * size).max_element();
+        let (us, vs) = (samples(size_of(u) * 0.5), samples(size_of(v) * 0.5));
+        let first = mesh.positions.len() as u32;
+        for y in &vs {
+            for x in &us {
+                let point = normal * (normal.abs() * half).max_element() + u * *x + v * *y;
+                let inner = point.clamp(-(half - radius), half - radius);
+                let direction = (point - inner).try_normalize().unwrap_or(normal);
+                mesh.positions.push(inner + direction * radius);
+                mesh.normals.push(direction);
+                mesh.uvs.push(Vec2::new(
+                    *x / size_of(u).max(f32::EPSILON) + 0.5,
+                    0.5 - *y / size_of(v).max(f32::EPSILON),
+                ));
+            }
+        }
+        mesh.grid(first, us.len() as u32, vs.len() as u32);
+    }
+    mesh.build()
+}
+
+/// A flat grid facing +Y, with its top edge towards -Z, cut into `subdivisions` + 1 squares
+/// along each side
+pub fn plane(size: Vec2, subdivisions: u32) -> Mesh {
+    let columns = subdivisions + 2;
+    let mut mesh = MeshData::default();
+    for z in 0..columns {
+        for x in 0..columns {
+            let uv = Vec2::new(x as f32, z as f32) / (columns - 1) as f32;
+            let position = (uv - 0.5) * size;
+            mesh.positions.push(Vec3::new(position.x, 0.0, position.y));
+            mesh.normals.push(Vec3::Y);
+            mesh.uvs.push(uv);
+        }
+    }
+    // Rows run towards +Z, so the winding is flipped from `MeshData::grid` to face +Y
+    for z in 0..columns - 1 {
+        for x in 0..columns - 1 {
+            let a = z * columns + x;
+            let (b, c, d) = (a + 1, a + columns, a + columns + 1);
+            mesh.indices.extend([a, c, b, b, c, d]);
+        }
+    }
+    mesh.build()
+}
+
+/// A capped cylinder along Y, `segments` around
+pub fn cylinder(diameter: f32, height: f32, segments: u32) -> Mesh {
+    let (radius, top) = (diameter * 0.5, height * 0.5);
+    lathe(
+        &[
+            &[(Vec2::new(0.0, -top), Vec2::NEG_Y), (Vec2::new(radius, -top), Vec2::NEG_Y)],
+            &[(Vec2::new(radius, -top), Vec2::X), (Vec2::new(radius, top), Vec2::X)],
+            &[(Vec2::new(radius, top), Vec2::Y), (Vec2::new(0.0, top), Vec2::Y)],
+        ],
+        segments,
+    )
+}
+
+/// A cone along Y with its base at the bottom and its tip at the top, `segments` around
+pub fn cone(diameter: f32, height: f32, segments: u32) -> Mesh {
+    let (radius, top) = (diameter * 0.5, height * 0.5);
+    let side = Vec2::new(height, radius).normalize_or_zero();
+    lathe(
+        &[
+            &[(Vec2::new(0.0, -top), Vec2::NEG_Y), (Vec2::new(radius, -top), Vec2::NEG_Y)],
+            &[(Vec2::new(radius, -top), side), (Vec2::new(0.0, top), side)],
+        ],
+        segments,
+    )
+}
+
+/// A UV sphere, `segments` around and half as many from pole to pole
+pub fn sphere(diameter: f32, segments: u32) -> Mesh {
+    let rings = (segments / 2).max(2);
+    let profile: Vec<(Vec2, Vec2)> = (0..=rings)
+        .map(|i| {
+            let normal = Vec2::from_angle(-FRAC_PI_2 + i as f32 / rings as f32 * PI);
+            (normal * diameter * 0.5, normal)
+        })
+        .collect();
+    lathe(&[&profile], segments)
+}
+
+/// A cylinder along Y with half spheres for caps, `height` tall from tip to tip,
+/// `segments` around
+pub fn capsule(diameter: f32, height: f32, segments: u32) -> Mesh {
+    let radius = diameter * 0.5;
+    let half_length = (height * 0.5 - radius).max(0.0);
+    let rings = (segments / 4).max(1);
+    let cap = |from: f32, center: f32| {
+        (0..=rings).map(move |i| {
+            let normal = Vec2::from_angle(from + i as f32 / rings as f32 * FRAC_PI_2);
+            (normal * radius + Vec2::new(0.0, center), normal)
+        })
+    };
+    let profile: Vec<(Vec2, Vec2)> =
+        cap(-FRAC_PI_2, -half_length).chain(cap(0.0, half_length)).collect();
+    lathe(&[&profile], segments)
+}
+
+/// Sweeps profiles of (radius, height) points and their normals around the Y axis. Each
+/// profile runs from the bottom of the shape to its top, so bottom caps go outwards and top
+/// caps inwards, separate profiles give hard edges. V runs along the profiles from the top.
+fn lathe(profiles: &[&[(Vec2, Vec2)]], segments: u32) -> Mesh {
+    let segments = segments.max(3);
+    let length: f32 = profiles
+        .iter()
+        .flat_map(|profile| profile.windows(2))
+        .map(|pair| pair[0].0.distance(pair[1].0))
+        .sum();
+    let mut mesh = MeshData::default();
+    let mut along = 0.0;
+    for profile in profiles {
+        let first = mesh.positions.len() as u32;
+        for (i, (point, normal)) in profile.iter().enumerate() {
+            if i > 0 {
+                along += point.distance(profile[i - 1].0);
+            }
+            // One more column than segments, so the seam gets its own UVs
+            for segment in 0..=segments {
+                let u = segment as f32 / segments as f32;
+                let (sin, cos) = (u * TAU).sin_cos();
+                let around = |v: Vec2| Vec3::new(v.x * sin, v.y, v.x * cos);
+                mesh.positions.push(around(*point));
+                mesh.normals.push(around(*normal).normalize_or_zero());
+                mesh.uvs.push(Vec2::new(u, 1.0 - along / length.max(f32::EPSILON)));
+            }
+        }
+        mesh.grid(first, segments + 1, profile.len() as u32);
+    }
+    mesh.build()
+}
+
+#[derive(Default)]
+struct MeshData {
+    positions: Vec<Vec3>,
+    normals: Vec<Vec3>,
+    uvs: Vec<Vec2>,
+    indices: Vec<u32>,
+}
+
+impl MeshData {
+    /// Triangles of a grid of vertices from `first`, rows of `columns` going up, each row
+    /// running right as seen from the front
+    fn grid(&mut self, first: u32, columns: u32, rows: u32) {
+        for row in 0..rows.saturating_sub(1) {
+            for column in 0..columns.saturating_sub(1) {
+                let a = first + row * columns + column;
+                let (b, c, d) = (a + 1, a + columns, a + columns + 1);
+                self.indices.extend([a, b, c, b, d, c]);
+            }
+        }
+    }
+
+    fn build(self) -> Mesh {
+        Mesh::new(PrimitiveTopology::TriangleList, RenderAssetUsages::default())
+            .with_inserted_attribute(Mesh::ATTRIBUTE_POSITION, self.positions)
+            .with_inserted_attribute(Mesh::ATTRIBUTE_NORMAL, self.normals)
+            .with_inserted_attribute(Mesh::ATTRIBUTE_UV_0, self.uvs)
+            .with_inserted_indices(Indices::U32(self.indices))
+            .with_generated_tangents()
+            .expect("generated meshes have normals and UVs")
+    }
+}
@@ -0,0 +1,149 @@
+use crate::hands::{Hand, HandJointId, HandJoints};
+use crate::ui::{SkUiPlugin, Ui};
+use bevy::diagnostic::{DiagnosticsStore, FrameTimeDiagnosticsPlugin};
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+
+/// Shows frame rate, frame time, visible meshes and the bevy_sk diagnostics on a small [`Ui`]
+/// panel that follows the head or a wrist, for profiling in a headset where the console can't
+/// be seen. Turned on and off with [`XrPerfHud::enabled`], not part of `SkPlugins`.
+///
+/// The bevy_sk values need `SkDiagnosticsPlugin`.
+pub struct XrPerfHudPlugin;
+
+impl Plugin for XrPerfHudPlugin {
+    fn build(&self, app: &mut App) {
+        if !app.is_plugin_added::<FrameTimeDiagnosticsPlugin>() {
+            app.add_plugins(FrameTimeDiagnosticsPlugin);
+        }
+        if !app.is_plugin_added::<SkUiPlugin>() {
+            app.add_plugins(SkUiPlugin);
+        }
+        app.init_resource::<XrPerfHud>();
+        app.register_type::<XrPerfHud>();
+        app.add_systems(Update, draw_perf_hud);
+    }
+}
+
+/// Settings of [`XrPerfHudPlugin`]
+#[derive(Resource, Reflect, Clone, Debug, PartialEq)]
+#[reflect(Resource)]
+pub struct XrPerfHud {
+    pub enabled: bool,
+    pub anchor: PerfHudAnchor,
+    /// Seconds between updates of the values, so they can be read
+    pub refresh_interval: f32,
+    /// Width of the panel in meters
+    pub width: f32,
+}
+
+impl Default for XrPerfHud {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            anchor: PerfHudAnchor::default(),
+            refresh_interval: 0.5,
+            width: 0.16,
+        }
+    }
+}
+
+/// What the [`XrPerfHud`] panel is attached to
+#[derive(Reflect, Clone, Copy, Debug, PartialEq)]
+pub enum PerfHudAnchor {
+    /// Locked in front of the first 3D camera, at `offset` in its local space
+    Head { offset: Vec3 },
+    /// Above the wrist of a tracked hand, facing the head, hidden while the hand isn't tracked
+    Wrist { hand: Hand, offset: Vec3 },
+}
+
+impl Default for PerfHudAnchor {
+    fn default() -> Self {
+        Self::Head {
+            offset: Vec3::new(-0.12, -0.08, -0.5),
+        }
+    }
+}
+
+/// The values on the panel, kept between refreshes
+#[derive(Default)]
+struct PerfHudText {
+    lines: Vec<String>,
+    since_refresh: f32,
+}
+
+#[derive(SystemParam)]
+struct PerfHudSources<'w, 's> {
+    time: Res<'w, Time<Real>>,
+    diagnostics: Res<'w, DiagnosticsStore>,
+    meshes: Query<'w, 's, &'static ViewVisibility, With<Handle<Mesh>>>,
+    cameras: Query<'w, 's, &'static GlobalTransform, With<Camera3d>>,
+    joints: Res<'w, HandJoints>,
+}
+
+impl PerfHudSources<'_, '_> {
+    fn lines(&self) -> Vec<String> {
+        let value = |path| {
+            self.diagnostics
+                .get(path)
+                .and_then(|diagnostic| diagnostic.smoothed())
+        };
+        let mut lines = Vec::new();
+        if let Some(fps) = value(&FrameTimeDiagnosticsPlugin::FPS) {
+            lines.push(format!("FPS {fps:.1}"));
+        }
+        if let Some(frame_time) = value(&FrameTimeDiagnosticsPlugin::FRAME_TIME) {
+            lines.push(format!("Frame {frame_time:.2} ms"));
+        }
+        // Bevy doesn't count draw calls, every visible mesh is at least one
+        let visible = self.meshes.iter().filter(|v| v.get()).count();
+        lines.push(format!("Visible meshes {visible}"));
+        for diagnostic in self.diagnostics.iter() {
+            let path = diagnostic.path().as_str();
+            let (Some(name), Some(value)) = (path.strip_prefix("bevy_sk/"), diagnostic.smoothed())
+            else {
+                continue;
+            };
+            lines.push(format!("{} {value:.1}{}", name.replace('_', " "), diagnostic.suffix));
+        }
+        lines
+    }
+
+    /// Top center of the panel
+    fn pose(&self, anchor: PerfHudAnchor) -> Option<Transform> {
+        let head = self.cameras.iter().next().map(|camera| camera.compute_transform());
+        match anchor {
+            PerfHudAnchor::Head { offset } => Some(head? * Transform::from_translation(offset)),
+            PerfHudAnchor::Wrist { hand, offset } => {
+                let wrist = self.joints.get(hand)?.joint(HandJointId::Wrist);
+                let position = wrist.position + wrist.rotation * offset;
+                let head = head.map_or(Vec3::ZERO, |head| head.translation);
+                Some(Transform::from_translation(position).looking_to(position - head, Vec3::Y))
+            }
+        }
+    }
+}
+
+fn draw_perf_hud(
+    hud: Res<XrPerfHud>,
+    sources: PerfHudSources,
+    mut ui: Ui,
+    mut text: Local<PerfHudText>,
+) {
+    if !hud.enabled {
+        return;
+    }
+    text.since_refresh += sources.time.delta_seconds();
+    if text.lines.is_empty() || text.since_refresh >= hud.refresh_interval {
+        text.since_refresh = 0.0;
+        text.lines = sources.lines();
+    }
+    let Some(mut pose) = sources.pose(hud.anchor) else {
+        return;
+    };
+    ui.window_begin("Performance", &mut pose, hud.width);
+    for line in &text.lines {
+        ui.label(line);
+    }
+    ui.window_end();
+}
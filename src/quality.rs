@@ -0,0 +1,121 @@
+use bevy::prelude::*;
+
+/// Global quality preset read by the sky and material plugins, `Low` suits standalone
+/// headsets like the Quest 2 and `High` PC VR
+#[derive(Resource, Reflect, Default, Clone, Debug, PartialEq)]
+#[reflect(Resource)]
+pub enum SkQuality {
+    Low,
+    #[default]
+    Medium,
+    High,
+    Custom(SkQualitySettings),
+}
+
+/// The individual knobs a [`SkQuality`] preset fans out to
+#[derive(Reflect, Clone, Debug, PartialEq)]
+pub struct SkQualitySettings {
+    /// Face size of the generated sky cubemap, rounded up to a power of two
+    pub sky_face_size: u32,
+    /// Prefilters an `EnvironmentMapLight` for generated skies that ask for one
+    pub specular_prefilter: bool,
+    /// Enables normal maps in `PbrMaterial`
+    pub normal_maps: bool,
+    /// Enables parallax occlusion mapping in `PbrMaterial`
+    pub parallax: bool,
+    /// Enables the clearcoat layer in `PbrMaterial`
+    pub clearcoat: bool,
+    /// Enables the detail color and normal textures in `PbrMaterial`
+    pub detail_textures: bool,
+    /// Anisotropic filtering clamp of `PbrMaterial` textures, 1 disables it, see
+    /// [`SkMaterialFeatures::anisotropy`]
+    pub anisotropy: u16,
+    /// Highest SH band `PbrMaterial` evaluates, from 1 to 3, see [`SkMaterialFeatures::sh_order`]
+    pub sh_order: u8,
+    /// Distance beyond which `PbrMaterial` falls back to SH-only shading, 0 disables it
+    pub material_lod_distance: f32,
+}
+
+impl SkQuality {
+    pub fn settings(&self) -> SkQualitySettings {
+        match self {
+            SkQuality::Low => SkQualitySettings {
+                sky_face_size: 8,
+                specular_prefilter: false,
+                normal_maps: false,
+                parallax: false,
+                clearcoat: false,
+                detail_textures: false,
+                anisotropy: 1,
+                sh_order: 2,
+                material_lod_distance: 10.0,
+            },
+            SkQuality::Medium => SkQualitySettings {
+                sky_face_size: 16,
+                specular_prefilter: true,
+                normal_maps: true,
+                parallax: false,
+                clearcoat: true,
+                detail_textures: true,
+                anisotropy: 4,
+                sh_order: 2,
+                material_lod_distance: 30.0,
+            },
+            SkQuality::High => SkQualitySettings {
+                sky_face_size: 64,
+                specular_prefilter: true,
+                normal_maps: true,
+                parallax: true,
+                clearcoat: true,
+                detail_textures: true,
+                anisotropy: 16,
+                sh_order: 3,
+                material_lod_distance: 0.0,
+            },
+            SkQuality::Custom(settings) => settings.clone(),
+        }
+    }
+}
+
+impl SkQualitySettings {
+    /// The `PbrMaterial` features these settings leave on
+    pub fn material_features(&self) -> SkMaterialFeatures {
+        SkMaterialFeatures {
+            normal_maps: self.normal_maps,
+            parallax: self.parallax,
+            clearcoat: self.clearcoat,
+            detail_textures: self.detail_textures,
+            sh_order: self.sh_order,
+            anisotropy: self.anisotropy.max(1),
+        }
+    }
+}
+
+/// Optional `PbrMaterial` features, turned off ones also drop their textures from the shader
+/// and the bind group layout, so `Low` binds fewer textures than `High`
+#[derive(Reflect, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct SkMaterialFeatures {
+    pub normal_maps: bool,
+    pub parallax: bool,
+    pub clearcoat: bool,
+    pub detail_textures: bool,
+    /// Highest SH band evaluated: 1 skips band 2, 2 is the usual second order SH and 3 adds
+    /// the band 3 reflections of the `sh3` feature, which has no effect without it
+    pub sh_order: u8,
+    /// Anisotropic filtering clamp the material samples its textures with, through its own
+    /// copies of their samplers so the `Image`s stay untouched, 1 disables it
+    pub anisotropy: u16,
+}
+
+impl Default for SkMaterialFeatures {
+    fn default() -> Self {
+        Self {
+            normal_maps: true,
+            parallax: true,
+            clearcoat: true,
+            detail_textures: true,
+            sh_order: 3,
+            anisotropy: 1,
+        }
+    }
+}
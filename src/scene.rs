@@ -0,0 +1,124 @@
+use crate::materials::pbr::PbrMaterial;
+use bevy::prelude::*;
+use bevy::reflect::Struct;
+
+/// Round-trips `PbrMaterial` assignments through `DynamicScene`s, the sky and lighting
+/// components are registered by their own plugins.
+///
+/// A `Handle<PbrMaterial>` can't be serialized, so before saving run
+/// [`store_scene_materials`], e.g. with `world.run_system_once(store_scene_materials)`, and
+/// leave the handles out with `DynamicSceneBuilder::deny::<Handle<PbrMaterial>>`. Spawned
+/// scenes get their handles back.
+pub struct SkScenePlugin;
+
+impl Plugin for SkScenePlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<ScenePbrMaterial>();
+        app.add_systems(PreUpdate, load_scene_materials);
+    }
+}
+
+/// Serializable stand in for the `Handle<PbrMaterial>` of an entity, holding a copy of the
+/// material with its textures replaced by their asset paths. Entities without a handle get
+/// one made from it.
+#[derive(Component, Reflect, Clone, Debug, PartialEq)]
+#[reflect(Component)]
+pub struct ScenePbrMaterial {
+    /// The material without its textures
+    pub material: PbrMaterial,
+    pub textures: Vec<SceneTexture>,
+}
+
+/// Texture of a [`ScenePbrMaterial`]
+#[derive(Reflect, Clone, Debug, PartialEq, Eq)]
+pub struct SceneTexture {
+    /// Name of the `PbrMaterial` field holding the texture
+    pub field: String,
+    pub path: String,
+}
+
+impl ScenePbrMaterial {
+    /// Takes the textures out of `material`, textures without an asset path, e.g. generated
+    /// ones, are dropped with a warning
+    pub fn new(material: &PbrMaterial, asset_server: &AssetServer) -> Self {
+        let mut material = material.clone();
+        let mut textures = Vec::new();
+        for index in 0..material.field_len() {
+            let field = material.name_at(index).unwrap_or_default().to_string();
+            let Some(texture) = material
+                .field_at_mut(index)
+                .and_then(|value| value.downcast_mut::<Option<Handle<Image>>>())
+                .and_then(Option::take)
+            else {
+                continue;
+            };
+            match asset_server.get_path(texture.id()) {
+                Some(path) => textures.push(SceneTexture {
+                    field,
+                    path: path.to_string(),
+                }),
+                None => warn!("PbrMaterial {field} wasn't loaded from a file, leaving it out"),
+            }
+        }
+        Self { material, textures }
+    }
+
+    /// The material with its textures loaded again
+    pub fn to_material(&self, asset_server: &AssetServer) -> PbrMaterial {
+        let mut material = self.material.clone();
+        for texture in &self.textures {
+            let slot = material
+                .field_mut(&texture.field)
+                .and_then(|value| value.downcast_mut::<Option<Handle<Image>>>());
+            match slot {
+                Some(slot) => *slot = Some(asset_server.load(texture.path.clone())),
+                None => warn!("PbrMaterial has no texture {}", texture.field),
+            }
+        }
+        material
+    }
+}
+
+/// Gives every entity with a `Handle<PbrMaterial>` a [`ScenePbrMaterial`], run before
+/// building a `DynamicScene`
+pub fn store_scene_materials(
+    mut commands: Commands,
+    entities: Query<(Entity, &Handle<PbrMaterial>)>,
+    materials: Res<Assets<PbrMaterial>>,
+    asset_server: Res<AssetServer>,
+) {
+    for (entity, handle) in entities.iter() {
+        if let Some(material) = materials.get(handle) {
+            commands
+                .entity(entity)
+                .insert(ScenePbrMaterial::new(material, &asset_server));
+        }
+    }
+}
+
+fn load_scene_materials(
+    mut commands: Commands,
+    entities: Query<
+        (Entity, &ScenePbrMaterial),
+        (Added<ScenePbrMaterial>, Without<Handle<PbrMaterial>>),
+    >,
+    mut materials: ResMut<Assets<PbrMaterial>>,
+    asset_server: Res<AssetServer>,
+) {
+    // Entities that shared a material before saving share it again
+    let mut loaded: Vec<(&ScenePbrMaterial, Handle<PbrMaterial>)> = Vec::new();
+    for (entity, scene_material) in entities.iter() {
+        let handle = match loaded.iter().find(|(other, _)| *other == scene_material) {
+            Some((_, handle)) => handle.clone(),
+            None => {
+                let handle = materials.add(scene_material.to_material(&asset_server));
+                loaded.push((scene_material, handle.clone()));
+                handle
+            }
+        };
+        commands
+            .entity(entity)
+            .insert(handle)
+            .remove::<ScenePbrMaterial>();
+    }
+}
@@ -0,0 +1,251 @@
+use crate::hands::{
+    Hand, HandJoint, HandJointId, HandJoints, HandPointer, HandPointers, HandPose,
+    HAND_JOINT_COUNT,
+};
+use bevy::input::mouse::{MouseMotion, MouseWheel};
+use bevy::prelude::*;
+use bevy::window::PrimaryWindow;
+use bevy_mod_xr::camera::XrCamera;
+
+/// Wrist space joint layout of an open right hand, palm down with the fingers along -Z: the
+/// metacarpal position, the sideways splay and the bone lengths towards the tip
+const FINGER_LAYOUT: [(Vec3, f32, [f32; 4]); 4] = [
+    (Vec3::new(-0.02, 0.0, -0.025), -0.1, [0.065, 0.04, 0.025, 0.02]),
+    (Vec3::new(-0.005, 0.0, -0.025), 0.0, [0.065, 0.045, 0.028, 0.02]),
+    (Vec3::new(0.01, 0.0, -0.025), 0.08, [0.06, 0.042, 0.026, 0.02]),
+    (Vec3::new(0.025, 0.0, -0.02), 0.18, [0.055, 0.032, 0.02, 0.018]),
+];
+const THUMB_METACARPAL: Vec3 = Vec3::new(-0.02, -0.01, -0.02);
+const THUMB_DIRECTION: Vec3 = Vec3::new(-0.6, -0.2, -0.75);
+const THUMB_BONES: [f32; 3] = [0.04, 0.032, 0.028];
+
+/// Simulates a head and a hand with mouse and keyboard while no XR camera exists, so apps can
+/// be developed on desktop.
+///
+/// Cameras with a [`FlatscreenHead`] move with WASD, Q and E, and look around while the right
+/// mouse button is held. The simulated hand follows the cursor at
+/// [`FlatscreenSim::hand_distance`], changed with the scroll wheel, and pinches while the left
+/// mouse button is held. It is written to [`HandJoints`] and [`HandPointers`] like tracked
+/// hands, the other hand is left untracked.
+pub struct FlatscreenSimPlugin;
+
+impl Plugin for FlatscreenSimPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<FlatscreenSim>();
+        app.init_resource::<HandJoints>();
+        app.init_resource::<HandPointers>();
+        app.register_type::<(FlatscreenSim, FlatscreenHead)>();
+        app.add_systems(
+            Update,
+            (move_simulated_head, move_simulated_hand)
+                .chain()
+                .run_if(not(any_with_component::<XrCamera>)),
+        );
+    }
+}
+
+/// Controls of [`FlatscreenSimPlugin`]
+#[derive(Resource, Reflect, Clone, Debug, PartialEq)]
+#[reflect(Resource)]
+pub struct FlatscreenSim {
+    /// Meters per second, doubled while shift is held
+    pub move_speed: f32,
+    /// Radians per pixel of mouse motion
+    pub look_sensitivity: f32,
+    pub hand: Hand,
+    /// Distance of the simulated wrist from the camera along the cursor ray
+    pub hand_distance: f32,
+    pub look_button: MouseButton,
+    pub pinch_button: MouseButton,
+}
+
+impl Default for FlatscreenSim {
+    fn default() -> Self {
+        Self {
+            move_speed: 1.5,
+            look_sensitivity: 0.003,
+            hand: Hand::Right,
+            hand_distance: 0.45,
+            look_button: MouseButton::Right,
+            pinch_button: MouseButton::Left,
+        }
+    }
+}
+
+/// Camera driven as the simulated head of [`FlatscreenSimPlugin`], the hand follows the
+/// cursor of the first one
+#[derive(Component, Reflect, Clone, Copy, Debug, Default)]
+#[reflect(Component)]
+pub struct FlatscreenHead;
+
+fn move_simulated_head(
+    mut settings: ResMut<FlatscreenSim>,
+    time: Res<Time>,
+    keys: Res<ButtonInput<KeyCode>>,
+    buttons: Res<ButtonInput<MouseButton>>,
+    mut motion: EventReader<MouseMotion>,
+    mut wheel: EventReader<MouseWheel>,
+    mut heads: Query<&mut Transform, With<FlatscreenHead>>,
+) {
+    let scroll: f32 = wheel.read().map(|w| w.y.signum()).sum();
+    if scroll != 0.0 {
+        settings.hand_distance = (settings.hand_distance + scroll * 0.05).clamp(0.15, 2.0);
+    }
+    let look: Vec2 = motion.read().map(|m| m.delta).sum();
+    let look = if buttons.pressed(settings.look_button) {
+        look * settings.look_sensitivity
+    } else {
+        Vec2::ZERO
+    };
+    let axis = |positive, negative| {
+        keys.pressed(positive) as i32 as f32 - keys.pressed(negative) as i32 as f32
+    };
+    let input = Vec3::new(
+        axis(KeyCode::KeyD, KeyCode::KeyA),
+        axis(KeyCode::KeyE, KeyCode::KeyQ),
+        axis(KeyCode::KeyS, KeyCode::KeyW),
+    );
+    let mut speed = settings.move_speed * time.delta_seconds();
+    if keys.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]) {
+        speed *= 2.0;
+    }
+
+    for mut transform in heads.iter_mut() {
+        if look != Vec2::ZERO {
+            let (yaw, pitch, _) = transform.rotation.to_euler(EulerRot::YXZ);
+            let pitch = (pitch - look.y).clamp(-1.5, 1.5);
+            transform.rotation = Quat::from_euler(EulerRot::YXZ, yaw - look.x, pitch, 0.0);
+        }
+        if input != Vec3::ZERO {
+            // Walks level with the floor whichever way the head is tilted
+            let (yaw, _, _) = transform.rotation.to_euler(EulerRot::YXZ);
+            let movement = Quat::from_rotation_y(yaw) * input.normalize() * speed;
+            transform.translation += movement;
+        }
+    }
+}
+
+fn move_simulated_hand(
+    settings: Res<FlatscreenSim>,
+    buttons: Res<ButtonInput<MouseButton>>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    heads: Query<(&Camera, &GlobalTransform), With<FlatscreenHead>>,
+    mut joints: ResMut<HandJoints>,
+    mut pointers: ResMut<HandPointers>,
+    mut pinch: Local<f32>,
+) {
+    let hand = settings.hand;
+    let ray = windows.get_single().ok().and_then(Window::cursor_position).and_then(|cursor| {
+        let (camera, transform) = heads.iter().next()?;
+        Some((camera.viewport_to_world(transform, cursor)?, transform.up()))
+    });
+    let Some((ray, up)) = ray else {
+        // The hand drops out of tracking while the cursor is outside the window
+        if joints.get(hand).is_some() {
+            joints.set(hand, None);
+            pointers.set(hand, None);
+        }
+        return;
+    };
+
+    // Eases into and out of the pinch rather than snapping
+    let target = buttons.pressed(settings.pinch_button) as i32 as f32;
+    *pinch += (target - *pinch).clamp(-0.25, 0.25);
+    let wrist = Transform::from_translation(ray.get_point(settings.hand_distance))
+        .looking_to(*ray.direction, up);
+    joints.set(hand, Some(simulated_hand_pose(hand, wrist, *pinch)));
+    pointers.set(
+        hand,
+        Some(HandPointer {
+            aim: wrist,
+            pinch: *pinch,
+        }),
+    );
+}
+
+/// A relaxed hand at `wrist`, palm down along the wrist's -Z, with thumb and index tip
+/// brought together by `pinch` from 0 to 1
+pub fn simulated_hand_pose(hand: Hand, wrist: Transform, pinch: f32) -> HandPose {
+    // The layout is of a right hand, the left one mirrors it
+    let side = match hand {
+        Hand::Left => Vec3::new(-1.0, 1.0, 1.0),
+        Hand::Right => Vec3::ONE,
+    };
+    let mut local = [(Vec3::ZERO, 0.01); HAND_JOINT_COUNT];
+    local[HandJointId::Wrist as usize] = (Vec3::ZERO, 0.02);
+
+    let fingers = [
+        HandJointId::IndexMetacarpal,
+        HandJointId::MiddleMetacarpal,
+        HandJointId::RingMetacarpal,
+        HandJointId::LittleMetacarpal,
+    ];
+    for (first, (base, splay, bones)) in fingers.into_iter().zip(FINGER_LAYOUT) {
+        // Every finger rests slightly curled, the index curls further into the pinch
+        let curl = if first == HandJointId::IndexMetacarpal {
+            0.15 + 0.45 * pinch
+        } else {
+            0.25
+        };
+        let mut direction = Vec3::new(splay, 0.0, -1.0).normalize();
+        let mut position = base;
+        let little = first == HandJointId::LittleMetacarpal;
+        for (i, bone) in bones.iter().enumerate() {
+            let radius = (0.011 - 0.001 * i as f32) * if little { 0.85 } else { 1.0 };
+            local[first as usize + i] = (position * side, radius);
+            if i > 0 {
+                direction = Quat::from_rotation_x(-curl) * direction;
+            }
+            position += direction * *bone;
+        }
+        let radius = 0.007 * if little { 0.85 } else { 1.0 };
+        local[first as usize + bones.len()] = (position * side, radius);
+    }
+
+    // The thumb joints slide from their open layout onto a line to the index tip
+    let index_tip = local[HandJointId::IndexTip as usize].0 * side;
+    let length: f32 = THUMB_BONES.iter().sum();
+    let mut open = THUMB_METACARPAL;
+    let mut along = 0.0;
+    local[HandJointId::ThumbMetacarpal as usize] = (THUMB_METACARPAL * side, 0.012);
+    for (i, bone) in THUMB_BONES.iter().enumerate() {
+        open += THUMB_DIRECTION.normalize() * *bone;
+        along += bone / length;
+        let pinched = THUMB_METACARPAL.lerp(index_tip, along);
+        let radius = 0.01 - 0.001 * i as f32;
+        local[HandJointId::ThumbProximal as usize + i] = (open.lerp(pinched, pinch) * side, radius);
+    }
+    let palm = local[HandJointId::MiddleProximal as usize].0 * 0.5;
+    local[HandJointId::Palm as usize] = (palm, 0.02);
+
+    let mut pose = HandPose::default();
+    for (i, (position, radius)) in local.iter().enumerate() {
+        // Each joint looks down its bone, the tips keep the aim of the bone before them
+        let towards = if is_tip(i) {
+            *position - local[i - 1].0
+        } else if i <= HandJointId::Wrist as usize {
+            Vec3::NEG_Z
+        } else {
+            local[i + 1].0 - *position
+        };
+        let rotation = Transform::default().looking_to(towards, Vec3::Y).rotation;
+        pose.joints[i] = HandJoint {
+            position: wrist.transform_point(*position),
+            rotation: wrist.rotation * rotation,
+            radius: *radius,
+        };
+    }
+    pose
+}
+
+fn is_tip(joint: usize) -> bool {
+    [
+        HandJointId::ThumbTip,
+        HandJointId::IndexTip,
+        HandJointId::MiddleTip,
+        HandJointId::RingTip,
+        HandJointId::LittleTip,
+    ]
+    .iter()
+    .any(|tip| *tip as usize == joint)
+}
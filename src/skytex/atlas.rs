@@ -0,0 +1,177 @@
+use crate::skytex::SetupSkyTex;
+use bevy::core_pipeline::Skybox;
+use bevy::prelude::*;
+use bevy::render::render_asset::RenderAssetUsages;
+use bevy::render::render_resource::{Extent3d, TextureDimension};
+use bevy::render::texture::ImageSampler;
+
+/// Shows the faces of a generated sky as a labeled cross in the corner of the window
+pub struct SkyAtlasDebugPlugin;
+
+impl Plugin for SkyAtlasDebugPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<SkyAtlasDebug>();
+        app.add_systems(Update, update_sky_atlas_overlay);
+    }
+}
+
+#[derive(Resource, Clone, Debug)]
+pub struct SkyAtlasDebug {
+    pub enabled: bool,
+    /// Camera whose skybox is shown, the first generated sky is used when `None`
+    pub camera: Option<Entity>,
+    /// On-screen size of a single face in logical pixels
+    pub face_size: f32,
+}
+
+impl Default for SkyAtlasDebug {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            camera: None,
+            face_size: 96.0,
+        }
+    }
+}
+
+/// Layer order of a cubemap, matching wgpu's +X, -X, +Y, -Y, +Z, -Z
+pub const CUBEMAP_FACE_LABELS: [&str; 6] = ["+X", "-X", "+Y", "-Y", "+Z", "-Z"];
+
+/// (column, row) of each cubemap layer in the 4x3 horizontal cross
+///
+/// ```text
+///        +Y
+///    -X  +Z  +X  -Z
+///        -Y
+/// ```
+pub const CUBEMAP_CROSS_LAYOUT: [(u32, u32); 6] = [(2, 1), (0, 1), (1, 0), (1, 2), (1, 1), (3, 1)];
+
+/// Lays the six layers of `cubemap` out as a 4x3 horizontal cross, unused cells are left
+/// transparent. Returns `None` for images that aren't 6-layer uncompressed textures.
+pub fn cubemap_atlas(cubemap: &Image) -> Option<Image> {
+    let size = cubemap.texture_descriptor.size;
+    if size.depth_or_array_layers != 6 {
+        return None;
+    }
+    let format = cubemap.texture_descriptor.format;
+    let texel_size = format.block_copy_size(None)? as usize;
+    let face = size.width as usize;
+    let face_bytes = face * face * texel_size;
+    // Layers are stored one after another with all of their mips, only mip 0 is shown
+    let layer_stride = cubemap.data.len() / 6;
+    if layer_stride < face_bytes {
+        return None;
+    }
+
+    let atlas_width = face * 4;
+    let atlas_height = face * 3;
+    let mut data = vec![0u8; atlas_width * atlas_height * texel_size];
+    for (layer, (column, row)) in CUBEMAP_CROSS_LAYOUT.iter().enumerate() {
+        let src = &cubemap.data[layer * layer_stride..layer * layer_stride + face_bytes];
+        for y in 0..face {
+            let src_row = &src[y * face * texel_size..(y + 1) * face * texel_size];
+            let dst_x = *column as usize * face;
+            let dst_y = *row as usize * face + y;
+            let dst = (dst_y * atlas_width + dst_x) * texel_size;
+            data[dst..dst + face * texel_size].copy_from_slice(src_row);
+        }
+    }
+
+    let mut atlas = Image::new(
+        Extent3d {
+            width: atlas_width as u32,
+            height: atlas_height as u32,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        data,
+        format,
+        RenderAssetUsages::default(),
+    );
+    atlas.sampler = ImageSampler::nearest();
+    Some(atlas)
+}
+
+#[derive(Component)]
+struct SkyAtlasOverlay {
+    sky: Handle<Image>,
+}
+
+fn update_sky_atlas_overlay(
+    mut commands: Commands,
+    settings: Res<SkyAtlasDebug>,
+    skies: Query<(Entity, &Skybox), With<SetupSkyTex>>,
+    overlays: Query<(Entity, &SkyAtlasOverlay)>,
+    mut images: ResMut<Assets<Image>>,
+) {
+    let sky = settings
+        .enabled
+        .then(|| match settings.camera {
+            Some(camera) => skies.get(camera).ok(),
+            None => skies.iter().next(),
+        })
+        .flatten()
+        .map(|(_, skybox)| skybox.image.clone());
+
+    // Rebuild whenever the shown sky is regenerated or the overlay gets toggled
+    let current = overlays.iter().next();
+    if current.map(|(_, overlay)| &overlay.sky) == sky.as_ref() {
+        return;
+    }
+    if let Some((entity, _)) = current {
+        commands.entity(entity).despawn_recursive();
+    }
+    let Some(sky) = sky else {
+        return;
+    };
+    let Some(atlas) = images.get(&sky).and_then(cubemap_atlas) else {
+        return;
+    };
+    let atlas = images.add(atlas);
+
+    let face = settings.face_size;
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    left: Val::Px(8.0),
+                    bottom: Val::Px(8.0),
+                    width: Val::Px(face * 4.0),
+                    height: Val::Px(face * 3.0),
+                    ..default()
+                },
+                ..default()
+            },
+            SkyAtlasOverlay { sky },
+        ))
+        .with_children(|parent| {
+            parent.spawn(ImageBundle {
+                style: Style {
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    ..default()
+                },
+                image: UiImage::new(atlas),
+                ..default()
+            });
+            for (label, (column, row)) in CUBEMAP_FACE_LABELS.iter().zip(CUBEMAP_CROSS_LAYOUT) {
+                parent.spawn(
+                    TextBundle::from_section(
+                        *label,
+                        TextStyle {
+                            font_size: 14.0,
+                            color: Color::WHITE,
+                            ..default()
+                        },
+                    )
+                    .with_style(Style {
+                        position_type: PositionType::Absolute,
+                        left: Val::Px(column as f32 * face + 4.0),
+                        top: Val::Px(row as f32 * face + 2.0),
+                        ..default()
+                    }),
+                );
+            }
+        });
+}
@@ -0,0 +1,210 @@
+use crate::skytex::lifecycle::NoSkyTex;
+use crate::skytex::paint::PaintedSky;
+use crate::skytex::{
+    setup_skytex, GeneratedSky, SetupSkyTex, SkyLighting, SkyRadiance, SkyTexFormat,
+    SKYBOX_BRIGHTNESS,
+};
+use bevy::core_pipeline::Skybox;
+use bevy::prelude::*;
+use std::f32::consts::PI;
+
+/// Replaces the generated SH sky with a single scattering Rayleigh / Mie atmosphere.
+///
+/// The sky is rebuilt whenever [`AtmosphereSky`] changes and its projected SH becomes the
+/// [`SkyLighting`], so `PbrMaterial` ambient matches the sky.
+pub struct AtmosphereSkyPlugin;
+
+impl Plugin for AtmosphereSkyPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<AtmosphereSky>();
+        app.register_type::<AtmosphereSky>();
+        app.add_systems(Update, apply_atmosphere_sky.before(setup_skytex));
+    }
+}
+
+#[derive(Resource, Reflect, Clone, Copy, Debug, PartialEq)]
+#[reflect(Resource)]
+pub struct AtmosphereSky {
+    /// Sun angle above the horizon in radians
+    pub sun_elevation: f32,
+    /// Sun angle around +Y in radians, 0 is towards -Z
+    pub sun_azimuth: f32,
+    /// Haze, 1 is a perfectly clear sky and 10 a very hazy one
+    pub turbidity: f32,
+    pub ground_albedo: Color,
+    /// Scales the sky radiance to the range the skybox and SH lighting are tuned for
+    pub intensity: f32,
+    pub face_size: u32,
+}
+
+impl Default for AtmosphereSky {
+    fn default() -> Self {
+        Self {
+            sun_elevation: 0.6,
+            sun_azimuth: 0.4,
+            turbidity: 2.0,
+            ground_albedo: Color::srgb(0.3, 0.3, 0.3),
+            intensity: 0.05,
+            face_size: 64,
+        }
+    }
+}
+
+const EARTH_RADIUS: f32 = 6_360e3;
+const ATMOSPHERE_RADIUS: f32 = 6_420e3;
+const RAYLEIGH_HEIGHT: f32 = 7_994.0;
+const MIE_HEIGHT: f32 = 1_200.0;
+const RAYLEIGH_SCATTERING: Vec3 = Vec3::new(5.8e-6, 13.5e-6, 33.1e-6);
+const MIE_SCATTERING: f32 = 4e-6;
+const MIE_G: f32 = 0.76;
+const SUN_INTENSITY: f32 = 20.0;
+const VIEW_SAMPLES: u32 = 16;
+const LIGHT_SAMPLES: u32 = 8;
+
+impl AtmosphereSky {
+    /// Direction from the viewer towards the sun
+    pub fn sun_direction(&self) -> Vec3 {
+        let (sin_el, cos_el) = self.sun_elevation.sin_cos();
+        let (sin_az, cos_az) = self.sun_azimuth.sin_cos();
+        Vec3::new(cos_el * sin_az, sin_el, -cos_el * cos_az)
+    }
+
+    /// Linear radiance seen in `dir`
+    pub fn radiance(&self, dir: Vec3) -> Vec4 {
+        let sun = self.sun_direction();
+        let color = if dir.y >= 0.0 {
+            self.scatter(dir, sun)
+        } else {
+            // Lambertian ground lit by the attenuated sun and the zenith sky
+            let albedo = self.ground_albedo.to_linear().to_vec3();
+            let sunlight = self.transmittance(Vec3::new(0.0, EARTH_RADIUS + 1.0, 0.0), sun)
+                * SUN_INTENSITY
+                * sun.y.max(0.0);
+            albedo * (sunlight + self.scatter(Vec3::Y, sun) * PI) / PI
+        };
+        (color * self.intensity).extend(1.0)
+    }
+
+    fn mie_scattering(&self) -> f32 {
+        MIE_SCATTERING * self.turbidity.max(1.0)
+    }
+
+    /// Single scattered sky radiance along `dir`, Nishita style ray marching
+    fn scatter(&self, dir: Vec3, sun: Vec3) -> Vec3 {
+        let origin = Vec3::new(0.0, EARTH_RADIUS + 1.0, 0.0);
+        let length = ray_sphere_exit(origin, dir, ATMOSPHERE_RADIUS);
+        let step = length / VIEW_SAMPLES as f32;
+        let mie = self.mie_scattering();
+
+        let mut rayleigh_sum = Vec3::ZERO;
+        let mut mie_sum = Vec3::ZERO;
+        let (mut depth_r, mut depth_m) = (0.0, 0.0);
+        for i in 0..VIEW_SAMPLES {
+            let point = origin + dir * (step * (i as f32 + 0.5));
+            let height = point.length() - EARTH_RADIUS;
+            let hr = (-height / RAYLEIGH_HEIGHT).exp() * step;
+            let hm = (-height / MIE_HEIGHT).exp() * step;
+            depth_r += hr;
+            depth_m += hm;
+
+            let Some((light_r, light_m)) = optical_depth(point, sun) else {
+                continue;
+            };
+            let tau = RAYLEIGH_SCATTERING * (depth_r + light_r)
+                + Vec3::splat(mie * 1.1 * (depth_m + light_m));
+            let attenuation = (-tau).exp();
+            rayleigh_sum += attenuation * hr;
+            mie_sum += attenuation * hm;
+        }
+
+        let mu = dir.dot(sun);
+        let phase_r = 3.0 / (16.0 * PI) * (1.0 + mu * mu);
+        let g2 = MIE_G * MIE_G;
+        let phase_m = 3.0 / (8.0 * PI) * ((1.0 - g2) * (1.0 + mu * mu))
+            / ((2.0 + g2) * (1.0 + g2 - 2.0 * MIE_G * mu).powf(1.5));
+
+        (rayleigh_sum * RAYLEIGH_SCATTERING * phase_r + mie_sum * mie * phase_m) * SUN_INTENSITY
+    }
+
+    fn transmittance(&self, point: Vec3, dir: Vec3) -> Vec3 {
+        match optical_depth(point, dir) {
+            Some((r, m)) => {
+                let tau = RAYLEIGH_SCATTERING * r + Vec3::splat(self.mie_scattering() * 1.1 * m);
+                (-tau).exp()
+            }
+            None => Vec3::ZERO,
+        }
+    }
+
+    /// Renders the atmosphere into a cubemap and projects its SH
+    pub fn paint(&self, format: SkyTexFormat) -> PaintedSky {
+        PaintedSky::from_fn(self.face_size, format, |dir| self.radiance(dir))
+    }
+}
+
+impl SkyRadiance for AtmosphereSky {
+    fn radiance(&self, dir: Vec3) -> Vec4 {
+        AtmosphereSky::radiance(self, dir)
+    }
+}
+
+/// Rayleigh and Mie optical depth from `point` to the top of the atmosphere along `dir`,
+/// `None` if the earth is in the way
+fn optical_depth(point: Vec3, dir: Vec3) -> Option<(f32, f32)> {
+    let length = ray_sphere_exit(point, dir, ATMOSPHERE_RADIUS);
+    let step = length / LIGHT_SAMPLES as f32;
+    let (mut depth_r, mut depth_m) = (0.0, 0.0);
+    for i in 0..LIGHT_SAMPLES {
+        let sample = point + dir * (step * (i as f32 + 0.5));
+        let height = sample.length() - EARTH_RADIUS;
+        if height < 0.0 {
+            return None;
+        }
+        depth_r += (-height / RAYLEIGH_HEIGHT).exp() * step;
+        depth_m += (-height / MIE_HEIGHT).exp() * step;
+    }
+    Some((depth_r, depth_m))
+}
+
+/// Distance from `origin`, inside a sphere of `radius` at the world origin, to its surface
+fn ray_sphere_exit(origin: Vec3, dir: Vec3, radius: f32) -> f32 {
+    let b = origin.dot(dir);
+    let c = origin.length_squared() - radius * radius;
+    -b + (b * b - c).max(0.0).sqrt()
+}
+
+fn apply_atmosphere_sky(
+    mut commands: Commands,
+    atmosphere: Res<AtmosphereSky>,
+    format: Res<SkyTexFormat>,
+    new_cameras: Query<Entity, (With<Camera3d>, Without<SetupSkyTex>, Without<NoSkyTex>)>,
+    cameras: Query<Entity, (With<Camera3d>, Without<NoSkyTex>)>,
+    mut images: ResMut<Assets<Image>>,
+    mut current: Local<Option<Handle<Image>>>,
+) {
+    let rebuilt = atmosphere.is_changed() || format.is_changed() || current.is_none();
+    if rebuilt {
+        let sky = atmosphere.paint(*format);
+        *current = Some(images.add(sky.image));
+        commands.insert_resource(SkyLighting(sky.lighting));
+    }
+    let image = current.clone().unwrap();
+
+    let targets: Vec<Entity> = if rebuilt {
+        cameras.iter().collect()
+    } else {
+        new_cameras.iter().collect()
+    };
+    for entity in targets {
+        commands
+            .entity(entity)
+            .insert((
+                Skybox {
+                    image: image.clone(),
+                    brightness: SKYBOX_BRIGHTNESS,
+                },
+                SetupSkyTex,
+            ))
+            .remove::<GeneratedSky>();
+    }
+}
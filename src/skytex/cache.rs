@@ -0,0 +1,167 @@
+use crate::skytex::stars::StarVisibility;
+use crate::skytex::{LightSpot, SkyTexFormat, SkyTexSettings, SphericalHarmonics};
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+use std::hash::{Hash, Hasher};
+
+/// SH steps finer than this don't visibly change the generated sky
+const SH_QUANTIZATION: f32 = 1024.0;
+
+/// Identifies a generated sky by everything that goes into its textures
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct SkyTexKey {
+    coefficients: [[i32; 3]; 9],
+    face_size: u32,
+    /// Digest of every painted light spot
+    spots: u64,
+    environment_face_size: Option<u32>,
+    /// Digest of the cloud and star layers
+    layers: u64,
+    format: SkyTexFormat,
+}
+
+impl SkyTexKey {
+    /// Key for `lighting`, already windowed, rendered with `settings` and `spots` at
+    /// `face_size`
+    pub(crate) fn new(
+        lighting: &SphericalHarmonics,
+        face_size: u32,
+        settings: &SkyTexSettings,
+        spots: &[LightSpot],
+        format: SkyTexFormat,
+    ) -> Self {
+        let quantize = |v: f32| (v * SH_QUANTIZATION).round() as i32;
+        Self {
+            coefficients: lighting
+                .coefficients
+                .map(|c| [quantize(c.x), quantize(c.y), quantize(c.z)]),
+            face_size,
+            spots: spots_digest(spots),
+            environment_face_size: settings.environment_face_size,
+            layers: layers_digest(settings),
+            format,
+        }
+    }
+}
+
+fn spots_digest(spots: &[LightSpot]) -> u64 {
+    let mut hasher = bevy::utils::AHasher::default();
+    for spot in spots {
+        let direction = spot.direction.extend(spot.radius).to_array();
+        for value in direction.into_iter().chain(spot.color.to_array()) {
+            ((value * SH_QUANTIZATION).round() as i32).hash(&mut hasher);
+        }
+    }
+    hasher.finish()
+}
+
+fn layers_digest(settings: &SkyTexSettings) -> u64 {
+    let mut hasher = bevy::utils::AHasher::default();
+    let mut write = |values: &[f32]| {
+        for value in values {
+            ((value * SH_QUANTIZATION).round() as i32).hash(&mut hasher);
+        }
+    };
+    if let Some(clouds) = &settings.clouds {
+        let color = clouds.color.to_linear();
+        write(&[clouds.coverage, clouds.density, clouds.scale, clouds.offset.x, clouds.offset.y]);
+        write(&[color.red, color.green, color.blue]);
+    }
+    // Keeps clouds and stars from hashing alike
+    write(&[-1.0]);
+    if let Some(stars) = &settings.stars {
+        let threshold = match stars.visibility {
+            StarVisibility::BelowBrightness(threshold) => threshold,
+            StarVisibility::Always => -1.0,
+        };
+        write(&[threshold, stars.density, stars.brightness]);
+        if let Some(moon) = &stars.moon {
+            let color = moon.color.to_linear();
+            write(&moon.direction.to_array());
+            write(&[moon.intensity, moon.radius, color.red, color.green, color.blue]);
+        }
+        stars.seed.hash(&mut hasher);
+    }
+    hasher.finish()
+}
+
+/// Textures of a generated sky
+#[derive(Clone, Debug)]
+pub struct CachedSky {
+    pub sky: Option<Handle<Image>>,
+    /// Diffuse and specular map of the `EnvironmentMapLight`
+    pub environment: Option<(Handle<Image>, Handle<Image>)>,
+    last_used: u64,
+}
+
+/// Generated skies shared between cameras and regenerations with the same parameters.
+///
+/// Holds strong handles, the least recently used entries are dropped beyond `capacity`.
+#[derive(Resource, Debug)]
+pub struct SkyTexCache {
+    pub capacity: usize,
+    entries: HashMap<SkyTexKey, CachedSky>,
+    tick: u64,
+}
+
+impl Default for SkyTexCache {
+    fn default() -> Self {
+        Self {
+            capacity: 8,
+            entries: HashMap::default(),
+            tick: 0,
+        }
+    }
+}
+
+impl SkyTexCache {
+    pub fn get(&mut self, key: &SkyTexKey) -> Option<&CachedSky> {
+        self.tick += 1;
+        let tick = self.tick;
+        self.entries.get_mut(key).map(|entry| {
+            entry.last_used = tick;
+            &*entry
+        })
+    }
+
+    pub fn insert(
+        &mut self,
+        key: SkyTexKey,
+        sky: Option<Handle<Image>>,
+        environment: Option<(Handle<Image>, Handle<Image>)>,
+    ) {
+        self.tick += 1;
+        self.entries.insert(
+            key,
+            CachedSky {
+                sky,
+                environment,
+                last_used: self.tick,
+            },
+        );
+        while self.entries.len() > self.capacity.max(1) {
+            let Some(oldest) = self
+                .entries
+                .iter()
+                .min_by_key(|(_, entry)| entry.last_used)
+                .map(|(key, _)| *key)
+            else {
+                break;
+            };
+            self.entries.remove(&oldest);
+        }
+    }
+
+    pub fn remove(&mut self, key: &SkyTexKey) {
+        self.entries.remove(key);
+    }
+
+    /// Keeps only the entries whose key passes `keep`
+    pub fn retain(&mut self, mut keep: impl FnMut(&SkyTexKey) -> bool) {
+        self.entries.retain(|key, _| keep(key));
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+}
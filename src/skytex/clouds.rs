@@ -0,0 +1,127 @@
+use crate::skytex::{sh_lookup, SkyTexSettings, SphericalHarmonics};
+use bevy::prelude::*;
+
+/// Noise clouds baked into the generated sky, set through [`SkyTexSettings::clouds`]
+#[derive(Reflect, Clone, Copy, Debug, PartialEq)]
+pub struct CloudLayer {
+    /// Fraction of the sky covered, 0 is clear and 1 overcast
+    pub coverage: f32,
+    /// Opacity of fully covered areas
+    pub density: f32,
+    /// Noise frequency, higher values give smaller clouds
+    pub scale: f32,
+    /// Drift of the noise per second, see [`CloudLayer::offset`]
+    pub speed: Vec2,
+    /// Current noise offset, advanced by `speed` every `update_interval`
+    pub offset: Vec2,
+    /// Real seconds between drift steps, every step regenerates the sky
+    pub update_interval: f32,
+    /// Albedo, lit by the sky straight above
+    pub color: Color,
+}
+
+impl Default for CloudLayer {
+    fn default() -> Self {
+        Self {
+            coverage: 0.4,
+            density: 0.85,
+            scale: 1.5,
+            speed: Vec2::new(0.02, 0.0),
+            offset: Vec2::ZERO,
+            update_interval: 1.0,
+            color: Color::WHITE,
+        }
+    }
+}
+
+impl CloudLayer {
+    /// Opacity of the clouds seen in `dir`, 0 below the horizon
+    pub fn cover(&self, dir: Vec3) -> f32 {
+        if dir.y <= 0.0 {
+            return 0.0;
+        }
+        // Project onto a flat cloud deck, the horizon fade hides its infinite extent
+        let uv = Vec2::new(dir.x, dir.z) / (dir.y + 0.1) * self.scale + self.offset;
+        let threshold = 1.0 - self.coverage.clamp(0.0, 1.0);
+        let t = ((fbm(uv) - threshold + 0.15) / 0.3).clamp(0.0, 1.0);
+        let horizon = (dir.y / 0.15).clamp(0.0, 1.0);
+        t * t * (3.0 - 2.0 * t) * self.density.clamp(0.0, 1.0) * horizon
+    }
+
+    /// Adds the clouds over `radiance`, the sky and light spot seen in `dir`
+    pub fn composite(&self, lighting: &SphericalHarmonics, dir: Vec3, radiance: Vec4) -> Vec4 {
+        let cover = self.cover(dir);
+        if cover <= 0.0 {
+            return radiance;
+        }
+        let lit = self.color.to_linear().to_vec4() * sh_lookup(lighting, Vec3::Y);
+        radiance.lerp(lit.truncate().extend(1.0), cover)
+    }
+
+    /// `lighting` dimmed by the cover, the directional bands more than the ambient one since
+    /// an overcast sky scatters light evenly
+    pub fn attenuate(&self, lighting: &SphericalHarmonics) -> SphericalHarmonics {
+        let cover = (self.coverage * self.density).clamp(0.0, 1.0);
+        let mut attenuated = *lighting;
+        attenuated.coefficients[0] *= 1.0 - 0.4 * cover;
+        for coefficient in attenuated.coefficients[1..].iter_mut() {
+            *coefficient *= 1.0 - 0.8 * cover;
+        }
+        attenuated
+    }
+}
+
+fn hash(p: IVec2) -> f32 {
+    let mut h = (p.x as u32).wrapping_mul(0x8da6_b343) ^ (p.y as u32).wrapping_mul(0xd816_3841);
+    h ^= h >> 13;
+    h = h.wrapping_mul(0x5bd1_e995);
+    h ^= h >> 15;
+    h as f32 / u32::MAX as f32
+}
+
+fn value_noise(p: Vec2) -> f32 {
+    let cell = p.floor();
+    let f = p - cell;
+    let u = f * f * (Vec2::splat(3.0) - 2.0 * f);
+    let i = cell.as_ivec2();
+    let a = hash(i);
+    let b = hash(i + IVec2::X);
+    let c = hash(i + IVec2::Y);
+    let d = hash(i + IVec2::ONE);
+    let top = a + (b - a) * u.x;
+    let bottom = c + (d - c) * u.x;
+    top + (bottom - top) * u.y
+}
+
+fn fbm(mut p: Vec2) -> f32 {
+    let mut sum = 0.0;
+    let mut amplitude = 0.5;
+    for _ in 0..4 {
+        sum += value_noise(p) * amplitude;
+        p = p * 2.03 + Vec2::splat(17.0);
+        amplitude *= 0.5;
+    }
+    sum / 0.9375
+}
+
+pub(crate) fn drift_clouds(
+    time: Res<Time>,
+    mut settings: ResMut<SkyTexSettings>,
+    mut since_step: Local<f32>,
+) {
+    let Some(clouds) = settings.clouds else {
+        return;
+    };
+    if clouds.speed == Vec2::ZERO {
+        return;
+    }
+    *since_step += time.delta_seconds();
+    if *since_step < clouds.update_interval {
+        return;
+    }
+    let step = *since_step;
+    *since_step = 0.0;
+    if let Some(clouds) = settings.clouds.as_mut() {
+        clouds.offset += clouds.speed * step;
+    }
+}
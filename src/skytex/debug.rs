@@ -0,0 +1,95 @@
+use crate::skytex::atlas::{SkyAtlasDebug, SkyAtlasDebugPlugin};
+use crate::skytex::SkyLighting;
+use bevy::prelude::*;
+use std::f32::consts::{PI, TAU};
+
+/// Draws [`SkyLighting`] as an SH shaded gizmo sphere with an arrow along the dominant light,
+/// optionally next to the [`SkyAtlasDebug`] face preview
+pub struct SkyDebugPlugin;
+
+impl Plugin for SkyDebugPlugin {
+    fn build(&self, app: &mut App) {
+        if !app.is_plugin_added::<SkyAtlasDebugPlugin>() {
+            app.add_plugins(SkyAtlasDebugPlugin);
+        }
+        app.init_resource::<SkyDebug>();
+        app.register_type::<SkyDebug>();
+        app.add_systems(Update, draw_sky_debug);
+    }
+}
+
+#[derive(Resource, Reflect, Clone, Copy, Debug, PartialEq)]
+#[reflect(Resource)]
+pub struct SkyDebug {
+    pub enabled: bool,
+    /// World space center of the sphere
+    pub position: Vec3,
+    pub radius: f32,
+    /// Also shows the six faces of the generated sky
+    pub show_faces: bool,
+}
+
+impl Default for SkyDebug {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            position: Vec3::new(0.0, 1.5, -1.0),
+            radius: 0.25,
+            show_faces: false,
+        }
+    }
+}
+
+const RINGS: u32 = 8;
+const SEGMENTS: u32 = 24;
+
+fn draw_sky_debug(
+    mut gizmos: Gizmos,
+    settings: Res<SkyDebug>,
+    lighting: Res<SkyLighting>,
+    mut atlas: ResMut<SkyAtlasDebug>,
+) {
+    if settings.is_changed() {
+        atlas.enabled = settings.enabled && settings.show_faces;
+    }
+    if !settings.enabled {
+        return;
+    }
+
+    // Reinhard keeps bright directions apart instead of clipping them all to white
+    let shade = |dir: Vec3| {
+        let irradiance = lighting.sample(dir).truncate().max(Vec3::ZERO);
+        let mapped = irradiance / (Vec3::ONE + irradiance);
+        Color::linear_rgb(mapped.x, mapped.y, mapped.z)
+    };
+    let direction = |polar: f32, azimuth: f32| {
+        Vec3::new(polar.sin() * azimuth.cos(), polar.cos(), polar.sin() * azimuth.sin())
+    };
+    let center = settings.position;
+    let radius = settings.radius;
+
+    for ring in 1..RINGS {
+        let polar = ring as f32 / RINGS as f32 * PI;
+        for segment in 0..SEGMENTS {
+            let a = direction(polar, segment as f32 / SEGMENTS as f32 * TAU);
+            let b = direction(polar, (segment + 1) as f32 / SEGMENTS as f32 * TAU);
+            gizmos.line_gradient(center + a * radius, center + b * radius, shade(a), shade(b));
+        }
+    }
+    for meridian in 0..SEGMENTS / 2 {
+        let azimuth = meridian as f32 / (SEGMENTS / 2) as f32 * TAU;
+        for segment in 0..RINGS {
+            let a = direction(segment as f32 / RINGS as f32 * PI, azimuth);
+            let b = direction((segment + 1) as f32 / RINGS as f32 * PI, azimuth);
+            gizmos.line_gradient(center + a * radius, center + b * radius, shade(a), shade(b));
+        }
+    }
+
+    // Comes in from the light and points the way it travels
+    let travel = lighting.dominant_dir();
+    gizmos.arrow(
+        center - travel * radius * 2.5,
+        center - travel * radius * 1.05,
+        shade(-travel),
+    );
+}
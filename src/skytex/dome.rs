@@ -0,0 +1,137 @@
+use bevy::core_pipeline::Skybox;
+use bevy::pbr::{NotShadowCaster, NotShadowReceiver};
+use bevy::prelude::*;
+use bevy::render::render_resource::{AsBindGroup, Face, ShaderRef};
+
+const SHADER_HANDLE: Handle<Shader> = Handle::weak_from_u128(0x8e37b5c41d29);
+
+pub struct SkyDomePlugin;
+
+impl Plugin for SkyDomePlugin {
+    fn build(&self, app: &mut App) {
+        load_shader!(app, SHADER_HANDLE, "dome.wgsl");
+        app.add_plugins(MaterialPlugin::<SkyDomeMaterial>::default());
+        app.register_type::<SkyDome>();
+        app.add_systems(PostUpdate, (move_sky_to_dome, restore_skybox));
+    }
+}
+
+/// Renders the generated sky of this camera on a sphere of finite `radius` around `center`
+/// instead of at infinity, so nearby room-scale content parallaxes against it
+#[derive(Component, Reflect, Clone, Copy, Debug, PartialEq)]
+#[reflect(Component)]
+pub struct SkyDome {
+    pub radius: f32,
+    pub center: Vec3,
+    /// Multiplier on the sky texture, 1.0 matches the SH lit `PbrMaterial` surfaces
+    pub brightness: f32,
+}
+
+impl Default for SkyDome {
+    fn default() -> Self {
+        Self {
+            radius: 50.0,
+            center: Vec3::ZERO,
+            brightness: 1.0,
+        }
+    }
+}
+
+/// The dome entity spawned for a [`SkyDome`] camera
+#[derive(Component, Clone, Copy, Debug)]
+pub struct SkyDomeOf(pub Entity);
+
+#[derive(Asset, AsBindGroup, TypePath, Clone, Debug)]
+pub struct SkyDomeMaterial {
+    #[uniform(0)]
+    pub center: Vec3,
+    #[uniform(0)]
+    pub brightness: f32,
+    #[texture(1, dimension = "cube")]
+    #[sampler(2)]
+    pub sky: Handle<Image>,
+}
+
+impl Material for SkyDomeMaterial {
+    fn fragment_shader() -> ShaderRef {
+        SHADER_HANDLE.into()
+    }
+
+    fn specialize(
+        _pipeline: &bevy::pbr::MaterialPipeline<Self>,
+        descriptor: &mut bevy::render::render_resource::RenderPipelineDescriptor,
+        _layout: &bevy::render::mesh::MeshVertexBufferLayoutRef,
+        _key: bevy::pbr::MaterialPipelineKey<Self>,
+    ) -> Result<(), bevy::render::render_resource::SpecializedMeshPipelineError> {
+        // The camera sits inside the sphere
+        descriptor.primitive.cull_mode = Some(Face::Front);
+        Ok(())
+    }
+}
+
+/// Takes the `Skybox` off dome cameras and shows its image on the dome instead
+fn move_sky_to_dome(
+    mut commands: Commands,
+    cameras: Query<(Entity, &SkyDome, &Skybox)>,
+    mut domes: Query<(&SkyDomeOf, &Handle<SkyDomeMaterial>, &mut Transform)>,
+    mut materials: ResMut<Assets<SkyDomeMaterial>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+) {
+    for (camera, dome, skybox) in cameras.iter() {
+        let existing = domes.iter_mut().find(|(of, _, _)| of.0 == camera);
+        match existing {
+            Some((_, material, mut transform)) => {
+                if let Some(material) = materials.get_mut(material) {
+                    material.sky = skybox.image.clone();
+                    material.center = dome.center;
+                    material.brightness = dome.brightness;
+                }
+                *transform = Transform::from_translation(dome.center).with_scale(Vec3::splat(dome.radius));
+            }
+            None => {
+                commands.spawn((
+                    MaterialMeshBundle {
+                        mesh: meshes.add(Sphere::new(1.0).mesh().uv(48, 24)),
+                        material: materials.add(SkyDomeMaterial {
+                            center: dome.center,
+                            brightness: dome.brightness,
+                            sky: skybox.image.clone(),
+                        }),
+                        transform: Transform::from_translation(dome.center)
+                            .with_scale(Vec3::splat(dome.radius)),
+                        ..default()
+                    },
+                    NotShadowCaster,
+                    NotShadowReceiver,
+                    SkyDomeOf(camera),
+                ));
+            }
+        }
+        commands.entity(camera).remove::<Skybox>();
+    }
+}
+
+/// Puts the sky back at infinity once `SkyDome` is removed from a camera
+fn restore_skybox(
+    mut commands: Commands,
+    mut removed: RemovedComponents<SkyDome>,
+    domes: Query<(Entity, &SkyDomeOf, &Handle<SkyDomeMaterial>)>,
+    materials: Res<Assets<SkyDomeMaterial>>,
+) {
+    for camera in removed.read() {
+        for (dome, of, material) in domes.iter() {
+            if of.0 != camera {
+                continue;
+            }
+            if let (Some(material), Some(mut camera)) =
+                (materials.get(material), commands.get_entity(camera))
+            {
+                camera.insert(Skybox {
+                    image: material.sky.clone(),
+                    brightness: crate::skytex::SKYBOX_BRIGHTNESS,
+                });
+            }
+            commands.entity(dome).despawn_recursive();
+        }
+    }
+}
@@ -0,0 +1,20 @@
+#import bevy_pbr::forward_io::VertexOutput
+
+struct SkyDome {
+    center: vec3<f32>,
+    brightness: f32,
+};
+
+@group(2) @binding(0)
+var<uniform> dome: SkyDome;
+@group(2) @binding(1)
+var sky_texture: texture_cube<f32>;
+@group(2) @binding(2)
+var sky_sampler: sampler;
+
+@fragment
+fn fragment(in: VertexOutput) -> @location(0) vec4<f32> {
+    // Looking the sky up from the dome center instead of the camera gives the parallax
+    let dir = normalize(in.world_position.xyz - dome.center);
+    return vec4(textureSample(sky_texture, sky_sampler, dir).rgb * dome.brightness, 1.0);
+}
@@ -0,0 +1,118 @@
+use crate::skytex::{
+    cubemap_image, cubemap_image_mips, for_each_cubemap_texel, sky_radiance, LightSpot,
+    SkyLayers, SkyTexFormat, SphericalHarmonics,
+};
+use bevy::prelude::*;
+use std::f32::consts::PI;
+
+const SPECULAR_SAMPLES: u32 = 64;
+const DIFFUSE_FACE_SIZE: u32 = 8;
+
+/// Cube textures for a Bevy `EnvironmentMapLight`, always `Rgba16Float`
+#[derive(Clone, Debug)]
+pub struct PrefilteredEnvironment {
+    /// Cosine convolved radiance, one mip
+    pub diffuse: Image,
+    /// GGX prefiltered radiance, roughness rises linearly from 0 at mip 0 to 1 at the last mip
+    pub specular: Image,
+}
+
+impl PrefilteredEnvironment {
+    /// Prefilters an analytic environment given as linear radiance per direction
+    pub fn from_fn(face_size: u32, radiance: impl Fn(Vec3) -> Vec4) -> Self {
+        Self {
+            diffuse: diffuse_image(|n| cosine_convolve(n, &radiance)),
+            specular: specular_image(face_size, &radiance),
+        }
+    }
+
+    /// Prefilters the generated sky, `lighting` already windowed
+    pub(crate) fn from_sky(
+        face_size: u32,
+        lighting: &SphericalHarmonics,
+        spots: &[LightSpot],
+        layers: SkyLayers,
+    ) -> Self {
+        Self::from_fn(face_size, |dir| sky_radiance(lighting, spots, layers, dir))
+    }
+}
+
+/// Diffuse is low frequency, a few texels per face are plenty
+fn diffuse_image(irradiance: impl Fn(Vec3) -> Vec4) -> Image {
+    let size = DIFFUSE_FACE_SIZE;
+    let mut data = vec![Vec4::ZERO; (size * size * 6) as usize];
+    for_each_cubemap_texel(size, |index, pt| data[index] = irradiance(pt.normalize()));
+    cubemap_image(size, &data, SkyTexFormat::Rgba16Float)
+}
+
+fn specular_image(face_size: u32, radiance: &impl Fn(Vec3) -> Vec4) -> Image {
+    let size = face_size.next_power_of_two();
+    let levels = size.trailing_zeros() + 1;
+    let mips: Vec<Vec<Vec4>> = (0..levels)
+        .map(|mip| {
+            let roughness = mip as f32 / (levels - 1).max(1) as f32;
+            prefilter_level(size >> mip, roughness, radiance)
+        })
+        .collect();
+    cubemap_image_mips(size, &mips, SkyTexFormat::Rgba16Float)
+}
+
+/// Split sum prefiltering with N = V = R
+fn prefilter_level(size: u32, roughness: f32, radiance: &impl Fn(Vec3) -> Vec4) -> Vec<Vec4> {
+    let mut data = vec![Vec4::ZERO; (size * size * 6) as usize];
+    for_each_cubemap_texel(size, |index, pt| {
+        let n = pt.normalize();
+        if roughness == 0.0 {
+            data[index] = radiance(n);
+            return;
+        }
+        let (tangent, bitangent) = tangent_frame(n);
+        let mut sum = Vec4::ZERO;
+        let mut weight = 0.0;
+        for i in 0..SPECULAR_SAMPLES {
+            let h = importance_sample_ggx(hammersley(i, SPECULAR_SAMPLES), roughness);
+            let h = tangent * h.x + bitangent * h.y + n * h.z;
+            let l = 2.0 * n.dot(h) * h - n;
+            let ndotl = n.dot(l);
+            if ndotl > 0.0 {
+                sum += radiance(l) * ndotl;
+                weight += ndotl;
+            }
+        }
+        data[index] = if weight > 0.0 { sum / weight } else { radiance(n) };
+    });
+    data
+}
+
+fn cosine_convolve(n: Vec3, radiance: &impl Fn(Vec3) -> Vec4) -> Vec4 {
+    let (tangent, bitangent) = tangent_frame(n);
+    let mut sum = Vec4::ZERO;
+    for i in 0..SPECULAR_SAMPLES {
+        let xi = hammersley(i, SPECULAR_SAMPLES);
+        // Cosine weighted hemisphere, the pdf cancels the cosine term
+        let phi = 2.0 * PI * xi.x;
+        let r = xi.y.sqrt();
+        let l = tangent * (r * phi.cos()) + bitangent * (r * phi.sin()) + n * (1.0 - xi.y).sqrt();
+        sum += radiance(l);
+    }
+    sum / SPECULAR_SAMPLES as f32
+}
+
+fn tangent_frame(n: Vec3) -> (Vec3, Vec3) {
+    let up = if n.z.abs() < 0.999 { Vec3::Z } else { Vec3::X };
+    let tangent = up.cross(n).normalize();
+    (tangent, n.cross(tangent))
+}
+
+fn hammersley(i: u32, count: u32) -> Vec2 {
+    Vec2::new(i as f32 / count as f32, i.reverse_bits() as f32 * 2.328_306_4e-10)
+}
+
+/// Half vector in tangent space, roughness is perceptual
+fn importance_sample_ggx(xi: Vec2, roughness: f32) -> Vec3 {
+    let a = roughness * roughness;
+    let phi = 2.0 * PI * xi.x;
+    let cos_theta = ((1.0 - xi.y) / (1.0 + (a * a - 1.0) * xi.y)).sqrt();
+    let sin_theta = (1.0 - cos_theta * cos_theta).sqrt();
+    Vec3::new(sin_theta * phi.cos(), sin_theta * phi.sin(), cos_theta)
+}
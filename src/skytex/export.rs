@@ -0,0 +1,321 @@
+use crate::skytex::read_texels;
+use bevy::core_pipeline::Skybox;
+use bevy::prelude::*;
+use bevy::render::render_resource::{Extent3d, TextureDimension, TextureFormat};
+use bevy::tasks::IoTaskPool;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+/// Writes skybox cubemaps to disk on [`SkyboxExportRequest`]
+pub struct SkyExportPlugin;
+
+impl Plugin for SkyExportPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<SkyboxExportRequest>();
+        app.add_systems(Last, export_skyboxes);
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SkyboxExportFormat {
+    /// A single KTX2 cubemap with all mips, loadable as a Bevy `Image`
+    #[default]
+    Ktx2,
+    /// Six 8 bit PNGs named `<path>_<face>.png`, HDR values are clamped
+    PngFaces,
+    /// Six 32 bit float EXRs named `<path>_<face>.exr`
+    ExrFaces,
+}
+
+/// Saves the current skybox of `camera` to `path`, the file is written on the IO task pool
+#[derive(Event, Clone, Debug)]
+pub struct SkyboxExportRequest {
+    pub camera: Entity,
+    pub path: PathBuf,
+    pub format: SkyboxExportFormat,
+}
+
+const FACE_NAMES: [&str; 6] = ["px", "nx", "py", "ny", "pz", "nz"];
+
+fn export_skyboxes(
+    mut requests: EventReader<SkyboxExportRequest>,
+    skyboxes: Query<&Skybox>,
+    images: Res<Assets<Image>>,
+) {
+    for request in requests.read() {
+        let Some(image) = skyboxes
+            .get(request.camera)
+            .ok()
+            .and_then(|skybox| images.get(&skybox.image))
+        else {
+            warn!("{:?} has no loaded skybox to export", request.camera);
+            continue;
+        };
+        let image = image.clone();
+        let request = request.clone();
+        IoTaskPool::get()
+            .spawn(async move {
+                let result = match request.format {
+                    SkyboxExportFormat::Ktx2 => std::fs::File::create(&request.path)
+                        .and_then(|file| write_ktx2(&image, io::BufWriter::new(file))),
+                    SkyboxExportFormat::PngFaces => write_faces(&image, &request.path, "png"),
+                    SkyboxExportFormat::ExrFaces => write_faces(&image, &request.path, "exr"),
+                };
+                match result {
+                    Ok(()) => info!("Exported skybox to {}", request.path.display()),
+                    Err(err) => {
+                        error!("Failed to export skybox to {}: {err}", request.path.display())
+                    }
+                }
+            })
+            .detach();
+    }
+}
+
+/// Vulkan format, channel byte size and whether the channels are floats
+fn ktx2_format(format: TextureFormat) -> Option<(u32, u32, bool)> {
+    Some(match format {
+        TextureFormat::Rgba8Unorm => (37, 1, false),
+        TextureFormat::Rgba8UnormSrgb => (43, 1, false),
+        TextureFormat::Rgba16Float => (97, 2, true),
+        TextureFormat::Rgba32Float => (109, 4, true),
+        _ => return None,
+    })
+}
+
+/// Byte range of `mip` of `layer` in the layer-major data of a 6 layer image
+fn face_range(image: &Image, layer: usize, mip: u32, texel_size: usize) -> std::ops::Range<usize> {
+    let size = image.width() as usize;
+    let mip_bytes = |m: u32| (size >> m).max(1).pow(2) * texel_size;
+    let layer_bytes: usize = (0..image.texture_descriptor.mip_level_count).map(mip_bytes).sum();
+    let start = layer * layer_bytes + (0..mip).map(mip_bytes).sum::<usize>();
+    start..start + mip_bytes(mip)
+}
+
+/// Writes a 6 layer RGBA image as a KTX2 cubemap
+pub fn write_ktx2(image: &Image, mut out: impl Write) -> io::Result<()> {
+    let format = image.texture_descriptor.format;
+    let Some((vk_format, type_size, float)) = ktx2_format(format) else {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("{format:?} can't be exported"),
+        ));
+    };
+    if image.texture_descriptor.size.depth_or_array_layers != 6 {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, "not a cubemap"));
+    }
+    let texel_size = (type_size * 4) as usize;
+    let levels = image.texture_descriptor.mip_level_count;
+
+    // Data Format Descriptor, a basic block with one sample per channel
+    let mut dfd = Vec::new();
+    let srgb = format == TextureFormat::Rgba8UnormSrgb;
+    dfd.extend(0u32.to_le_bytes());
+    dfd.extend(2u16.to_le_bytes());
+    dfd.extend((24u16 + 16 * 4).to_le_bytes());
+    dfd.extend([1, 1, if srgb { 2 } else { 1 }, 0]);
+    dfd.extend([0, 0, 0, 0]);
+    dfd.extend([texel_size as u8, 0, 0, 0, 0, 0, 0, 0]);
+    for (i, channel) in [0u8, 1, 2, 15].into_iter().enumerate() {
+        let bits = type_size * 8;
+        let mut channel_type = channel;
+        if float {
+            channel_type |= 0x80 | 0x40;
+        }
+        if srgb && channel == 15 {
+            channel_type |= 0x10;
+        }
+        dfd.extend((i as u16 * bits as u16).to_le_bytes());
+        dfd.push((bits - 1) as u8);
+        dfd.push(channel_type);
+        dfd.extend([0, 0, 0, 0]);
+        let (lower, upper) = if float {
+            ((-1.0f32).to_bits(), 1.0f32.to_bits())
+        } else {
+            (0, (1u32 << bits) - 1)
+        };
+        dfd.extend(lower.to_le_bytes());
+        dfd.extend(upper.to_le_bytes());
+    }
+    let dfd_total = (dfd.len() + 4) as u32;
+
+    let header_size = 80 + 24 * levels as usize;
+    let dfd_offset = header_size;
+    let align = |offset: usize| offset.div_ceil(texel_size.max(4)) * texel_size.max(4);
+
+    // Levels are stored smallest first, each level holds its six faces
+    let mut level_data = Vec::new();
+    let mut level_index = vec![(0u64, 0u64); levels as usize];
+    let mut offset = dfd_offset + dfd_total as usize;
+    for mip in (0..levels).rev() {
+        let padded = align(offset);
+        level_data.resize(level_data.len() + padded - offset, 0);
+        let start = padded;
+        for layer in 0..6 {
+            level_data.extend_from_slice(&image.data[face_range(image, layer, mip, texel_size)]);
+        }
+        offset = dfd_offset + dfd_total as usize + level_data.len();
+        level_index[mip as usize] = (start as u64, (offset - start) as u64);
+    }
+
+    out.write_all(&[0xAB, 0x4B, 0x54, 0x58, 0x20, 0x32, 0x30, 0xBB, 0x0D, 0x0A, 0x1A, 0x0A])?;
+    for value in [
+        vk_format,
+        type_size,
+        image.width(),
+        image.height(),
+        0,
+        0,
+        6,
+        levels,
+        0,
+    ] {
+        out.write_all(&value.to_le_bytes())?;
+    }
+    out.write_all(&(dfd_offset as u32).to_le_bytes())?;
+    out.write_all(&dfd_total.to_le_bytes())?;
+    // No key/value data or supercompression globals
+    out.write_all(&0u32.to_le_bytes())?;
+    out.write_all(&0u32.to_le_bytes())?;
+    out.write_all(&0u64.to_le_bytes())?;
+    out.write_all(&0u64.to_le_bytes())?;
+    for (offset, length) in level_index {
+        out.write_all(&offset.to_le_bytes())?;
+        out.write_all(&length.to_le_bytes())?;
+        out.write_all(&length.to_le_bytes())?;
+    }
+    out.write_all(&dfd_total.to_le_bytes())?;
+    out.write_all(&dfd)?;
+    out.write_all(&level_data)?;
+    out.flush()
+}
+
+/// Writes mip 0 of every face as its own image file named `<path>_<face>.<extension>`, PNGs
+/// are sRGB encoded and EXRs linear
+pub(crate) fn write_faces(image: &Image, path: &Path, extension: &str) -> io::Result<()> {
+    let invalid = || {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("{:?} can't be exported", image.texture_descriptor.format),
+        )
+    };
+    let (_, type_size, _) = ktx2_format(image.texture_descriptor.format).ok_or_else(invalid)?;
+    let texel_size = (type_size * 4) as usize;
+    let (width, height) = (image.width(), image.height());
+    let stem = path.with_extension("");
+
+    for (layer, name) in FACE_NAMES.iter().enumerate() {
+        let range = face_range(image, layer, 0, texel_size);
+        let mut face = Image::new(
+            Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            },
+            TextureDimension::D2,
+            image.data[range].to_vec(),
+            image.texture_descriptor.format,
+            default(),
+        );
+        face.texture_descriptor.mip_level_count = 1;
+        let texels = read_texels(&face).ok_or_else(invalid)?;
+        let face_path = PathBuf::from(format!("{}_{name}.{extension}", stem.display()));
+        write_texels(&texels, width, height, &face_path)?;
+    }
+    Ok(())
+}
+
+/// Writes linear texels as a 32 bit float EXR if `path` ends in `.exr`, otherwise as an 8 bit
+/// sRGB encoded image of the format the extension names, with HDR values clamped
+pub(crate) fn write_texels(
+    texels: &[Vec4],
+    width: u32,
+    height: u32,
+    path: &Path,
+) -> io::Result<()> {
+    let saved = if path.extension().is_some_and(|extension| extension == "exr") {
+        let data = texels.iter().flat_map(|t| t.to_array()).collect();
+        image::Rgba32FImage::from_raw(width, height, data).map(|image| image.save(path))
+    } else {
+        let unorm = |c: f32| (c * 255.0).clamp(0.0, 255.0) as u8;
+        let encode = |c: f32| unorm(Srgba::gamma_function_inverse(c.max(0.0)));
+        let data = texels
+            .iter()
+            .flat_map(|t| [encode(t.x), encode(t.y), encode(t.z), unorm(t.w)])
+            .collect();
+        image::RgbaImage::from_raw(width, height, data).map(|image| image.save(path))
+    };
+    saved
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "texel count doesn't match"))?
+        .map_err(|err| io::Error::new(io::ErrorKind::Other, err.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A 2x2 `Rgba8Unorm` cubemap with `mips` levels and every byte numbered
+    fn numbered_cubemap(mips: u32) -> Image {
+        let mut image = Image::default();
+        image.texture_descriptor.size = Extent3d {
+            width: 2,
+            height: 2,
+            depth_or_array_layers: 6,
+        };
+        image.texture_descriptor.format = TextureFormat::Rgba8Unorm;
+        image.texture_descriptor.mip_level_count = mips;
+        let layer_bytes: usize = (0..mips).map(|mip| (2usize >> mip).max(1).pow(2) * 4).sum();
+        image.data = (0..6 * layer_bytes).map(|i| i as u8).collect();
+        image
+    }
+
+    fn read_u32(bytes: &[u8], offset: usize) -> u32 {
+        u32::from_le_bytes(bytes[offset..offset + 4].try_into().unwrap())
+    }
+
+    /// Bytes of `level` in a written KTX2 file, from its level index
+    fn level_bytes(ktx2: &[u8], level: usize) -> &[u8] {
+        let entry = 80 + 24 * level;
+        let offset = u64::from_le_bytes(ktx2[entry..entry + 8].try_into().unwrap()) as usize;
+        let length = u64::from_le_bytes(ktx2[entry + 8..entry + 16].try_into().unwrap());
+        &ktx2[offset..offset + length as usize]
+    }
+
+    #[test]
+    fn ktx2_header_describes_the_cubemap() {
+        let mut ktx2 = Vec::new();
+        write_ktx2(&numbered_cubemap(1), &mut ktx2).unwrap();
+        assert_eq!(
+            ktx2[..12],
+            [0xAB, 0x4B, 0x54, 0x58, 0x20, 0x32, 0x30, 0xBB, 0x0D, 0x0A, 0x1A, 0x0A]
+        );
+        assert_eq!(read_u32(&ktx2, 20), 2);
+        assert_eq!(read_u32(&ktx2, 36), 6);
+        assert_eq!(read_u32(&ktx2, 40), 1);
+    }
+
+    #[test]
+    fn ktx2_levels_hold_every_face() {
+        let image = numbered_cubemap(2);
+        let mut ktx2 = Vec::new();
+        write_ktx2(&image, &mut ktx2).unwrap();
+
+        // Bevy stores the faces layer major, KTX2 level major
+        let layer_bytes = 16 + 4;
+        let mip0: Vec<u8> = (0..6)
+            .flat_map(|layer| image.data[layer * layer_bytes..][..16].to_vec())
+            .collect();
+        let mip1: Vec<u8> = (0..6)
+            .flat_map(|layer| image.data[layer * layer_bytes + 16..][..4].to_vec())
+            .collect();
+        assert_eq!(level_bytes(&ktx2, 0), mip0);
+        assert_eq!(level_bytes(&ktx2, 1), mip1);
+    }
+
+    #[test]
+    fn ktx2_rejects_flat_images() {
+        let mut image = numbered_cubemap(1);
+        image.texture_descriptor.size.depth_or_array_layers = 1;
+        assert!(write_ktx2(&image, Vec::new()).is_err());
+    }
+}
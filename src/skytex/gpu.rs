@@ -0,0 +1,237 @@
+use crate::compat::is_downlevel;
+use crate::quality::SkQuality;
+use crate::skytex::lifecycle::NoSkyTex;
+use crate::skytex::{
+    sh_lookup_coefficients, GeneratedSky, LightSpot, SetupSkyTex, SkyLighting, SkyTexConfig,
+    SkyTexSettings, SkyboxGenerated, SKYBOX_BRIGHTNESS,
+};
+use bevy::core_pipeline::Skybox;
+use bevy::prelude::*;
+use bevy::render::extract_component::{ExtractComponent, ExtractComponentPlugin};
+use bevy::render::render_asset::{RenderAssetUsages, RenderAssets};
+use bevy::render::render_resource::binding_types::{texture_storage_2d_array, uniform_buffer};
+use bevy::render::render_resource::{
+    BindGroupEntries, BindGroupLayout, BindGroupLayoutEntries, CachedComputePipelineId,
+    CommandEncoderDescriptor, ComputePassDescriptor, ComputePipelineDescriptor, Extent3d,
+    PipelineCache, ShaderStages, ShaderType, StorageTextureAccess, TextureDimension,
+    TextureFormat, TextureUsages, TextureViewDescriptor, TextureViewDimension, UniformBuffer,
+};
+use bevy::render::renderer::{RenderDevice, RenderQueue};
+use bevy::render::texture::GpuImage;
+use bevy::render::{Render, RenderApp, RenderSet};
+use bevy::utils::HashSet;
+
+const SHADER_HANDLE: Handle<Shader> = Handle::weak_from_u128(0x4a71c9e2f60b);
+const WORKGROUP_SIZE: u32 = 8;
+
+/// Generates the sky of [`GpuSkyTex`] cameras with a compute shader instead of on the CPU.
+///
+/// The shader evaluates the SH with `bevy_sk::lighting`, so `PbrPlugin` has to be added too.
+pub struct GpuSkyTexPlugin;
+
+impl Plugin for GpuSkyTexPlugin {
+    fn build(&self, app: &mut App) {
+        load_shader!(app, SHADER_HANDLE, "gpu.wgsl");
+        app.add_plugins(ExtractComponentPlugin::<GpuSkyJob>::default());
+        app.register_type::<GpuSkyTex>();
+
+        if let Some(render_app) = app.get_sub_app_mut(RenderApp) {
+            render_app.add_systems(
+                Render,
+                dispatch_gpu_sky
+                    .run_if(resource_exists::<GpuSkyPipeline>)
+                    .in_set(RenderSet::Prepare)
+                    .after(RenderSet::PrepareResources),
+            );
+        }
+    }
+
+    fn finish(&self, app: &mut App) {
+        if let Some(render_app) = app.get_sub_app_mut(RenderApp) {
+            // `SkCompatibilityPlugin` moves these skies to the CPU
+            if !is_downlevel(render_app.world().resource::<RenderDevice>()) {
+                render_app.init_resource::<GpuSkyPipeline>();
+            }
+        }
+    }
+}
+
+/// Generates this camera's sky on the GPU, in `Rgba16Float` so the light spot keeps its range
+#[derive(Component, Reflect, Clone, Copy, Debug, Default)]
+#[reflect(Component)]
+pub struct GpuSkyTex;
+
+/// A cube texture still to be filled by the compute pass
+#[derive(Component, ExtractComponent, Clone)]
+pub struct GpuSkyJob {
+    image: Handle<Image>,
+    params: GpuSkyParams,
+}
+
+#[derive(ShaderType, Clone, Copy)]
+struct GpuSkyParams {
+    sh: [Vec4; 9],
+    spot_direction: Vec3,
+    spot_inner: f32,
+    spot_color: Vec3,
+    spot_radius: f32,
+    face_size: u32,
+}
+
+pub fn setup_gpu_skytex(
+    mut commands: Commands,
+    query: Query<
+        (Entity, Option<&SkyTexConfig>),
+        (
+            With<Camera3d>,
+            With<GpuSkyTex>,
+            Without<SetupSkyTex>,
+            Without<NoSkyTex>,
+        ),
+    >,
+    mut images: ResMut<Assets<Image>>,
+    quality: Res<SkQuality>,
+    lighting: Res<SkyLighting>,
+    settings: Res<SkyTexSettings>,
+    mut generated: EventWriter<SkyboxGenerated>,
+) {
+    for (entity, config) in query.iter() {
+        let (lighting, settings) = SkyTexConfig::resolve(config, &lighting, &settings);
+        let face_size = settings.face_size(&quality).next_power_of_two();
+        let windowed_lighting = settings.windowed(&lighting);
+        let spot = LightSpot::new(&windowed_lighting, settings.spot_size, settings.spot_intensity);
+        let image = images.add(gpu_sky_image(face_size));
+        // Filled by the compute pass before the camera first renders it
+        generated.send(SkyboxGenerated {
+            camera: entity,
+            image: image.clone(),
+        });
+
+        commands.entity(entity).insert((
+            Skybox {
+                image: image.clone(),
+                brightness: SKYBOX_BRIGHTNESS,
+            },
+            GpuSkyJob {
+                image,
+                params: GpuSkyParams {
+                    sh: sh_lookup_coefficients(&windowed_lighting).map(|c| c.extend(0.0)),
+                    spot_direction: spot.direction,
+                    spot_inner: spot.inner,
+                    spot_color: spot.color.truncate(),
+                    spot_radius: spot.radius,
+                    face_size,
+                },
+            },
+            SetupSkyTex,
+            GeneratedSky,
+        ));
+    }
+}
+
+/// Empty cube texture the compute pass writes into
+fn gpu_sky_image(face_size: u32) -> Image {
+    let mut image = Image::new_fill(
+        Extent3d {
+            width: face_size,
+            height: face_size,
+            depth_or_array_layers: 6,
+        },
+        TextureDimension::D2,
+        &[0; 8],
+        TextureFormat::Rgba16Float,
+        RenderAssetUsages::RENDER_WORLD,
+    );
+    image.texture_descriptor.usage |= TextureUsages::STORAGE_BINDING;
+    image.texture_view_descriptor = Some(TextureViewDescriptor {
+        dimension: Some(TextureViewDimension::Cube),
+        ..default()
+    });
+    image
+}
+
+#[derive(Resource)]
+struct GpuSkyPipeline {
+    layout: BindGroupLayout,
+    pipeline: CachedComputePipelineId,
+}
+
+impl FromWorld for GpuSkyPipeline {
+    fn from_world(world: &mut World) -> Self {
+        let layout = world.resource::<RenderDevice>().create_bind_group_layout(
+            "sk_gpu_sky_layout",
+            &BindGroupLayoutEntries::sequential(
+                ShaderStages::COMPUTE,
+                (
+                    uniform_buffer::<GpuSkyParams>(false),
+                    texture_storage_2d_array(
+                        TextureFormat::Rgba16Float,
+                        StorageTextureAccess::WriteOnly,
+                    ),
+                ),
+            ),
+        );
+        let pipeline =
+            world
+                .resource::<PipelineCache>()
+                .queue_compute_pipeline(ComputePipelineDescriptor {
+                    label: Some("sk_gpu_sky_pipeline".into()),
+                    layout: vec![layout.clone()],
+                    push_constant_ranges: Vec::new(),
+                    shader: SHADER_HANDLE,
+                    shader_defs: Vec::new(),
+                    entry_point: "generate".into(),
+                });
+        Self { layout, pipeline }
+    }
+}
+
+fn dispatch_gpu_sky(
+    jobs: Query<&GpuSkyJob>,
+    pipeline: Res<GpuSkyPipeline>,
+    pipeline_cache: Res<PipelineCache>,
+    images: Res<RenderAssets<GpuImage>>,
+    device: Res<RenderDevice>,
+    queue: Res<RenderQueue>,
+    mut completed: Local<HashSet<AssetId<Image>>>,
+) {
+    // Jobs stay on the camera, so remember which images are filled already
+    completed.retain(|id| jobs.iter().any(|job| job.image.id() == *id));
+    let Some(compute_pipeline) = pipeline_cache.get_compute_pipeline(pipeline.pipeline) else {
+        return;
+    };
+
+    for job in jobs.iter() {
+        if completed.contains(&job.image.id()) {
+            continue;
+        }
+        let Some(gpu_image) = images.get(&job.image) else {
+            continue;
+        };
+
+        let mut params = UniformBuffer::from(job.params);
+        params.write_buffer(&device, &queue);
+        let view = gpu_image.texture.create_view(&TextureViewDescriptor {
+            dimension: Some(TextureViewDimension::D2Array),
+            ..default()
+        });
+        let bind_group = device.create_bind_group(
+            "sk_gpu_sky_bind_group",
+            &pipeline.layout,
+            &BindGroupEntries::sequential((params.binding().unwrap(), &view)),
+        );
+
+        let mut encoder = device.create_command_encoder(&CommandEncoderDescriptor {
+            label: Some("sk_gpu_sky"),
+        });
+        {
+            let mut pass = encoder.begin_compute_pass(&ComputePassDescriptor::default());
+            pass.set_pipeline(compute_pipeline);
+            pass.set_bind_group(0, &bind_group, &[]);
+            let groups = job.params.face_size.div_ceil(WORKGROUP_SIZE);
+            pass.dispatch_workgroups(groups, groups, 6);
+        }
+        queue.submit([encoder.finish()]);
+        completed.insert(job.image.id());
+    }
+}
@@ -0,0 +1,50 @@
+#import bevy_sk::lighting::sk_lighting
+
+struct GpuSkyParams {
+    // Premultiplied like sh_lookup on the CPU
+    sh: array<vec4<f32>, 9>,
+    spot_direction: vec3<f32>,
+    spot_inner: f32,
+    spot_color: vec3<f32>,
+    spot_radius: f32,
+    face_size: u32,
+};
+
+@group(0) @binding(0)
+var<uniform> params: GpuSkyParams;
+@group(0) @binding(1)
+var sky: texture_storage_2d_array<rgba16float, write>;
+
+// Direction through a texel of the given layer, in the +X, -X, +Y, -Y, +Z, -Z layer order
+fn cube_direction(face: u32, uv: vec2<f32>) -> vec3<f32> {
+    switch face {
+        case 0u: { return vec3(1.0, -uv.y, -uv.x); }
+        case 1u: { return vec3(-1.0, -uv.y, uv.x); }
+        case 2u: { return vec3(uv.x, 1.0, uv.y); }
+        case 3u: { return vec3(uv.x, -1.0, -uv.y); }
+        case 4u: { return vec3(uv.x, -uv.y, 1.0); }
+        default: { return vec3(-uv.x, -uv.y, -1.0); }
+    }
+}
+
+@compute @workgroup_size(8, 8, 1)
+fn generate(@builtin(global_invocation_id) id: vec3<u32>) {
+    if (id.x >= params.face_size || id.y >= params.face_size) {
+        return;
+    }
+    let uv = (vec2<f32>(id.xy) + 0.5) / f32(params.face_size) * 2.0 - 1.0;
+    // Bevy's skybox samples the cubemap with z negated, so flip it back into world space
+    let dir = normalize(cube_direction(id.z, uv) * vec3(1.0, 1.0, -1.0));
+
+    var sh: array<vec3<f32>, 9>;
+    for (var i = 0u; i < 9u; i += 1u) {
+        sh[i] = params.sh[i].xyz;
+    }
+    let sky_color = sk_lighting(dir, sh);
+
+    let angle = acos(clamp(dot(dir, params.spot_direction), -1.0, 1.0));
+    let spot = 1.0 - smoothstep(params.spot_inner, params.spot_radius, angle);
+    let color = mix(sky_color, params.spot_color, spot);
+
+    textureStore(sky, vec2<i32>(id.xy), i32(id.z), vec4(color, 1.0));
+}
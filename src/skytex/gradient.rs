@@ -0,0 +1,101 @@
+use crate::skytex::paint::PaintedSky;
+use crate::skytex::{SkyRadiance, SkyTexFormat, SphericalHarmonics};
+use bevy::prelude::*;
+
+/// Declarative sky made of color stops from horizon to zenith plus a sun.
+///
+/// ```ignore
+/// let sky = SkyGradient::new()
+///     .stop(Color::srgb(0.2, 0.18, 0.16), -1.0)
+///     .stop(Color::srgb(0.8, 0.85, 0.9), 0.0)
+///     .stop(Color::srgb(0.25, 0.45, 0.85), 1.0)
+///     .sun(Vec3::new(0.3, 0.6, -0.5), Color::WHITE, 8.0);
+/// commands.add(InsertPaintedSky { camera, sky: sky.paint(64, SkyTexFormat::Rgba16Float) });
+/// ```
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SkyGradient {
+    /// Colors by altitude, the y of the view direction from -1 (nadir) to 1 (zenith)
+    pub stops: Vec<(Color, f32)>,
+    pub sun: Option<GradientSun>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct GradientSun {
+    /// Direction from the viewer towards the sun
+    pub direction: Vec3,
+    pub color: Color,
+    pub intensity: f32,
+    /// Angular radius of the disk in radians
+    pub radius: f32,
+}
+
+impl SkyGradient {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a color stop at `altitude`, stops may be added in any order
+    pub fn stop(mut self, color: Color, altitude: f32) -> Self {
+        self.stops.push((color, altitude.clamp(-1.0, 1.0)));
+        self.stops.sort_by(|a, b| a.1.total_cmp(&b.1));
+        self
+    }
+
+    pub fn sun(mut self, direction: Vec3, color: Color, intensity: f32) -> Self {
+        self.sun = Some(GradientSun {
+            direction: direction.normalize(),
+            color,
+            intensity,
+            radius: 0.05,
+        });
+        self
+    }
+
+    /// Linear radiance seen in `dir`
+    pub fn radiance(&self, dir: Vec3) -> Vec4 {
+        let mut color = self.gradient(dir.y);
+        if let Some(sun) = self.sun {
+            let angle = dir.dot(sun.direction).clamp(-1.0, 1.0).acos();
+            let t = (angle / sun.radius).clamp(0.0, 1.0);
+            let disk = 1.0 - t * t * (3.0 - 2.0 * t);
+            color += sun.color.to_linear().to_vec3() * sun.intensity * disk;
+        }
+        color.extend(1.0)
+    }
+
+    fn gradient(&self, altitude: f32) -> Vec3 {
+        let linear = |c: Color| c.to_linear().to_vec3();
+        match self.stops.as_slice() {
+            [] => Vec3::ZERO,
+            [(color, _)] => linear(*color),
+            stops => {
+                let upper = stops.partition_point(|(_, a)| *a < altitude);
+                if upper == 0 {
+                    return linear(stops[0].0);
+                }
+                if upper == stops.len() {
+                    return linear(stops[stops.len() - 1].0);
+                }
+                let (low, high) = (stops[upper - 1], stops[upper]);
+                let t = (altitude - low.1) / (high.1 - low.1).max(f32::EPSILON);
+                linear(low.0).lerp(linear(high.0), t)
+            }
+        }
+    }
+
+    /// Renders the gradient into a cubemap and projects its SH
+    pub fn paint(&self, face_size: u32, format: SkyTexFormat) -> PaintedSky {
+        PaintedSky::from_fn(face_size, format, |dir| self.radiance(dir))
+    }
+
+    /// Just the SH of the gradient, e.g. for `SkyLighting`
+    pub fn lighting(&self) -> SphericalHarmonics {
+        self.paint(32, SkyTexFormat::Rgba16Float).lighting
+    }
+}
+
+impl SkyRadiance for SkyGradient {
+    fn radiance(&self, dir: Vec3) -> Vec4 {
+        SkyGradient::radiance(self, dir)
+    }
+}
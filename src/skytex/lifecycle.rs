@@ -0,0 +1,138 @@
+use crate::skytex::cache::{SkyTexCache, SkyTexKey};
+use crate::skytex::dome::SkyDome;
+use crate::skytex::{GeneratedSky, PendingSkyTex, SetupSkyTex};
+use bevy::core_pipeline::Skybox;
+use bevy::pbr::environment_map::EnvironmentMapLight;
+use bevy::prelude::*;
+use bevy::render::view::RenderLayers;
+use bevy::utils::HashSet;
+
+/// Keeps `setup_skytex` from giving this camera a sky
+#[derive(Component, Reflect, Clone, Copy, Debug, Default)]
+#[reflect(Component)]
+pub struct NoSkyTex;
+
+/// Limits the generated sky and its `EnvironmentMapLight` to cameras on these `RenderLayers`,
+/// e.g. the XR views but not a minimap or UI camera. `None` gives every `Camera3d` a sky.
+///
+/// `PbrMaterial`s share the global SH lighting, so it isn't limited.
+#[derive(Resource, Reflect, Clone, Debug, Default, PartialEq)]
+#[reflect(Resource)]
+pub struct SkyTexLayers(pub Option<RenderLayers>);
+
+/// A camera [`SkyTexLayers`] marked [`NoSkyTex`], unmarked again once it passes the filter
+#[derive(Component)]
+pub(crate) struct FilteredSkyTex;
+
+/// Rebuilds the generated sky of this camera from scratch, bypassing the [`SkyTexCache`].
+/// Removed once handled, cameras with a painted or loaded sky ignore it.
+#[derive(Component, Reflect, Clone, Copy, Debug, Default)]
+#[reflect(Component)]
+pub struct RegenerateSky;
+
+/// What happens when a `Skybox` is removed from a camera with a generated sky
+#[derive(Resource, Reflect, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[reflect(Resource)]
+pub enum SkyboxRemovalPolicy {
+    /// Leave the camera without a sky, marks it [`NoSkyTex`]
+    #[default]
+    Respect,
+    /// Generate the sky again
+    Reinsert,
+}
+
+/// The cache entry a generated sky was built from
+#[derive(Component, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct GeneratedSkyKey(pub SkyTexKey);
+
+pub(crate) fn handle_regenerate_requests(
+    mut commands: Commands,
+    query: Query<(Entity, Option<&GeneratedSkyKey>, Has<GeneratedSky>), With<RegenerateSky>>,
+    mut cache: ResMut<SkyTexCache>,
+) {
+    for (entity, key, generated) in query.iter() {
+        let mut camera = commands.entity(entity);
+        camera.remove::<RegenerateSky>();
+        if !generated {
+            continue;
+        }
+        if let Some(key) = key {
+            cache.remove(&key.0);
+        }
+        camera.remove::<(SetupSkyTex, PendingSkyTex)>();
+    }
+}
+
+pub(crate) fn handle_removed_skyboxes(
+    mut commands: Commands,
+    mut removed: RemovedComponents<Skybox>,
+    policy: Res<SkyboxRemovalPolicy>,
+    cameras: Query<(), (With<GeneratedSky>, Without<PendingSkyTex>, Without<SkyDome>)>,
+) {
+    for entity in removed.read() {
+        // Despawned cameras fail this as well, dome cameras hand their skybox to the dome
+        if !cameras.contains(entity) {
+            continue;
+        }
+        let mut camera = commands.entity(entity);
+        match *policy {
+            SkyboxRemovalPolicy::Respect => {
+                camera
+                    .insert(NoSkyTex)
+                    .remove::<(GeneratedSky, GeneratedSkyKey, EnvironmentMapLight)>();
+            }
+            SkyboxRemovalPolicy::Reinsert => {
+                camera.remove::<SetupSkyTex>();
+            }
+        }
+    }
+}
+
+/// Drops cached skies no camera uses anymore once a generated sky camera went away
+pub(crate) fn evict_unused_skies(
+    mut removed: RemovedComponents<GeneratedSkyKey>,
+    keys: Query<&GeneratedSkyKey>,
+    mut cache: ResMut<SkyTexCache>,
+) {
+    if removed.read().count() == 0 {
+        return;
+    }
+    let used: HashSet<SkyTexKey> = keys.iter().map(|key| key.0).collect();
+    cache.retain(|key| used.contains(key));
+}
+
+/// Whether [`filter_skytex_cameras`] could mark or unmark a camera
+pub(crate) fn skytex_layers_changed(
+    filter: Res<SkyTexLayers>,
+    cameras: Query<(), (With<Camera3d>, Or<(Added<Camera3d>, Changed<RenderLayers>)>)>,
+) -> bool {
+    filter.is_changed() || !cameras.is_empty()
+}
+
+pub(crate) fn filter_skytex_cameras(
+    mut commands: Commands,
+    filter: Res<SkyTexLayers>,
+    cameras: Query<
+        (Entity, Option<&RenderLayers>, Has<NoSkyTex>, Has<FilteredSkyTex>),
+        With<Camera3d>,
+    >,
+) {
+    for (entity, layers, no_sky, filtered) in cameras.iter() {
+        let passes = filter.0.as_ref().map_or(true, |filter| {
+            filter.intersects(layers.unwrap_or(&RenderLayers::default()))
+        });
+        let mut camera = commands.entity(entity);
+        if passes && filtered {
+            camera.remove::<(NoSkyTex, FilteredSkyTex, SetupSkyTex)>();
+        } else if !passes && !no_sky {
+            camera.insert((NoSkyTex, FilteredSkyTex)).remove::<(
+                SetupSkyTex,
+                PendingSkyTex,
+                GeneratedSky,
+                GeneratedSkyKey,
+                Skybox,
+                EnvironmentMapLight,
+            )>();
+        }
+    }
+}
@@ -0,0 +1,1438 @@
+use crate::color::{SkColorSettings, SkExposure};
+use crate::lighting::buffer::{ShLightingBuffer, ShSlot};
+use crate::quality::SkQuality;
+use bevy::math::{Vec3, Vec4};
+use bevy::ecs::system::EntityCommands;
+use bevy::pbr::environment_map::EnvironmentMapLight;
+use bevy::prelude::*;
+use bevy::render::camera::Exposure;
+use bevy::tasks::futures_lite::future;
+use bevy::tasks::{block_on, AsyncComputeTaskPool, ComputeTaskPool, Task, TaskPool};
+use bevy::utils::{Duration, HashSet, Instant};
+use bevy::render::render_resource::{
+    Extent3d, ShaderType, TextureDimension, TextureFormat, TextureViewDescriptor,
+    TextureViewDimension,
+};
+
+pub mod atlas;
+pub mod atmosphere;
+pub mod cache;
+pub mod clouds;
+pub mod debug;
+pub mod dome;
+pub mod envmap;
+pub mod export;
+pub mod gpu;
+pub mod gradient;
+pub mod lifecycle;
+pub mod paint;
+pub mod panorama;
+pub mod preset;
+pub mod procedural;
+pub mod sh3;
+pub mod sh_file;
+pub mod spots;
+pub mod stars;
+pub mod studio;
+pub mod sun;
+pub mod time_of_day;
+pub mod transition;
+
+pub struct SkyTexPlugin;
+
+impl Plugin for SkyTexPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins((
+            dome::SkyDomePlugin,
+            gpu::GpuSkyTexPlugin,
+            export::SkyExportPlugin,
+            transition::SkyTransitionPlugin,
+            procedural::ProceduralSkyPlugin,
+            panorama::SkyPanoramaPlugin,
+        ));
+        app.init_resource::<SkyTexFormat>();
+        app.add_event::<SkyboxGenerated>();
+        app.add_event::<SkyGenerationTime>();
+        app.init_resource::<SkQuality>();
+        app.init_resource::<SkColorSettings>();
+        app.init_resource::<SkExposure>();
+        app.init_resource::<SkyLighting>();
+        app.init_resource::<SkyTexSettings>();
+        app.init_resource::<SkyTexFallback>();
+        app.init_resource::<spots::SkyLightSpots>();
+        app.init_resource::<cache::SkyTexCache>();
+        app.init_resource::<lifecycle::SkyboxRemovalPolicy>();
+        app.init_resource::<lifecycle::SkyTexLayers>();
+        app.init_asset::<SphericalHarmonics>();
+        app.register_asset_reflect::<SphericalHarmonics>();
+        app.init_asset_loader::<sh_file::ShFileLoader>();
+        app.init_asset::<preset::SkyPreset>();
+        app.register_asset_reflect::<preset::SkyPreset>();
+        app.register_type::<(
+            SkyTexFormat,
+            SkyLighting,
+            SkyTexSettings,
+            ShDeringing,
+            SkyTexFallback,
+            SkyTexConfig,
+            SetupSkyTex,
+            GeneratedSky,
+            preset::ActiveSkyPreset,
+            preset::BuiltinSkyPreset,
+            lifecycle::NoSkyTex,
+            lifecycle::RegenerateSky,
+            lifecycle::SkyboxRemovalPolicy,
+            lifecycle::SkyTexLayers,
+            spots::SkyLightSpots,
+        )>();
+        app.init_resource::<preset::ActiveSkyPreset>();
+        #[cfg(feature = "serde")]
+        app.init_asset_loader::<preset::SkyPresetLoader>();
+        app.add_systems(
+            Update,
+            (
+                preset::apply_sky_preset,
+                clouds::drift_clouds,
+                sync_sky_lighting.run_if(
+                    resource_changed::<SkyLighting>.or_else(resource_changed::<SkyTexSettings>),
+                ),
+                regenerate_sky_on_change,
+                (
+                    lifecycle::filter_skytex_cameras.run_if(lifecycle::skytex_layers_changed),
+                    lifecycle::handle_regenerate_requests,
+                    lifecycle::handle_removed_skyboxes,
+                    lifecycle::evict_unused_skies,
+                ),
+                (setup_skytex, gpu::setup_gpu_skytex).run_if(cameras_without_sky),
+                poll_skytex_tasks.run_if(any_with_component::<PendingSkyTex>),
+                sync_sky_exposure,
+            )
+                .chain(),
+        );
+    }
+}
+
+/// Skybox brightness the generated sky is tuned for at [`REFERENCE_EV100`]
+pub const SKYBOX_BRIGHTNESS: f32 = 800.0;
+/// Camera exposure the built-in sky brightness and SH lighting were tuned for
+pub const REFERENCE_EV100: f32 = Exposure::EV100_BLENDER;
+
+/// Factor that keeps the sky looking the same under `exposure` as under [`REFERENCE_EV100`].
+///
+/// `PbrMaterial` outputs its SH lighting without applying the view exposure unless
+/// [`SkColorSettings::follow_camera_exposure`] is on, so compensating the skybox keeps the sky
+/// and the SH lit surfaces in sync.
+pub fn exposure_compensation(exposure: &Exposure) -> f32 {
+    Exposure {
+        ev100: REFERENCE_EV100,
+    }
+    .exposure()
+        / exposure.exposure()
+}
+
+/// Texture format the generated sky cubemap is written in
+#[derive(Resource, Reflect, Default, Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[reflect(Resource)]
+pub enum SkyTexFormat {
+    /// Linear values stored as `Rgba8Unorm`
+    #[default]
+    Linear,
+    /// sRGB encoded values stored as `Rgba8UnormSrgb`
+    Srgb,
+    /// Unclamped linear values, keeps the light spot bright for tonemapping and bloom
+    Rgba16Float,
+    /// Unclamped linear values at full precision
+    Rgba32Float,
+}
+
+impl SkyTexFormat {
+    pub fn texture_format(self) -> TextureFormat {
+        match self {
+            SkyTexFormat::Linear => TextureFormat::Rgba8Unorm,
+            SkyTexFormat::Srgb => TextureFormat::Rgba8UnormSrgb,
+            SkyTexFormat::Rgba16Float => TextureFormat::Rgba16Float,
+            SkyTexFormat::Rgba32Float => TextureFormat::Rgba32Float,
+        }
+    }
+
+    pub fn is_hdr(self) -> bool {
+        matches!(self, SkyTexFormat::Rgba16Float | SkyTexFormat::Rgba32Float)
+    }
+
+    /// Bytes of one texel
+    fn texel_size(self) -> usize {
+        match self {
+            SkyTexFormat::Linear | SkyTexFormat::Srgb => 4,
+            SkyTexFormat::Rgba16Float => 8,
+            SkyTexFormat::Rgba32Float => 16,
+        }
+    }
+
+    /// Writes `v` into the `texel_size` bytes of `out`
+    fn encode(self, v: Vec4, out: &mut [u8]) {
+        let unorm = |c: f32| (c * 255.0).clamp(0.0, 255.0) as u8;
+        match self {
+            SkyTexFormat::Linear => {
+                out.copy_from_slice(&[unorm(v.x), unorm(v.y), unorm(v.z), unorm(v.w)]);
+            }
+            SkyTexFormat::Srgb => out.copy_from_slice(&[
+                unorm(Srgba::gamma_function_inverse(v.x.max(0.0))),
+                unorm(Srgba::gamma_function_inverse(v.y.max(0.0))),
+                unorm(Srgba::gamma_function_inverse(v.z.max(0.0))),
+                unorm(v.w),
+            ]),
+            SkyTexFormat::Rgba16Float => {
+                let channels = v.max(Vec4::ZERO).to_array();
+                for (c, out) in channels.into_iter().zip(out.chunks_exact_mut(2)) {
+                    out.copy_from_slice(&half::f16::from_f32(c).to_le_bytes());
+                }
+            }
+            SkyTexFormat::Rgba32Float => {
+                let channels = v.max(Vec4::ZERO).to_array();
+                for (c, out) in channels.into_iter().zip(out.chunks_exact_mut(4)) {
+                    out.copy_from_slice(&c.to_le_bytes());
+                }
+            }
+        }
+    }
+}
+
+/// The scene's SH lighting, used for the generated sky and as the global `PbrMaterial` lighting.
+///
+/// Changing it regenerates every generated skybox and updates all materials lit by
+/// `ShSlot::GLOBAL`.
+#[derive(Resource, Reflect, Clone, Copy, Debug, PartialEq, Deref, DerefMut)]
+#[reflect(Resource)]
+pub struct SkyLighting(pub SphericalHarmonics);
+
+impl Default for SkyLighting {
+    fn default() -> Self {
+        Self(DEFAULT_LIGHTING)
+    }
+}
+
+#[derive(Component, Reflect, Default)]
+#[reflect(Component)]
+pub struct SetupSkyTex;
+
+/// Sent when a camera's generated sky, from the CPU or [`GpuSkyTex`](gpu::GpuSkyTex), is put
+/// into its `Skybox`
+#[derive(Event, Clone, Debug)]
+pub struct SkyboxGenerated {
+    pub camera: Entity,
+    pub image: Handle<Image>,
+}
+
+/// Marks a skybox generated from [`SkyLighting`], as opposed to a painted or loaded one
+#[derive(Component, Reflect, Default)]
+#[reflect(Component)]
+pub struct GeneratedSky;
+
+/// Parameters of the generated sky, changing them regenerates every generated skybox
+#[derive(Resource, Reflect, Clone, Copy, Debug, PartialEq)]
+#[reflect(Resource)]
+pub struct SkyTexSettings {
+    /// Cubemap face size, `None` follows [`SkQuality`]
+    pub face_size: Option<u32>,
+    /// Half width of the light spot on a cube face
+    pub spot_size: f32,
+    /// Light spot color as a multiple of the SH in its direction
+    pub spot_intensity: f32,
+    /// Skybox brightness at [`REFERENCE_EV100`]
+    pub brightness: f32,
+    /// SH windowing width, higher values smooth the sky more at the cost of contrast
+    pub window_width: f32,
+    /// How `window_width` is applied to the sky's SH
+    pub deringing: ShDeringing,
+    /// Yaw around +Y in radians applied to the sky and its lighting, turning the sun around
+    /// the scene
+    pub rotation: f32,
+    /// Face size of the prefiltered `EnvironmentMapLight` added next to the skybox, `None`
+    /// skips it, as does a [`SkQuality`] without `specular_prefilter`
+    pub environment_face_size: Option<u32>,
+    /// Cloud layer baked into the sky, also dims the global SH lighting
+    pub clouds: Option<clouds::CloudLayer>,
+    /// Star field baked into the sky at night
+    pub stars: Option<stars::StarField>,
+}
+
+impl Default for SkyTexSettings {
+    fn default() -> Self {
+        Self {
+            face_size: None,
+            spot_size: 0.3,
+            spot_intensity: 6.0,
+            brightness: SKYBOX_BRIGHTNESS,
+            window_width: 1.0,
+            deringing: ShDeringing::Fixed,
+            rotation: 0.0,
+            environment_face_size: Some(32),
+            clouds: None,
+            stars: None,
+        }
+    }
+}
+
+impl SkyTexSettings {
+    pub fn face_size(&self, quality: &SkQuality) -> u32 {
+        self.face_size.unwrap_or(quality.settings().sky_face_size)
+    }
+
+    /// `environment_face_size` if `quality` allows the specular prefilter
+    pub fn environment_size(&self, quality: &SkQuality) -> Option<u32> {
+        self.environment_face_size.filter(|_| quality.settings().specular_prefilter)
+    }
+
+    /// `lighting` rotated by `rotation` and windowed by `window_width` according to
+    /// `deringing`
+    pub fn windowed(&self, lighting: &SphericalHarmonics) -> SphericalHarmonics {
+        let rotated = self.rotated(lighting);
+        match self.deringing {
+            ShDeringing::Fixed => rotated.window(self.window_width),
+            ShDeringing::Minimal => rotated.dering(self.window_width),
+        }
+    }
+
+    /// `lighting` turned by `rotation`
+    pub fn rotated(&self, lighting: &SphericalHarmonics) -> SphericalHarmonics {
+        if self.rotation == 0.0 {
+            return *lighting;
+        }
+        lighting.rotate(Quat::from_rotation_y(self.rotation))
+    }
+}
+
+/// How [`SkyTexSettings::window_width`] dampens the higher SH bands
+#[derive(Reflect, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ShDeringing {
+    /// Always applies the full window width
+    #[default]
+    Fixed,
+    /// Applies only as much of the window width as needed to remove negative lobes, keeping
+    /// the contrast of SH that doesn't ring
+    Minimal,
+}
+
+/// Per camera overrides of the generated sky, e.g. for a mirror camera.
+///
+/// The lighting override only changes this camera's sky, `PbrMaterial`s keep using
+/// [`SkyLighting`].
+#[derive(Component, Reflect, Clone, Copy, Debug, Default, PartialEq)]
+#[reflect(Component)]
+pub struct SkyTexConfig {
+    pub lighting: Option<SphericalHarmonics>,
+    pub settings: Option<SkyTexSettings>,
+}
+
+impl SkyTexConfig {
+    /// The lighting and settings a camera with an optional config generates its sky from
+    pub fn resolve(
+        config: Option<&SkyTexConfig>,
+        lighting: &SkyLighting,
+        settings: &SkyTexSettings,
+    ) -> (SphericalHarmonics, SkyTexSettings) {
+        (
+            config.and_then(|c| c.lighting).unwrap_or(lighting.0),
+            config.and_then(|c| c.settings).unwrap_or(*settings),
+        )
+    }
+}
+
+/// Solid color shown while a generated sky is still being built, none shows no sky until then
+#[derive(Resource, Reflect, Default, Clone, Copy, Debug, PartialEq)]
+#[reflect(Resource)]
+pub struct SkyTexFallback(pub Option<Color>);
+
+/// Cubemap generation running on the `AsyncComputeTaskPool`, without a task the camera waits
+/// for another camera generating the same sky
+#[derive(Component)]
+pub struct PendingSkyTex(Option<Task<GeneratedSkyImages>>, cache::SkyTexKey);
+
+struct GeneratedSkyImages {
+    sky: Option<Image>,
+    environment: Option<envmap::PrefilteredEnvironment>,
+    environment_intensity: f32,
+    generation_time: Duration,
+}
+
+/// How long a finished sky generation task ran, for `SkDiagnosticsPlugin`
+#[derive(Event, Clone, Copy, Debug)]
+pub(crate) struct SkyGenerationTime(pub Duration);
+
+/// Whether a `Camera3d` is waiting for [`setup_skytex`] or `setup_gpu_skytex`
+fn cameras_without_sky(
+    cameras: Query<(), (With<Camera3d>, Without<SetupSkyTex>, Without<lifecycle::NoSkyTex>)>,
+) -> bool {
+    !cameras.is_empty()
+}
+
+pub fn setup_skytex(
+    mut commands: Commands,
+    query: Query<
+        (Entity, Option<&SkyTexConfig>),
+        (
+            With<Camera3d>,
+            Without<SetupSkyTex>,
+            Without<gpu::GpuSkyTex>,
+            Without<lifecycle::NoSkyTex>,
+        ),
+    >,
+    mut images: ResMut<Assets<Image>>,
+    format: Res<SkyTexFormat>,
+    quality: Res<SkQuality>,
+    lighting: Res<SkyLighting>,
+    settings: Res<SkyTexSettings>,
+    fallback: Res<SkyTexFallback>,
+    spots: Res<spots::SkyLightSpots>,
+    mut cache: ResMut<cache::SkyTexCache>,
+    pending: Query<&PendingSkyTex>,
+    mut generated: EventWriter<SkyboxGenerated>,
+) {
+    let pool = AsyncComputeTaskPool::get();
+    // Cameras with the same sky share a single generation task and the resulting images
+    let mut in_flight: HashSet<cache::SkyTexKey> = pending
+        .iter()
+        .filter(|pending| pending.0.is_some())
+        .map(|pending| pending.1)
+        .collect();
+    for (entity, config) in query.iter() {
+        let (lighting, mut settings) = SkyTexConfig::resolve(config, &lighting, &settings);
+        let face_size = settings.face_size(&quality);
+        // Resolved before the key, so skies with and without the prefilter don't share it
+        let prefilter_skipped = settings.environment_face_size.is_some()
+            && settings.environment_size(&quality).is_none();
+        settings.environment_face_size = settings.environment_size(&quality);
+        let windowed_lighting = settings.windowed(&lighting);
+        let format = *format;
+        let light_spots =
+            spots.light_spots(&windowed_lighting, settings.spot_size, settings.spot_intensity);
+
+        let key =
+            cache::SkyTexKey::new(&windowed_lighting, face_size, &settings, &light_spots, format);
+        if prefilter_skipped {
+            commands.entity(entity).remove::<EnvironmentMapLight>();
+        }
+        if let Some(cached) = cache.get(&key) {
+            let mut camera = commands.entity(entity);
+            camera
+                .insert((SetupSkyTex, GeneratedSky, lifecycle::GeneratedSkyKey(key)))
+                .remove::<PendingSkyTex>();
+            insert_cached_sky(&mut camera, cached, settings.brightness, &mut generated);
+            continue;
+        }
+
+        let task = (!in_flight.contains(&key)).then(|| {
+            in_flight.insert(key);
+            pool.spawn(async move {
+                let start = Instant::now();
+                let layers = SkyLayers::from_settings(&settings);
+                let radiance = GeneratedSkyRadiance {
+                    lookup: &windowed_lighting,
+                    spots: &light_spots,
+                    layers,
+                };
+                let sky = Some(generate_cubemap(&radiance, face_size, format));
+                let environment = settings.environment_face_size.map(|size| {
+                    envmap::PrefilteredEnvironment::from_sky(
+                        size,
+                        &windowed_lighting,
+                        &light_spots,
+                        layers,
+                    )
+                });
+                GeneratedSkyImages {
+                    sky,
+                    environment,
+                    environment_intensity: settings.brightness,
+                    generation_time: start.elapsed(),
+                }
+            })
+        });
+
+        let mut camera = commands.entity(entity);
+        camera.insert((
+            PendingSkyTex(task, key),
+            SetupSkyTex,
+            GeneratedSky,
+            lifecycle::GeneratedSkyKey(key),
+        ));
+        if let Some(color) = fallback.0 {
+            let texel = color.to_linear().to_vec4();
+            camera.insert(bevy::core_pipeline::Skybox {
+                image: images.add(cubemap_image(1, &[texel; 6], format)),
+                brightness: SKYBOX_BRIGHTNESS,
+            });
+        }
+    }
+}
+
+/// Swaps finished cubemaps into the camera's skybox and environment map light
+pub fn poll_skytex_tasks(
+    mut commands: Commands,
+    mut query: Query<(Entity, &mut PendingSkyTex)>,
+    mut images: ResMut<Assets<Image>>,
+    mut cache: ResMut<cache::SkyTexCache>,
+    settings: Res<SkyTexSettings>,
+    mut generated: EventWriter<SkyboxGenerated>,
+    mut times: EventWriter<SkyGenerationTime>,
+) {
+    let mut in_flight = HashSet::new();
+    for (entity, mut pending) in query.iter_mut() {
+        let key = pending.1;
+        let Some(task) = pending.0.as_mut() else {
+            continue;
+        };
+        let Some(result) = block_on(future::poll_once(task)) else {
+            in_flight.insert(key);
+            continue;
+        };
+        times.send(SkyGenerationTime(result.generation_time));
+        let mut camera = commands.entity(entity);
+        camera.remove::<PendingSkyTex>();
+
+        // A regenerated camera may have finished the same sky first
+        if cache.get(&key).is_none() {
+            let environment = result
+                .environment
+                .map(|env| (images.add(env.diffuse), images.add(env.specular)));
+            let sky = result.sky.map(|image| images.add(image));
+            cache.insert(key, sky, environment);
+        }
+        if let Some(cached) = cache.get(&key) {
+            insert_cached_sky(&mut camera, cached, result.environment_intensity, &mut generated);
+        }
+    }
+
+    // Cameras waiting on another camera's task pick up its images, or start over if that
+    // camera went away before finishing
+    for (entity, pending) in query.iter() {
+        if pending.0.is_some() || in_flight.contains(&pending.1) {
+            continue;
+        }
+        let mut camera = commands.entity(entity);
+        camera.remove::<PendingSkyTex>();
+        match cache.get(&pending.1) {
+            Some(cached) => {
+                insert_cached_sky(&mut camera, cached, settings.brightness, &mut generated);
+            }
+            None => {
+                camera.remove::<SetupSkyTex>();
+            }
+        }
+    }
+}
+
+fn insert_cached_sky(
+    camera: &mut EntityCommands,
+    cached: &cache::CachedSky,
+    intensity: f32,
+    generated: &mut EventWriter<SkyboxGenerated>,
+) {
+    if let Some((diffuse_map, specular_map)) = &cached.environment {
+        camera.insert(EnvironmentMapLight {
+            diffuse_map: diffuse_map.clone(),
+            specular_map: specular_map.clone(),
+            intensity,
+        });
+    }
+    let Some(image) = cached.sky.clone() else {
+        return;
+    };
+    generated.send(SkyboxGenerated {
+        camera: camera.id(),
+        image: image.clone(),
+    });
+    // Keep the brightness sync_sky_exposure already applied to the fallback
+    camera.add(move |mut entity: EntityWorldMut| {
+        match entity.get_mut::<bevy::core_pipeline::Skybox>() {
+            Some(mut skybox) => skybox.image = image,
+            None => {
+                entity.insert(bevy::core_pipeline::Skybox {
+                    image,
+                    brightness: SKYBOX_BRIGHTNESS,
+                });
+            }
+        }
+    });
+}
+
+/// Pushes [`SkyLighting`], dimmed by the clouds, into the global SH slot and rebuilds the
+/// generated skies
+pub fn sync_sky_lighting(
+    mut commands: Commands,
+    lighting: Res<SkyLighting>,
+    settings: Res<SkyTexSettings>,
+    buffer: Option<ResMut<ShLightingBuffer>>,
+    query: Query<(Entity, Option<&SkyTexConfig>), With<GeneratedSky>>,
+) {
+    if !lighting.is_changed() && !settings.is_changed() {
+        return;
+    }
+    if let Some(mut buffer) = buffer {
+        let global = global_lighting(&lighting.0, &settings);
+        if buffer.get(ShSlot::GLOBAL) != Some(&global) {
+            buffer.set(ShSlot::GLOBAL, global);
+        }
+    }
+    if !lighting.is_changed() || lighting.is_added() {
+        return;
+    }
+    for (entity, config) in query.iter() {
+        if config.map_or(true, |c| c.lighting.is_none()) {
+            commands.entity(entity).remove::<SetupSkyTex>();
+        }
+    }
+}
+
+/// What [`sync_sky_lighting`] puts into [`ShSlot::GLOBAL`] for `lighting`
+pub fn global_lighting(
+    lighting: &SphericalHarmonics,
+    settings: &SkyTexSettings,
+) -> SphericalHarmonics {
+    let rotated = settings.rotated(lighting);
+    match &settings.clouds {
+        Some(clouds) => clouds.attenuate(&rotated),
+        None => rotated,
+    }
+}
+
+/// Drops [`SetupSkyTex`] so the sky is rebuilt with the new quality, settings, format or
+/// camera config
+pub fn regenerate_sky_on_change(
+    mut commands: Commands,
+    quality: Res<SkQuality>,
+    settings: Res<SkyTexSettings>,
+    format: Res<SkyTexFormat>,
+    spots: Res<spots::SkyLightSpots>,
+    query: Query<Entity, With<GeneratedSky>>,
+    configs: Query<Entity, (With<GeneratedSky>, Changed<SkyTexConfig>)>,
+    mut removed_configs: RemovedComponents<SkyTexConfig>,
+) {
+    for entity in configs.iter().chain(removed_configs.read()) {
+        if query.contains(entity) {
+            commands.entity(entity).remove::<SetupSkyTex>();
+        }
+    }
+
+    let changed = |added: bool, changed: bool| changed && !added;
+    if !changed(quality.is_added(), quality.is_changed())
+        && !changed(settings.is_added(), settings.is_changed())
+        && !changed(format.is_added(), format.is_changed())
+        && !changed(spots.is_added(), spots.is_changed())
+    {
+        return;
+    }
+    for entity in query.iter() {
+        commands.entity(entity).remove::<SetupSkyTex>();
+    }
+}
+
+pub fn sync_sky_exposure(
+    mut query: Query<
+        (
+            &mut bevy::core_pipeline::Skybox,
+            Option<Ref<Exposure>>,
+            Option<Ref<SkyTexConfig>>,
+            Option<&mut EnvironmentMapLight>,
+        ),
+        With<SetupSkyTex>,
+    >,
+    settings: Res<SkyTexSettings>,
+    color: Res<SkColorSettings>,
+    sk_exposure: Res<SkExposure>,
+) {
+    let settings_changed = settings.is_changed() || color.is_changed() || sk_exposure.is_changed();
+    for (mut skybox, exposure, config, environment) in query.iter_mut() {
+        let exposure_changed = exposure.as_ref().is_some_and(|e| e.is_changed());
+        let config_changed = config.as_ref().is_some_and(|c| c.is_changed());
+        // Also catches skies replaced by inserting a new Skybox over the old one
+        if !skybox.is_changed() && !exposure_changed && !config_changed && !settings_changed {
+            continue;
+        }
+        let exposure = exposure.map(|e| *e).unwrap_or_default();
+        let settings = config.and_then(|c| c.settings).unwrap_or(*settings);
+        let brightness = settings.brightness * sk_exposure.scale;
+        // Bevy exposes the skybox and environment map alike, so both get the same
+        // compensation to keep the reflections matching the sky
+        let exposure_factor = color.sky_exposure_factor(&exposure);
+        skybox.brightness = brightness * exposure_factor;
+        if let Some(mut environment) = environment {
+            environment.intensity = brightness * exposure_factor;
+        }
+    }
+}
+
+/// 9 RGB coefficient SH lighting, also an asset loadable from StereoKit `.sh` dumps
+#[derive(Asset, Reflect, ShaderType, Default, Copy, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SphericalHarmonics {
+    pub coefficients: [Vec3; 9],
+}
+
+impl SphericalHarmonics {
+    /// Projects an equirectangular panorama, e.g. a loaded `.hdr`, into SH.
+    ///
+    /// The image center looks down -Z with +Y up. Returns `None` for texel formats that can't
+    /// be read back. The result is unwindowed, run it through the sky windowing before using it
+    /// for a generated sky to avoid ringing.
+    pub fn from_equirect(image: &Image) -> Option<Self> {
+        let texels = read_texels(image)?;
+        let (width, height) = (image.width() as usize, image.height() as usize);
+        if width == 0 || height == 0 || texels.len() < width * height {
+            return None;
+        }
+
+        let mut harmonics = Self::default();
+        let mut total_weight = 0.0;
+        for y in 0..height {
+            let lat = (0.5 - (y as f32 + 0.5) / height as f32) * std::f32::consts::PI;
+            // Rows near the poles cover less of the sphere
+            let weight = lat.cos();
+            for x in 0..width {
+                let lon = ((x as f32 + 0.5) / width as f32 - 0.5) * std::f32::consts::TAU;
+                let dir = Vec3::new(lat.cos() * lon.sin(), lat.sin(), -lat.cos() * lon.cos());
+                harmonics.accumulate(dir, texels[y * width + x].truncate() * weight);
+                total_weight += weight;
+            }
+        }
+
+        let normalization = 4.0 * std::f32::consts::PI / total_weight;
+        for coefficient in harmonics.coefficients.iter_mut() {
+            *coefficient *= normalization;
+        }
+        Some(harmonics)
+    }
+
+    /// Projects a 6 layer cubemap, e.g. an authored skybox, into windowed SH.
+    ///
+    /// Returns `None` if the image isn't a square 6 layer texture in a readable format.
+    pub fn from_cubemap(image: &Image) -> Option<Self> {
+        let (size, data) = read_cubemap_faces(image)?;
+        let mut harmonics = project_cubemap_sh(size, &data);
+        sh_windowing(&mut harmonics, 1.0);
+        Some(harmonics)
+    }
+
+    /// SH of distant point lights, each given as the direction towards it and its color,
+    /// like StereoKit's `sh_create`
+    pub fn from_lights(lights: &[(Vec3, Color)]) -> Self {
+        let mut harmonics = Self::default();
+        for (dir, color) in lights {
+            harmonics.add(*dir, *color);
+        }
+        harmonics
+    }
+
+    /// Adds a distant point light in direction `dir`
+    pub fn add(&mut self, dir: Vec3, color: Color) {
+        self.accumulate(dir.normalize(), color.to_linear().to_vec3());
+    }
+
+    /// Luminance of the ambient term, roughly how bright a surface lit by this SH appears
+    pub fn brightness(&self) -> f32 {
+        let ambient = self.coefficients[0] * 0.282095 * std::f32::consts::PI;
+        ambient.dot(Vec3::new(0.2126, 0.7152, 0.0722))
+    }
+
+    /// Linear irradiance arriving at a surface facing `normal`, alpha is 1
+    pub fn sample(&self, normal: Vec3) -> Vec4 {
+        sh_lookup(self, normal)
+    }
+
+    /// Direction the dominant light travels in, negate it for the direction towards the light
+    pub fn dominant_dir(&self) -> Vec3 {
+        sh_dominant_dir(self)
+    }
+
+    /// Dampens the higher bands to remove ringing, wider windows give smoother lighting
+    pub fn window(mut self, width: f32) -> Self {
+        sh_windowing(&mut self, width);
+        self
+    }
+
+    /// Smallest window up to `max_width` that leaves no negative irradiance, after Sloan's
+    /// "Deringing Spherical Harmonics". Returns the SH windowed by `max_width` if no smaller
+    /// window does.
+    pub fn dering(&self, max_width: f32) -> Self {
+        let negative = |width: f32| {
+            let windowed = self.window(width);
+            (0..DERING_SAMPLES).any(|i| {
+                let dir = fibonacci_dir(i, DERING_SAMPLES);
+                sh_lookup(&windowed, dir).truncate().min_element() < 0.0
+            })
+        };
+        if max_width <= 0.0 || !negative(0.0) {
+            return *self;
+        }
+        if negative(max_width) {
+            return self.window(max_width);
+        }
+        let (mut low, mut high) = (0.0, max_width);
+        for _ in 0..12 {
+            let mid = (low + high) * 0.5;
+            if negative(mid) {
+                low = mid;
+            } else {
+                high = mid;
+            }
+        }
+        self.window(high)
+    }
+
+    pub fn scale(mut self, factor: f32) -> Self {
+        for coefficient in self.coefficients.iter_mut() {
+            *coefficient *= factor;
+        }
+        self
+    }
+
+    pub fn lerp(&self, other: &Self, t: f32) -> Self {
+        Self {
+            coefficients: std::array::from_fn(|i| {
+                self.coefficients[i].lerp(other.coefficients[i], t)
+            }),
+        }
+    }
+
+    /// Adds `color` seen along the normalized direction `n` to the coefficients
+    fn accumulate(&mut self, n: Vec3, color: Vec3) {
+        for (coefficient, basis) in self.coefficients.iter_mut().zip(sh_basis(n)) {
+            *coefficient += color * basis;
+        }
+    }
+
+    /// The lighting turned by `rotation`. Band 1 rotates like a vector, band 2 is refit from
+    /// the rotated values at five fixed directions, which is exact for a single band.
+    pub fn rotate(&self, rotation: Quat) -> Self {
+        let c = &self.coefficients;
+        let mut rotated = *self;
+
+        // Band 1 is a dot product of the direction with (c3, c1, c2)
+        let m = Mat3::from_quat(rotation);
+        let v = [c[3], c[1], c[2]];
+        let r: [Vec3; 3] = std::array::from_fn(|i| (0..3).map(|j| v[j] * m.col(j)[i]).sum());
+        rotated.coefficients[1] = r[1];
+        rotated.coefficients[2] = r[2];
+        rotated.coefficients[3] = r[0];
+
+        let inverse = rotation.inverse();
+        let mut matrix = [[0.0; 5]; 5];
+        let mut values = [Vec3::ZERO; 5];
+        for (i, dir) in SH_ROTATION_DIRS.iter().enumerate() {
+            let dir = dir.normalize();
+            matrix[i].copy_from_slice(&sh_basis(dir)[4..]);
+            let source = sh_basis(inverse * dir);
+            values[i] = (0..5).map(|k| c[4 + k] * source[4 + k]).sum();
+        }
+        rotated.coefficients[4..].copy_from_slice(&solve_band(matrix, values));
+        rotated
+    }
+}
+
+/// Sample directions for refitting band 2, chosen so every basis function is independent
+const SH_ROTATION_DIRS: [Vec3; 5] = [
+    Vec3::X,
+    Vec3::Z,
+    Vec3::new(1.0, 1.0, 0.0),
+    Vec3::new(1.0, 0.0, 1.0),
+    Vec3::new(0.0, 1.0, 1.0),
+];
+
+fn sh_basis(n: Vec3) -> [f32; 9] {
+    [
+        0.282095,
+        0.488603 * n.y,
+        0.488603 * n.z,
+        0.488603 * n.x,
+        1.092548 * n.x * n.y,
+        1.092548 * n.y * n.z,
+        0.315392 * (3.0 * n.z * n.z - 1.0),
+        1.092548 * n.x * n.z,
+        0.546274 * (n.x * n.x - n.y * n.y),
+    ]
+}
+
+/// Solves `matrix * x = values` by Gaussian elimination with partial pivoting
+fn solve_band(mut matrix: [[f32; 5]; 5], mut values: [Vec3; 5]) -> [Vec3; 5] {
+    for col in 0..5 {
+        let pivot = (col..5)
+            .max_by(|a, b| matrix[*a][col].abs().total_cmp(&matrix[*b][col].abs()))
+            .unwrap();
+        matrix.swap(col, pivot);
+        values.swap(col, pivot);
+        for row in col + 1..5 {
+            let factor = matrix[row][col] / matrix[col][col];
+            for k in col..5 {
+                matrix[row][k] -= factor * matrix[col][k];
+            }
+            values[row] -= values[col] * factor;
+        }
+    }
+    let mut x = [Vec3::ZERO; 5];
+    for row in (0..5).rev() {
+        let rest: Vec3 = (row + 1..5).map(|k| x[k] * matrix[row][k]).sum();
+        x[row] = (values[row] - rest) / matrix[row][row];
+    }
+    x
+}
+
+/// The first mip of every face of a square 6 layer image, with its face size
+pub(crate) fn read_cubemap_faces(image: &Image) -> Option<(u32, Vec<Vec4>)> {
+    let size = image.texture_descriptor.size;
+    if size.depth_or_array_layers != 6 || size.width != size.height || size.width == 0 {
+        return None;
+    }
+    let texels = read_texels(image)?;
+    // Layers may carry mips after their first level, only the first one is used
+    let face = (size.width * size.width) as usize;
+    let layer_stride = texels.len() / 6;
+    if layer_stride < face {
+        return None;
+    }
+    let data = (0..6)
+        .flat_map(|layer| &texels[layer * layer_stride..layer * layer_stride + face])
+        .copied()
+        .collect();
+    Some((size.width, data))
+}
+
+/// Reads the first mip of `image` back as linear RGBA
+pub(crate) fn read_texels(image: &Image) -> Option<Vec<Vec4>> {
+    let data = &image.data;
+    let texels = match image.texture_descriptor.format {
+        TextureFormat::Rgba32Float => data
+            .chunks_exact(16)
+            .map(|c| Vec4::from_array(std::array::from_fn(|i| f32::from_le_bytes(c[i * 4..i * 4 + 4].try_into().unwrap()))))
+            .collect(),
+        TextureFormat::Rgba16Float => data
+            .chunks_exact(8)
+            .map(|c| Vec4::from_array(std::array::from_fn(|i| half::f16::from_le_bytes([c[i * 2], c[i * 2 + 1]]).to_f32())))
+            .collect(),
+        TextureFormat::Rgb9e5Ufloat => data
+            .chunks_exact(4)
+            .map(|c| {
+                let v = u32::from_le_bytes(c.try_into().unwrap());
+                let scale = 2f32.powi((v >> 27) as i32 - 15 - 9);
+                let channel = |shift: u32| ((v >> shift) & 0x1ff) as f32 * scale;
+                Vec4::new(channel(0), channel(9), channel(18), 1.0)
+            })
+            .collect(),
+        TextureFormat::Rgba8Unorm => data
+            .chunks_exact(4)
+            .map(|c| Vec4::from_array(std::array::from_fn(|i| c[i] as f32 / 255.0)))
+            .collect(),
+        TextureFormat::Rgba8UnormSrgb => data
+            .chunks_exact(4)
+            .map(|c| {
+                let linear = |v: u8| Srgba::gamma_function(v as f32 / 255.0);
+                Vec4::new(linear(c[0]), linear(c[1]), linear(c[2]), c[3] as f32 / 255.0)
+            })
+            .collect(),
+        _ => return None,
+    };
+    Some(texels)
+}
+
+pub const DEFAULT_LIGHTING: SphericalHarmonics = SphericalHarmonics {
+    coefficients: [
+        Vec3::new(0.74, 0.74, 0.73),
+        Vec3::new(0.24, 0.25, 0.26),
+        Vec3::new(0.09, 0.09, 0.09),
+        Vec3::new(0.05, 0.05, 0.06),
+        Vec3::new(-0.01, -0.01, -0.01),
+        Vec3::new(-0.03, -0.03, -0.03),
+        Vec3::new(0.00, 0.00, 0.00),
+        Vec3::new(-0.02, -0.02, -0.02),
+        Vec3::new(0.04, 0.04, 0.04),
+    ],
+};
+
+const DERING_SAMPLES: u32 = 128;
+
+/// `i`th of `count` evenly spread directions on the sphere
+fn fibonacci_dir(i: u32, count: u32) -> Vec3 {
+    let golden_angle = std::f32::consts::PI * (3.0 - 5f32.sqrt());
+    let y = 1.0 - 2.0 * (i as f32 + 0.5) / count as f32;
+    let r = (1.0 - y * y).max(0.0).sqrt();
+    let (sin, cos) = (golden_angle * i as f32).sin_cos();
+    Vec3::new(r * cos, y, r * sin)
+}
+
+pub(crate) fn sh_windowing(harmonics: &mut SphericalHarmonics, window_width: f32) {
+    let mut i = 0;
+    for band in 0..=2 {
+        let s = 1.0 / (1.0 + window_width * (band * band * (band + 1) * (band + 1)) as f32);
+        for _ in -band..=band {
+            harmonics.coefficients[i] *= s;
+            i += 1;
+        }
+    }
+}
+
+/// A sun disk painted into the generated sky
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct LightSpot {
+    pub direction: Vec3,
+    pub color: Vec4,
+    /// Angle in radians where the edge starts fading out
+    pub inner: f32,
+    /// Angle in radians where the spot ends
+    pub radius: f32,
+}
+
+impl LightSpot {
+    /// `size_pct` is the spot's half width on a cube face, measured at the face center
+    pub fn new(lookup: &SphericalHarmonics, size_pct: f32, intensity: f32) -> Self {
+        // The spot sits opposite the dominant SH direction, like the light it stands for
+        let direction = -sh_dominant_dir(lookup);
+        let radius = size_pct.atan();
+        Self {
+            direction,
+            color: sh_lookup(lookup, direction) * intensity,
+            inner: radius * 0.75,
+            radius,
+        }
+    }
+
+    /// How much of the spot covers `dir`. Angular distance keeps the disk round on every
+    /// face, the edge fades out smoothly.
+    pub fn coverage(&self, dir: Vec3) -> f32 {
+        let angle = dir.dot(self.direction).clamp(-1.0, 1.0).acos();
+        let t = ((angle - self.inner) / (self.radius - self.inner)).clamp(0.0, 1.0);
+        1.0 - t * t * (3.0 - 2.0 * t)
+    }
+}
+
+/// Optional detail composited over the SH sky
+#[derive(Clone, Copy, Default)]
+pub(crate) struct SkyLayers<'a> {
+    pub clouds: Option<&'a clouds::CloudLayer>,
+    pub stars: Option<&'a stars::StarField>,
+}
+
+impl<'a> SkyLayers<'a> {
+    pub fn from_settings(settings: &'a SkyTexSettings) -> Self {
+        Self {
+            clouds: settings.clouds.as_ref(),
+            stars: settings.stars.as_ref(),
+        }
+    }
+}
+
+/// The generated sky seen in `dir`
+pub(crate) fn sky_radiance(
+    lookup: &SphericalHarmonics,
+    spots: &[LightSpot],
+    layers: SkyLayers,
+    dir: Vec3,
+) -> Vec4 {
+    let mut radiance = sh_lookup(lookup, dir);
+    for spot in spots {
+        radiance = radiance.lerp(spot.color, spot.coverage(dir));
+    }
+    // Stars sit behind the clouds
+    if let Some(stars) = layers.stars {
+        radiance = stars.composite(stars.strength(lookup), dir, radiance);
+    }
+    if let Some(clouds) = layers.clouds {
+        radiance = clouds.composite(lookup, dir, radiance);
+    }
+    radiance
+}
+
+/// A sky the CPU can evaluate in any direction, for [`generate_cubemap`] and tests that need
+/// no GPU
+pub trait SkyRadiance: Sync {
+    /// Linear color seen in the normalized world direction `dir`
+    fn radiance(&self, dir: Vec3) -> Vec4;
+}
+
+impl SkyRadiance for SphericalHarmonics {
+    fn radiance(&self, dir: Vec3) -> Vec4 {
+        sh_lookup(self, dir)
+    }
+}
+
+/// The sky [`setup_skytex`] generates, SH with its light spots and layers
+#[derive(Clone, Copy)]
+pub(crate) struct GeneratedSkyRadiance<'a> {
+    pub lookup: &'a SphericalHarmonics,
+    pub spots: &'a [LightSpot],
+    pub layers: SkyLayers<'a>,
+}
+
+impl SkyRadiance for GeneratedSkyRadiance<'_> {
+    fn radiance(&self, dir: Vec3) -> Vec4 {
+        sky_radiance(self.lookup, self.spots, self.layers, dir)
+    }
+}
+
+/// Renders `sky` into a cube texture with a full mip chain on the CPU, `face_size` is rounded
+/// up to a power of two
+pub fn generate_cubemap(sky: &impl SkyRadiance, face_size: u32, format: SkyTexFormat) -> Image {
+    let size = face_size.next_power_of_two();
+    let data = par_cubemap_texels(size, |dir| sky.radiance(dir.normalize()));
+    cubemap_image_mips(size, &cubemap_mips(size, data), format)
+}
+
+/// Layer-major texels of a cubemap with `size`² faces, `f` gets each texel's unnormalized
+/// direction on the unit cube. Runs in bands of rows on the `ComputeTaskPool`.
+pub(crate) fn par_cubemap_texels(size: u32, f: impl Fn(Vec3) -> Vec4 + Sync) -> Vec<Vec4> {
+    let mut data = vec![Vec4::ZERO; (size * size * 6) as usize];
+    // Enough texels per task to outweigh spawning it
+    let band = (size * (1024 / size).max(1)) as usize;
+    let f = &f;
+    compute_task_pool().scope(|scope| {
+        for (i, texels) in data.chunks_mut(band).enumerate() {
+            scope.spawn(async move {
+                for (j, texel) in texels.iter_mut().enumerate() {
+                    let index = (i * band + j) as u32;
+                    let (face, y, x) = (index / (size * size), index / size % size, index % size);
+                    *texel = f(cubemap_texel_dir(size, face as i32, x, y));
+                }
+            });
+        }
+    });
+    data
+}
+
+/// The task pool is only set up by Bevy's `TaskPoolPlugin`, not in tools and tests
+fn compute_task_pool() -> &'static TaskPool {
+    ComputeTaskPool::get_or_init(TaskPool::default)
+}
+
+/// Box filters layer-major cubemap texels down to 1x1, returning every level starting at `data`
+pub(crate) fn cubemap_mips(size: u32, data: Vec<Vec4>) -> Vec<Vec<Vec4>> {
+    let mut levels = vec![data];
+    let mut size = size as usize;
+    while size > 1 {
+        let half = size / 2;
+        let previous = levels.last().unwrap();
+        let mut next = Vec::with_capacity(half * half * 6);
+        for face in 0..6 {
+            let texels = &previous[face * size * size..(face + 1) * size * size];
+            for y in 0..half {
+                for x in 0..half {
+                    let at = |dx: usize, dy: usize| texels[(y * 2 + dy) * size + x * 2 + dx];
+                    next.push((at(0, 0) + at(1, 0) + at(0, 1) + at(1, 1)) * 0.25);
+                }
+            }
+        }
+        levels.push(next);
+        size = half;
+    }
+    levels
+}
+
+/// Visits every texel of a cubemap with `size`² faces, passing its index into the layer-major
+/// texel data and its unnormalized direction on the unit cube
+pub(crate) fn for_each_cubemap_texel(size: u32, mut f: impl FnMut(usize, Vec3)) {
+    let mut index = 0;
+    for face in 0..6 {
+        for y in 0..size {
+            for x in 0..size {
+                f(index, cubemap_texel_dir(size, face, x, y));
+                index += 1;
+            }
+        }
+    }
+}
+
+/// Major axis, right and down direction of every cube layer in wgpu's +X, -X, +Y, -Y, +Z, -Z
+/// order, in the cube's own space. Matches `cube_direction` in `gpu.wgsl`.
+const CUBE_FACES: [(Vec3, Vec3, Vec3); 6] = [
+    (Vec3::X, Vec3::NEG_Z, Vec3::NEG_Y),
+    (Vec3::NEG_X, Vec3::Z, Vec3::NEG_Y),
+    (Vec3::Y, Vec3::X, Vec3::Z),
+    (Vec3::NEG_Y, Vec3::X, Vec3::NEG_Z),
+    (Vec3::Z, Vec3::X, Vec3::NEG_Y),
+    (Vec3::NEG_Z, Vec3::NEG_X, Vec3::NEG_Y),
+];
+
+/// Unnormalized world direction on the unit cube through the center of texel `x`, `y` of
+/// `face`.
+///
+/// Bevy samples skyboxes and environment maps with z negated, so the cube's space is flipped
+/// back into world space. Texel centers line up across faces since wgpu filters cubemaps
+/// seamlessly.
+fn cubemap_texel_dir(size: u32, face: i32, x: u32, y: u32) -> Vec3 {
+    let (axis, right, down) = CUBE_FACES[face as usize];
+    let u = (x as f32 + 0.5) / size as f32 * 2.0 - 1.0;
+    let v = (y as f32 + 0.5) / size as f32 * 2.0 - 1.0;
+    (axis + right * u + down * v) * Vec3::new(1.0, 1.0, -1.0)
+}
+
+/// Index of the layer-major texel of a `size` cubemap that the world direction `dir`
+/// samples, the inverse of `cubemap_texel_dir`
+pub(crate) fn cubemap_texel_at(size: u32, dir: Vec3) -> usize {
+    let dir = dir * Vec3::new(1.0, 1.0, -1.0);
+    let (face, (axis, right, down)) = CUBE_FACES
+        .iter()
+        .enumerate()
+        .max_by(|(_, a), (_, b)| dir.dot(a.0).total_cmp(&dir.dot(b.0)))
+        .unwrap();
+    let major = dir.dot(*axis).max(f32::EPSILON);
+    let texel = |s: f32| (((s / major + 1.0) * 0.5 * size as f32) as u32).min(size - 1);
+    (face as u32 * size * size + texel(dir.dot(*down)) * size + texel(dir.dot(*right))) as usize
+}
+
+/// Builds a cube texture from layer-major linear texel data
+pub(crate) fn cubemap_image(size: u32, data: &[Vec4], format: SkyTexFormat) -> Image {
+    cubemap_image_mips(size, &[data.to_vec()], format)
+}
+
+/// Builds a cube texture with a mip chain, `levels[i]` holds the layer-major texels of mip `i`
+pub(crate) fn cubemap_image_mips(size: u32, levels: &[Vec<Vec4>], format: SkyTexFormat) -> Image {
+    // wgpu expects every layer with all of its mips before the next layer
+    let texel_size = format.texel_size();
+    let face_texels = move |mip: usize| ((size >> mip).max(1) * (size >> mip).max(1)) as usize;
+    let layer_bytes = (0..levels.len()).map(face_texels).sum::<usize>() * texel_size;
+    let mut image_data = vec![0; layer_bytes * 6];
+    compute_task_pool().scope(|scope| {
+        for (layer, bytes) in image_data.chunks_mut(layer_bytes).enumerate() {
+            scope.spawn(async move {
+                let texels = levels.iter().enumerate().flat_map(|(mip, data)| {
+                    let face = face_texels(mip);
+                    &data[layer * face..(layer + 1) * face]
+                });
+                for (v, out) in texels.zip(bytes.chunks_exact_mut(texel_size)) {
+                    format.encode(*v, out);
+                }
+            });
+        }
+    });
+
+    let mut image = Image {
+        data: image_data,
+        ..default()
+    };
+    image.texture_descriptor.size = Extent3d {
+        width: size,
+        height: size,
+        depth_or_array_layers: 6,
+    };
+    image.texture_descriptor.dimension = TextureDimension::D2;
+    image.texture_descriptor.format = format.texture_format();
+    image.texture_descriptor.mip_level_count = levels.len() as u32;
+
+    image.texture_view_descriptor = Some(TextureViewDescriptor {
+        dimension: Some(TextureViewDimension::Cube),
+        ..default()
+    });
+
+    image
+}
+
+/// Projects layer-major cubemap texels into 2nd order SH, weighting texels by solid angle
+pub(crate) fn project_cubemap_sh(size: u32, data: &[Vec4]) -> SphericalHarmonics {
+    let mut harmonics = SphericalHarmonics::default();
+    let mut total_weight = 0.0;
+
+    for_each_cubemap_texel(size, |index, pt| {
+        // Solid angle of a texel on the unit cube falls off with 1 / |pt|³
+        let weight = 1.0 / pt.length().powi(3);
+        harmonics.accumulate(pt.normalize(), data[index].truncate() * weight);
+        total_weight += weight;
+    });
+
+    let normalization = 4.0 * std::f32::consts::PI / total_weight;
+    for coefficient in harmonics.coefficients.iter_mut() {
+        *coefficient *= normalization;
+    }
+    harmonics
+}
+
+pub(crate) fn sh_dominant_dir(harmonics: &SphericalHarmonics) -> Vec3 {
+    let dir = Vec3::new(
+        harmonics.coefficients[3].x * 0.3
+            + harmonics.coefficients[3].y * 0.59
+            + harmonics.coefficients[3].z,
+        harmonics.coefficients[1].x * 0.3
+            + harmonics.coefficients[1].y * 0.59
+            + harmonics.coefficients[1].z,
+        harmonics.coefficients[2].x * 0.3
+            + harmonics.coefficients[2].y * 0.59
+            + harmonics.coefficients[2].z,
+    );
+    -dir.normalize()
+}
+
+/// Coefficients premultiplied by the basis and cosine lobe constants of [`sh_lookup`], so the
+/// shader side `sk_lighting` evaluates the same irradiance
+pub(crate) fn sh_lookup_coefficients(harmonics: &SphericalHarmonics) -> [Vec3; 9] {
+    const PI: f32 = std::f32::consts::PI;
+    const A0: f32 = PI;
+    const A1: f32 = (2.0 * PI) / 3.0;
+    const A2: f32 = PI * 0.25;
+    let scale = [
+        0.282095 * A0,
+        0.488603 * A1,
+        0.488603 * A1,
+        0.488603 * A1,
+        1.092548 * A2,
+        1.092548 * A2,
+        0.315392 * A2,
+        1.092548 * A2,
+        0.546274 * A2,
+    ];
+    std::array::from_fn(|i| harmonics.coefficients[i] * scale[i])
+}
+
+pub(crate) fn sh_lookup(harmonics: &SphericalHarmonics, normal: Vec3) -> Vec4 {
+    const PI: f32 = std::f32::consts::PI;
+    const COSINE_A0: f32 = PI;
+    const COSINE_A1: f32 = (2.0 * PI) / 3.0;
+    const COSINE_A2: f32 = PI * 0.25;
+
+    let mut result = Vec3::ZERO;
+
+    // Band 0
+    result += harmonics.coefficients[0] * (0.282095 * COSINE_A0);
+    // Band 1
+    result += harmonics.coefficients[1] * (0.488603 * normal.y * COSINE_A1);
+    result += harmonics.coefficients[2] * (0.488603 * normal.z * COSINE_A1);
+    result += harmonics.coefficients[3] * (0.488603 * normal.x * COSINE_A1);
+    // Band 2
+    result += harmonics.coefficients[4] * (1.092548 * normal.x * normal.y * COSINE_A2);
+    result += harmonics.coefficients[5] * (1.092548 * normal.y * normal.z * COSINE_A2);
+    result +=
+        harmonics.coefficients[6] * (0.315392 * (3.0 * normal.z * normal.z - 1.0) * COSINE_A2);
+    result += harmonics.coefficients[7] * (1.092548 * normal.x * normal.z * COSINE_A2);
+    result += harmonics.coefficients[8]
+        * (0.546274 * (normal.x * normal.x - normal.y * normal.y) * COSINE_A2);
+
+    Vec4::new(result.x, result.y, result.z, 1.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Texel a direction samples, following the cube face selection of the WebGPU spec after
+    /// Bevy's z flip
+    fn reference_texel(size: u32, dir: Vec3) -> usize {
+        let c = dir * Vec3::new(1.0, 1.0, -1.0);
+        let a = c.abs();
+        let (face, sc, tc, ma) = if a.x >= a.y && a.x >= a.z {
+            if c.x > 0.0 {
+                (0, -c.z, -c.y, a.x)
+            } else {
+                (1, c.z, -c.y, a.x)
+            }
+        } else if a.y >= a.z {
+            if c.y > 0.0 {
+                (2, c.x, c.z, a.y)
+            } else {
+                (3, c.x, -c.z, a.y)
+            }
+        } else if c.z > 0.0 {
+            (4, c.x, -c.y, a.z)
+        } else {
+            (5, -c.x, -c.y, a.z)
+        };
+        let texel = |s: f32| (((s / ma + 1.0) * 0.5 * size as f32) as u32).min(size - 1);
+        (face * size * size + texel(tc) * size + texel(sc)) as usize
+    }
+
+    fn sun_lighting(dir: Vec3) -> SphericalHarmonics {
+        let mut harmonics = SphericalHarmonics::default();
+        harmonics.accumulate(dir.normalize(), Vec3::ONE);
+        harmonics
+    }
+
+    #[test]
+    fn texels_are_sampled_along_their_direction() {
+        for size in [1, 2, 5, 16] {
+            for_each_cubemap_texel(size, |index, dir| {
+                assert_eq!(reference_texel(size, dir), index, "size {size}, dir {dir}");
+                assert_eq!(cubemap_texel_at(size, dir), index, "size {size}, dir {dir}");
+            });
+        }
+    }
+
+    #[test]
+    fn faces_have_reference_orientation() {
+        // World direction of the top left texel of each layer of a 2x2 cubemap
+        let top_left = [
+            Vec3::new(1.0, 0.5, -0.5),
+            Vec3::new(-1.0, 0.5, 0.5),
+            Vec3::new(-0.5, 1.0, 0.5),
+            Vec3::new(-0.5, -1.0, -0.5),
+            Vec3::new(-0.5, 0.5, -1.0),
+            Vec3::new(0.5, 0.5, 1.0),
+        ];
+        for (face, expected) in top_left.into_iter().enumerate() {
+            let dir = cubemap_texel_dir(2, face as i32, 0, 0);
+            assert!(dir.abs_diff_eq(expected, 1e-6), "face {face}: {dir} != {expected}");
+        }
+    }
+
+    #[test]
+    fn sky_is_continuous_across_face_edges() {
+        let size = 8;
+        let lighting = sun_lighting(Vec3::new(0.3, -0.5, 0.8));
+        let data = par_cubemap_texels(size, |dir| sh_lookup(&lighting, dir.normalize()));
+        let step = |a: usize, b: usize| (data[a] - data[b]).truncate().length();
+        let index = |face: usize, x: u32, y: u32| {
+            face * (size * size) as usize + (y * size + x) as usize
+        };
+
+        // The largest change between neighbors inside a face bounds the change across edges
+        let mut inside = 0.0f32;
+        let mut across = 0.0f32;
+        for (face, (axis, right, down)) in CUBE_FACES.into_iter().enumerate() {
+            for y in 0..size {
+                for x in 0..size {
+                    if x + 1 < size {
+                        inside = inside.max(step(index(face, x, y), index(face, x + 1, y)));
+                    }
+                    if y + 1 < size {
+                        inside = inside.max(step(index(face, x, y), index(face, x, y + 1)));
+                    }
+                    // The texel a step past the edge, in the face it wraps onto
+                    let uv = |c: u32| (c as f32 + 0.5) / size as f32 * 2.0 - 1.0;
+                    let texel = 2.0 / size as f32;
+                    let (u, v) = (uv(x), uv(y));
+                    let outside = [(u - texel, v), (u + texel, v), (u, v - texel), (u, v + texel)];
+                    let past_edge = |(u, v): &(f32, f32)| u.abs() > 1.0 || v.abs() > 1.0;
+                    for (u, v) in outside.into_iter().filter(past_edge) {
+                        let dir = (axis + right * u + down * v) * Vec3::new(1.0, 1.0, -1.0);
+                        across = across.max(step(index(face, x, y), reference_texel(size, dir)));
+                    }
+                }
+            }
+        }
+        assert!(across <= inside * 1.5, "{across} across edges, {inside} inside faces");
+    }
+
+    #[test]
+    fn generated_sky_is_not_mirrored() {
+        let size = 8;
+        let sun = Vec3::new(0.6, 0.3, 0.74).normalize();
+        let lighting = sun_lighting(sun);
+        let data = par_cubemap_texels(size, |dir| sh_lookup(&lighting, dir.normalize()));
+        let mirrored = sun * Vec3::new(1.0, 1.0, -1.0);
+        let brightness = |dir: Vec3| data[reference_texel(size, dir)].truncate().length();
+        assert!(brightness(sun) > brightness(mirrored) * 2.0);
+    }
+
+    #[test]
+    fn generated_cubemap_reads_back() {
+        let size = 8;
+        let lighting = sun_lighting(Vec3::new(-0.2, 0.9, 0.4));
+        let layers = SkyLayers::default();
+        let format = SkyTexFormat::Rgba32Float;
+        let radiance = GeneratedSkyRadiance {
+            lookup: &lighting,
+            spots: &[],
+            layers,
+        };
+        let image = generate_cubemap(&radiance, size, format);
+        let (read_size, texels) = read_cubemap_faces(&image).unwrap();
+        assert_eq!(read_size, size);
+        for_each_cubemap_texel(size, |index, dir| {
+            let expected = sky_radiance(&lighting, &[], layers, dir.normalize()).max(Vec4::ZERO);
+            assert!(texels[index].abs_diff_eq(expected, 1e-5), "texel {index}");
+        });
+    }
+}
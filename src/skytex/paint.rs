@@ -0,0 +1,102 @@
+use crate::skytex::{
+    cubemap_image, for_each_cubemap_texel, project_cubemap_sh, read_texels, sh_windowing,
+    GeneratedSky, SetupSkyTex, SkyLighting, SkyTexFormat, SphericalHarmonics, SKYBOX_BRIGHTNESS,
+};
+use bevy::core_pipeline::Skybox;
+use bevy::ecs::world::Command;
+use bevy::prelude::*;
+
+/// A user painted sky cubemap together with the SH projected from it
+#[derive(Clone, Debug)]
+pub struct PaintedSky {
+    pub image: Image,
+    /// Windowed SH of the painted texels, used as the scene lighting once inserted
+    pub lighting: SphericalHarmonics,
+}
+
+impl PaintedSky {
+    /// Paints every texel from its normalized view direction, colors are linear
+    pub fn from_fn(face_size: u32, format: SkyTexFormat, mut paint: impl FnMut(Vec3) -> Vec4) -> Self {
+        let size = face_size.next_power_of_two();
+        let mut data = vec![Vec4::ZERO; (size * size * 6) as usize];
+        for_each_cubemap_texel(size, |index, pt| data[index] = paint(pt.normalize()));
+        Self::from_texels(size, format, &data)
+    }
+
+    /// Resamples an equirectangular panorama, e.g. a loaded `.hdr`, laid out like
+    /// [`SphericalHarmonics::from_equirect`] expects. Returns `None` for texel formats that
+    /// can't be read back.
+    pub fn from_equirect(panorama: &Image, face_size: u32, format: SkyTexFormat) -> Option<Self> {
+        let texels = read_texels(panorama)?;
+        let (width, height) = (panorama.width() as usize, panorama.height() as usize);
+        if width == 0 || height == 0 || texels.len() < width * height {
+            return None;
+        }
+        let texel =
+            |x: isize, y: usize| texels[y * width + x.rem_euclid(width as isize) as usize];
+        Some(Self::from_fn(face_size, format, |dir| {
+            // Bilinear, wrapping around in longitude and clamping at the poles
+            let lon = dir.x.atan2(-dir.z) / std::f32::consts::TAU + 0.5;
+            let lat = 0.5 - dir.y.clamp(-1.0, 1.0).asin() / std::f32::consts::PI;
+            let x = lon * width as f32 - 0.5;
+            let y = (lat * height as f32 - 0.5).clamp(0.0, (height - 1) as f32);
+            let (x0, y0) = (x.floor(), y.floor());
+            let (fx, fy) = (x - x0, y - y0);
+            let (x0, y0) = (x0 as isize, y0 as usize);
+            let y1 = (y0 + 1).min(height - 1);
+            let top = texel(x0, y0).lerp(texel(x0 + 1, y0), fx);
+            let bottom = texel(x0, y1).lerp(texel(x0 + 1, y1), fx);
+            top.lerp(bottom, fy).truncate().extend(1.0)
+        }))
+    }
+
+    /// Uses caller provided linear texels, laid out face by face in +X, -X, +Y, -Y, +Z, -Z
+    /// order with `face_size`² rows-major texels each. Returns `None` if `face_size` isn't a
+    /// power of two or `data` has the wrong length.
+    pub fn from_faces(face_size: u32, format: SkyTexFormat, data: &[Vec4]) -> Option<Self> {
+        if !face_size.is_power_of_two() || data.len() != (face_size * face_size * 6) as usize {
+            return None;
+        }
+        Some(Self::from_texels(face_size, format, data))
+    }
+
+    fn from_texels(size: u32, format: SkyTexFormat, data: &[Vec4]) -> Self {
+        let mut lighting = project_cubemap_sh(size, data);
+        sh_windowing(&mut lighting, 1.0);
+        Self {
+            image: cubemap_image(size, data, format),
+            lighting,
+        }
+    }
+}
+
+/// Inserts a [`PaintedSky`] as the skybox of `camera` and makes its SH the scene lighting.
+///
+/// ```ignore
+/// commands.add(InsertPaintedSky {
+///     camera,
+///     sky: PaintedSky::from_fn(64, SkyTexFormat::Linear, |dir| Vec4::new(0.2, 0.4, dir.y.max(0.0), 1.0)),
+/// });
+/// ```
+pub struct InsertPaintedSky {
+    pub camera: Entity,
+    pub sky: PaintedSky,
+}
+
+impl Command for InsertPaintedSky {
+    fn apply(self, world: &mut World) {
+        let image = world.resource_mut::<Assets<Image>>().add(self.sky.image);
+        if let Some(mut camera) = world.get_entity_mut(self.camera) {
+            camera
+                .insert((
+                    Skybox {
+                        image,
+                        brightness: SKYBOX_BRIGHTNESS,
+                    },
+                    SetupSkyTex,
+                ))
+                .remove::<GeneratedSky>();
+        }
+        world.insert_resource(SkyLighting(self.sky.lighting));
+    }
+}
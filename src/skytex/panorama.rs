@@ -0,0 +1,81 @@
+use crate::quality::SkQuality;
+use crate::skytex::paint::{InsertPaintedSky, PaintedSky};
+use crate::skytex::{setup_skytex, SetupSkyTex, SkyTexFormat, SkyTexSettings};
+use bevy::prelude::*;
+
+/// Uses an equirectangular HDRI as this camera's skybox and the scene lighting.
+///
+/// Once the image has loaded it's resampled into a cubemap of the [`SkyTexSettings`] face
+/// size and inserted like a [`PaintedSky`], reloading the image rebuilds the sky.
+///
+/// ```ignore
+/// commands.spawn((Camera3dBundle::default(), SkyFromPanorama(assets.load("sky.hdr"))));
+/// ```
+#[derive(Component, Reflect, Clone, Debug, Default, PartialEq, Eq, Deref)]
+#[reflect(Component)]
+pub struct SkyFromPanorama(pub Handle<Image>);
+
+/// The panorama a camera's sky was last built from
+#[derive(Component)]
+struct PanoramaSky(AssetId<Image>);
+
+/// Builds the skies of [`SkyFromPanorama`] cameras, added by `SkyTexPlugin`
+pub struct SkyPanoramaPlugin;
+
+impl Plugin for SkyPanoramaPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<SkyFromPanorama>();
+        app.add_systems(
+            Update,
+            (reload_sky_panoramas, apply_sky_panoramas)
+                .chain()
+                .before(setup_skytex),
+        );
+    }
+}
+
+fn reload_sky_panoramas(
+    mut commands: Commands,
+    mut events: EventReader<AssetEvent<Image>>,
+    cameras: Query<(Entity, &PanoramaSky)>,
+) {
+    for event in events.read() {
+        if let AssetEvent::Modified { id } = event {
+            for (entity, sky) in cameras.iter() {
+                if sky.0 == *id {
+                    commands.entity(entity).remove::<PanoramaSky>();
+                }
+            }
+        }
+    }
+}
+
+fn apply_sky_panoramas(
+    mut commands: Commands,
+    cameras: Query<(Entity, &SkyFromPanorama, Option<&PanoramaSky>, Has<SetupSkyTex>)>,
+    images: Res<Assets<Image>>,
+    settings: Res<SkyTexSettings>,
+    quality: Res<SkQuality>,
+    format: Res<SkyTexFormat>,
+) {
+    for (entity, panorama, current, setup) in cameras.iter() {
+        if current.is_some_and(|current| current.0 == panorama.id()) {
+            continue;
+        }
+        if !setup {
+            // Keeps the generated sky from being built while the panorama is loading
+            commands.entity(entity).insert(SetupSkyTex);
+        }
+        let Some(image) = images.get(&panorama.0) else {
+            continue;
+        };
+        let face_size = settings.face_size(&quality);
+        let Some(sky) = PaintedSky::from_equirect(image, face_size, *format) else {
+            warn!("{:?} can't be read back to build a sky", image.texture_descriptor.format);
+            commands.entity(entity).insert(PanoramaSky(panorama.id()));
+            continue;
+        };
+        commands.add(InsertPaintedSky { camera: entity, sky });
+        commands.entity(entity).insert(PanoramaSky(panorama.id()));
+    }
+}
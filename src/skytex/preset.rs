@@ -0,0 +1,277 @@
+use crate::skytex::clouds::CloudLayer;
+use crate::skytex::gradient::SkyGradient;
+use crate::skytex::stars::StarField;
+use crate::skytex::{SkyLighting, SkyTexSettings, SphericalHarmonics, SKYBOX_BRIGHTNESS};
+use bevy::ecs::world::Command;
+use bevy::prelude::*;
+
+/// A sky stored as an asset, applied through [`ActiveSkyPreset`].
+///
+/// With the `serde` feature presets load from `.sky.ron` files and hot reload with Bevy's
+/// `file_watcher`:
+///
+/// ```ron
+/// (
+///     lighting: (coefficients: [(0.6, 0.65, 0.7), (0.1, 0.1, 0.12), ...]),
+///     spot_size: 0.3,
+///     spot_intensity: 6.0,
+///     brightness: 800.0,
+/// )
+/// ```
+#[derive(Asset, Reflect, Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct SkyPreset {
+    pub lighting: SphericalHarmonics,
+    pub spot_size: f32,
+    pub spot_intensity: f32,
+    pub brightness: f32,
+}
+
+impl Default for SkyPreset {
+    fn default() -> Self {
+        let settings = SkyTexSettings::default();
+        Self {
+            lighting: SkyLighting::default().0,
+            spot_size: settings.spot_size,
+            spot_intensity: settings.spot_intensity,
+            brightness: SKYBOX_BRIGHTNESS,
+        }
+    }
+}
+
+impl SkyPreset {
+    /// `settings` with the preset's spot and brightness
+    pub fn settings(&self, settings: SkyTexSettings) -> SkyTexSettings {
+        SkyTexSettings {
+            spot_size: self.spot_size,
+            spot_intensity: self.spot_intensity,
+            brightness: self.brightness,
+            ..settings
+        }
+    }
+}
+
+/// Curated skies for common situations, applied with [`ApplySkyPreset`]
+#[derive(Reflect, Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum BuiltinSkyPreset {
+    #[default]
+    ClearNoon,
+    GoldenHour,
+    Overcast,
+    Night,
+    /// Warm ceiling light over wooden floors
+    IndoorWarm,
+    /// Cool office lighting
+    IndoorCool,
+}
+
+impl BuiltinSkyPreset {
+    pub const ALL: [BuiltinSkyPreset; 6] = [
+        BuiltinSkyPreset::ClearNoon,
+        BuiltinSkyPreset::GoldenHour,
+        BuiltinSkyPreset::Overcast,
+        BuiltinSkyPreset::Night,
+        BuiltinSkyPreset::IndoorWarm,
+        BuiltinSkyPreset::IndoorCool,
+    ];
+
+    /// The sky the preset's lighting is projected from
+    pub fn gradient(self) -> SkyGradient {
+        let stops = |ground: Color, horizon: Color, zenith: Color| {
+            SkyGradient::new()
+                .stop(ground, -1.0)
+                .stop(horizon, 0.0)
+                .stop(zenith, 1.0)
+        };
+        match self {
+            BuiltinSkyPreset::ClearNoon => stops(
+                Color::srgb(0.3, 0.28, 0.25),
+                Color::srgb(0.8, 0.87, 0.95),
+                Color::srgb(0.3, 0.5, 0.9),
+            )
+            .sun(Vec3::new(0.3, 0.9, -0.3), Color::srgb(1.0, 0.97, 0.9), 8.0),
+            BuiltinSkyPreset::GoldenHour => stops(
+                Color::srgb(0.15, 0.11, 0.09),
+                Color::srgb(0.98, 0.6, 0.32),
+                Color::srgb(0.25, 0.32, 0.55),
+            )
+            .sun(Vec3::new(0.8, 0.15, -0.6), Color::srgb(1.0, 0.7, 0.4), 6.0),
+            BuiltinSkyPreset::Overcast => stops(
+                Color::srgb(0.22, 0.22, 0.22),
+                Color::srgb(0.62, 0.64, 0.66),
+                Color::srgb(0.72, 0.74, 0.76),
+            ),
+            BuiltinSkyPreset::Night => stops(
+                Color::srgb(0.01, 0.01, 0.01),
+                Color::srgb(0.03, 0.04, 0.07),
+                Color::srgb(0.01, 0.015, 0.04),
+            )
+            .sun(Vec3::new(-0.4, 0.7, 0.3), Color::srgb(0.6, 0.7, 1.0), 0.4),
+            BuiltinSkyPreset::IndoorWarm => stops(
+                Color::srgb(0.25, 0.18, 0.12),
+                Color::srgb(0.55, 0.45, 0.35),
+                Color::srgb(0.9, 0.75, 0.55),
+            ),
+            BuiltinSkyPreset::IndoorCool => stops(
+                Color::srgb(0.2, 0.2, 0.22),
+                Color::srgb(0.5, 0.52, 0.55),
+                Color::srgb(0.85, 0.9, 1.0),
+            ),
+        }
+    }
+
+    pub fn preset(self) -> SkyPreset {
+        let (spot_size, spot_intensity) = match self {
+            BuiltinSkyPreset::ClearNoon => (0.3, 6.0),
+            BuiltinSkyPreset::GoldenHour => (0.35, 8.0),
+            BuiltinSkyPreset::Night => (0.1, 3.0),
+            // Diffuse light has no disk to paint
+            BuiltinSkyPreset::Overcast
+            | BuiltinSkyPreset::IndoorWarm
+            | BuiltinSkyPreset::IndoorCool => (0.3, 0.0),
+        };
+        SkyPreset {
+            lighting: self.gradient().lighting(),
+            spot_size,
+            spot_intensity,
+            brightness: SKYBOX_BRIGHTNESS,
+        }
+    }
+
+    /// `settings` with the preset's generation parameters, including its clouds and stars
+    pub fn settings(self, settings: SkyTexSettings) -> SkyTexSettings {
+        let clouds = match self {
+            BuiltinSkyPreset::ClearNoon => Some(CloudLayer {
+                coverage: 0.2,
+                ..default()
+            }),
+            BuiltinSkyPreset::GoldenHour => Some(CloudLayer {
+                coverage: 0.3,
+                color: Color::srgb(1.0, 0.8, 0.7),
+                ..default()
+            }),
+            BuiltinSkyPreset::Overcast => Some(CloudLayer {
+                coverage: 0.95,
+                density: 0.95,
+                color: Color::srgb(0.8, 0.8, 0.82),
+                ..default()
+            }),
+            _ => None,
+        };
+        let stars = (self == BuiltinSkyPreset::Night).then(StarField::default);
+        SkyTexSettings {
+            clouds,
+            stars,
+            ..self.preset().settings(settings)
+        }
+    }
+
+    /// Makes the preset the scene's [`SkyLighting`] and [`SkyTexSettings`], replacing any
+    /// [`ActiveSkyPreset`]
+    pub fn apply(self, world: &mut World) {
+        let settings = self.settings(*world.resource::<SkyTexSettings>());
+        world.insert_resource(SkyLighting(self.preset().lighting));
+        world.insert_resource(settings);
+        world.insert_resource(ActiveSkyPreset(None));
+    }
+}
+
+impl From<BuiltinSkyPreset> for SkyPreset {
+    fn from(preset: BuiltinSkyPreset) -> Self {
+        preset.preset()
+    }
+}
+
+/// Applies a [`BuiltinSkyPreset`], e.g. `commands.add(ApplySkyPreset(BuiltinSkyPreset::Night))`
+pub struct ApplySkyPreset(pub BuiltinSkyPreset);
+
+impl Command for ApplySkyPreset {
+    fn apply(self, world: &mut World) {
+        self.0.apply(world);
+    }
+}
+
+/// The preset driving [`SkyLighting`] and [`SkyTexSettings`], reapplied when it (re)loads
+#[derive(Resource, Reflect, Clone, Debug, Default)]
+#[reflect(Resource)]
+pub struct ActiveSkyPreset(pub Option<Handle<SkyPreset>>);
+
+pub(crate) fn apply_sky_preset(
+    active: Res<ActiveSkyPreset>,
+    mut events: EventReader<AssetEvent<SkyPreset>>,
+    presets: Res<Assets<SkyPreset>>,
+    mut lighting: ResMut<SkyLighting>,
+    mut settings: ResMut<SkyTexSettings>,
+) {
+    let Some(handle) = &active.0 else {
+        events.clear();
+        return;
+    };
+    let reloaded = events.read().any(|event| match event {
+        AssetEvent::LoadedWithDependencies { id } | AssetEvent::Modified { id } => {
+            *id == handle.id()
+        }
+        _ => false,
+    });
+    if !(reloaded || active.is_changed()) {
+        return;
+    }
+    let Some(preset) = presets.get(handle) else {
+        return;
+    };
+    lighting.set_if_neq(SkyLighting(preset.lighting));
+    let preset_settings = preset.settings(*settings);
+    settings.set_if_neq(preset_settings);
+}
+
+/// Loads [`SkyPreset`]s from RON
+#[cfg(feature = "serde")]
+#[derive(Default)]
+pub struct SkyPresetLoader;
+
+#[cfg(feature = "serde")]
+#[derive(Debug)]
+pub enum SkyPresetError {
+    Io(std::io::Error),
+    Ron(ron::error::SpannedError),
+}
+
+#[cfg(feature = "serde")]
+impl std::fmt::Display for SkyPresetError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SkyPresetError::Io(err) => write!(f, "couldn't read sky preset: {err}"),
+            SkyPresetError::Ron(err) => write!(f, "invalid sky preset: {err}"),
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl std::error::Error for SkyPresetError {}
+
+#[cfg(feature = "serde")]
+impl bevy::asset::AssetLoader for SkyPresetLoader {
+    type Asset = SkyPreset;
+    type Settings = ();
+    type Error = SkyPresetError;
+
+    async fn load<'a>(
+        &'a self,
+        reader: &'a mut bevy::asset::io::Reader<'_>,
+        _settings: &'a (),
+        _load_context: &'a mut bevy::asset::LoadContext<'_>,
+    ) -> Result<Self::Asset, Self::Error> {
+        use bevy::asset::AsyncReadExt;
+        let mut bytes = Vec::new();
+        reader
+            .read_to_end(&mut bytes)
+            .await
+            .map_err(SkyPresetError::Io)?;
+        ron::de::from_bytes(&bytes).map_err(SkyPresetError::Ron)
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["sky.ron"]
+    }
+}
@@ -0,0 +1,184 @@
+use crate::skytex::lifecycle::NoSkyTex;
+use crate::skytex::{
+    setup_skytex, sh_lookup_coefficients, GeneratedSky, LightSpot, PendingSkyTex, SetupSkyTex,
+    SkyLighting, SkyTexConfig, SkyTexSettings,
+};
+use bevy::core_pipeline::Skybox;
+use bevy::pbr::{NotShadowCaster, NotShadowReceiver};
+use bevy::prelude::*;
+use bevy::render::render_resource::{AsBindGroup, ShaderRef, ShaderType};
+use bevy::render::view::NoFrustumCulling;
+
+const SHADER_HANDLE: Handle<Shader> = Handle::weak_from_u128(0x3c58e1a97d46);
+
+/// Evaluates the sky per pixel from the SH instead of baking a cube texture.
+///
+/// Lighting changes only update a uniform, so animating [`SkyLighting`] every frame is cheap
+/// and no sky texture memory is used. Needs `PbrPlugin` for the shared WGSL lighting.
+pub struct ProceduralSkyPlugin;
+
+impl Plugin for ProceduralSkyPlugin {
+    fn build(&self, app: &mut App) {
+        load_shader!(app, SHADER_HANDLE, "procedural.wgsl");
+        app.add_plugins(MaterialPlugin::<ProceduralSkyMaterial> {
+            prepass_enabled: false,
+            shadows_enabled: false,
+            ..default()
+        });
+        app.register_type::<ProceduralSky>();
+        app.add_systems(Update, spawn_procedural_skies.before(setup_skytex));
+        app.add_systems(PostUpdate, (update_procedural_skies, remove_procedural_skies));
+    }
+}
+
+/// Draws this camera's sky with a [`ProceduralSkyMaterial`] in place of a generated `Skybox`
+#[derive(Component, Reflect, Clone, Copy, Debug, PartialEq)]
+#[reflect(Component)]
+pub struct ProceduralSky {
+    /// Multiplier on the sky, 1.0 matches the SH lit `PbrMaterial` surfaces
+    pub brightness: f32,
+}
+
+impl Default for ProceduralSky {
+    fn default() -> Self {
+        Self { brightness: 1.0 }
+    }
+}
+
+/// The sky sphere drawn for a [`ProceduralSky`] camera
+#[derive(Component, Clone, Copy, Debug)]
+pub struct ProceduralSkyOf(pub Entity);
+
+#[derive(Asset, AsBindGroup, TypePath, Clone, Debug)]
+pub struct ProceduralSkyMaterial {
+    #[uniform(0)]
+    pub params: ProceduralSkyParams,
+}
+
+#[derive(ShaderType, Clone, Copy, Debug, Default, PartialEq)]
+pub struct ProceduralSkyParams {
+    sh: [Vec4; 9],
+    spot_direction: Vec3,
+    spot_inner: f32,
+    spot_color: Vec3,
+    spot_radius: f32,
+    brightness: f32,
+}
+
+impl ProceduralSkyParams {
+    pub fn new(
+        lighting: &SkyLighting,
+        config: Option<&SkyTexConfig>,
+        settings: &SkyTexSettings,
+        brightness: f32,
+    ) -> Self {
+        let (lighting, settings) = SkyTexConfig::resolve(config, lighting, settings);
+        let windowed_lighting = settings.windowed(&lighting);
+        let spot = LightSpot::new(&windowed_lighting, settings.spot_size, settings.spot_intensity);
+        Self {
+            sh: sh_lookup_coefficients(&windowed_lighting).map(|c| c.extend(0.0)),
+            spot_direction: spot.direction,
+            spot_inner: spot.inner,
+            spot_color: spot.color.truncate(),
+            spot_radius: spot.radius,
+            brightness,
+        }
+    }
+}
+
+impl Material for ProceduralSkyMaterial {
+    fn vertex_shader() -> ShaderRef {
+        SHADER_HANDLE.into()
+    }
+
+    fn fragment_shader() -> ShaderRef {
+        SHADER_HANDLE.into()
+    }
+
+    fn specialize(
+        _pipeline: &bevy::pbr::MaterialPipeline<Self>,
+        descriptor: &mut bevy::render::render_resource::RenderPipelineDescriptor,
+        _layout: &bevy::render::mesh::MeshVertexBufferLayoutRef,
+        _key: bevy::pbr::MaterialPipelineKey<Self>,
+    ) -> Result<(), bevy::render::render_resource::SpecializedMeshPipelineError> {
+        // The camera sits inside the sphere, which only fills what nothing else covered
+        descriptor.primitive.cull_mode = None;
+        if let Some(depth_stencil) = descriptor.depth_stencil.as_mut() {
+            depth_stencil.depth_write_enabled = false;
+        }
+        Ok(())
+    }
+}
+
+fn spawn_procedural_skies(
+    mut commands: Commands,
+    cameras: Query<(Entity, &ProceduralSky, Option<&SkyTexConfig>), Added<ProceduralSky>>,
+    lighting: Res<SkyLighting>,
+    settings: Res<SkyTexSettings>,
+    mut materials: ResMut<Assets<ProceduralSkyMaterial>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+) {
+    for (camera, sky, config) in cameras.iter() {
+        commands.spawn((
+            MaterialMeshBundle {
+                mesh: meshes.add(Sphere::new(1.0).mesh().ico(3).unwrap()),
+                material: materials.add(ProceduralSkyMaterial {
+                    params: ProceduralSkyParams::new(&lighting, config, &settings, sky.brightness),
+                }),
+                ..default()
+            },
+            NoFrustumCulling,
+            NotShadowCaster,
+            NotShadowReceiver,
+            ProceduralSkyOf(camera),
+        ));
+        commands
+            .entity(camera)
+            .insert(NoSkyTex)
+            .remove::<(Skybox, PendingSkyTex, SetupSkyTex, GeneratedSky)>();
+    }
+}
+
+fn update_procedural_skies(
+    cameras: Query<(Ref<ProceduralSky>, Option<Ref<SkyTexConfig>>)>,
+    skies: Query<(&ProceduralSkyOf, &Handle<ProceduralSkyMaterial>)>,
+    lighting: Res<SkyLighting>,
+    settings: Res<SkyTexSettings>,
+    mut materials: ResMut<Assets<ProceduralSkyMaterial>>,
+) {
+    for (of, material) in skies.iter() {
+        let Ok((sky, config)) = cameras.get(of.0) else {
+            continue;
+        };
+        let changed = lighting.is_changed()
+            || settings.is_changed()
+            || sky.is_changed()
+            || config.as_ref().is_some_and(|c| c.is_changed());
+        if !changed {
+            continue;
+        }
+        let params =
+            ProceduralSkyParams::new(&lighting, config.as_deref(), &settings, sky.brightness);
+        if let Some(material) = materials.get_mut(material) {
+            material.params = params;
+        }
+    }
+}
+
+/// Despawns the sphere and lets `setup_skytex` give the camera a generated sky again
+fn remove_procedural_skies(
+    mut commands: Commands,
+    mut removed: RemovedComponents<ProceduralSky>,
+    skies: Query<(Entity, &ProceduralSkyOf)>,
+) {
+    for camera in removed.read() {
+        for (entity, of) in skies.iter() {
+            if of.0 == camera {
+                commands.entity(entity).despawn_recursive();
+            }
+        }
+        if let Some(mut camera) = commands.get_entity(camera) {
+            camera.remove::<NoSkyTex>();
+        }
+    }
+}
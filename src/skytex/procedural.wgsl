@@ -0,0 +1,52 @@
+#import bevy_pbr::mesh_view_bindings::view
+#import bevy_sk::lighting::sk_lighting
+
+struct ProceduralSkyParams {
+    // Premultiplied like sh_lookup on the CPU
+    sh: array<vec4<f32>, 9>,
+    spot_direction: vec3<f32>,
+    spot_inner: f32,
+    spot_color: vec3<f32>,
+    spot_radius: f32,
+    brightness: f32,
+};
+
+@group(2) @binding(0)
+var<uniform> params: ProceduralSkyParams;
+
+struct Vertex {
+    @location(0) position: vec3<f32>,
+};
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) direction: vec3<f32>,
+};
+
+@vertex
+fn vertex(vertex: Vertex) -> VertexOutput {
+    var out: VertexOutput;
+    // Centered on the camera and pushed onto the far plane, depth 0 with Bevy's reverse z
+    var clip = view.clip_from_world * vec4(view.world_position + vertex.position, 1.0);
+    clip.z = 0.0;
+    out.clip_position = clip;
+    out.direction = vertex.position;
+    return out;
+}
+
+@fragment
+fn fragment(in: VertexOutput) -> @location(0) vec4<f32> {
+    let dir = normalize(in.direction);
+
+    var sh: array<vec3<f32>, 9>;
+    for (var i = 0u; i < 9u; i += 1u) {
+        sh[i] = params.sh[i].xyz;
+    }
+    let sky_color = sk_lighting(dir, sh);
+
+    let angle = acos(clamp(dot(dir, params.spot_direction), -1.0, 1.0));
+    let spot = 1.0 - smoothstep(params.spot_inner, params.spot_radius, angle);
+    let color = mix(sky_color, params.spot_color, spot);
+
+    return vec4(color * params.brightness, 1.0);
+}
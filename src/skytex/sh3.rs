@@ -0,0 +1,130 @@
+use crate::skytex::paint::PaintedSky;
+use crate::skytex::{
+    for_each_cubemap_texel, read_cubemap_faces, sh_lookup, SkyTexFormat, SphericalHarmonics,
+};
+use bevy::prelude::*;
+
+/// Third order SH with 16 coefficients, for sharper gradients than [`SphericalHarmonics`].
+///
+/// The clamped cosine lobe has no band 3 component, so diffuse lighting is the same as with
+/// the first 9 coefficients. Band 3 sharpens radiance instead: the sky painted from it and,
+/// with the `sh3` feature, the SH reflections of `PbrMaterial` (see
+/// `ShLightingBuffer::set_sh3`).
+#[derive(Reflect, Clone, Copy, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Sh3 {
+    pub coefficients: [Vec3; 16],
+}
+
+/// Real SH basis up to band 3, z is the zonal axis like the L2 basis
+fn basis(n: Vec3) -> [f32; 16] {
+    let (x, y, z) = (n.x, n.y, n.z);
+    [
+        0.282095,
+        0.488603 * y,
+        0.488603 * z,
+        0.488603 * x,
+        1.092548 * x * y,
+        1.092548 * y * z,
+        0.315392 * (3.0 * z * z - 1.0),
+        1.092548 * x * z,
+        0.546274 * (x * x - y * y),
+        0.590044 * y * (3.0 * x * x - y * y),
+        2.890611 * x * y * z,
+        0.457046 * y * (5.0 * z * z - 1.0),
+        0.373176 * z * (5.0 * z * z - 3.0),
+        0.457046 * x * (5.0 * z * z - 1.0),
+        1.445306 * z * (x * x - y * y),
+        0.590044 * x * (x * x - 3.0 * y * y),
+    ]
+}
+
+impl Sh3 {
+    /// Band 3 left empty
+    pub fn from_l2(harmonics: &SphericalHarmonics) -> Self {
+        let mut coefficients = [Vec3::ZERO; 16];
+        coefficients[..9].copy_from_slice(&harmonics.coefficients);
+        Self { coefficients }
+    }
+
+    /// The first three bands
+    pub fn l2(&self) -> SphericalHarmonics {
+        SphericalHarmonics {
+            coefficients: std::array::from_fn(|i| self.coefficients[i]),
+        }
+    }
+
+    /// Projects texels laid out like the generated sky, see `PaintedSky::from_faces`
+    pub fn project_cubemap(face_size: u32, data: &[Vec4]) -> Self {
+        let mut harmonics = Self::default();
+        let mut total_weight = 0.0;
+        for_each_cubemap_texel(face_size, |index, pt| {
+            let weight = 1.0 / pt.length().powi(3);
+            harmonics.accumulate(pt.normalize(), data[index].truncate() * weight);
+            total_weight += weight;
+        });
+        let normalization = 4.0 * std::f32::consts::PI / total_weight;
+        for coefficient in harmonics.coefficients.iter_mut() {
+            *coefficient *= normalization;
+        }
+        harmonics
+    }
+
+    /// Unwindowed projection of a 6 layer cubemap, `None` for unreadable images
+    pub fn from_cubemap(image: &Image) -> Option<Self> {
+        let (size, data) = read_cubemap_faces(image)?;
+        Some(Self::project_cubemap(size, &data))
+    }
+
+    /// Adds a distant point light in direction `dir`
+    pub fn add(&mut self, dir: Vec3, color: Color) {
+        self.accumulate(dir.normalize(), color.to_linear().to_vec3());
+    }
+
+    fn accumulate(&mut self, n: Vec3, color: Vec3) {
+        for (coefficient, basis) in self.coefficients.iter_mut().zip(basis(n)) {
+            *coefficient += color * basis;
+        }
+    }
+
+    /// Linear radiance arriving from `dir`
+    pub fn radiance(&self, dir: Vec3) -> Vec3 {
+        self.coefficients
+            .iter()
+            .zip(basis(dir.normalize()))
+            .map(|(coefficient, basis)| *coefficient * basis)
+            .sum()
+    }
+
+    /// Linear irradiance on a surface facing `normal`, alpha is 1
+    pub fn irradiance(&self, normal: Vec3) -> Vec4 {
+        sh_lookup(&self.l2(), normal)
+    }
+
+    /// Same falloff as `SphericalHarmonics::window`, extended to band 3
+    pub fn window(mut self, width: f32) -> Self {
+        let mut i = 0;
+        for band in 0..=3i32 {
+            let s = 1.0 / (1.0 + width * (band * band * (band + 1) * (band + 1)) as f32);
+            for _ in -band..=band {
+                self.coefficients[i] *= s;
+                i += 1;
+            }
+        }
+        self
+    }
+
+    /// Band 3 premultiplied by its basis constants, the layout read by `sk_lighting_band3`
+    pub fn shader_band3(&self) -> [Vec3; 7] {
+        const SCALE: [f32; 7] =
+            [0.590044, 2.890611, 0.457046, 0.373176, 0.457046, 1.445306, 0.590044];
+        std::array::from_fn(|i| self.coefficients[9 + i] * SCALE[i])
+    }
+
+    /// Paints the radiance into a sky cubemap
+    pub fn paint(&self, face_size: u32, format: SkyTexFormat) -> PaintedSky {
+        PaintedSky::from_fn(face_size, format, |dir| {
+            self.radiance(dir).max(Vec3::ZERO).extend(1.0)
+        })
+    }
+}
@@ -0,0 +1,143 @@
+use crate::skytex::SphericalHarmonics;
+use bevy::asset::io::Reader;
+use bevy::asset::{AssetLoader, AsyncReadExt, LoadContext};
+use bevy::prelude::*;
+use std::fmt;
+
+/// Loads StereoKit style SH coefficient dumps as [`SphericalHarmonics`] assets.
+///
+/// Accepts either text holding the 9 RGB coefficients as 27 numbers, in any separator or
+/// syntax such as a pasted `new Vec3(..)` list, or 108 bytes of little endian `f32`s.
+#[derive(Default)]
+pub struct ShFileLoader;
+
+#[derive(Debug)]
+pub enum ShFileError {
+    Io(std::io::Error),
+    /// Neither 27 numbers nor 27 raw floats were found, holds how many numbers were parsed
+    WrongCoefficientCount(usize),
+}
+
+impl fmt::Display for ShFileError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ShFileError::Io(err) => write!(f, "couldn't read SH file: {err}"),
+            ShFileError::WrongCoefficientCount(count) => {
+                write!(f, "expected 27 SH coefficient values, found {count}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for ShFileError {}
+
+impl From<std::io::Error> for ShFileError {
+    fn from(err: std::io::Error) -> Self {
+        ShFileError::Io(err)
+    }
+}
+
+impl AssetLoader for ShFileLoader {
+    type Asset = SphericalHarmonics;
+    type Settings = ();
+    type Error = ShFileError;
+
+    async fn load<'a>(
+        &'a self,
+        reader: &'a mut Reader<'_>,
+        _settings: &'a (),
+        _load_context: &'a mut LoadContext<'_>,
+    ) -> Result<Self::Asset, Self::Error> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+        parse_sh_file(&bytes)
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["sh"]
+    }
+}
+
+/// Parses the text or binary coefficient format, see [`ShFileLoader`]
+pub fn parse_sh_file(bytes: &[u8]) -> Result<SphericalHarmonics, ShFileError> {
+    let text_values = std::str::from_utf8(bytes).ok().map(parse_numbers);
+    // Raw floats can happen to be valid UTF-8, so they're the fallback for text that isn't SH
+    let values = match text_values {
+        Some(values) if values.len() == 27 => values,
+        _ if bytes.len() == 27 * 4 => bytes
+            .chunks_exact(4)
+            .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+            .collect(),
+        Some(values) => values,
+        None => return Err(ShFileError::WrongCoefficientCount(bytes.len() / 4)),
+    };
+    if values.len() != 27 {
+        return Err(ShFileError::WrongCoefficientCount(values.len()));
+    }
+    let mut sh = SphericalHarmonics::default();
+    for (coefficient, rgb) in sh.coefficients.iter_mut().zip(values.chunks_exact(3)) {
+        *coefficient = Vec3::new(rgb[0], rgb[1], rgb[2]);
+    }
+    Ok(sh)
+}
+
+/// Every number in `text`, skipping identifiers like `Vec3` and C# `f` suffixes
+fn parse_numbers(text: &str) -> Vec<f32> {
+    let is_number_char = |c: char| c.is_ascii_digit() || matches!(c, '.' | '-' | '+' | 'e' | 'E');
+    let mut values = Vec::new();
+    let mut rest = text;
+    while let Some(start) = rest.find(|c: char| c.is_ascii_digit() || c == '-' || c == '.') {
+        // Digits glued to an identifier, like the 3 in `Vec3`, aren't values
+        let in_identifier = rest[..start]
+            .chars()
+            .next_back()
+            .is_some_and(|c| c.is_alphanumeric() || c == '_');
+        let token_len = rest[start..]
+            .find(|c: char| !is_number_char(c))
+            .unwrap_or(rest.len() - start);
+        let token = &rest[start..start + token_len];
+        if !in_identifier {
+            if let Ok(value) = token.parse::<f32>() {
+                values.push(value);
+            }
+        }
+        rest = &rest[start + token_len.max(1)..];
+    }
+    values
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn text_coefficients_are_parsed() {
+        let vectors: Vec<String> = (0..9)
+            .map(|i| format!("new Vec3({i}.5f, -{i}, {i}e-1)"))
+            .collect();
+        let sh = parse_sh_file(vectors.join(",\n").as_bytes()).unwrap();
+        assert_eq!(sh.coefficients[0], Vec3::new(0.5, 0.0, 0.0));
+        assert_eq!(sh.coefficients[8], Vec3::new(8.5, -8.0, 0.8));
+    }
+
+    #[test]
+    fn binary_coefficients_are_parsed() {
+        let bytes: Vec<u8> = (0..27).flat_map(|i| (i as f32).to_le_bytes()).collect();
+        let sh = parse_sh_file(&bytes).unwrap();
+        assert_eq!(sh.coefficients[1], Vec3::new(3.0, 4.0, 5.0));
+    }
+
+    #[test]
+    fn binary_coefficients_that_are_valid_utf8_are_parsed() {
+        // Reads as text too, but without any numbers in it
+        let bytes = b"abcd".repeat(27);
+        let sh = parse_sh_file(&bytes).unwrap();
+        assert_eq!(sh.coefficients[4], Vec3::splat(f32::from_le_bytes(*b"abcd")));
+    }
+
+    #[test]
+    fn wrong_coefficient_counts_are_rejected() {
+        let result = parse_sh_file(b"1, 2, 3");
+        assert!(matches!(result, Err(ShFileError::WrongCoefficientCount(3))));
+    }
+}
@@ -0,0 +1,60 @@
+use crate::skytex::{LightSpot, SphericalHarmonics};
+use bevy::prelude::*;
+
+/// Extra bright spots painted into the generated sky next to the dominant SH light, e.g. a
+/// window or a second sun. Changing them regenerates every generated skybox.
+///
+/// The GPU and procedural skies only draw the dominant spot.
+#[derive(Resource, Reflect, Clone, Debug, PartialEq)]
+#[reflect(Resource)]
+pub struct SkyLightSpots {
+    pub spots: Vec<SkySpot>,
+    /// Keeps the spot derived from the dominant SH direction
+    pub include_dominant: bool,
+}
+
+impl Default for SkyLightSpots {
+    fn default() -> Self {
+        Self {
+            spots: Vec::new(),
+            include_dominant: true,
+        }
+    }
+}
+
+#[derive(Reflect, Clone, Copy, Debug, PartialEq)]
+pub struct SkySpot {
+    /// Direction from the viewer towards the light
+    pub direction: Vec3,
+    pub color: Color,
+    pub intensity: f32,
+    /// Half width on a cube face, like `SkyTexSettings::spot_size`
+    pub size: f32,
+}
+
+impl SkyLightSpots {
+    /// Every spot to paint for `lighting`, with the dominant one sized by `size` and
+    /// `intensity`
+    pub(crate) fn light_spots(
+        &self,
+        lighting: &SphericalHarmonics,
+        size: f32,
+        intensity: f32,
+    ) -> Vec<LightSpot> {
+        let dominant = self
+            .include_dominant
+            .then(|| LightSpot::new(lighting, size, intensity));
+        dominant
+            .into_iter()
+            .chain(self.spots.iter().map(|spot| {
+                let radius = spot.size.atan();
+                LightSpot {
+                    direction: spot.direction.normalize(),
+                    color: spot.color.to_linear().to_vec4() * spot.intensity,
+                    inner: radius * 0.75,
+                    radius,
+                }
+            }))
+            .collect()
+    }
+}
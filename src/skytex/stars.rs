@@ -0,0 +1,128 @@
+use crate::skytex::SphericalHarmonics;
+use bevy::prelude::*;
+
+/// Procedural stars baked into the generated sky, set through `SkyTexSettings::stars`
+#[derive(Reflect, Clone, Copy, Debug, PartialEq)]
+pub struct StarField {
+    pub visibility: StarVisibility,
+    /// Fraction of grid cells holding a star
+    pub density: f32,
+    /// Radiance of the brightest stars
+    pub brightness: f32,
+    pub seed: u32,
+    pub moon: Option<Moon>,
+}
+
+impl Default for StarField {
+    fn default() -> Self {
+        Self {
+            visibility: StarVisibility::default(),
+            density: 0.15,
+            brightness: 2.0,
+            seed: 0,
+            moon: Some(Moon::default()),
+        }
+    }
+}
+
+/// When the star field shows up
+#[derive(Reflect, Clone, Copy, Debug, PartialEq)]
+pub enum StarVisibility {
+    /// Fades in as the SH `brightness()` drops below the threshold
+    BelowBrightness(f32),
+    Always,
+}
+
+impl Default for StarVisibility {
+    fn default() -> Self {
+        StarVisibility::BelowBrightness(0.1)
+    }
+}
+
+#[derive(Reflect, Clone, Copy, Debug, PartialEq)]
+pub struct Moon {
+    /// Direction from the viewer towards the moon
+    pub direction: Vec3,
+    pub color: Color,
+    pub intensity: f32,
+    /// Angular radius in radians
+    pub radius: f32,
+}
+
+impl Default for Moon {
+    fn default() -> Self {
+        Self {
+            direction: Vec3::new(-0.4, 0.5, -0.6).normalize(),
+            color: Color::srgb(0.9, 0.92, 1.0),
+            intensity: 1.5,
+            radius: 0.03,
+        }
+    }
+}
+
+/// Stars per unit of direction space along each axis
+const GRID: f32 = 48.0;
+const STAR_RADIUS: f32 = 0.12;
+
+impl StarField {
+    /// How strongly the stars show under `lighting`, 0 hides them
+    pub fn strength(&self, lighting: &SphericalHarmonics) -> f32 {
+        match self.visibility {
+            StarVisibility::Always => 1.0,
+            StarVisibility::BelowBrightness(threshold) if threshold > 0.0 => {
+                (1.0 - lighting.brightness() / threshold).clamp(0.0, 1.0)
+            }
+            StarVisibility::BelowBrightness(_) => 0.0,
+        }
+    }
+
+    /// Linear radiance of the stars and moon in `dir`, before `strength`
+    pub fn radiance(&self, dir: Vec3) -> Vec3 {
+        let mut radiance = Vec3::ZERO;
+        if let Some(moon) = self.moon {
+            let angle = dir.dot(moon.direction.normalize()).clamp(-1.0, 1.0).acos();
+            let t = (angle / moon.radius.max(f32::EPSILON)).clamp(0.0, 1.0);
+            let disk = 1.0 - t * t * (3.0 - 2.0 * t);
+            radiance += moon.color.to_linear().to_vec3() * moon.intensity * disk;
+        }
+
+        let p = dir * GRID;
+        let cell = p.floor();
+        let h = hash(cell.as_ivec3(), self.seed);
+        if h.x < self.density {
+            // Jittered inside the cell so the grid doesn't show
+            let center = cell + Vec3::splat(0.25) + Vec3::new(h.y, h.z, h.w) * 0.5;
+            let d = (p - center).length() / STAR_RADIUS;
+            if d < 1.0 {
+                let twinkle = h.y * 0.7 + 0.3;
+                let warm = Vec3::new(1.0, 0.85 + 0.15 * h.z, 0.7 + 0.3 * h.w);
+                radiance += warm * self.brightness * twinkle * (1.0 - d * d);
+            }
+        }
+        radiance
+    }
+
+    /// Adds the stars to `radiance`, the sky seen in `dir`
+    pub fn composite(&self, strength: f32, dir: Vec3, radiance: Vec4) -> Vec4 {
+        if strength <= 0.0 {
+            return radiance;
+        }
+        radiance + (self.radiance(dir) * strength).extend(0.0)
+    }
+}
+
+fn hash(p: IVec3, seed: u32) -> Vec4 {
+    let mut h = (p.x as u32).wrapping_mul(0x8da6_b343)
+        ^ (p.y as u32).wrapping_mul(0xd816_3841)
+        ^ (p.z as u32).wrapping_mul(0xcb1a_b31f)
+        ^ seed.wrapping_mul(0x1656_67b1);
+    let mut next = || {
+        h ^= h >> 15;
+        h = h.wrapping_mul(0x2c1b_3c6d);
+        h ^= h >> 12;
+        h = h.wrapping_mul(0x297a_2d39);
+        h ^= h >> 15;
+        h as f32 / u32::MAX as f32
+    };
+    Vec4::new(next(), next(), next(), next())
+}
@@ -0,0 +1,100 @@
+use crate::skytex::paint::PaintedSky;
+use crate::skytex::{SkyRadiance, SkyTexFormat};
+use bevy::prelude::*;
+
+/// A rectangular light panel in a [`StudioLighting`] environment
+#[derive(Clone, Debug, PartialEq)]
+pub struct Softbox {
+    /// Direction from the subject towards the softbox
+    pub direction: Vec3,
+    /// Angular half width and half height in radians
+    pub half_size: Vec2,
+    pub color: Color,
+    pub intensity: f32,
+}
+
+/// Product-shot style environment: bright softboxes over a neutral background and floor
+#[derive(Clone, Debug, PartialEq)]
+pub struct StudioLighting {
+    pub background: Color,
+    pub floor: Color,
+    pub softboxes: Vec<Softbox>,
+    pub face_size: u32,
+}
+
+impl Default for StudioLighting {
+    fn default() -> Self {
+        Self::three_point()
+    }
+}
+
+impl StudioLighting {
+    /// Classic key, fill and rim setup
+    pub fn three_point() -> Self {
+        Self {
+            background: Color::srgb(0.18, 0.18, 0.19),
+            floor: Color::srgb(0.1, 0.1, 0.1),
+            softboxes: vec![
+                // Key
+                Softbox {
+                    direction: Vec3::new(-1.0, 0.8, 1.0),
+                    half_size: Vec2::new(0.35, 0.25),
+                    color: Color::WHITE,
+                    intensity: 6.0,
+                },
+                // Fill
+                Softbox {
+                    direction: Vec3::new(1.0, 0.3, 0.8),
+                    half_size: Vec2::new(0.4, 0.3),
+                    color: Color::srgb(0.9, 0.95, 1.0),
+                    intensity: 2.0,
+                },
+                // Rim
+                Softbox {
+                    direction: Vec3::new(0.2, 0.6, -1.0),
+                    half_size: Vec2::new(0.15, 0.45),
+                    color: Color::WHITE,
+                    intensity: 4.0,
+                },
+            ],
+            face_size: 64,
+        }
+    }
+
+    /// Linear radiance seen in `dir`
+    pub fn radiance(&self, dir: Vec3) -> Vec4 {
+        let base = if dir.y < 0.0 { self.floor } else { self.background };
+        let mut color = base.to_linear().to_vec3();
+
+        for softbox in &self.softboxes {
+            let forward = softbox.direction.normalize();
+            let facing = dir.dot(forward);
+            if facing <= 0.0 {
+                continue;
+            }
+            let up = if forward.y.abs() > 0.99 { Vec3::Z } else { Vec3::Y };
+            let right = up.cross(forward).normalize();
+            let up = forward.cross(right);
+
+            // Gnomonic projection onto the softbox plane keeps its edges straight
+            let local = Vec2::new(dir.dot(right), dir.dot(up)) / facing;
+            let extent = Vec2::new(softbox.half_size.x.tan(), softbox.half_size.y.tan());
+            let edge = (extent - local.abs()) / extent;
+            let coverage = (edge.min_element() / 0.1).clamp(0.0, 1.0);
+            color += softbox.color.to_linear().to_vec3() * softbox.intensity * coverage;
+        }
+
+        color.extend(1.0)
+    }
+
+    /// Renders the environment into a cubemap and projects its SH
+    pub fn paint(&self, format: SkyTexFormat) -> PaintedSky {
+        PaintedSky::from_fn(self.face_size, format, |dir| self.radiance(dir))
+    }
+}
+
+impl SkyRadiance for StudioLighting {
+    fn radiance(&self, dir: Vec3) -> Vec4 {
+        StudioLighting::radiance(self, dir)
+    }
+}
@@ -0,0 +1,82 @@
+use crate::skytex::{sh_dominant_dir, sh_lookup, SkyLighting};
+use bevy::prelude::*;
+
+/// Keeps a `DirectionalLight` aligned with the dominant direction of [`SkyLighting`] so Bevy's
+/// shadows and `StandardMaterial` highlights match the generated sky
+pub struct SkySunPlugin;
+
+impl Plugin for SkySunPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<SkySunSettings>();
+        app.register_type::<(SkySunSettings, SkySun)>();
+        app.add_systems(Update, sync_sky_sun);
+    }
+}
+
+#[derive(Resource, Reflect, Clone, Copy, Debug, PartialEq)]
+#[reflect(Resource)]
+pub struct SkySunSettings {
+    /// Lux per unit of SH irradiance in the sun direction
+    pub illuminance_scale: f32,
+    pub shadows_enabled: bool,
+}
+
+impl Default for SkySunSettings {
+    fn default() -> Self {
+        Self {
+            illuminance_scale: 2000.0,
+            shadows_enabled: true,
+        }
+    }
+}
+
+/// The light spawned by [`SkySunPlugin`]
+#[derive(Component, Reflect, Default)]
+#[reflect(Component)]
+pub struct SkySun;
+
+pub(crate) fn sync_sky_sun(
+    mut commands: Commands,
+    lighting: Res<SkyLighting>,
+    settings: Res<SkySunSettings>,
+    mut suns: Query<(&mut DirectionalLight, &mut Transform), With<SkySun>>,
+) {
+    if !lighting.is_changed() && !settings.is_changed() && !suns.is_empty() {
+        return;
+    }
+
+    // sh_dominant_dir points the way the light travels
+    let travel = sh_dominant_dir(&lighting);
+    let radiance = sh_lookup(&lighting, -travel).truncate().max(Vec3::ZERO);
+    let brightness = radiance.max_element();
+    let color = if brightness > 0.0 {
+        radiance / brightness
+    } else {
+        Vec3::ONE
+    };
+    let light = DirectionalLight {
+        color: LinearRgba::rgb(color.x, color.y, color.z).into(),
+        illuminance: brightness * settings.illuminance_scale,
+        shadows_enabled: settings.shadows_enabled,
+        ..default()
+    };
+    let transform = Transform::IDENTITY.looking_to(travel, Vec3::Y);
+
+    match suns.get_single_mut() {
+        Ok((mut current, mut current_transform)) => {
+            *current = light;
+            *current_transform = transform;
+        }
+        Err(_) => {
+            commands.spawn((
+                DirectionalLightBundle {
+                    directional_light: light,
+                    transform,
+                    ..default()
+                },
+                SkySun,
+                Name::new("Sky sun"),
+            ));
+        }
+    }
+}
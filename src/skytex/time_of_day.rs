@@ -0,0 +1,154 @@
+use crate::skytex::gradient::SkyGradient;
+use crate::skytex::SkyLighting;
+use bevy::prelude::*;
+use std::f32::consts::TAU;
+
+/// Animates [`SkyLighting`] over a day cycle, which in turn regenerates the generated skyboxes
+/// and updates the global `PbrMaterial` lighting.
+pub struct TimeOfDayPlugin;
+
+impl Plugin for TimeOfDayPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<TimeOfDay>();
+        app.register_type::<TimeOfDay>();
+        app.add_systems(
+            Update,
+            (advance_time_of_day, apply_time_of_day)
+                .chain()
+                .in_set(TimeOfDaySet),
+        );
+    }
+}
+
+/// Systems updating [`TimeOfDay`] and the lighting derived from it
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct TimeOfDaySet;
+
+#[derive(Resource, Reflect, Clone, Copy, Debug, PartialEq)]
+#[reflect(Resource)]
+pub struct TimeOfDay {
+    /// Hour of the day in 0..24
+    pub hour: f32,
+    /// Real seconds per in-game day, 0 stops the clock
+    pub day_length: f32,
+    /// Angle the sun's path is tilted away from the zenith towards -Z, in radians
+    pub tilt: f32,
+    /// Minimum real seconds between lighting updates, regenerating the sky is not free
+    pub update_interval: f32,
+    pub palette: DayPalette,
+}
+
+impl Default for TimeOfDay {
+    fn default() -> Self {
+        Self {
+            hour: 10.0,
+            day_length: 600.0,
+            tilt: 0.5,
+            update_interval: 0.25,
+            palette: DayPalette::default(),
+        }
+    }
+}
+
+/// Sky colors at night, at sunrise and sunset and at midday
+#[derive(Reflect, Clone, Copy, Debug, PartialEq)]
+pub struct DayPalette {
+    pub night: SkyColors,
+    pub twilight: SkyColors,
+    pub day: SkyColors,
+    pub sun: Color,
+    pub sun_intensity: f32,
+}
+
+#[derive(Reflect, Clone, Copy, Debug, PartialEq)]
+pub struct SkyColors {
+    pub zenith: Color,
+    pub horizon: Color,
+    pub ground: Color,
+}
+
+impl SkyColors {
+    fn mix(&self, other: &SkyColors, t: f32) -> SkyColors {
+        SkyColors {
+            zenith: self.zenith.mix(&other.zenith, t),
+            horizon: self.horizon.mix(&other.horizon, t),
+            ground: self.ground.mix(&other.ground, t),
+        }
+    }
+}
+
+impl Default for DayPalette {
+    fn default() -> Self {
+        Self {
+            night: SkyColors {
+                zenith: Color::srgb(0.01, 0.015, 0.04),
+                horizon: Color::srgb(0.03, 0.04, 0.07),
+                ground: Color::srgb(0.01, 0.01, 0.01),
+            },
+            twilight: SkyColors {
+                zenith: Color::srgb(0.2, 0.25, 0.45),
+                horizon: Color::srgb(0.95, 0.55, 0.3),
+                ground: Color::srgb(0.12, 0.1, 0.09),
+            },
+            day: SkyColors {
+                zenith: Color::srgb(0.3, 0.5, 0.9),
+                horizon: Color::srgb(0.8, 0.87, 0.95),
+                ground: Color::srgb(0.3, 0.28, 0.25),
+            },
+            sun: Color::srgb(1.0, 0.95, 0.85),
+            sun_intensity: 8.0,
+        }
+    }
+}
+
+impl TimeOfDay {
+    /// Direction from the viewer towards the sun, rising in +X and setting in -X
+    pub fn sun_direction(&self) -> Vec3 {
+        let angle = self.hour / 24.0 * TAU;
+        Quat::from_rotation_x(-self.tilt) * Vec3::new(angle.sin(), -angle.cos(), 0.0)
+    }
+
+    /// The sky at the current hour
+    pub fn gradient(&self) -> SkyGradient {
+        let sun = self.sun_direction();
+        let palette = &self.palette;
+        let colors = if sun.y < 0.0 {
+            palette.twilight.mix(&palette.night, (-sun.y / 0.2).clamp(0.0, 1.0))
+        } else {
+            palette.twilight.mix(&palette.day, (sun.y / 0.4).clamp(0.0, 1.0))
+        };
+        // The disk sinks below the horizon instead of popping out
+        let sun_visibility = (sun.y / 0.05 + 1.0).clamp(0.0, 1.0);
+
+        SkyGradient::new()
+            .stop(colors.ground, -1.0)
+            .stop(colors.horizon, 0.0)
+            .stop(colors.zenith, 1.0)
+            .sun(sun, palette.sun, palette.sun_intensity * sun_visibility)
+    }
+}
+
+fn advance_time_of_day(time: Res<Time>, mut time_of_day: ResMut<TimeOfDay>) {
+    if time_of_day.day_length <= 0.0 {
+        return;
+    }
+    let hours = time.delta_seconds() / time_of_day.day_length * 24.0;
+    time_of_day.hour = (time_of_day.hour + hours).rem_euclid(24.0);
+}
+
+fn apply_time_of_day(
+    time: Res<Time>,
+    time_of_day: Res<TimeOfDay>,
+    mut lighting: ResMut<SkyLighting>,
+    mut since_update: Local<Option<f32>>,
+) {
+    let elapsed = since_update.map_or(f32::INFINITY, |t| t + time.delta_seconds());
+    if elapsed < time_of_day.update_interval {
+        *since_update = Some(elapsed);
+        return;
+    }
+    *since_update = Some(0.0);
+
+    let sh = time_of_day.gradient().lighting();
+    lighting.set_if_neq(SkyLighting(sh));
+}
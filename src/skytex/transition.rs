@@ -0,0 +1,153 @@
+use crate::skytex::{sync_sky_lighting, SkyLighting, SkyTexSettings, SphericalHarmonics};
+use bevy::prelude::*;
+
+/// Runs the active [`SkyTransition`], removing it once it finished
+pub struct SkyTransitionPlugin;
+
+impl Plugin for SkyTransitionPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<SkyTransitionFinished>();
+        app.register_type::<SkyTransition>();
+        app.add_systems(Update, advance_sky_transition.before(sync_sky_lighting));
+    }
+}
+
+/// Shape of a [`SkyTransition`] over its duration
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Reflect)]
+pub enum SkyEasing {
+    Linear,
+    #[default]
+    SmoothStep,
+    EaseIn,
+    EaseOut,
+}
+
+impl SkyEasing {
+    pub fn ease(self, t: f32) -> f32 {
+        let t = t.clamp(0.0, 1.0);
+        match self {
+            SkyEasing::Linear => t,
+            SkyEasing::SmoothStep => t * t * (3.0 - 2.0 * t),
+            SkyEasing::EaseIn => t * t,
+            SkyEasing::EaseOut => t * (2.0 - t),
+        }
+    }
+}
+
+/// Crossfades [`SkyLighting`], and optionally [`SkyTexSettings`], to a new sky.
+///
+/// Every step updates the material SH and regenerates the generated skyboxes, so steps are
+/// throttled to `step_interval`.
+///
+/// ```ignore
+/// commands.insert_resource(SkyTransition::new(dusk_sh, 4.0).with_easing(SkyEasing::EaseOut));
+/// ```
+#[derive(Resource, Clone, Debug, PartialEq, Reflect)]
+#[reflect(Resource)]
+pub struct SkyTransition {
+    /// Lighting to blend from, `None` captures the current [`SkyLighting`] on the first step
+    pub from: Option<SphericalHarmonics>,
+    pub to: SphericalHarmonics,
+    /// Settings blended towards, brightness and spot parameters are interpolated
+    pub to_settings: Option<SkyTexSettings>,
+    /// Seconds the transition takes
+    pub duration: f32,
+    pub easing: SkyEasing,
+    /// Minimum seconds between steps
+    pub step_interval: f32,
+    elapsed: f32,
+    since_step: f32,
+    from_settings: Option<SkyTexSettings>,
+}
+
+impl SkyTransition {
+    pub fn new(to: SphericalHarmonics, duration: f32) -> Self {
+        Self {
+            from: None,
+            to,
+            to_settings: None,
+            duration,
+            easing: SkyEasing::default(),
+            step_interval: 0.1,
+            elapsed: 0.0,
+            since_step: f32::INFINITY,
+            from_settings: None,
+        }
+    }
+
+    pub fn with_easing(mut self, easing: SkyEasing) -> Self {
+        self.easing = easing;
+        self
+    }
+
+    pub fn with_settings(mut self, settings: SkyTexSettings) -> Self {
+        self.to_settings = Some(settings);
+        self
+    }
+
+    /// Eased progress in 0..1
+    pub fn progress(&self) -> f32 {
+        if self.duration <= 0.0 {
+            return 1.0;
+        }
+        self.easing.ease(self.elapsed / self.duration)
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.elapsed >= self.duration
+    }
+}
+
+/// Sent when a [`SkyTransition`] reached its target and was removed
+#[derive(Event, Clone, Copy, Debug)]
+pub struct SkyTransitionFinished;
+
+fn lerp_settings(from: &SkyTexSettings, to: &SkyTexSettings, t: f32) -> SkyTexSettings {
+    let lerp = |a: f32, b: f32| a + (b - a) * t;
+    SkyTexSettings {
+        spot_size: lerp(from.spot_size, to.spot_size),
+        spot_intensity: lerp(from.spot_intensity, to.spot_intensity),
+        brightness: lerp(from.brightness, to.brightness),
+        window_width: lerp(from.window_width, to.window_width),
+        rotation: lerp(from.rotation, to.rotation),
+        ..*to
+    }
+}
+
+fn advance_sky_transition(
+    mut commands: Commands,
+    time: Res<Time>,
+    transition: Option<ResMut<SkyTransition>>,
+    mut lighting: ResMut<SkyLighting>,
+    mut settings: ResMut<SkyTexSettings>,
+    mut finished: EventWriter<SkyTransitionFinished>,
+) {
+    let Some(mut transition) = transition else {
+        return;
+    };
+    let transition = transition.bypass_change_detection();
+    let from = *transition.from.get_or_insert(lighting.0);
+    if transition.to_settings.is_some() && transition.from_settings.is_none() {
+        transition.from_settings = Some(*settings);
+    }
+
+    let delta = time.delta_seconds();
+    transition.elapsed += delta;
+    transition.since_step += delta;
+    let done = transition.is_finished();
+    if transition.since_step < transition.step_interval && !done {
+        return;
+    }
+    transition.since_step = 0.0;
+
+    let t = transition.progress();
+    lighting.set_if_neq(SkyLighting(from.lerp(&transition.to, t)));
+    if let (Some(from), Some(to)) = (transition.from_settings, transition.to_settings) {
+        settings.set_if_neq(lerp_settings(&from, &to, t));
+    }
+
+    if done {
+        commands.remove_resource::<SkyTransition>();
+        finished.send(SkyTransitionFinished);
+    }
+}
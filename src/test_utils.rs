@@ -0,0 +1,165 @@
+//! Fixtures for integration tests of bevy_sk scenes, behind the `test-utils` feature.
+//!
+//! [`headless_app`] runs the sky and material systems without a GPU: skies are generated on
+//! the CPU, `StandardMaterial`s are converted and SH lighting is updated, but nothing is
+//! drawn. Everything that ends up in assets and components can be asserted on.
+
+use crate::compat::SkCompatibilityPlugin;
+use crate::materials::formats::TextureFormatSupport;
+use crate::materials::pbr::{PbrMaterial, PbrPlugin};
+use crate::skytex::{
+    cubemap_texel_at, read_cubemap_faces, PendingSkyTex, SkyTexPlugin, SphericalHarmonics,
+};
+use bevy::app::PluginsState;
+use bevy::audio::AudioPlugin;
+use bevy::core_pipeline::Skybox;
+use bevy::gilrs::GilrsPlugin;
+use bevy::log::LogPlugin;
+use bevy::prelude::*;
+use bevy::render::render_resource::{Extent3d, TextureDimension, TextureFormat};
+use bevy::render::settings::WgpuSettings;
+use bevy::render::RenderPlugin;
+use bevy::window::ExitCondition;
+use bevy::winit::WinitPlugin;
+use std::time::Duration;
+
+/// An `App` with Bevy's plugins minus windowing, audio and input devices, a renderer without
+/// a GPU, and `PbrPlugin`, `SkyTexPlugin` and `SkCompatibilityPlugin`.
+///
+/// Add further plugins before the first [`update`] or [`update_until`], which finish the
+/// plugins like `App::run` does.
+pub fn headless_app() -> App {
+    let mut app = App::new();
+    app.add_plugins(
+        DefaultPlugins
+            .build()
+            .disable::<WinitPlugin>()
+            .disable::<LogPlugin>()
+            .disable::<AudioPlugin>()
+            .disable::<GilrsPlugin>()
+            .set(WindowPlugin {
+                primary_window: None,
+                exit_condition: ExitCondition::DontExit,
+                ..default()
+            })
+            .set(RenderPlugin {
+                render_creation: WgpuSettings {
+                    backends: None,
+                    ..default()
+                }
+                .into(),
+                ..default()
+            }),
+    );
+    app.add_plugins((PbrPlugin, SkyTexPlugin, SkCompatibilityPlugin));
+    app
+}
+
+fn finish_plugins(app: &mut App) {
+    if app.plugins_state() == PluginsState::Ready {
+        app.finish();
+        app.cleanup();
+    }
+}
+
+/// Runs `updates` frames
+pub fn update(app: &mut App, updates: usize) {
+    finish_plugins(app);
+    for _ in 0..updates {
+        app.update();
+    }
+}
+
+/// Runs frames until `done`, giving background tasks like sky generation time in between.
+/// Returns `false` if `done` still fails after `max_updates`.
+pub fn update_until(
+    app: &mut App,
+    max_updates: usize,
+    mut done: impl FnMut(&mut World) -> bool,
+) -> bool {
+    finish_plugins(app);
+    for _ in 0..max_updates {
+        app.update();
+        if done(app.world_mut()) {
+            return true;
+        }
+        std::thread::sleep(Duration::from_millis(1));
+    }
+    false
+}
+
+/// A `Camera3d` at `position` looking at the origin
+pub fn spawn_camera(app: &mut App, position: Vec3) -> Entity {
+    app.world_mut()
+        .spawn(Camera3dBundle {
+            transform: Transform::from_translation(position).looking_at(Vec3::ZERO, Vec3::Y),
+            ..default()
+        })
+        .id()
+}
+
+/// Waits until the generated sky of `camera` is in its `Skybox`, returning the cubemap
+pub fn wait_for_sky(app: &mut App, camera: Entity) -> Option<Image> {
+    let generated = update_until(app, 500, |world| {
+        world.get::<PendingSkyTex>(camera).is_none() && world.get::<Skybox>(camera).is_some()
+    });
+    if !generated {
+        return None;
+    }
+    let world = app.world();
+    let skybox = world.get::<Skybox>(camera)?;
+    world.resource::<Assets<Image>>().get(&skybox.image).cloned()
+}
+
+/// Linear color a 6 layer cubemap shows in the world direction `dir`, from its first mip
+pub fn cubemap_radiance(cubemap: &Image, dir: Vec3) -> Option<Vec4> {
+    let (size, texels) = read_cubemap_faces(cubemap)?;
+    texels.get(cubemap_texel_at(size, dir)).copied()
+}
+
+/// SH lighting of a single distant light, brightest looking along `dir`
+pub fn sun_lighting(dir: Vec3, color: Color) -> SphericalHarmonics {
+    SphericalHarmonics::from_lights(&[(dir, color)])
+}
+
+/// A unit cube with `material`, converted to a `PbrMaterial` on the next update
+pub fn spawn_standard_cube(app: &mut App, material: StandardMaterial) -> Entity {
+    let world = app.world_mut();
+    let mesh = world.resource_mut::<Assets<Mesh>>().add(Cuboid::default());
+    let material = world.resource_mut::<Assets<StandardMaterial>>().add(material);
+    world
+        .spawn(PbrBundle {
+            mesh,
+            material,
+            ..default()
+        })
+        .id()
+}
+
+/// The `PbrMaterial` `entity` is drawn with
+pub fn pbr_material(app: &App, entity: Entity) -> Option<&PbrMaterial> {
+    let world = app.world();
+    let handle = world.get::<Handle<PbrMaterial>>(entity)?;
+    world.resource::<Assets<PbrMaterial>>().get(handle)
+}
+
+/// A 1x1 `Rgba8UnormSrgb` texture of `color`
+pub fn solid_image(color: Color) -> Image {
+    Image::new_fill(
+        Extent3d::default(),
+        TextureDimension::D2,
+        &color.to_srgba().to_u8_array(),
+        TextureFormat::Rgba8UnormSrgb,
+        default(),
+    )
+}
+
+/// A device without optional features, like a mobile GPU that samples no BC textures
+#[derive(Clone, Copy, Debug, Default)]
+pub struct BaselineFormats;
+
+impl TextureFormatSupport for BaselineFormats {
+    fn supports(&self, format: TextureFormat) -> bool {
+        format.required_features().is_empty()
+    }
+}
@@ -0,0 +1,686 @@
+use crate::bounds::{ray_intersect_bounds, Bounds};
+use crate::hands::{Hand, HandJointId, HandJoints, HandPointers};
+use crate::interaction::{Grabbable, Interactable, InteractionPlugin, Poked, Pressable};
+use crate::materials::text::{SdfFont, SkText, SkTextMaterial, TextAlign};
+use crate::materials::ui::SkUiMaterial;
+use bevy::ecs::system::SystemParam;
+use bevy::pbr::{NotShadowCaster, NotShadowReceiver};
+use bevy::prelude::*;
+use bevy::transform::TransformSystem;
+use bevy::utils::HashMap;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::ops::RangeInclusive;
+
+/// StereoKit-style immediate mode UI in world space, built each frame through the [`Ui`]
+/// system parameter and drawn with the StereoKit UI materials.
+///
+/// Windows are panels grabbed by their title bar, their elements are pressed with the index
+/// fingertip or by pinching while pointing at them, see `InteractionPlugin`. Input reaches
+/// the app one frame after it happens. Text needs [`UiTheme::font`].
+pub struct SkUiPlugin;
+
+impl Plugin for SkUiPlugin {
+    fn build(&self, app: &mut App) {
+        if !app.is_plugin_added::<InteractionPlugin>() {
+            app.add_plugins(InteractionPlugin);
+        }
+        app.init_resource::<UiState>();
+        app.init_resource::<UiTheme>();
+        app.register_type::<UiTheme>();
+        app.add_systems(PostUpdate, sync_ui.before(TransformSystem::TransformPropagate));
+    }
+}
+
+/// Look and layout of the [`Ui`], in meters
+#[derive(Resource, Reflect, Clone, Debug, PartialEq)]
+#[reflect(Resource)]
+pub struct UiTheme {
+    /// Font of every label, no text is drawn without one
+    pub font: Option<Handle<SdfFont>>,
+    /// The font atlas stores a multi channel distance field
+    pub multi_channel_font: bool,
+    /// Meters per em
+    pub text_size: f32,
+    pub text_color: Color,
+    pub panel_color: Color,
+    pub element_color: Color,
+    /// Pressed buttons, set toggles and slider knobs
+    pub active_color: Color,
+    /// Height of the title bar hands grab windows by
+    pub header_height: f32,
+    pub row_height: f32,
+    pub padding: f32,
+    /// Thickness of the panels and elements
+    pub depth: f32,
+}
+
+impl Default for UiTheme {
+    fn default() -> Self {
+        Self {
+            font: None,
+            multi_channel_font: false,
+            text_size: 0.012,
+            text_color: Color::WHITE,
+            panel_color: Color::srgb(0.12, 0.12, 0.14),
+            element_color: Color::srgb(0.25, 0.25, 0.3),
+            active_color: Color::srgb(0.3, 0.55, 0.9),
+            header_height: 0.03,
+            row_height: 0.03,
+            padding: 0.008,
+            depth: 0.008,
+        }
+    }
+}
+
+/// Windows and elements of the [`Ui`] between frames, kept by `SkUiPlugin`
+#[derive(Resource, Default)]
+pub struct UiState {
+    windows: HashMap<u64, UiWindow>,
+    elements: HashMap<u64, UiElement>,
+    /// Window between `window_begin` and `window_end`
+    current: Option<u64>,
+}
+
+struct UiWindow {
+    title: String,
+    /// Top center of the window
+    pose: Transform,
+    width: f32,
+    /// Bottom of the last element, down from the top of the window
+    cursor: f32,
+    /// A hand moved the window since the app last passed its pose
+    moved: bool,
+    used: bool,
+    entities: Option<WindowEntities>,
+}
+
+#[derive(Clone, Copy)]
+struct WindowEntities {
+    root: Entity,
+    panel: Entity,
+    title: Option<Entity>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum ElementKind {
+    Label,
+    Button,
+    Toggle,
+    Slider,
+}
+
+struct UiElement {
+    window: u64,
+    kind: ElementKind,
+    text: String,
+    /// Center of the row, down from the top of the window
+    y: f32,
+    /// 0 or 1 for toggles, the slider position from 0 to 1
+    value: f32,
+    used: bool,
+    /// Input since the app last asked
+    clicked: bool,
+    new_value: Option<f32>,
+    entities: Option<ElementEntities>,
+}
+
+#[derive(Clone, Copy)]
+struct ElementEntities {
+    kind: ElementKind,
+    /// What hands interact with, none for labels
+    body: Option<Entity>,
+    knob: Option<Entity>,
+    text: Option<Entity>,
+}
+
+fn hash_id(parts: impl Hash) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    parts.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Builds the world space UI, call it every frame for every window that should stay open.
+///
+/// Elements go between [`Ui::window_begin`] and [`Ui::window_end`], laid out top to bottom
+/// and identified by their window's title and their text, which must be unique per window.
+#[derive(SystemParam)]
+pub struct Ui<'w> {
+    state: ResMut<'w, UiState>,
+    theme: Res<'w, UiTheme>,
+}
+
+impl Ui<'_> {
+    /// Starts a window `width` meters wide with its title bar's top center at `pose`, which is
+    /// updated while a hand drags the window around
+    pub fn window_begin(&mut self, title: &str, pose: &mut Transform, width: f32) {
+        let id = hash_id(title);
+        let window = self.state.windows.entry(id).or_insert_with(|| UiWindow {
+            title: String::new(),
+            pose: *pose,
+            width,
+            cursor: 0.0,
+            moved: false,
+            used: false,
+            entities: None,
+        });
+        if window.moved {
+            *pose = window.pose;
+            window.moved = false;
+        } else {
+            window.pose = *pose;
+        }
+        if window.title != title {
+            window.title = title.to_string();
+        }
+        window.width = width;
+        window.cursor = self.theme.header_height + self.theme.padding;
+        window.used = true;
+        self.state.current = Some(id);
+    }
+
+    pub fn window_end(&mut self) {
+        self.state.current = None;
+    }
+
+    pub fn label(&mut self, text: &str) {
+        self.element(ElementKind::Label, text, 0.0);
+    }
+
+    /// Whether the button was pressed
+    pub fn button(&mut self, text: &str) -> bool {
+        self.element(ElementKind::Button, text, 0.0)
+            .is_some_and(|element| std::mem::take(&mut element.clicked))
+    }
+
+    /// Flips `value` when pressed, returns whether it did
+    pub fn toggle(&mut self, text: &str, value: &mut bool) -> bool {
+        let Some(element) = self.element(ElementKind::Toggle, text, *value as u8 as f32) else {
+            return false;
+        };
+        let clicked = std::mem::take(&mut element.clicked);
+        if clicked {
+            *value = !*value;
+            element.value = *value as u8 as f32;
+        }
+        clicked
+    }
+
+    /// Moves `value` within `range` while a hand drags the slider, returns whether it did
+    pub fn slider(&mut self, text: &str, value: &mut f32, range: RangeInclusive<f32>) -> bool {
+        let (start, span) = (*range.start(), range.end() - range.start());
+        let position = if span > 0.0 {
+            ((*value - start) / span).clamp(0.0, 1.0)
+        } else {
+            0.0
+        };
+        let Some(element) = self.element(ElementKind::Slider, text, position) else {
+            return false;
+        };
+        let Some(position) = element.new_value.take() else {
+            return false;
+        };
+        *value = start + position * span;
+        element.value = position;
+        true
+    }
+
+    /// Lays out the next row of the current window
+    fn element(&mut self, kind: ElementKind, text: &str, value: f32) -> Option<&mut UiElement> {
+        let Some(window_id) = self.state.current else {
+            warn!("Ui element {text:?} outside of window_begin and window_end");
+            return None;
+        };
+        let UiState {
+            windows, elements, ..
+        } = &mut *self.state;
+        let window = windows.get_mut(&window_id)?;
+        let y = window.cursor + self.theme.row_height * 0.5;
+        window.cursor += self.theme.row_height + self.theme.padding;
+
+        let element = elements
+            .entry(hash_id((window_id, text)))
+            .or_insert_with(|| UiElement {
+                window: window_id,
+                kind,
+                text: String::new(),
+                y,
+                value,
+                used: false,
+                clicked: false,
+                new_value: None,
+                entities: None,
+            });
+        if element.text != text {
+            element.text = text.to_string();
+        }
+        element.kind = kind;
+        element.y = y;
+        element.value = value;
+        element.used = true;
+        Some(element)
+    }
+}
+
+/// Shared meshes and materials of the UI entities
+#[derive(Default)]
+pub(crate) struct UiAssetCache {
+    pub cube: Handle<Mesh>,
+    pub panel: Handle<SkUiMaterial>,
+    pub element: Handle<SkUiMaterial>,
+    pub active: Handle<SkUiMaterial>,
+    pub text: Option<Handle<SkTextMaterial>>,
+}
+
+#[derive(SystemParam)]
+pub(crate) struct UiAssets<'w, 's> {
+    meshes: ResMut<'w, Assets<Mesh>>,
+    materials: ResMut<'w, Assets<SkUiMaterial>>,
+    text_materials: ResMut<'w, Assets<SkTextMaterial>>,
+    fonts: Res<'w, Assets<SdfFont>>,
+    pub cache: Local<'s, UiAssetCache>,
+}
+
+impl UiAssets<'_, '_> {
+    /// Creates the assets on first use and keeps them in sync with `theme`
+    pub fn update(&mut self, theme: &UiTheme) {
+        if self.cache.cube == Handle::default() {
+            self.cache.cube = self.meshes.add(Cuboid::default());
+            self.cache.panel = self.materials.add(SkUiMaterial::default());
+            self.cache.element = self.materials.add(SkUiMaterial::default());
+            self.cache.active = self.materials.add(SkUiMaterial::default());
+        }
+        let colors = [
+            (self.cache.panel.clone(), theme.panel_color),
+            (self.cache.element.clone(), theme.element_color),
+            (self.cache.active.clone(), theme.active_color),
+        ];
+        for (handle, color) in colors {
+            if let Some(material) = self.materials.get_mut(&handle).filter(|m| m.color != color)
+            {
+                material.color = color;
+            }
+        }
+
+        let font = theme.font.as_ref().and_then(|font| self.fonts.get(font));
+        match (&self.cache.text, font) {
+            (None, Some(font)) => {
+                self.cache.text = Some(self.text_materials.add(SkTextMaterial {
+                    color: theme.text_color,
+                    multi_channel: theme.multi_channel_font,
+                    ..SkTextMaterial::new(font)
+                }));
+            }
+            (Some(handle), Some(font)) => {
+                let material = SkTextMaterial {
+                    color: theme.text_color,
+                    multi_channel: theme.multi_channel_font,
+                    ..SkTextMaterial::new(font)
+                };
+                if self.text_materials.get(handle) != Some(&material) {
+                    self.text_materials.insert(handle, material);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+#[derive(SystemParam)]
+struct UiEntities<'w, 's> {
+    transforms: Query<'w, 's, &'static mut Transform>,
+    global_transforms: Query<'w, 's, (&'static GlobalTransform, &'static Bounds)>,
+    interactions: Query<'w, 's, (&'static Interactable, &'static Pressable)>,
+    grabbables: Query<'w, 's, &'static Grabbable>,
+    ui_materials: Query<'w, 's, &'static mut Handle<SkUiMaterial>>,
+    texts: Query<'w, 's, &'static mut SkText>,
+}
+
+#[derive(SystemParam)]
+struct UiInput<'w, 's> {
+    poked: EventReader<'w, 's, Poked>,
+    joints: Res<'w, HandJoints>,
+    pointers: Res<'w, HandPointers>,
+}
+
+impl UiInput<'_, '_> {
+    /// Where `hand` touches or points at the entity, for dragging sliders
+    fn drag_point(
+        &self,
+        hand: Hand,
+        pressable: &Pressable,
+        (transform, bounds): (&GlobalTransform, &Bounds),
+    ) -> Option<Vec3> {
+        if pressable.press > 0.0 {
+            let pose = self.joints.get(hand)?;
+            return Some(pose.joint(HandJointId::IndexTip).position);
+        }
+        let pointer = self.pointers.get(hand).filter(|pointer| pointer.pinching())?;
+        Some(ray_intersect_bounds(pointer.ray(), bounds, transform)?.point)
+    }
+}
+
+fn sync_ui(
+    mut commands: Commands,
+    mut state: ResMut<UiState>,
+    theme: Res<UiTheme>,
+    mut assets: UiAssets,
+    mut entities: UiEntities,
+    mut input: UiInput,
+) {
+    assets.update(&theme);
+    let UiState {
+        windows, elements, ..
+    } = &mut *state;
+
+    // Input of this frame, read by the app next frame
+    let poked: Vec<Entity> = input.poked.read().map(|poked| poked.entity).collect();
+    for element in elements.values_mut() {
+        let Some(body) = element.entities.and_then(|entities| entities.body) else {
+            continue;
+        };
+        match element.kind {
+            ElementKind::Button | ElementKind::Toggle => {
+                element.clicked |= poked.contains(&body);
+            }
+            ElementKind::Slider => {
+                let Ok((interactable, pressable)) = entities.interactions.get(body) else {
+                    continue;
+                };
+                let Ok(bounds) = entities.global_transforms.get(body) else {
+                    continue;
+                };
+                let point = interactable
+                    .hovered
+                    .and_then(|hand| input.drag_point(hand, pressable, bounds));
+                if let Some(point) = point {
+                    let local = bounds.0.affine().inverse().transform_point3(point);
+                    let width = bounds.1.dimensions.x;
+                    element.new_value = Some((local.x / width + 0.5).clamp(0.0, 1.0));
+                }
+            }
+            ElementKind::Label => {}
+        }
+    }
+    for window in windows.values_mut() {
+        let Some(root) = window.entities.map(|entities| entities.root) else {
+            continue;
+        };
+        if entities.grabbables.get(root).is_ok_and(|g| g.grabbed_by.is_some()) {
+            if let Ok(transform) = entities.transforms.get(root) {
+                window.pose = *transform;
+                window.moved = true;
+            }
+        }
+    }
+
+    // Everything the app didn't build this frame goes away
+    windows.retain(|_, window| {
+        if !window.used {
+            if let Some(entities) = window.entities {
+                commands.entity(entities.root).despawn_recursive();
+            }
+        }
+        std::mem::take(&mut window.used)
+    });
+    elements.retain(|_, element| {
+        let keep = element.used && windows.contains_key(&element.window);
+        let respawn = element.entities.is_some_and(|entities| entities.kind != element.kind);
+        if (!keep || respawn) && windows.contains_key(&element.window) {
+            if let Some(spawned) = element.entities.take() {
+                for entity in [spawned.body, spawned.knob, spawned.text].into_iter().flatten() {
+                    commands.entity(entity).despawn_recursive();
+                }
+            }
+        }
+        std::mem::take(&mut element.used) && keep
+    });
+
+    let text_material = assets.cache.text.clone();
+    let spawn_text = |commands: &mut Commands, parent: Entity, text: &str, align| {
+        let material = text_material.clone()?;
+        let font = theme.font.clone()?;
+        let entity = commands
+            .spawn((
+                MaterialMeshBundle {
+                    material,
+                    ..default()
+                },
+                SkText {
+                    text: text.to_string(),
+                    font,
+                    size: theme.text_size,
+                    align,
+                },
+                NotShadowCaster,
+                NotShadowReceiver,
+            ))
+            .id();
+        commands.entity(parent).add_child(entity);
+        Some(entity)
+    };
+    let cube = |material: &Handle<SkUiMaterial>| {
+        (
+            MaterialMeshBundle {
+                mesh: assets.cache.cube.clone(),
+                material: material.clone(),
+                ..default()
+            },
+            NotShadowCaster,
+            NotShadowReceiver,
+        )
+    };
+
+    // Windows, their height follows from the rows laid out this frame
+    for window in windows.values_mut() {
+        let spawned = *window.entities.get_or_insert_with(|| {
+            let root = commands
+                .spawn((
+                    SpatialBundle::from_transform(window.pose),
+                    Interactable::default(),
+                    Grabbable::default(),
+                ))
+                .id();
+            let panel = commands.spawn(cube(&assets.cache.panel)).set_parent(root).id();
+            WindowEntities {
+                root,
+                panel,
+                title: None,
+            }
+        });
+        let height = window.cursor;
+        let header = Bounds::from_min_max(
+            Vec3::new(-window.width * 0.5, -theme.header_height, -theme.depth),
+            Vec3::new(window.width * 0.5, 0.0, 0.0),
+        );
+        let bounds = entities.global_transforms.get(spawned.root);
+        if bounds.map_or(true, |(_, bounds)| *bounds != header) {
+            commands.entity(spawned.root).insert(header);
+        }
+        if let Ok(mut transform) = entities.transforms.get_mut(spawned.root) {
+            if !window.moved {
+                transform.set_if_neq(window.pose);
+            }
+        }
+        if let Ok(mut transform) = entities.transforms.get_mut(spawned.panel) {
+            transform.set_if_neq(Transform {
+                translation: Vec3::new(0.0, -height * 0.5, -theme.depth * 0.5),
+                scale: Vec3::new(window.width, height, theme.depth),
+                ..default()
+            });
+        }
+
+        let title_position = Vec3::new(
+            -window.width * 0.5 + theme.padding,
+            -theme.header_height * 0.5 - theme.text_size * 0.35,
+            0.0005,
+        );
+        match spawned.title {
+            Some(title) => {
+                if let Ok(mut text) = entities.texts.get_mut(title) {
+                    if text.text != window.title {
+                        text.text.clone_from(&window.title);
+                    }
+                }
+                if let Ok(mut transform) = entities.transforms.get_mut(title) {
+                    transform.set_if_neq(Transform::from_translation(title_position));
+                }
+            }
+            None => {
+                let title = spawn_text(&mut commands, spawned.root, &window.title, TextAlign::Left);
+                if let Some(title) = title {
+                    commands
+                        .entity(title)
+                        .insert(Transform::from_translation(title_position));
+                    window.entities = Some(WindowEntities {
+                        title: Some(title),
+                        ..spawned
+                    });
+                }
+            }
+        }
+    }
+
+    for element in elements.values_mut() {
+        let Some(window) = windows.get(&element.window) else {
+            continue;
+        };
+        let Some(root) = window.entities.map(|entities| entities.root) else {
+            continue;
+        };
+        let width = window.width - theme.padding * 2.0;
+        let row = theme.row_height;
+        let depth = theme.depth;
+        let text_y = -element.y - theme.text_size * 0.35;
+
+        let spawned = *element.entities.get_or_insert_with(|| {
+            let (body, knob) = match element.kind {
+                ElementKind::Label => (None, None),
+                ElementKind::Button | ElementKind::Toggle => {
+                    let body = commands
+                        .spawn((
+                            cube(&assets.cache.element),
+                            Interactable::default(),
+                            // Pushed most of the way into the element
+                            Pressable {
+                                depth: depth * 0.75,
+                                ..default()
+                            },
+                        ))
+                        .set_parent(root)
+                        .id();
+                    (Some(body), None)
+                }
+                ElementKind::Slider => {
+                    // The whole row half is dragged, wider than the track it draws
+                    let track_width = width * 0.5;
+                    let body = commands
+                        .spawn((
+                            SpatialBundle::default(),
+                            Bounds::from_min_max(
+                                Vec3::new(-track_width * 0.5, -row * 0.5, 0.0),
+                                Vec3::new(track_width * 0.5, row * 0.5, depth),
+                            ),
+                            Interactable::default(),
+                            Pressable::default(),
+                        ))
+                        .set_parent(root)
+                        .id();
+                    let mut track = commands.spawn(cube(&assets.cache.element));
+                    track.insert(Transform {
+                        translation: Vec3::new(0.0, 0.0, depth * 0.25),
+                        scale: Vec3::new(track_width, row * 0.2, depth * 0.5),
+                        ..default()
+                    });
+                    track.set_parent(body);
+                    let knob = commands.spawn(cube(&assets.cache.active)).set_parent(body).id();
+                    (Some(body), Some(knob))
+                }
+            };
+            ElementEntities {
+                kind: element.kind,
+                body,
+                knob,
+                text: None,
+            }
+        });
+
+        let (body_transform, text_position, align) = match element.kind {
+            ElementKind::Label => (
+                None,
+                Vec3::new(-width * 0.5, text_y, 0.0005),
+                TextAlign::Left,
+            ),
+            ElementKind::Button | ElementKind::Toggle => (
+                Some(Transform {
+                    translation: Vec3::new(0.0, -element.y, depth * 0.5),
+                    scale: Vec3::new(width, row, depth),
+                    ..default()
+                }),
+                Vec3::new(0.0, text_y, depth + 0.0005),
+                TextAlign::Center,
+            ),
+            ElementKind::Slider => (
+                Some(Transform::from_xyz(width * 0.25, -element.y, 0.0)),
+                Vec3::new(-width * 0.5, text_y, 0.0005),
+                TextAlign::Left,
+            ),
+        };
+        if let Some(body) = spawned.body {
+            if let Ok(mut transform) = entities.transforms.get_mut(body) {
+                transform.set_if_neq(body_transform.unwrap_or_default());
+            }
+            let pressed = entities
+                .interactions
+                .get(body)
+                .is_ok_and(|(_, pressable)| pressable.pressed);
+            let active = match element.kind {
+                ElementKind::Toggle => element.value > 0.5 || pressed,
+                _ => pressed,
+            };
+            if element.kind != ElementKind::Slider {
+                let material = if active {
+                    &assets.cache.active
+                } else {
+                    &assets.cache.element
+                };
+                if let Ok(mut handle) = entities.ui_materials.get_mut(body) {
+                    handle.set_if_neq(material.clone());
+                }
+            }
+        }
+        if let Some(knob) = spawned.knob {
+            if let Ok(mut transform) = entities.transforms.get_mut(knob) {
+                let x = (element.value - 0.5) * width * 0.5;
+                transform.set_if_neq(Transform {
+                    translation: Vec3::new(x, 0.0, depth * 0.5),
+                    scale: Vec3::new(row * 0.4, row * 0.8, depth),
+                    ..default()
+                });
+            }
+        }
+        match spawned.text {
+            Some(text_entity) => {
+                if let Ok(mut text) = entities.texts.get_mut(text_entity) {
+                    if text.text != element.text {
+                        text.text.clone_from(&element.text);
+                    }
+                }
+                if let Ok(mut transform) = entities.transforms.get_mut(text_entity) {
+                    transform.set_if_neq(Transform::from_translation(text_position));
+                }
+            }
+            None => {
+                if let Some(text) = spawn_text(&mut commands, root, &element.text, align) {
+                    commands
+                        .entity(text)
+                        .insert(Transform::from_translation(text_position));
+                    element.entities = Some(ElementEntities {
+                        text: Some(text),
+                        ..spawned
+                    });
+                }
+            }
+        }
+    }
+}
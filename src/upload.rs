@@ -0,0 +1,238 @@
+use crate::materials::pbr::PbrMaterial;
+use bevy::asset::AssetEvents;
+use bevy::prelude::*;
+use bevy::utils::HashSet;
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+
+/// Staged image uploads that are handed to the renderer in priority order.
+///
+/// Bevy uploads render assets in the order they appear, so under a small
+/// `RenderAssetBytesPerFrame` budget one large texture can hold back small critical ones for
+/// seconds. Images queued through [`UploadQueue`] only enter `Assets<Image>` once their turn
+/// comes, keeping the renderer's own queue short. Images loaded by the `AssetServer` wait in it
+/// too, with the [`UploadPriority`] of the entities drawing them.
+pub struct UploadSchedulingPlugin;
+
+impl Plugin for UploadSchedulingPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<UploadQueue>();
+        app.add_event::<UploadProgress>();
+        app.register_type::<UploadPriority>();
+        app.add_systems(PreUpdate, release_uploads);
+        app.add_systems(PostUpdate, queue_loaded_images.after(AssetEvents));
+    }
+}
+
+/// Priority of an upload. On an entity, the priority its `Handle<Image>` and the textures of
+/// its `PbrMaterial` are released with once the `AssetServer` loads them, the highest one wins
+/// for images several entities share.
+///
+/// The priority is looked up when the image finishes loading, so spawn the entity right after
+/// starting the load. Loaded images nothing gives a priority wait as `Normal`, `Critical`
+/// ones skip the queue.
+#[derive(Component, Reflect, Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[reflect(Component)]
+pub enum UploadPriority {
+    Low,
+    #[default]
+    Normal,
+    High,
+    /// UI, hands and other assets that must never wait behind scenery
+    Critical,
+}
+
+struct PendingUpload {
+    priority: UploadPriority,
+    size: usize,
+    sequence: u64,
+    handle: Handle<Image>,
+    image: Image,
+}
+
+impl PartialEq for PendingUpload {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for PendingUpload {}
+
+impl PartialOrd for PendingUpload {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for PendingUpload {
+    // Highest priority first, then smallest, then oldest
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.priority
+            .cmp(&other.priority)
+            .then_with(|| other.size.cmp(&self.size))
+            .then_with(|| other.sequence.cmp(&self.sequence))
+    }
+}
+
+#[derive(Resource)]
+pub struct UploadQueue {
+    /// Bytes released to the renderer per frame, at least one image is released every frame
+    pub bytes_per_frame: usize,
+    pending: BinaryHeap<PendingUpload>,
+    sequence: u64,
+    total_bytes: usize,
+    released_bytes: usize,
+    /// Released this frame, so their `Added` events aren't taken for new loads
+    released: HashSet<AssetId<Image>>,
+}
+
+impl Default for UploadQueue {
+    fn default() -> Self {
+        Self {
+            bytes_per_frame: 4096,
+            pending: BinaryHeap::new(),
+            sequence: 0,
+            total_bytes: 0,
+            released_bytes: 0,
+            released: HashSet::new(),
+        }
+    }
+}
+
+impl UploadQueue {
+    /// Queues `image` and returns a handle that becomes valid once it is released
+    pub fn enqueue(
+        &mut self,
+        images: &Assets<Image>,
+        image: Image,
+        priority: UploadPriority,
+    ) -> Handle<Image> {
+        let handle = images.reserve_handle();
+        self.push(handle.clone(), image, priority);
+        handle
+    }
+
+    fn push(&mut self, handle: Handle<Image>, image: Image, priority: UploadPriority) {
+        let size = image.data.len();
+        self.pending.push(PendingUpload {
+            priority,
+            size,
+            sequence: self.sequence,
+            handle,
+            image,
+        });
+        self.sequence += 1;
+        self.total_bytes += size;
+    }
+
+    pub fn pending(&self) -> usize {
+        self.pending.len()
+    }
+
+    pub fn is_idle(&self) -> bool {
+        self.pending.is_empty()
+    }
+}
+
+/// Sent every frame in which queued images were released
+#[derive(Event, Clone, Copy, Debug)]
+pub struct UploadProgress {
+    pub released: usize,
+    pub pending: usize,
+    pub released_bytes: usize,
+    pub total_bytes: usize,
+}
+
+impl UploadProgress {
+    pub fn fraction(&self) -> f32 {
+        if self.total_bytes == 0 {
+            return 1.0;
+        }
+        self.released_bytes as f32 / self.total_bytes as f32
+    }
+}
+
+fn release_uploads(
+    mut queue: ResMut<UploadQueue>,
+    mut images: ResMut<Assets<Image>>,
+    mut progress: EventWriter<UploadProgress>,
+) {
+    if queue.pending.is_empty() {
+        return;
+    }
+
+    let mut budget = queue.bytes_per_frame;
+    let mut released = 0;
+    while let Some(next) = queue.pending.peek() {
+        if released > 0 && next.size > budget {
+            break;
+        }
+        let upload = queue.pending.pop().unwrap();
+        budget = budget.saturating_sub(upload.size);
+        queue.released_bytes += upload.size;
+        queue.released.insert(upload.handle.id());
+        images.insert(upload.handle.id(), upload.image);
+        released += 1;
+    }
+
+    progress.send(UploadProgress {
+        released,
+        pending: queue.pending.len(),
+        released_bytes: queue.released_bytes,
+        total_bytes: queue.total_bytes,
+    });
+
+    if queue.pending.is_empty() {
+        queue.total_bytes = 0;
+        queue.released_bytes = 0;
+    }
+}
+
+/// Takes images the `AssetServer` just loaded back out of `Assets<Image>` before they're
+/// extracted, and queues them with their [`UploadPriority`]
+fn queue_loaded_images(
+    mut events: EventReader<AssetEvent<Image>>,
+    asset_server: Res<AssetServer>,
+    prioritized: Query<(
+        &UploadPriority,
+        Option<&Handle<Image>>,
+        Option<&Handle<PbrMaterial>>,
+    )>,
+    materials: Res<Assets<PbrMaterial>>,
+    mut queue: ResMut<UploadQueue>,
+    mut images: ResMut<Assets<Image>>,
+) {
+    let mut loaded = Vec::new();
+    for event in events.read() {
+        if let AssetEvent::Added { id } = *event {
+            if !queue.released.remove(&id) && asset_server.get_path(id).is_some() {
+                loaded.push(id);
+            }
+        }
+    }
+    queue.released.clear();
+
+    for id in loaded {
+        let priority = prioritized
+            .iter()
+            .filter(|(_, image, material)| {
+                image.is_some_and(|image| image.id() == id)
+                    || material
+                        .and_then(|material| materials.get(material))
+                        .is_some_and(|material| material.textures().any(|t| t.id() == id))
+            })
+            .map(|(priority, ..)| *priority)
+            .max()
+            .unwrap_or_default();
+        if priority == UploadPriority::Critical {
+            continue;
+        }
+        // The handle keeps the image alive while it waits
+        let Some(handle) = asset_server.get_id_handle(id) else {
+            continue;
+        };
+        if let Some(image) = images.remove(id) {
+            queue.push(handle, image, priority);
+        }
+    }
+}
@@ -0,0 +1,93 @@
+use bevy::prelude::*;
+use bevy::render::mesh::MeshVertexBufferLayoutRef;
+use bevy::render::render_asset::RenderAssets;
+use bevy::render::render_resource::{
+    AsBindGroup, AsBindGroupShaderType, CompareFunction, RenderPipelineDescriptor, ShaderRef,
+    ShaderType, SpecializedMeshPipelineError,
+};
+use bevy::render::texture::GpuImage;
+
+const SHADER_HANDLE: Handle<Shader> = Handle::weak_from_u128(0x2b8f61d0e4a7);
+
+/// Registers [`SkVignetteMaterial`], added by `ComfortVignettePlugin`
+pub struct SkVignetteMaterialPlugin;
+
+impl Plugin for SkVignetteMaterialPlugin {
+    fn build(&self, app: &mut App) {
+        load_shader!(app, SHADER_HANDLE, "vignette.wgsl");
+        app.add_plugins(MaterialPlugin::<SkVignetteMaterial> {
+            prepass_enabled: false,
+            shadows_enabled: false,
+            ..default()
+        });
+        app.register_asset_reflect::<SkVignetteMaterial>();
+    }
+}
+
+/// Darkens the edges of every view it is drawn into, over everything else. Expects a
+/// triangle covering clip space, whatever the entity's transform.
+#[derive(Asset, AsBindGroup, Reflect, PartialEq, Debug, Clone)]
+#[uniform(0, SkVignetteMaterialUniform)]
+pub struct SkVignetteMaterial {
+    pub color: Color,
+    /// How far in from the corners the vignette reaches, 0 hides it and 1 covers the view
+    pub strength: f32,
+    /// Width of the fade from clear to `color`, as part of the view's radius
+    pub softness: f32,
+}
+
+impl Default for SkVignetteMaterial {
+    fn default() -> Self {
+        Self {
+            color: Color::BLACK,
+            strength: 0.0,
+            softness: 0.3,
+        }
+    }
+}
+
+#[derive(Clone, Default, ShaderType)]
+pub struct SkVignetteMaterialUniform {
+    pub color: Vec4,
+    pub strength: f32,
+    pub softness: f32,
+}
+
+impl AsBindGroupShaderType<SkVignetteMaterialUniform> for SkVignetteMaterial {
+    fn as_bind_group_shader_type(&self, _: &RenderAssets<GpuImage>) -> SkVignetteMaterialUniform {
+        SkVignetteMaterialUniform {
+            color: self.color.to_linear().to_vec4(),
+            strength: self.strength,
+            softness: self.softness.max(0.001),
+        }
+    }
+}
+
+impl Material for SkVignetteMaterial {
+    fn vertex_shader() -> ShaderRef {
+        SHADER_HANDLE.into()
+    }
+
+    fn fragment_shader() -> ShaderRef {
+        SHADER_HANDLE.into()
+    }
+
+    fn alpha_mode(&self) -> AlphaMode {
+        AlphaMode::Blend
+    }
+
+    fn specialize(
+        _pipeline: &bevy::pbr::MaterialPipeline<Self>,
+        descriptor: &mut RenderPipelineDescriptor,
+        _layout: &MeshVertexBufferLayoutRef,
+        _key: bevy::pbr::MaterialPipelineKey<Self>,
+    ) -> Result<(), SpecializedMeshPipelineError> {
+        // Drawn over the whole view, in front of whatever is there
+        descriptor.primitive.cull_mode = None;
+        if let Some(depth_stencil) = descriptor.depth_stencil.as_mut() {
+            depth_stencil.depth_compare = CompareFunction::Always;
+            depth_stencil.depth_write_enabled = false;
+        }
+        Ok(())
+    }
+}
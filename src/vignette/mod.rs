@@ -0,0 +1,173 @@
+pub mod material;
+
+use crate::vignette::material::{SkVignetteMaterial, SkVignetteMaterialPlugin};
+use bevy::pbr::{NotShadowCaster, NotShadowReceiver};
+use bevy::prelude::*;
+use bevy::render::mesh::{Indices, PrimitiveTopology};
+use bevy::render::render_asset::RenderAssetUsages;
+use bevy::render::view::{NoFrustumCulling, VisibilitySystems};
+use bevy::transform::TransformSystem;
+
+/// Narrows the view with a [`SkVignetteMaterial`] while a [`ComfortVignette`] entity moves
+/// or turns faster than its thresholds, to ease motion sickness during artificial locomotion
+pub struct ComfortVignettePlugin;
+
+impl Plugin for ComfortVignettePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins(SkVignetteMaterialPlugin);
+        app.register_type::<ComfortVignette>();
+        app.add_systems(
+            PostUpdate,
+            update_comfort_vignettes
+                .after(TransformSystem::TransformPropagate)
+                .before(VisibilitySystems::VisibilityPropagate),
+        );
+    }
+}
+
+/// Vignette driven by the world space velocity of its entity.
+///
+/// On a camera it reacts to head motion as well, put it on the XR tracking root instead to
+/// react to artificial locomotion alone. The vignette is drawn into every view, so both eyes
+/// of the headset get it.
+#[derive(Component, Reflect, Clone, Debug, PartialEq)]
+#[reflect(Component)]
+pub struct ComfortVignette {
+    /// Meters per second before the vignette starts closing in, and where it is at its
+    /// strongest
+    pub linear_threshold: f32,
+    pub linear_full: f32,
+    /// Radians per second before the vignette starts closing in, and where it is at its
+    /// strongest
+    pub angular_threshold: f32,
+    pub angular_full: f32,
+    /// Strength at full speed, see [`SkVignetteMaterial::strength`]
+    pub max_strength: f32,
+    /// How quickly the vignette follows the motion, per second
+    pub response: f32,
+    /// The current strength, updated every frame
+    pub strength: f32,
+    previous: Option<GlobalTransform>,
+    overlay: Option<Entity>,
+}
+
+impl Default for ComfortVignette {
+    fn default() -> Self {
+        Self {
+            linear_threshold: 0.5,
+            linear_full: 3.0,
+            angular_threshold: 0.6,
+            angular_full: 2.0,
+            max_strength: 0.6,
+            response: 8.0,
+            strength: 0.0,
+            previous: None,
+            overlay: None,
+        }
+    }
+}
+
+impl ComfortVignette {
+    /// Strength the vignette moves towards at these velocities
+    pub fn target_strength(&self, linear: f32, angular: f32) -> f32 {
+        let ramp = |speed: f32, threshold: f32, full: f32| {
+            ((speed - threshold) / (full - threshold).max(0.001)).clamp(0.0, 1.0)
+        };
+        let linear = ramp(linear, self.linear_threshold, self.linear_full);
+        let angular = ramp(angular, self.angular_threshold, self.angular_full);
+        linear.max(angular) * self.max_strength
+    }
+}
+
+/// The fullscreen triangle drawing a [`ComfortVignette`]
+#[derive(Component)]
+struct VignetteOverlay {
+    owner: Entity,
+}
+
+/// A triangle covering clip space
+fn fullscreen_triangle() -> Mesh {
+    Mesh::new(PrimitiveTopology::TriangleList, RenderAssetUsages::RENDER_WORLD)
+        .with_inserted_attribute(
+            Mesh::ATTRIBUTE_POSITION,
+            vec![[-1.0, -1.0, 0.0], [3.0, -1.0, 0.0], [-1.0, 3.0, 0.0]],
+        )
+        .with_inserted_indices(Indices::U32(vec![0, 1, 2]))
+}
+
+fn update_comfort_vignettes(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut vignettes: Query<(Entity, &mut ComfortVignette, &GlobalTransform)>,
+    mut overlays: Query<(
+        Entity,
+        &VignetteOverlay,
+        &Handle<SkVignetteMaterial>,
+        &mut Visibility,
+        &mut Transform,
+    )>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<SkVignetteMaterial>>,
+) {
+    for (entity, overlay, ..) in overlays.iter() {
+        if !vignettes.contains(overlay.owner) {
+            commands.entity(entity).despawn();
+        }
+    }
+
+    let delta = time.delta_seconds();
+    for (entity, mut vignette, transform) in vignettes.iter_mut() {
+        let Some(overlay) = vignette.overlay else {
+            let overlay = commands
+                .spawn((
+                    MaterialMeshBundle {
+                        mesh: meshes.add(fullscreen_triangle()),
+                        material: materials.add(SkVignetteMaterial::default()),
+                        visibility: Visibility::Hidden,
+                        ..default()
+                    },
+                    VignetteOverlay { owner: entity },
+                    NoFrustumCulling,
+                    NotShadowCaster,
+                    NotShadowReceiver,
+                ))
+                .id();
+            vignette.overlay = Some(overlay);
+            vignette.previous = Some(*transform);
+            continue;
+        };
+
+        let previous = vignette.previous.replace(*transform).unwrap_or(*transform);
+        let target = if delta > 0.0 {
+            let linear = previous.translation().distance(transform.translation()) / delta;
+            let rotation = previous.compute_transform().rotation;
+            let angular = rotation.angle_between(transform.compute_transform().rotation) / delta;
+            vignette.target_strength(linear, angular)
+        } else {
+            vignette.strength
+        };
+        let blend = 1.0 - (-vignette.response * delta).exp();
+        let strength = vignette.strength + (target - vignette.strength) * blend;
+        // Settles at exactly 0 so the overlay can be hidden
+        let strength = if strength < 0.001 { 0.0 } else { strength };
+        if vignette.strength != strength {
+            vignette.strength = strength;
+        }
+
+        let Ok((_, _, material, mut visibility, mut overlay_transform)) =
+            overlays.get_mut(overlay)
+        else {
+            continue;
+        };
+        if strength == 0.0 {
+            visibility.set_if_neq(Visibility::Hidden);
+            continue;
+        }
+        visibility.set_if_neq(Visibility::Inherited);
+        // At the vignette so it is sorted in front of everything its camera sees
+        overlay_transform.set_if_neq(transform.compute_transform());
+        if let Some(material) = materials.get_mut(material).filter(|m| m.strength != strength) {
+            material.strength = strength;
+        }
+    }
+}
@@ -0,0 +1,38 @@
+#import bevy_pbr::{
+    forward_io::{Vertex, VertexOutput},
+    mesh_view_bindings::view,
+}
+
+struct SkVignetteMaterial {
+    color: vec4<f32>,
+    strength: f32,
+    softness: f32,
+};
+
+@group(2) @binding(0)
+var<uniform> material: SkVignetteMaterial;
+
+@vertex
+fn vertex(vertex: Vertex) -> VertexOutput {
+    var out: VertexOutput;
+    // The mesh is already in clip space, covering every view it is drawn into
+    out.position = vec4(vertex.position.xy, 1.0, 1.0);
+    out.world_position = vec4(view.world_position, 1.0);
+    out.world_normal = vec3(0.0, 0.0, 1.0);
+#ifdef VERTEX_OUTPUT_INSTANCE_INDEX
+    out.instance_index = vertex.instance_index;
+#endif
+    return out;
+}
+
+@fragment
+fn fragment(in: VertexOutput) -> @location(0) vec4<f32> {
+    // 0 at the center of this eye's viewport, 1 in its corners
+    let half_size = view.viewport.zw * 0.5;
+    let offset = (in.position.xy - view.viewport.xy - half_size) / half_size;
+    let radius = length(offset) * inverseSqrt(2.0);
+
+    let inner = 1.0 - material.strength;
+    let coverage = smoothstep(inner - material.softness, inner, radius);
+    return vec4(material.color.rgb, material.color.a * coverage * step(0.001, material.strength));
+}
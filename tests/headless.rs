@@ -0,0 +1,97 @@
+use bevy::prelude::*;
+use bevy::render::render_resource::{Extent3d, TextureFormat};
+use bevy_sk::lighting::cookie::{
+    LightCookie, LIGHT_COOKIES_IMAGE_HANDLE, LIGHT_COOKIE_DATA_IMAGE_HANDLE,
+};
+use bevy_sk::materials::formats::negotiate_material_textures;
+use bevy_sk::materials::pbr::PbrMaterial;
+use bevy_sk::skytex::gpu::GpuSkyTex;
+use bevy_sk::skytex::{SkyLighting, SkyTexFormat};
+use bevy_sk::test_utils::*;
+
+#[test]
+fn generated_sky_is_brightest_towards_the_sun() {
+    let mut app = headless_app();
+    let sun = Vec3::new(0.3, 0.8, -0.5).normalize();
+    app.insert_resource(SkyLighting(sun_lighting(sun, Color::WHITE)));
+    app.insert_resource(SkyTexFormat::Rgba16Float);
+    let camera = spawn_camera(&mut app, Vec3::new(0.0, 1.5, 3.0));
+
+    let sky = wait_for_sky(&mut app, camera).expect("sky was never generated");
+    let brightness = |dir: Vec3| cubemap_radiance(&sky, dir).unwrap().truncate().length();
+    assert!(brightness(sun) > brightness(-sun) * 2.0);
+}
+
+#[test]
+fn gpu_skies_fall_back_to_the_cpu() {
+    let mut app = headless_app();
+    let camera = spawn_camera(&mut app, Vec3::Z);
+    app.world_mut().entity_mut(camera).insert(GpuSkyTex);
+
+    assert!(wait_for_sky(&mut app, camera).is_some());
+    assert!(app.world().get::<GpuSkyTex>(camera).is_none());
+}
+
+#[test]
+fn standard_materials_are_converted() {
+    let mut app = headless_app();
+    let cube = spawn_standard_cube(
+        &mut app,
+        StandardMaterial {
+            base_color: Color::srgb(1.0, 0.0, 0.0),
+            metallic: 0.25,
+            ..default()
+        },
+    );
+    let converted = update_until(&mut app, 10, |world| {
+        world.get::<Handle<PbrMaterial>>(cube).is_some()
+    });
+    assert!(converted);
+
+    let material = pbr_material(&app, cube).unwrap();
+    assert_eq!(material.metallic, 0.25);
+    let color = material.color.to_srgba();
+    assert!((color.red - 1.0).abs() < 1e-4 && color.green.abs() < 1e-4, "{color:?}");
+}
+
+#[test]
+fn unsupported_textures_are_decompressed() {
+    // One BC1 block, red in both endpoints
+    let mut texture = solid_image(Color::WHITE);
+    texture.texture_descriptor.format = TextureFormat::Bc1RgbaUnorm;
+    texture.texture_descriptor.size = Extent3d {
+        width: 4,
+        height: 4,
+        depth_or_array_layers: 1,
+    };
+    texture.data = vec![0x00, 0xf8, 0x00, 0xf8, 0, 0, 0, 0];
+
+    let mut images = Assets::<Image>::default();
+    let mut materials = Assets::<PbrMaterial>::default();
+    let handle = images.add(texture);
+    materials.add(PbrMaterial {
+        color_texture: Some(handle.clone()),
+        ..default()
+    });
+    negotiate_material_textures(&BaselineFormats, &materials, &mut images);
+
+    let texture = images.get(&handle).unwrap();
+    assert_eq!(texture.texture_descriptor.format, TextureFormat::Rgba8Unorm);
+    assert_eq!(texture.data[..4], [255, 0, 0, 255]);
+}
+
+#[test]
+fn light_cookies_are_packed_for_spot_lights() {
+    let mut app = headless_app();
+    let world = app.world_mut();
+    let red = solid_image(Color::srgb(1.0, 0.0, 0.0));
+    let texture = world.resource_mut::<Assets<Image>>().add(red);
+    world.spawn((SpotLightBundle::default(), LightCookie::new(texture)));
+    update(&mut app, 2);
+
+    let images = app.world().resource::<Assets<Image>>();
+    let data = &images.get(&LIGHT_COOKIE_DATA_IMAGE_HANDLE).unwrap().data;
+    assert_eq!(data[12..16], 2.0f32.to_le_bytes());
+    let cookies = &images.get(&LIGHT_COOKIES_IMAGE_HANDLE).unwrap().data;
+    assert_eq!(cookies[..4], [255, 0, 0, 255]);
+}